@@ -0,0 +1,251 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Expr, ExprLit, FnArg, ItemFn, Lit, LitStr, Pat, Type};
+
+/// The properties in `#[command(...)]`
+#[derive(Default)]
+pub(super) struct CommandAttributes {
+    name: Option<String>,
+    description: Option<String>,
+    permission: Option<String>,
+    aliases: Vec<String>,
+    suggestions: Vec<(String, syn::Path)>,
+    register: Option<bool>,
+    krate: Option<syn::Path>,
+}
+
+impl CommandAttributes {
+    pub(super) fn parse(&mut self, meta: syn::meta::ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse::<LitStr>()?.value());
+        } else if meta.path.is_ident("description") {
+            self.description = Some(meta.value()?.parse::<LitStr>()?.value());
+        } else if meta.path.is_ident("permission") {
+            self.permission = Some(meta.value()?.parse::<LitStr>()?.value());
+        } else if meta.path.is_ident("aliases") {
+            let array: syn::ExprArray = meta.value()?.parse()?;
+            for alias in array.elems {
+                match alias {
+                    Expr::Lit(ExprLit { lit: Lit::Str(alias), .. }) => self.aliases.push(alias.value()),
+                    alias => return Err(syn::Error::new(alias.span(), "aliases must be string literals")),
+                }
+            }
+        } else if meta.path.is_ident("suggestions") {
+            meta.parse_nested_meta(|argument| {
+                let Some(name) = argument.path.get_ident() else {
+                    return Err(argument.error("expected the name of an argument"));
+                };
+                self.suggestions.push((name.to_string(), argument.value()?.parse()?));
+                Ok(())
+            })?;
+        } else if meta.path.is_ident("register") {
+            self.register = Some(meta.value()?.parse::<syn::LitBool>()?.value);
+        } else if meta.path.is_ident("crate") {
+            self.krate = Some(parse_crate(meta)?);
+        } else {
+            return Err(meta.error("expected `name`, `description`, `permission`, `aliases`, `suggestions`, `register` or `crate`"));
+        }
+        Ok(())
+    }
+}
+
+/// `crate = "path"` names the server crate for code outside of it, like serde's `crate` attribute
+fn parse_crate(meta: syn::meta::ParseNestedMeta) -> syn::Result<syn::Path> {
+    meta.value()?.parse::<LitStr>()?.parse()
+}
+
+/// The server crate path from `#[command(crate = "...")]` on a derive, `crate` by default
+fn get_crate(attrs: &[syn::Attribute]) -> syn::Result<syn::Path> {
+    let mut krate = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("command")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                krate = Some(parse_crate(meta)?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `crate`"))
+            }
+        })?;
+    }
+    Ok(krate.unwrap_or_else(|| syn::parse_quote!(crate)))
+}
+
+fn is_type(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Path(path) => path.path.segments.last().is_some_and(|segment| segment.ident == name),
+        _ => false,
+    }
+}
+
+/// Keeps the function and adds `<function>_command()`, which builds its `Command`.
+/// The function may take `&CommandSender` first, then parameters which implement
+/// `CommandParameter`, where `Option`s must come last.
+/// The command is registered with the builtin commands, unless `register = false`.
+/// `suggestions(argument = provider)` sets the suggestions of an argument.
+pub(super) fn impl_command(attributes: CommandAttributes, function: ItemFn) -> syn::Result<TokenStream> {
+    let ident = &function.sig.ident;
+    let vis = &function.vis;
+    let name = attributes.name.unwrap_or(ident.to_string());
+    let description = match attributes.description {
+        Some(description) => quote!(Some(#description)),
+        //The doc comment of the function otherwise
+        None => {
+            let docs: Vec<String> = function.attrs.iter()
+                .filter(|attr| attr.path().is_ident("doc"))
+                .filter_map(|attr| match &attr.meta {
+                    syn::Meta::NameValue(syn::MetaNameValue {
+                        value: Expr::Lit(ExprLit { lit: Lit::Str(doc), .. }), ..
+                    }) => Some(doc.value().trim().to_string()),
+                    _ => None,
+                })
+                .collect();
+            match docs.is_empty() {
+                true => quote!(None),
+                false => {
+                    let docs = docs.join(" ");
+                    quote!(Some(#docs))
+                },
+            }
+        },
+    };
+    let permission = match attributes.permission {
+        Some(permission) => quote!(Some(#permission)),
+        None => quote!(None),
+    };
+    let aliases = attributes.aliases;
+    let (suggested, providers): (Vec<String>, Vec<syn::Path>) = attributes.suggestions.into_iter().unzip();
+    let krate = attributes.krate.unwrap_or_else(|| syn::parse_quote!(crate));
+
+    let mut takes_sender = false;
+    let mut parameters: Vec<(syn::Ident, &Type)> = Vec::new();
+    let mut optional = false;
+    for (index, input) in function.sig.inputs.iter().enumerate() {
+        let FnArg::Typed(input) = input else {
+            return Err(syn::Error::new(input.span(), "commands can't take `self`"));
+        };
+        if let Type::Reference(reference) = input.ty.as_ref() {
+            if index == 0 && is_type(&reference.elem, "CommandSender") {
+                takes_sender = true;
+                continue;
+            }
+            return Err(syn::Error::new(input.ty.span(), "only `&CommandSender` may be borrowed, as the first parameter"));
+        }
+        let Pat::Ident(pat) = input.pat.as_ref() else {
+            return Err(syn::Error::new(input.pat.span(), "command parameters must be named"));
+        };
+        match (optional, is_type(&input.ty, "Option")) {
+            (true, false) => return Err(syn::Error::new(
+                input.ty.span(),
+                "mandatory parameters can't come after `Option` parameters"
+            )),
+            (_, is_option) => optional |= is_option,
+        }
+        parameters.push((pat.ident.clone(), input.ty.as_ref()));
+    }
+
+    let argument_names: Vec<String> = parameters.iter().map(|(ident, _)| ident.to_string()).collect();
+    let idents: Vec<&syn::Ident> = parameters.iter().map(|(ident, _)| ident).collect();
+    let types: Vec<&Type> = parameters.iter().map(|(_, ty)| *ty).collect();
+    let sender = takes_sender.then(|| quote!(&sender,));
+    let call_await = function.sig.asyncness.map(|_| quote!(.await));
+    let builder = format_ident!("{}_command", ident);
+    let builder_doc = format!("The `/{name}` command, which runs [`{ident}`]");
+    let registration = attributes.register.unwrap_or(true).then(|| quote! {
+        #krate::command::parameter::inventory::submit! {
+            #krate::command::parameter::RegisteredCommand(#builder)
+        }
+    });
+
+    Ok(quote! {
+        #function
+
+        #[doc = #builder_doc]
+        #vis fn #builder() -> #krate::command::Command {
+            use #krate::command::parameter::{CommandParameter, IntoCommandResult};
+
+            fn handler(e: #krate::event::events::command::CommandEvent) -> #krate::command::CommandFuture {
+                Box::pin(async move {
+                    let sender = e.get_sender().clone();
+                    let mut args = #krate::command::parameter::get_args(&e).into_iter();
+                    #(
+                        let #idents = #krate::command::parameter::next_arg::<#types>(
+                            &mut args,
+                            #argument_names,
+                            &sender
+                        ).await?;
+                    )*
+                    #ident(#sender #(#idents),*)#call_await.into_command_result()
+                })
+            }
+
+            let mut usages: Vec<Vec<#krate::command::argument::Argument>> = vec![vec![]];
+            #(
+                usages = #krate::command::parameter::extend_usages(
+                    usages,
+                    <#types as CommandParameter>::get_arguments(#argument_names)
+                );
+            )*
+            let usages: Vec<&[#krate::command::argument::Argument]> = usages.iter().map(Vec::as_slice).collect();
+            #krate::command::Command::new(
+                #name,
+                #description,
+                #krate::command::command_tree::CommandNode::multi_usage(#name, &usages),
+                #permission,
+                &[#(#aliases.to_string()),*],
+                handler
+            )#(.with_suggestions(#suggested, #providers))*
+        }
+
+        #registration
+    })
+}
+
+/// Implements `CommandParameter` for an enum of unit variants, which are used
+/// as literals in snake case.
+pub(super) fn impl_command_enum(ast: &DeriveInput) -> syn::Result<TokenStream> {
+    use convert_case::{Case, Casing};
+
+    let Data::Enum(data) = &ast.data else {
+        return Err(syn::Error::new(ast.span(), "#[derive(CommandEnum)] is only implemented for `enum`s"));
+    };
+    let name = &ast.ident;
+    let krate = get_crate(&ast.attrs)?;
+    let mut variants = Vec::new();
+    let mut literals = Vec::new();
+    for variant in data.variants.iter() {
+        if !variant.fields.is_empty() {
+            return Err(syn::Error::new(variant.span(), "command enums can only have unit variants"));
+        }
+        variants.push(&variant.ident);
+        literals.push(variant.ident.to_string().to_case(Case::Snake));
+    }
+
+    Ok(quote! {
+        #[async_trait::async_trait]
+        impl #krate::command::parameter::CommandParameter for #name {
+            fn get_arguments(_: &str) -> Vec<#krate::command::argument::Argument> {
+                vec![#(
+                    #krate::command::argument::Argument::new(
+                        #literals.to_string(),
+                        #krate::command::argument::ArgType::Subcommand,
+                        true
+                    )
+                ),*]
+            }
+
+            async fn from_arg(
+                arg: #krate::command::CommandArg,
+                _: &#krate::command::CommandSender
+            ) -> Result<Self, String> {
+                match arg {
+                    #krate::command::CommandArg::Subcommand(literal, _) => match literal.as_str() {
+                        #(#literals => Ok(#name::#variants),)*
+                        literal => Err(format!("Unknown argument '{literal}'")),
+                    },
+                    arg => Err(format!("Unexpected argument {arg:?}")),
+                }
+            }
+        }
+    })
+}
@@ -34,6 +34,7 @@ use server_util::ConnectionState;
 
 use base64::prelude::*;
 
+mod command;
 mod entity;
 mod packet;
 mod registry;
@@ -1133,6 +1134,32 @@ pub fn spacket_derive(input: TokenStream) -> TokenStream {
     packet::impl_spacket(&ast).into()
 }
 
+/// Builds a command from a function, see `server_macros::command::impl_command`.
+///
+/// `#[command(name = "tp", permission = "minecraft.command.teleport", aliases = ["teleport"])]`
+///
+/// The command is registered with the builtin commands, `register = false` leaves it out.
+///
+/// Outside of the server, `crate = "rustmcsrv"` names the server crate.
+#[proc_macro_attribute]
+pub fn command(attrs: TokenStream, input: TokenStream) -> TokenStream {
+    let mut attributes = command::CommandAttributes::default();
+    let parser = syn::meta::parser(|meta| attributes.parse(meta));
+    parse_macro_input!(attrs with parser);
+    let function = parse_macro_input!(input as syn::ItemFn);
+    command::impl_command(attributes, function)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(CommandEnum, attributes(command))]
+pub fn command_enum_derive(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    command::impl_command_enum(&ast)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
 #[proc_macro]
 pub fn base64_image(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as LitStr);
@@ -27,6 +27,7 @@ pub(super) fn impl_cpacket(ast: &syn::DeriveInput) -> TokenStream {
             "InferredByteArray" => "&",
             "PropertyArray" => "&",
             "DeathLocation" => "&",
+            "MessageSignature" => "&",
             _ => "",
         }
     }
@@ -145,6 +146,8 @@ pub(super) fn impl_spacket(ast: &syn::DeriveInput) -> TokenStream {
             "InferredByteArray" => "&",
             "PropertyArray" => "&",
             "DeathLocation" => "&",
+            "MessageSignature" => "&",
+            "ArgumentSignatures" => "&",
             "Vec<DataPackID>" => "&",
             _ => "",
         }
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 
-use crate::data_types::Identifier;
+use crate::data_types::{BlockPos, Identifier};
 
+/// The block state id of air
+pub const AIR: i32 = 0;
 
 pub struct BlockState {
     name: Identifier,
@@ -15,6 +17,7 @@ pub enum BlockStateValue {
 
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Facing {
     North,
     South,
@@ -22,4 +25,33 @@ pub enum Facing {
     West,
     Up,
     Down,
+}
+
+impl Facing {
+    pub const ALL: [Facing; 6] = [Facing::Down, Facing::Up, Facing::North, Facing::South, Facing::West, Facing::East];
+
+    /// From the id of a block face clients send
+    pub fn from_id(id: i32) -> Option<Self> {
+        match id {
+            0 => Some(Facing::Down),
+            1 => Some(Facing::Up),
+            2 => Some(Facing::North),
+            3 => Some(Facing::South),
+            4 => Some(Facing::West),
+            5 => Some(Facing::East),
+            _ => None,
+        }
+    }
+
+    /// The position of the block on this side of `position`
+    pub fn offset(&self, position: BlockPos) -> BlockPos {
+        match self {
+            Facing::North => position.offset(0, 0, -1),
+            Facing::South => position.offset(0, 0, 1),
+            Facing::East => position.offset(1, 0, 0),
+            Facing::West => position.offset(-1, 0, 0),
+            Facing::Up => position.offset(0, 1, 0),
+            Facing::Down => position.offset(0, -1, 0),
+        }
+    }
 }
\ No newline at end of file
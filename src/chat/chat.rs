@@ -1,7 +1,145 @@
-// use tokio::sync::mpsc::channel;
+use std::sync::{Arc, OnceLock, Weak};
+
+use log::{info, warn};
+use tokio::sync::mpsc;
+
+use crate::data_types::registry::chat_type::Decoration;
+use crate::data_types::{PreviousMessage, TextComponent};
+use crate::event::{self, AsyncPlayerChatEvent, EventResult};
+use crate::packet::play::{CDisguisedChatMessage, CPlayerChatMessage};
+use crate::packet::Clientbound;
+use crate::player::Player;
+use crate::{REGISTRY_NBT, SERVER_REGISTRY, THE_SERVER};
+
+use super::chat_message::ChatMessage;
+use super::secure_chat::ChatValidationError;
+
+static CHAT_QUEUE: OnceLock<mpsc::UnboundedSender<ChatMessage>> = OnceLock::new();
+
+/// Hands a chat message to the chat thread.
+pub fn queue_message(message: ChatMessage) {
+    match CHAT_QUEUE.get() {
+        Some(tx) => {
+            if tx.send(message).is_err() {
+                warn!("Chat thread has stopped, dropping chat message.");
+            }
+        },
+        None => warn!("Chat thread has not started, dropping chat message."),
+    }
+}
 
 /// The chat thread is I/O heavy and spends most of its time awaiting new messages.
 /// It is therefore most appropriate to run this thread on the tokio runtime.
 pub async fn chat_thread() {
-    
+    let (tx, mut rx) = mpsc::unbounded_channel::<ChatMessage>();
+    if CHAT_QUEUE.set(tx).is_err() {
+        warn!("Chat thread is already running.");
+        return;
+    }
+    while let Some(message) = rx.recv().await {
+        broadcast(message).await;
+    }
+}
+
+/// The network id of an entry in the `minecraft:chat_type` registry,
+/// which is its position in the registry data sent during configuration.
+pub fn get_chat_type_id(chat_type: &str) -> Option<i32> {
+    REGISTRY_NBT.get("chat_type")?
+        .iter()
+        .position(|entry| entry.entry_identifier == chat_type)
+        .map(|position| position as i32)
+}
+
+/// The chat decoration of an entry in the `minecraft:chat_type` registry
+pub fn get_chat_decoration(chat_type: &str) -> Option<&'static Decoration> {
+    SERVER_REGISTRY.get("chat_type")?
+        .get(chat_type)?
+        .get_element()
+        .as_chat_type()
+        .map(|(chat, _)| chat)
+}
+
+async fn broadcast(message: ChatMessage) {
+    let Some(sender) = message.get_sender().upgrade() else { return };
+
+    //Players get an entity id once they are in the play state
+    let recipients: Vec<Weak<Player>> = THE_SERVER.get_players_async().await
+        .into_iter()
+        .filter(|weak| weak.upgrade().is_some_and(|player| player.get_entity_id() != -1))
+        .collect();
+
+    let mut e = AsyncPlayerChatEvent::new(
+        message.get_sender(),
+        message.get_message(),
+        recipients
+    );
+    if event::listen(THE_SERVER.get_event_manager(), &mut e).await == EventResult::Deny {
+        return;
+    }
+
+    let Some(chat_type) = get_chat_type_id(e.get_chat_type()) else {
+        warn!("Unknown chat type {}, dropping chat message.", e.get_chat_type());
+        return;
+    };
+
+    match get_chat_decoration(e.get_chat_type()) {
+        Some(decoration) => info!("{}", decoration.format_plain(sender.get_name(), None, e.get_message())),
+        None => info!("<{}> {}", sender.get_name(), e.get_message()),
+    }
+
+    let sender_name = TextComponent::builder().text(sender.get_name()).build();
+
+    //Only the exact message the player signed can be sent as player chat
+    let signed = message.get_signed().filter(|_| e.get_message() == message.get_message());
+
+    for recipient in e.get_recipients().iter().filter_map(Weak::upgrade) {
+        match signed {
+            Some(signed) => {
+                //The recipient has to acknowledge the message in their next chat message
+                let pending = recipient.get_chat_state().lock().unwrap()
+                    .add_pending(signed.get_signature().clone());
+                if let Err(e) = pending {
+                    kick(recipient, e);
+                    continue;
+                }
+                let packet = CPlayerChatMessage::new(
+                    sender.get_uuid(),
+                    signed.get_index(),
+                    Some(signed.get_signature().clone()),
+                    message.get_message().to_string(),
+                    message.get_timestamp(),
+                    message.get_salt(),
+                    signed.get_last_seen().iter()
+                        .map(|signature| PreviousMessage::Signature(signature.clone()))
+                        .collect(),
+                    None,
+                    chat_type,
+                    sender_name.clone(),
+                    None
+                );
+                send(recipient, packet).await;
+            },
+            None => {
+                let packet = CDisguisedChatMessage::new(
+                    TextComponent::builder().text(e.get_message()).build(),
+                    chat_type,
+                    sender_name.clone(),
+                    None
+                );
+                send(recipient, packet).await;
+            },
+        }
+    }
+}
+
+/// Queues the message, so each recipient gets their messages in the order they were broadcast.
+/// The queue is sent on the next tick of the recipient's world.
+async fn send(recipient: Arc<Player>, packet: impl Clientbound) {
+    recipient.queue_send_packet(packet).await;
+}
+
+fn kick(player: Arc<Player>, reason: ChatValidationError) {
+    crate::RUNTIME.spawn(async move {
+        player.disconnect_tc(reason.to_text_component()).await;
+    });
 }
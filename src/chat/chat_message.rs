@@ -1,9 +1,53 @@
-use std::rc::Weak;
-use crate::data_types::text_component::Nbt;
+use std::sync::Weak;
 use crate::player::Player;
-use crate::data_types::TextComponent;
 
+use super::secure_chat::SignedChat;
+
+/// A chat message as received from a player, before it is broadcast.
+#[derive(Debug)]
 pub struct ChatMessage {
     sender: Weak<Player>,
-    message: TextComponent<Nbt>,
-}
\ No newline at end of file
+    message: String,
+    timestamp: i64,
+    salt: i64,
+    signed: Option<SignedChat>,
+}
+
+impl ChatMessage {
+    pub fn new(
+        sender: Weak<Player>, 
+        message: &str, 
+        timestamp: i64, 
+        salt: i64, 
+        signed: Option<SignedChat>
+    ) -> Self {
+        Self {
+            sender,
+            message: message.to_string(),
+            timestamp,
+            salt,
+            signed,
+        }
+    }
+
+    pub fn get_sender(&self) -> Weak<Player> {
+        self.sender.clone()
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn get_timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    pub fn get_salt(&self) -> i64 {
+        self.salt
+    }
+
+    /// `None` if the sender has no chat session
+    pub fn get_signed(&self) -> Option<&SignedChat> {
+        self.signed.as_ref()
+    }
+}
@@ -1,4 +1,5 @@
 pub mod chat;
 pub mod chat_message;
+pub mod secure_chat;
 
 pub use chat::*;
\ No newline at end of file
@@ -0,0 +1,457 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::sync::OnceLock;
+
+use base64::Engine;
+use log::{info, warn};
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use serde::Deserialize;
+use sha1::Sha1;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::data_types::message_signature::LAST_SEEN_LENGTH;
+use crate::data_types::text_component::Nbt;
+use crate::data_types::{AcknowledgedMessages, ChatSessionData, MessageSignature, TextComponent};
+
+/// Clients which have not acknowledged this many messages are kicked.
+const MAX_PENDING_MESSAGES: usize = 4096;
+
+static PROFILE_KEYS: OnceLock<ProfileKeys> = OnceLock::new();
+
+/// Mojang's public keys, used to verify the keys of players' chat sessions.
+#[derive(Debug, Clone)]
+pub struct ProfileKeys {
+    keys: Vec<RsaPublicKey>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct APIPublicKeysResponse {
+    player_certificate_keys: Vec<APIPublicKey>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct APIPublicKey {
+    public_key: String,
+}
+
+impl ProfileKeys {
+    pub fn new(keys: Vec<RsaPublicKey>) -> Self {
+        Self { keys }
+    }
+
+    /// Fetches the keys from `url`, which responds like `https://api.minecraftservices.com/publickeys`
+    pub async fn fetch(url: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let text = reqwest::get(url).await?.text().await?;
+        let response: APIPublicKeysResponse = serde_json::from_str(text.as_str())?;
+        let keys = response.player_certificate_keys.iter()
+            .map(|key| {
+                let der = base64::engine::general_purpose::STANDARD.decode(&key.public_key)?;
+                Ok(RsaPublicKey::from_public_key_der(der.as_slice())?)
+            })
+            .collect::<Result<Vec<RsaPublicKey>, Box<dyn Error + Send + Sync>>>()?;
+        Ok(Self { keys })
+    }
+
+    /// Whether any of the keys made `signature` for `payload` with SHA1withRSA
+    pub fn verify(&self, payload: &[u8], signature: &[u8]) -> bool {
+        let Ok(signature) = Signature::try_from(signature) else { return false };
+        self.keys.iter().any(|key| {
+            VerifyingKey::<Sha1>::new(key.clone()).verify(payload, &signature).is_ok()
+        })
+    }
+}
+
+/// Sets the keys chat sessions are verified with. Only the first call has an effect.
+pub fn set_profile_keys(keys: ProfileKeys) -> Result<(), ProfileKeys> {
+    PROFILE_KEYS.set(keys)
+}
+
+/// `None` until the keys have been fetched, in which case chat sessions are ignored.
+pub fn get_profile_keys() -> Option<&'static ProfileKeys> {
+    PROFILE_KEYS.get()
+}
+
+/// Fetches the profile keys from the url in server.properties.
+pub async fn load_profile_keys() {
+    let url = crate::THE_SERVER.get_properties().get_profile_keys_url();
+    match ProfileKeys::fetch(url).await {
+        Ok(keys) => {
+            info!("Loaded {} profile public keys.", keys.keys.len());
+            let _ = set_profile_keys(keys);
+        },
+        Err(e) => warn!("Could not load profile public keys from {url}, chat sessions will be ignored: {e}"),
+    }
+}
+
+/// Why a player was kicked while validating secure chat
+#[derive(Debug, PartialEq, Eq)]
+pub enum ChatValidationError {
+    MissingPublicKey,
+    InvalidPublicKey,
+    InvalidPublicKeySignature,
+    ExpiredPublicKey,
+    UnsignedChat,
+    OutOfOrderChat,
+    ChatValidationFailed(String),
+    TooManyPendingChats,
+}
+
+impl ChatValidationError {
+    /// The vanilla translation key of the kick message
+    pub fn get_translation_key(&self) -> &'static str {
+        match self {
+            ChatValidationError::MissingPublicKey => "multiplayer.disconnect.missing_public_key",
+            ChatValidationError::InvalidPublicKey => "multiplayer.disconnect.invalid_public_key_signature",
+            ChatValidationError::InvalidPublicKeySignature => "multiplayer.disconnect.invalid_public_key_signature",
+            ChatValidationError::ExpiredPublicKey => "multiplayer.disconnect.expired_public_key",
+            ChatValidationError::UnsignedChat => "multiplayer.disconnect.unsigned_chat",
+            ChatValidationError::OutOfOrderChat => "multiplayer.disconnect.out_of_order_chat",
+            ChatValidationError::ChatValidationFailed(_) => "multiplayer.disconnect.chat_validation_failed",
+            ChatValidationError::TooManyPendingChats => "multiplayer.disconnect.too_many_pending_chats",
+        }
+    }
+
+    pub fn to_text_component(&self) -> TextComponent<Nbt> {
+        TextComponent::builder().translate(self.get_translation_key()).build()
+    }
+}
+
+impl Error for ChatValidationError {}
+
+impl std::fmt::Display for ChatValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChatValidationError::ChatValidationFailed(reason) => {
+                write!(f, "{}: {reason}", self.get_translation_key())
+            },
+            _ => write!(f, "{}", self.get_translation_key()),
+        }
+    }
+}
+
+/// A chat session whose key has been verified against the profile keys.
+#[derive(Debug, Clone)]
+pub struct RemoteChatSession {
+    data: ChatSessionData,
+    key: RsaPublicKey,
+}
+
+impl RemoteChatSession {
+    /// Checks that Mojang signed the session's key for this player and that it hasn't expired.
+    /// `now` is in milliseconds since the unix epoch.
+    pub fn validate(
+        data: ChatSessionData,
+        profile: Uuid,
+        keys: &ProfileKeys,
+        now: i64
+    ) -> Result<Self, ChatValidationError> {
+        if data.get_expires_at() < now {
+            return Err(ChatValidationError::ExpiredPublicKey);
+        }
+        let (most, least) = profile.as_u64_pair();
+        let mut payload = Vec::with_capacity(24 + data.get_public_key().len());
+        payload.extend(most.to_be_bytes());
+        payload.extend(least.to_be_bytes());
+        payload.extend(data.get_expires_at().to_be_bytes());
+        payload.extend(data.get_public_key());
+        if !keys.verify(&payload, data.get_key_signature()) {
+            return Err(ChatValidationError::InvalidPublicKeySignature);
+        }
+        let key = RsaPublicKey::from_public_key_der(data.get_public_key())
+            .map_err(|_| ChatValidationError::InvalidPublicKey)?;
+        Ok(Self { data, key })
+    }
+
+    pub fn get_data(&self) -> &ChatSessionData {
+        &self.data
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.data.get_expires_at() < now
+    }
+
+    /// Verifies the SHA256withRSA signature of a chat message.
+    /// `timestamp` is in milliseconds since the unix epoch, as sent by the client.
+    pub fn verify_message(
+        &self,
+        sender: Uuid,
+        index: i32,
+        salt: i64,
+        timestamp: i64,
+        message: &str,
+        last_seen: &[MessageSignature],
+        signature: &MessageSignature
+    ) -> bool {
+        let mut payload: Vec<u8> = Vec::with_capacity(64 + message.len() + last_seen.len() * 256);
+        payload.extend(1i32.to_be_bytes());
+        payload.extend(sender.as_bytes());
+        payload.extend(self.data.get_session_id().as_bytes());
+        payload.extend(index.to_be_bytes());
+        payload.extend(salt.to_be_bytes());
+        payload.extend((timestamp / 1000).to_be_bytes());
+        payload.extend((message.len() as i32).to_be_bytes());
+        payload.extend(message.as_bytes());
+        payload.extend((last_seen.len() as i32).to_be_bytes());
+        last_seen.iter().for_each(|seen| payload.extend(seen.get_bytes()));
+
+        let Ok(signature) = Signature::try_from(signature.get_bytes().as_slice()) else { return false };
+        VerifyingKey::<Sha256>::new(self.key.clone()).verify(&payload, &signature).is_ok()
+    }
+}
+
+/// Tracks the signed messages sent to a player, to validate
+/// which of them the player claims to have seen when they chat.
+#[derive(Debug)]
+pub struct LastSeenMessagesValidator {
+    //The entry and whether it is still pending
+    tracked: VecDeque<Option<(MessageSignature, bool)>>,
+}
+
+impl LastSeenMessagesValidator {
+    pub fn new() -> Self {
+        Self { tracked: (0..LAST_SEEN_LENGTH).map(|_| None).collect() }
+    }
+
+    /// Records a signed message sent to the player
+    pub fn add_pending(&mut self, signature: MessageSignature) -> Result<(), ChatValidationError> {
+        self.tracked.push_back(Some((signature, true)));
+        if self.tracked.len() > MAX_PENDING_MESSAGES {
+            return Err(ChatValidationError::TooManyPendingChats);
+        }
+        Ok(())
+    }
+
+    /// Moves the window forward by `offset` messages
+    pub fn apply_offset(&mut self, offset: i32) -> Result<(), ChatValidationError> {
+        let max = self.tracked.len() - LAST_SEEN_LENGTH;
+        if offset < 0 || offset as usize > max {
+            return Err(ChatValidationError::ChatValidationFailed(format!(
+                "Advanced last seen window by {offset} messages, but expected at most {max}"
+            )));
+        }
+        self.tracked.drain(..offset as usize);
+        Ok(())
+    }
+
+    /// Applies the update sent with a chat message, returning the signatures of
+    /// the acknowledged messages, oldest first.
+    pub fn apply_update(
+        &mut self,
+        offset: i32,
+        acknowledged: AcknowledgedMessages
+    ) -> Result<Vec<MessageSignature>, ChatValidationError> {
+        self.apply_offset(offset)?;
+        let mut last_seen = Vec::with_capacity(acknowledged.count() as usize);
+        for i in 0..LAST_SEEN_LENGTH {
+            let entry = &mut self.tracked[i];
+            if acknowledged.is_acknowledged(i) {
+                match entry {
+                    Some((signature, pending)) => {
+                        *pending = false;
+                        last_seen.push(signature.clone());
+                    },
+                    None => return Err(ChatValidationError::ChatValidationFailed(format!(
+                        "Last seen update acknowledged unknown or previously ignored message at index {i}"
+                    ))),
+                }
+            } else {
+                if matches!(entry, Some((_, false))) {
+                    return Err(ChatValidationError::ChatValidationFailed(format!(
+                        "Last seen update ignored previously acknowledged message at index {i}"
+                    )));
+                }
+                *entry = None;
+            }
+        }
+        Ok(last_seen)
+    }
+}
+
+/// The secure chat state of a player
+#[derive(Debug)]
+pub struct ChatState {
+    session: Option<RemoteChatSession>,
+    last_seen: LastSeenMessagesValidator,
+    next_index: i32,
+    last_timestamp: i64,
+}
+
+/// The chain data of a chat message whose signature has been verified
+#[derive(Debug, Clone)]
+pub struct SignedChat {
+    index: i32,
+    signature: MessageSignature,
+    last_seen: Vec<MessageSignature>,
+}
+
+impl SignedChat {
+    /// The index of the message in the sender's chat session
+    pub fn get_index(&self) -> i32 {
+        self.index
+    }
+
+    pub fn get_signature(&self) -> &MessageSignature {
+        &self.signature
+    }
+
+    /// The signatures of the messages the sender had seen, oldest first
+    pub fn get_last_seen(&self) -> &[MessageSignature] {
+        &self.last_seen
+    }
+}
+
+impl ChatState {
+    pub fn new() -> Self {
+        Self {
+            session: None,
+            last_seen: LastSeenMessagesValidator::new(),
+            next_index: 0,
+            last_timestamp: i64::MIN,
+        }
+    }
+
+    pub fn get_session(&self) -> Option<&RemoteChatSession> {
+        self.session.as_ref()
+    }
+
+    /// Starts a new message chain with the session
+    pub fn set_session(&mut self, session: RemoteChatSession) {
+        self.session = Some(session);
+        self.next_index = 0;
+    }
+
+    pub fn add_pending(&mut self, signature: MessageSignature) -> Result<(), ChatValidationError> {
+        self.last_seen.add_pending(signature)
+    }
+
+    pub fn apply_offset(&mut self, offset: i32) -> Result<(), ChatValidationError> {
+        self.last_seen.apply_offset(offset)
+    }
+
+    /// Validates the last seen update and order of a chat message or signed command,
+    /// returning the signatures of the acknowledged messages.
+    pub fn apply_update(
+        &mut self,
+        timestamp: i64,
+        offset: i32,
+        acknowledged: AcknowledgedMessages
+    ) -> Result<Vec<MessageSignature>, ChatValidationError> {
+        let last_seen = self.last_seen.apply_update(offset, acknowledged)?;
+        if timestamp < self.last_timestamp {
+            return Err(ChatValidationError::OutOfOrderChat);
+        }
+        self.last_timestamp = timestamp;
+        Ok(last_seen)
+    }
+
+    /// Validates the last seen update and, if the player has a chat session, the signature.
+    /// Returns `None` for unsigned messages which are allowed.
+    ///
+    /// `enforce` is whether unsigned chat is rejected, `now` is in milliseconds since the unix epoch.
+    pub fn validate(
+        &mut self,
+        sender: Uuid,
+        message: &str,
+        timestamp: i64,
+        salt: i64,
+        signature: Option<&MessageSignature>,
+        offset: i32,
+        acknowledged: AcknowledgedMessages,
+        enforce: bool,
+        now: i64,
+    ) -> Result<Option<SignedChat>, ChatValidationError> {
+        let last_seen = self.apply_update(timestamp, offset, acknowledged)?;
+
+        match (&self.session, signature) {
+            (Some(session), Some(signature)) => {
+                if session.is_expired(now) {
+                    return Err(ChatValidationError::ExpiredPublicKey);
+                }
+                let index = self.next_index;
+                if !session.verify_message(sender, index, salt, timestamp, message, &last_seen, signature) {
+                    return Err(ChatValidationError::UnsignedChat);
+                }
+                self.next_index += 1;
+                Ok(Some(SignedChat { index, signature: signature.clone(), last_seen }))
+            },
+            (Some(_), None) => Err(ChatValidationError::UnsignedChat),
+            (None, _) if enforce => Err(ChatValidationError::UnsignedChat),
+            (None, _) => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rsa::pkcs1v15::SigningKey;
+    use rsa::pkcs8::EncodePublicKey;
+    use rsa::signature::{SignatureEncoding, Signer};
+    use rsa::RsaPrivateKey;
+
+    use super::*;
+
+    fn signature(byte: u8) -> MessageSignature {
+        MessageSignature::new([byte; 256])
+    }
+
+    fn acknowledged(bits: [u8; 3]) -> AcknowledgedMessages {
+        use crate::data_types::FromProtocol;
+        AcknowledgedMessages::from_protocol_iter(&mut bits.into_iter()).unwrap()
+    }
+
+    #[test]
+    fn last_seen_chain() {
+        let mut validator = LastSeenMessagesValidator::new();
+        validator.add_pending(signature(1)).unwrap();
+        validator.add_pending(signature(2)).unwrap();
+
+        //Both messages are now the newest entries of the window
+        validator.apply_offset(2).unwrap();
+        let seen = validator.apply_update(0, acknowledged([0, 0, 0b1100])).unwrap();
+        assert_eq!(seen, vec![signature(1), signature(2)]);
+
+        //Ignoring a message which was acknowledged before is invalid
+        assert!(validator.apply_update(0, acknowledged([0, 0, 0b1000])).is_err());
+    }
+
+    #[test]
+    fn last_seen_unknown_message() {
+        let mut validator = LastSeenMessagesValidator::new();
+        assert!(validator.apply_update(0, acknowledged([1, 0, 0])).is_err());
+        assert!(validator.apply_offset(1).is_err());
+    }
+
+    #[test]
+    fn session_key_signature() {
+        let mut rng = rsa::rand_core::OsRng;
+        let mojang = RsaPrivateKey::new(&mut rng, 1024).unwrap();
+        let keys = ProfileKeys::new(vec![RsaPublicKey::from(&mojang)]);
+
+        let player_key = RsaPublicKey::from(&mojang).to_public_key_der().unwrap().as_bytes().to_vec();
+        let profile = Uuid::new_v4();
+        let expires_at = 2_000_000_000_000i64;
+
+        let mut payload = Vec::new();
+        payload.extend(profile.as_bytes());
+        payload.extend(expires_at.to_be_bytes());
+        payload.extend(player_key.iter());
+        let key_signature = SigningKey::<Sha1>::new(mojang).sign(&payload).to_vec();
+
+        let data = ChatSessionData::new(Uuid::new_v4(), expires_at, player_key, key_signature);
+        assert!(RemoteChatSession::validate(data.clone(), profile, &keys, 0).is_ok());
+        assert_eq!(
+            RemoteChatSession::validate(data.clone(), Uuid::new_v4(), &keys, 0).unwrap_err(),
+            ChatValidationError::InvalidPublicKeySignature
+        );
+        assert_eq!(
+            RemoteChatSession::validate(data, profile, &keys, expires_at + 1).unwrap_err(),
+            ChatValidationError::ExpiredPublicKey
+        );
+    }
+}
@@ -3,6 +3,31 @@ use std::{
     hash::Hash
 };
 
+use crate::data_types::{ArgumentParser, ToProtocol, VarInt};
+
+/// Ids of the argument types in the `minecraft:command_argument_type` registry
+pub mod parser_id {
+    pub const BOOL: i32 = 0;
+    pub const FLOAT: i32 = 1;
+    pub const DOUBLE: i32 = 2;
+    pub const INTEGER: i32 = 3;
+    pub const LONG: i32 = 4;
+    pub const STRING: i32 = 5;
+    pub const ENTITY: i32 = 6;
+    pub const BLOCK_POS: i32 = 8;
+    pub const VEC3: i32 = 10;
+    pub const BLOCK_STATE: i32 = 12;
+    pub const ITEM_STACK: i32 = 14;
+    pub const COMPONENT: i32 = 17;
+    pub const NBT_COMPOUND_TAG: i32 = 20;
+    pub const RESOURCE_LOCATION: i32 = 35;
+    pub const FUNCTION: i32 = 36;
+    pub const GAMEMODE: i32 = 41;
+    pub const TIME: i32 = 42;
+    pub const RESOURCE: i32 = 45;
+    pub const UUID: i32 = 53;
+}
+
 #[derive(Debug, Clone, PartialOrd)]
 pub enum ArgType {
     Command { label: String },
@@ -13,6 +38,29 @@ pub enum ArgType {
     Int { bounds: Option<NumericArgProperties<i32>> },
     Long { bounds: Option<NumericArgProperties<i64>> },
     String { properties: Option<StringArgProperties> },
+    /// A selector, player name or UUID, see [`EntitySelector`](super::selector::EntitySelector)
+    Entity { single: bool, players_only: bool },
+    /// Integer coordinates of a block, see [`Coordinates`](super::coordinates::Coordinates)
+    BlockPos,
+    /// Coordinates of a position
+    Vec3,
+    /// A block with optional properties and NBT, like `oak_stairs[facing=east]`
+    BlockState,
+    /// An item with optional components, like `diamond_sword[damage=5]`
+    Item,
+    /// A resource location, which must be an entry of `registry` if there is one
+    Identifier { registry: Option<String> },
+    /// An SNBT compound
+    Nbt,
+    /// A duration in ticks, like `3d`, `10s` or `20t`, of at least `min` ticks
+    Time { min: i32 },
+    Uuid,
+    /// A text component as JSON, or a plain string
+    Component,
+    /// The name of a game mode, like `creative`
+    GameMode,
+    /// A function, or a function tag starting with `#`
+    Function,
 }
 
 impl PartialEq for ArgType {
@@ -30,7 +78,62 @@ impl Hash for ArgType {
 impl Eq for ArgType {}
 
 impl ArgType {
+    /// Whether the argument is sent to clients as a literal node
+    pub fn is_literal(&self) -> bool {
+        matches!(self, ArgType::Command { .. } | ArgType::Subcommand)
+    }
 
+    /// The Brigadier parser of the argument, `None` for literals
+    pub fn get_parser(&self) -> Option<ArgumentParser> {
+        let parser = match self {
+            ArgType::Command { .. } | ArgType::Subcommand => return None,
+            ArgType::Bool => ArgumentParser::new(parser_id::BOOL, vec![]),
+            ArgType::Float { bounds } => ArgumentParser::new(parser_id::FLOAT, numeric_properties(bounds)),
+            ArgType::Double { bounds } => ArgumentParser::new(parser_id::DOUBLE, numeric_properties(bounds)),
+            ArgType::Int { bounds } => ArgumentParser::new(parser_id::INTEGER, numeric_properties(bounds)),
+            ArgType::Long { bounds } => ArgumentParser::new(parser_id::LONG, numeric_properties(bounds)),
+            ArgType::String { properties } => {
+                let string_type = match properties.as_ref().map(|p| p.get_type()) {
+                    Some(StringType::Word) | None => 0,
+                    Some(StringType::Phrase) => 1,
+                    Some(StringType::Greedy) => 2,
+                };
+                ArgumentParser::new(parser_id::STRING, VarInt::new(string_type).to_protocol_bytes())
+            },
+            ArgType::Entity { single, players_only } => {
+                let flags = (*single as u8) | (*players_only as u8) << 1;
+                ArgumentParser::new(parser_id::ENTITY, vec![flags])
+            },
+            ArgType::BlockPos => ArgumentParser::new(parser_id::BLOCK_POS, vec![]),
+            ArgType::Vec3 => ArgumentParser::new(parser_id::VEC3, vec![]),
+            ArgType::BlockState => ArgumentParser::new(parser_id::BLOCK_STATE, vec![]),
+            ArgType::Item => ArgumentParser::new(parser_id::ITEM_STACK, vec![]),
+            ArgType::Identifier { registry: None } => ArgumentParser::new(parser_id::RESOURCE_LOCATION, vec![]),
+            ArgType::Identifier { registry: Some(registry) } => {
+                ArgumentParser::new(parser_id::RESOURCE, registry.to_protocol_bytes())
+            },
+            ArgType::Nbt => ArgumentParser::new(parser_id::NBT_COMPOUND_TAG, vec![]),
+            ArgType::Time { min } => ArgumentParser::new(parser_id::TIME, min.to_protocol_bytes()),
+            ArgType::Uuid => ArgumentParser::new(parser_id::UUID, vec![]),
+            ArgType::Component => ArgumentParser::new(parser_id::COMPONENT, vec![]),
+            ArgType::GameMode => ArgumentParser::new(parser_id::GAMEMODE, vec![]),
+            ArgType::Function => ArgumentParser::new(parser_id::FUNCTION, vec![]),
+        };
+        Some(parser)
+    }
+}
+
+/// Brigadier's numeric properties: a flags byte for which bounds are present, then the bounds.
+fn numeric_properties<T: Numeric>(bounds: &Option<NumericArgProperties<T>>) -> Vec<u8> {
+    match bounds {
+        Some(bounds) => {
+            let mut out = vec![0x03];
+            out.append(&mut bounds.get_min().to_protocol_bytes());
+            out.append(&mut bounds.get_max().to_protocol_bytes());
+            out
+        },
+        None => vec![0x00],
+    }
 }
 
 
@@ -87,7 +190,7 @@ impl Display for Argument {
 
 trait Properties {}
 
-trait Numeric: Display + Copy {}
+pub trait Numeric: Display + Copy + ToProtocol {}
 impl Numeric for i32 {}
 impl Numeric for i64 {}
 impl Numeric for f32 {}
@@ -100,6 +203,10 @@ pub struct NumericArgProperties<T: Numeric> {
 }
 
 impl<T: Numeric> NumericArgProperties<T> {
+    pub fn new(min: T, max: T) -> Self {
+        Self { min, max }
+    }
+
     pub fn get_min(&self) -> T {
         self.min
     }
@@ -116,6 +223,16 @@ pub struct StringArgProperties {
     r#type: StringType
 }
 
+impl StringArgProperties {
+    pub fn new(r#type: StringType) -> Self {
+        Self { r#type }
+    }
+
+    pub fn get_type(&self) -> &StringType {
+        &self.r#type
+    }
+}
+
 impl Properties for StringArgProperties {}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd)]
@@ -0,0 +1,317 @@
+use std::sync::Arc;
+
+use itertools::Itertools;
+
+use crate::data_types::{CommandGraphNode, CommandGraphNodeType, Identifier, Suggestion};
+use crate::packet::play::{CCommandSuggestionsResponse, CCommands};
+use crate::data_types::VarInt;
+use crate::game::gamemode::Gamemode;
+use crate::player::Player;
+use crate::{BUILTIN_REGISTRIES, COMMAND_MAP};
+
+use super::argument::ArgType;
+use super::command_tree::{CommandNode, Redirect};
+use super::parser::{parse_argument, StringReader};
+use super::{Command, CommandMap, CommandSender};
+
+/// Nodes with this suggestion type make the client send `SCommandSuggestionsRequest`
+const ASK_SERVER: &str = "minecraft:ask_server";
+
+/// Flattens the commands `sender` may use into the nodes of a Brigadier graph.
+/// The root is the first node.
+pub fn build_graph(command_map: &CommandMap, sender: &CommandSender) -> Vec<CommandGraphNode> {
+    let mut nodes = vec![CommandGraphNode::root()];
+
+    let mut commands: Vec<&Command> = command_map.get_commands()
+        .filter(|command| command.can_use(sender))
+        .collect();
+    commands.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+
+    for command in commands {
+        let root = nodes.len() as i32;
+        let index = add_node(&mut nodes, command.get_usage_tree(), command, root);
+        nodes[0].add_child(index);
+
+        //Aliases forward to the arguments of the command
+        let executable = nodes[index as usize].is_executable();
+        for alias in command.get_aliases() {
+            let mut node = CommandGraphNode::new(
+                CommandGraphNodeType::Literal { name: alias.clone() },
+                executable
+            );
+            node.set_redirect(Some(index));
+            nodes.push(node);
+            let alias_index = nodes.len() as i32 - 1;
+            nodes[0].add_child(alias_index);
+        }
+    }
+    nodes
+}
+
+/// `root` is the index of the command's own node, which redirects back to the command point to
+fn add_node(nodes: &mut Vec<CommandGraphNode>, node: &CommandNode, command: &Command, root: i32) -> i32 {
+    let argument = node.get_argument();
+    let node_type = match argument.get_type().get_parser() {
+        Some(parser) => CommandGraphNodeType::Argument {
+            name: argument.get_name().to_string(),
+            parser,
+            suggestions: command.get_suggestion_provider(argument.get_name())
+                .map(|_| Identifier::new(ASK_SERVER).unwrap()),
+        },
+        None => CommandGraphNodeType::Literal { name: argument.get_name().to_string() },
+    };
+    let mut graph_node = CommandGraphNode::new(node_type, node.is_executable());
+    match node.get_redirect() {
+        Some(Redirect::Command) => graph_node.set_redirect(Some(root)),
+        Some(Redirect::Root) => graph_node.set_redirect(Some(0)),
+        None => {},
+    }
+    nodes.push(graph_node);
+    let index = nodes.len() as i32 - 1;
+    for child in node.get_children() {
+        let child_index = add_node(nodes, child, command, root);
+        nodes[index as usize].add_child(child_index);
+    }
+    index
+}
+
+/// Sends the commands the player may use, which is needed for the client to
+/// highlight and complete commands.
+pub async fn send_commands(player: &Arc<Player>) {
+    let sender = CommandSender::Player { player: Arc::downgrade(player) };
+    let nodes = match COMMAND_MAP.lock().await.as_ref() {
+        Some(command_map) => build_graph(command_map, &sender),
+        None => vec![CommandGraphNode::root()],
+    };
+    if player.send_packet(CCommands::new(nodes, VarInt::new(0))).await.is_err() {
+        player.connection_lost().await;
+    }
+}
+
+/// Answers `SCommandSuggestionsRequest`, `text` includes the leading `/`.
+pub async fn get_suggestions_response(
+    player: &Arc<Player>,
+    transaction_id: i32,
+    text: &str
+) -> CCommandSuggestionsResponse {
+    let sender = CommandSender::Player { player: Arc::downgrade(player) };
+    let online = get_online_names().await;
+    let (start, matches) = match COMMAND_MAP.lock().await.as_ref() {
+        Some(command_map) => get_suggestions(command_map, &sender, text, &online),
+        None => (0, vec![]),
+    };
+    let length = text.len().saturating_sub(start);
+    CCommandSuggestionsResponse::new(
+        VarInt::new(transaction_id),
+        VarInt::new(start as i32),
+        VarInt::new(length as i32),
+        matches
+    )
+}
+
+/// The names of the online players, which entity arguments suggest
+pub async fn get_online_names() -> Vec<String> {
+    crate::THE_SERVER.get_players_async().await.iter()
+        .filter_map(std::sync::Weak::upgrade)
+        .map(|player| player.get_name().to_string())
+        .collect()
+}
+
+/// Completes the last argument of `text`. Returns the byte offset the matches
+/// replace from, up to the end of `text`. `online` are the player names from `get_online_names`.
+pub fn get_suggestions(
+    command_map: &CommandMap,
+    sender: &CommandSender,
+    text: &str,
+    online: &[String]
+) -> (usize, Vec<Suggestion>) {
+    let offset = if text.starts_with('/') { 1 } else { 0 };
+    let input = &text[offset..];
+
+    let Some((label, remaining)) = input.split_once(' ') else {
+        let mut labels: Vec<&str> = command_map.get_commands()
+            .filter(|command| command.can_use(sender))
+            .flat_map(|command| {
+                std::iter::once(command.get_name())
+                    .chain(command.get_aliases().iter().map(String::as_str))
+            })
+            .filter(|label| label.starts_with(input))
+            .collect();
+        labels.sort();
+        return (offset, labels.into_iter().map(Suggestion::from).collect());
+    };
+
+    let Some(command) = command_map.get_command(label).filter(|command| command.can_use(sender)) else {
+        return (text.len(), vec![]);
+    };
+
+    let mut reader = StringReader::new(text);
+    reader.set_cursor(text.len() - remaining.len());
+    let mut node = command.get_usage_tree();
+    loop {
+        //Descend into the first child which parses and is followed by another argument
+        let start = reader.get_cursor();
+        let next = node.get_children().iter().find_map(|child| {
+            let mut child_reader = reader.clone();
+            parse_argument(&mut child_reader, child.get_argument()).ok()
+                .filter(|_| child_reader.peek() == Some(' '))
+                .map(|_| (child, child_reader))
+        });
+        match next {
+            Some((child, child_reader)) => {
+                reader = child_reader;
+                reader.skip();
+                node = match child.get_redirect() {
+                    Some(Redirect::Command) => command.get_usage_tree(),
+                    //The rest is another command, completed on its own
+                    Some(Redirect::Root) => {
+                        let start = reader.get_cursor();
+                        let (from, matches) = get_suggestions(command_map, sender, &text[start..], online);
+                        return (start + from, matches);
+                    },
+                    None => child,
+                };
+            },
+            None => return (start, suggest(command, node, sender, &text[start..], online)),
+        }
+    }
+}
+
+fn suggest(command: &Command, node: &CommandNode, sender: &CommandSender, partial: &str, online: &[String]) -> Vec<Suggestion> {
+    let lowercase = partial.to_lowercase();
+    let mut out: Vec<Suggestion> = Vec::new();
+    for child in node.get_children() {
+        let argument = child.get_argument();
+        let suggestions = match (argument.get_type(), command.get_suggestion_provider(argument.get_name())) {
+            (_, Some(provider)) => provider(sender, partial),
+            (ArgType::Command { .. } | ArgType::Subcommand, None) => vec![Suggestion::from(argument.get_name())],
+            (ArgType::Bool, None) => vec![Suggestion::from("true"), Suggestion::from("false")],
+            (ArgType::Entity { .. }, None) => suggest_entities(online),
+            (ArgType::BlockPos | ArgType::Vec3, None) => vec![Suggestion::from("~ ~ ~"), Suggestion::from("^ ^ ^")],
+            (ArgType::BlockState, None) => suggest_registry("minecraft:block"),
+            (ArgType::Item, None) => suggest_registry("minecraft:item"),
+            (ArgType::Identifier { registry: Some(registry) }, None) => suggest_registry(registry),
+            (ArgType::GameMode, None) => Gamemode::ALL.iter()
+                .map(|game_mode| Suggestion::from(game_mode.get_name()))
+                .collect(),
+            _ => vec![],
+        };
+        out.extend(suggestions.into_iter().filter(|suggestion| {
+            let text = suggestion.get_text().to_lowercase();
+            //Entries of the default namespace may be typed without it
+            text.starts_with(&lowercase)
+                || text.strip_prefix("minecraft:").is_some_and(|text| text.starts_with(&lowercase))
+        }));
+    }
+    out
+}
+
+/// The entries of a builtin registry, sorted
+fn suggest_registry(registry: &str) -> Vec<Suggestion> {
+    let Some(mapping) = BUILTIN_REGISTRIES.get(registry) else {
+        return vec![];
+    };
+    mapping.get_names().sorted().map(|name| Suggestion::from(name.as_str())).collect()
+}
+
+/// The selector kinds and the names of online players
+fn suggest_entities(online: &[String]) -> Vec<Suggestion> {
+    ["@a", "@e", "@p", "@r", "@s"].into_iter()
+        .chain(online.iter().map(String::as_str))
+        .map(Suggestion::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::argument::Argument;
+    use crate::command::{CommandMapBuilder, CommandSuccess};
+
+    use super::*;
+
+    fn suggest_colors(_: &CommandSender, _: &str) -> Vec<Suggestion> {
+        vec![Suggestion::from("red"), Suggestion::from("green")]
+    }
+
+    fn command_map() -> CommandMap {
+        let args = [
+            Argument::new("color".to_string(), ArgType::String { properties: None }, true),
+            Argument::new("amount".to_string(), ArgType::Int { bounds: None }, false),
+        ];
+        let mut builder = CommandMapBuilder::new();
+        builder.register_command(Command::new(
+            "paint",
+            None,
+            CommandNode::single_usage("paint", &args),
+            None,
+            &["p".to_string()],
+            |_| Box::pin(std::future::ready(Ok(CommandSuccess::new(1))))
+        ).with_suggestions("color", suggest_colors)).unwrap();
+        builder.build()
+    }
+
+    #[test]
+    fn graph() {
+        let nodes = build_graph(&command_map(), &CommandSender::Console);
+        let root = &nodes[0];
+        //The builtin commands and their aliases, then paint and its alias
+        let builtin: usize = crate::command::builtin::get_commands().iter()
+            .map(|command| 1 + command.get_aliases().len())
+            .sum();
+        assert_eq!(root.get_children().len(), builtin + 2);
+
+        let paint = root.get_children().iter()
+            .map(|child| &nodes[*child as usize])
+            .find(|node| matches!(node.get_type(), CommandGraphNodeType::Literal { name } if name == "paint"))
+            .unwrap();
+        assert!(!paint.is_executable());
+        let color = &nodes[paint.get_children()[0] as usize];
+        assert!(matches!(color.get_type(), CommandGraphNodeType::Argument { suggestions: Some(_), .. }));
+        //The amount is optional
+        assert!(color.is_executable());
+
+        let alias = root.get_children().iter()
+            .map(|child| &nodes[*child as usize])
+            .find(|node| matches!(node.get_type(), CommandGraphNodeType::Literal { name } if name == "p"))
+            .unwrap();
+        assert!(alias.get_redirect().is_some());
+
+        //The subcommands of execute lead back to it, and run to the root
+        let find = |children: &[i32], name: &str| children.iter().copied()
+            .find(|child| matches!(nodes[*child as usize].get_type(),
+                CommandGraphNodeType::Literal { name: n } | CommandGraphNodeType::Argument { name: n, .. } if n == name))
+            .unwrap();
+        let execute = find(root.get_children(), "execute");
+        let as_ = find(nodes[execute as usize].get_children(), "as");
+        let targets = &nodes[find(nodes[as_ as usize].get_children(), "targets") as usize];
+        assert_eq!(targets.get_redirect(), Some(execute));
+        assert!(!targets.is_executable());
+        let run = &nodes[find(nodes[execute as usize].get_children(), "run") as usize];
+        assert_eq!(run.get_redirect(), Some(0));
+    }
+
+    #[test]
+    fn suggestions() {
+        let command_map = command_map();
+        let sender = CommandSender::Console;
+
+        let (start, labels) = get_suggestions(&command_map, &sender, "/pai", &[]);
+        assert_eq!(start, 1);
+        assert_eq!(labels.iter().map(Suggestion::get_text).collect::<Vec<_>>(), vec!["paint"]);
+
+        let (start, colors) = get_suggestions(&command_map, &sender, "/paint gr", &[]);
+        assert_eq!(start, 7);
+        assert_eq!(colors.iter().map(Suggestion::get_text).collect::<Vec<_>>(), vec!["green"]);
+
+        let (_, none) = get_suggestions(&command_map, &sender, "/paint red 1", &[]);
+        assert!(none.is_empty());
+
+        //Through the redirects of execute
+        let (start, subcommands) = get_suggestions(&command_map, &sender, "/execute as @a po", &[]);
+        assert_eq!(start, 15);
+        assert_eq!(subcommands.iter().map(Suggestion::get_text).collect::<Vec<_>>(), vec!["positioned"]);
+        let (start, colors) = get_suggestions(&command_map, &sender, "/execute at @s run paint r", &[]);
+        assert_eq!(start, 25);
+        assert_eq!(colors.iter().map(Suggestion::get_text).collect::<Vec<_>>(), vec!["red"]);
+    }
+}
@@ -0,0 +1,323 @@
+use std::net::IpAddr;
+use std::sync::Weak;
+
+use chrono::{Local, TimeDelta};
+use server_macros::{command, CommandEnum};
+use uuid::Uuid;
+
+use crate::command::argument::ArgType;
+use crate::command::brigadier;
+use crate::command::command_tree::CommandNode;
+use crate::command::parameter::GreedyString;
+use crate::command::{Command, CommandArg, CommandError, CommandFuture, CommandResult, CommandSender, CommandSuccess};
+use crate::event::events::command::CommandEvent;
+use crate::server::user_list::{BanDetails, IpBanEntry, OpEntry, PlayerBanEntry, UserListError, WhitelistEntry};
+use crate::state::login_state::get_player_uuid;
+use crate::THE_SERVER;
+
+use super::{get_string, literal, permission, required};
+
+pub(super) fn get_commands() -> Vec<Command> {
+    let player = || required("player", ArgType::String { properties: None });
+    vec![
+        Command::new(
+            "whitelist",
+            Some("Manages who may join while the whitelist is on."),
+            CommandNode::multi_usage("whitelist", &[
+                &[literal("on")],
+                &[literal("off")],
+                &[literal("list")],
+                &[literal("reload")],
+                &[literal("add"), player()],
+                &[literal("remove"), player()],
+            ]),
+            Some(&permission("whitelist")),
+            &[],
+            command_whitelist
+        ),
+    ]
+}
+
+/// An online player by name, otherwise the player Mojang knows by the name,
+/// or the offline player in offline mode
+async fn find_profile(name: &str) -> Result<(Uuid, String), CommandError> {
+    if let Some(player) = THE_SERVER.get_player_by_name_async(name).await.and_then(|player| player.upgrade()) {
+        return Ok((player.get_uuid(), player.get_name().to_string()));
+    }
+    get_player_uuid(&name.to_string()).await
+        .map(|uuid| (uuid, name.to_string()))
+        .map_err(|_| "That player does not exist".into())
+}
+
+fn save_error(e: UserListError) -> CommandError {
+    format!("Couldn't save the list: {e}").into()
+}
+
+/// Resends the commands to the player if they are online, after their permissions changed
+async fn refresh(uuid: Uuid) {
+    if let Some(player) = THE_SERVER.get_player_by_uuid_async(uuid).await.and_then(|player| player.upgrade()) {
+        brigadier::send_commands(&player).await;
+    }
+}
+
+/// Makes a player a server operator.
+#[command(permission = "minecraft.command.op")]
+async fn op(player: String) -> CommandResult {
+    let (uuid, name) = find_profile(&player).await?;
+    if THE_SERVER.get_ops().contains(&uuid) {
+        return Err("Nothing changed. The player already is an operator".into());
+    }
+    let level = THE_SERVER.get_properties().get_op_permission_level();
+    THE_SERVER.get_ops().add(OpEntry::new(uuid, &name, level)).map_err(save_error)?;
+    refresh(uuid).await;
+    Ok(CommandSuccess::new(1).with_message(&format!("Made {name} a server operator")))
+}
+
+/// Makes a player no longer a server operator.
+#[command(permission = "minecraft.command.deop")]
+async fn deop(player: String) -> CommandResult {
+    let (uuid, name) = find_profile(&player).await?;
+    if !THE_SERVER.get_ops().remove(&uuid).map_err(save_error)? {
+        return Err("Nothing changed. The player is not an operator".into());
+    }
+    refresh(uuid).await;
+    Ok(CommandSuccess::new(1).with_message(&format!("Made {name} no longer a server operator")))
+}
+
+fn command_whitelist(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let whitelist = THE_SERVER.get_whitelist();
+        let Some(CommandArg::Subcommand(action, rest)) = e.get_parsed_args().first() else {
+            return Err(CommandError::InvalidUsage { command: e.get_command().clone() });
+        };
+        let message = match (action.as_str(), get_string(rest.first())) {
+            ("on", _) => {
+                if THE_SERVER.is_whitelist_enabled() {
+                    return Err("Whitelist is already turned on".into());
+                }
+                THE_SERVER.set_whitelist_enabled(true)
+                    .map_err(|e| CommandError::from(format!("Couldn't save server.properties: {e}")))?;
+                THE_SERVER.enforce_whitelist().await;
+                "Whitelist is now turned on".to_string()
+            },
+            ("off", _) => {
+                if !THE_SERVER.is_whitelist_enabled() {
+                    return Err("Whitelist is already turned off".into());
+                }
+                THE_SERVER.set_whitelist_enabled(false)
+                    .map_err(|e| CommandError::from(format!("Couldn't save server.properties: {e}")))?;
+                "Whitelist is now turned off".to_string()
+            },
+            ("list", _) => {
+                let names: Vec<String> = whitelist.get_entries().iter()
+                    .map(|entry| entry.get_name().to_string())
+                    .collect();
+                let message = match names.len() {
+                    0 => "There are no whitelisted players".to_string(),
+                    count => format!("There are {count} whitelisted player(s): {}", names.join(", ")),
+                };
+                return Ok(CommandSuccess::new(names.len() as i32).with_message(&message).with_broadcast(false));
+            },
+            ("reload", _) => {
+                whitelist.reload().map_err(|e| CommandError::from(format!("Couldn't reload the whitelist: {e}")))?;
+                THE_SERVER.enforce_whitelist().await;
+                "Reloaded the whitelist".to_string()
+            },
+            ("add", Some(player)) => {
+                let (uuid, name) = find_profile(player).await?;
+                if whitelist.contains(&uuid) {
+                    return Err("Player is already whitelisted".into());
+                }
+                whitelist.add(WhitelistEntry::new(uuid, &name)).map_err(save_error)?;
+                format!("Added {name} to the whitelist")
+            },
+            ("remove", Some(player)) => {
+                let (uuid, name) = find_profile(player).await?;
+                if !whitelist.remove(&uuid).map_err(save_error)? {
+                    return Err("Player is not whitelisted".into());
+                }
+                THE_SERVER.enforce_whitelist().await;
+                format!("Removed {name} from the whitelist")
+            },
+            _ => return Err(CommandError::InvalidUsage { command: e.get_command().clone() }),
+        };
+        Ok(CommandSuccess::new(1).with_message(&message))
+    })
+}
+
+/// Parses durations like `30m`, `12h` or `1d12h`, in seconds, minutes, hours, days or weeks
+fn parse_duration(input: &str) -> Option<TimeDelta> {
+    let mut total = TimeDelta::zero();
+    let mut number = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let amount: i64 = number.parse().ok()?;
+        number.clear();
+        total = total.checked_add(&match c {
+            's' => TimeDelta::try_seconds(amount)?,
+            'm' => TimeDelta::try_minutes(amount)?,
+            'h' => TimeDelta::try_hours(amount)?,
+            'd' => TimeDelta::try_days(amount)?,
+            'w' => TimeDelta::try_weeks(amount)?,
+            _ => return None,
+        })?;
+    }
+    (number.is_empty() && total > TimeDelta::zero()).then_some(total)
+}
+
+fn get_source(sender: &CommandSender) -> String {
+    sender.get_name().unwrap_or("Server".to_string())
+}
+
+/// Bans the player and kicks them if they are online
+async fn ban_player(uuid: Uuid, name: &str, details: BanDetails) -> CommandResult {
+    if THE_SERVER.get_banned_players().contains(&uuid) {
+        return Err("Nothing changed. The player is already banned".into());
+    }
+    let message = match details.get_expires() {
+        Some(expires) => format!("Banned {name} until {}: {}", expires.format("%Y-%m-%d %H:%M:%S"), details.get_reason()),
+        None => format!("Banned {name}: {}", details.get_reason()),
+    };
+    let kick_message = details.get_kick_message("You are banned from this server.");
+    THE_SERVER.get_banned_players().add(PlayerBanEntry::new(uuid, name, details)).map_err(save_error)?;
+    if let Some(player) = THE_SERVER.get_player_by_uuid_async(uuid).await.and_then(|player| player.upgrade()) {
+        player.disconnect_tc(kick_message).await;
+    }
+    Ok(CommandSuccess::new(1).with_message(&message))
+}
+
+/// Bans a player from the server.
+#[command(permission = "minecraft.command.ban")]
+async fn ban(sender: &CommandSender, player: String, reason: Option<GreedyString>) -> CommandResult {
+    let (uuid, name) = find_profile(&player).await?;
+    let reason = reason.map(|reason| reason.0);
+    ban_player(uuid, &name, BanDetails::new(&get_source(sender), reason.as_deref(), None)).await
+}
+
+/// Bans a player from the server for a while, like `30m`, `12h` or `7d`.
+#[command(permission = "minecraft.command.tempban")]
+async fn tempban(
+    sender: &CommandSender,
+    player: String,
+    duration: String,
+    reason: Option<GreedyString>
+) -> CommandResult {
+    let Some(duration) = parse_duration(&duration) else {
+        return Err(format!("Invalid duration '{duration}', expected something like 30m, 12h or 7d").into());
+    };
+    let (uuid, name) = find_profile(&player).await?;
+    let reason = reason.map(|reason| reason.0);
+    let expires = Local::now().fixed_offset() + duration;
+    ban_player(uuid, &name, BanDetails::new(&get_source(sender), reason.as_deref(), Some(expires))).await
+}
+
+/// Bans an IP address, or the address of an online player, from the server.
+#[command(name = "ban-ip", permission = "minecraft.command.ban-ip")]
+async fn ban_ip(sender: &CommandSender, target: String, reason: Option<GreedyString>) -> CommandResult {
+    let ip = match target.parse::<IpAddr>() {
+        Ok(ip) => ip,
+        Err(_) => match THE_SERVER.get_player_by_name_async(&target).await.and_then(|player| player.upgrade()) {
+            Some(player) => player.get_addr().await.ip(),
+            None => return Err("Invalid IP address or unknown player".into()),
+        },
+    };
+    let ip = ip.to_string();
+    if THE_SERVER.get_banned_ips().contains(&ip) {
+        return Err("Nothing changed. That IP is already banned".into());
+    }
+    let reason = reason.map(|reason| reason.0);
+    let details = BanDetails::new(&get_source(sender), reason.as_deref(), None);
+    let mut success = CommandSuccess::new(1).with_message(&format!("Banned IP {ip}: {}", details.get_reason()));
+    let kick_message = details.get_kick_message("Your IP address is banned from this server.");
+    THE_SERVER.get_banned_ips().add(IpBanEntry::new(&ip, details)).map_err(save_error)?;
+
+    let mut banned = Vec::new();
+    for player in THE_SERVER.get_players_async().await.iter().filter_map(Weak::upgrade) {
+        if player.get_addr().await.ip().to_string() == ip {
+            banned.push(player.get_name().to_string());
+            player.disconnect_tc(kick_message.clone()).await;
+        }
+    }
+    if !banned.is_empty() {
+        success = success.with_message(&format!("This ban affects {} player(s): {}", banned.len(), banned.join(", ")));
+    }
+    Ok(success)
+}
+
+/// Unbans a player.
+#[command(permission = "minecraft.command.pardon")]
+async fn pardon(player: String) -> CommandResult {
+    //Banned players are found by the name they were banned with first
+    let banned = THE_SERVER.get_banned_players().get_entries().into_iter()
+        .find(|ban| ban.get_name().eq_ignore_ascii_case(&player));
+    let (uuid, name) = match banned {
+        Some(ban) => (ban.get_uuid(), ban.get_name().to_string()),
+        None => find_profile(&player).await?,
+    };
+    if !THE_SERVER.get_banned_players().remove(&uuid).map_err(save_error)? {
+        return Err("Nothing changed. The player isn't banned".into());
+    }
+    Ok(CommandSuccess::new(1).with_message(&format!("Unbanned {name}")))
+}
+
+/// Unbans an IP address.
+#[command(name = "pardon-ip", permission = "minecraft.command.pardon-ip")]
+async fn pardon_ip(target: String) -> CommandResult {
+    let Ok(ip) = target.parse::<IpAddr>() else {
+        return Err("Invalid IP address".into());
+    };
+    if !THE_SERVER.get_banned_ips().remove(&ip.to_string()).map_err(save_error)? {
+        return Err("Nothing changed. That IP isn't banned".into());
+    }
+    Ok(CommandSuccess::new(1).with_message(&format!("Unbanned IP {ip}")))
+}
+
+#[derive(Debug, PartialEq, CommandEnum)]
+enum BanListType {
+    Ips,
+    Players,
+}
+
+/// Lists the banned players and IP addresses.
+#[command(permission = "minecraft.command.banlist")]
+async fn banlist(list: Option<BanListType>) -> CommandResult {
+    let describe = |target: &str, details: &BanDetails| {
+        format!("{target} was banned by {}: {}", details.get_source(), details.get_reason())
+    };
+    let mut bans = Vec::new();
+    if list != Some(BanListType::Ips) {
+        bans.extend(THE_SERVER.get_banned_players().get_entries().iter()
+            .map(|ban| describe(ban.get_name(), ban.get_details())));
+    }
+    if list != Some(BanListType::Players) {
+        bans.extend(THE_SERVER.get_banned_ips().get_entries().iter()
+            .map(|ban| describe(ban.get_ip(), ban.get_details())));
+    }
+    if bans.is_empty() {
+        return Ok(CommandSuccess::new(0).with_message("There are no bans").with_broadcast(false));
+    }
+    Ok(bans.iter().fold(
+        CommandSuccess::new(bans.len() as i32)
+            .with_message(&format!("There are {} ban(s):", bans.len()))
+            .with_broadcast(false),
+        |success, ban| success.with_message(ban)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("30m"), TimeDelta::try_minutes(30));
+        assert_eq!(parse_duration("1d12h"), TimeDelta::try_hours(36));
+        assert_eq!(parse_duration("2w"), TimeDelta::try_days(14));
+        assert_eq!(parse_duration("12"), None);
+        assert_eq!(parse_duration("5y"), None);
+        assert_eq!(parse_duration("0s"), None);
+    }
+}
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use server_macros::command;
+
+use crate::command::parameter::GreedyString;
+use crate::command::CommandSender;
+use crate::data_types::TextComponent;
+use crate::player::Player;
+use crate::THE_SERVER;
+
+/// Sends a message to every player.
+#[command(permission = "minecraft.command.say")]
+async fn say(sender: &CommandSender, message: GreedyString) {
+    let name = sender.get_name().unwrap_or_default();
+    THE_SERVER.broadcast_text(TextComponent::builder().text(&format!("[{name}] {}", message.0)).build()).await;
+}
+
+/// Sends a private message to players.
+#[command(permission = "minecraft.command.msg", aliases = ["tell", "w"])]
+async fn msg(sender: &CommandSender, targets: Vec<Arc<Player>>, message: GreedyString) -> String {
+    let name = sender.get_name().unwrap_or_default();
+    let mut messages = Vec::new();
+    for player in targets.iter() {
+        player.send_message(format!("{name} whispers to you: {}", message.0)).await;
+        messages.push(format!("You whisper to {}: {}", player.get_name(), message.0));
+    }
+    messages.join("\n")
+}
@@ -0,0 +1,262 @@
+use std::sync::Arc;
+
+use crate::command::argument::{ArgType, NumericArgProperties};
+use crate::command::command_tree::CommandNode;
+use crate::command::selector::SelectorTarget;
+use crate::command::{Command, CommandArg, CommandError, CommandFuture, CommandSuccess};
+use crate::data_types::{PlayerInfoActions, Vec3d, VarInt};
+use crate::event::events::command::CommandEvent;
+use crate::game::gamemode::Gamemode;
+use crate::inventory;
+use crate::player::Player;
+use crate::packet::play::{CGameEvent, CPlayerInfoUpdate, CSetContainerSlot, CSetHealth};
+use crate::{BUILTIN_REGISTRIES, THE_SERVER};
+
+use super::{get_display_name, get_entity, optional, permission, required, resolve_players};
+
+const GAME_EVENT_CHANGE_GAME_MODE: u8 = 3;
+
+pub(super) fn get_commands() -> Vec<Command> {
+    let targets = || required("targets", ArgType::Entity { single: false, players_only: false });
+    let players = |mandatory: bool| match mandatory {
+        true => required("targets", ArgType::Entity { single: false, players_only: true }),
+        false => optional("targets", ArgType::Entity { single: false, players_only: true }),
+    };
+    let location = || required("location", ArgType::Vec3);
+    let destination = || required("destination", ArgType::Entity { single: true, players_only: false });
+    vec![
+        Command::new(
+            "teleport",
+            Some("Teleports entities to a location or another entity."),
+            CommandNode::multi_usage("teleport", &[
+                &[location()],
+                &[destination()],
+                &[targets(), location()],
+                &[targets(), destination()],
+            ]),
+            Some(&permission("teleport")),
+            &["tp".to_string()],
+            command_teleport
+        ),
+        Command::new(
+            "give",
+            Some("Gives an item to players."),
+            CommandNode::single_usage("give", &[
+                players(true),
+                required("item", ArgType::Item),
+                optional("count", ArgType::Int { bounds: Some(NumericArgProperties::new(1, i32::MAX)) }),
+            ]),
+            Some(&permission("give")),
+            &[],
+            command_give
+        ),
+        Command::new(
+            "gamemode",
+            Some("Sets the game mode of players."),
+            CommandNode::single_usage("gamemode", &[
+                required("gamemode", ArgType::GameMode),
+                players(false),
+            ]),
+            Some(&permission("gamemode")),
+            &[],
+            command_gamemode
+        ),
+        Command::new(
+            "kill",
+            Some("Kills entities."),
+            CommandNode::single_usage("kill", &[
+                optional("targets", ArgType::Entity { single: false, players_only: false }),
+            ]),
+            Some(&permission("kill")),
+            &[],
+            command_kill
+        ),
+    ]
+}
+
+fn command_teleport(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let sender = e.get_sender();
+        let args = e.get_parsed_args();
+        let (origin, yaw, pitch) = sender.get_location().await;
+        let (targets, destination) = match args {
+            [destination] => (None, destination),
+            [CommandArg::Entity(targets), destination] => (Some(targets), destination),
+            _ => return Err(CommandError::InvalidUsage { command: e.get_command().clone() }),
+        };
+        let targets = match targets {
+            Some(targets) => targets.get_entities(&sender).await?,
+            None => {
+                let player = sender.get_player()
+                    .ok_or("An entity is required to run this command here")?;
+                vec![SelectorTarget::new(
+                    player.get_entity_id(),
+                    player.get_uuid(),
+                    "minecraft:player",
+                    origin
+                ).with_player(Arc::downgrade(&player), player.get_name(), player.get_game_mode().get_id() as i32)]
+            },
+        };
+        let (position, destination_name) = match destination {
+            CommandArg::Vec3(coordinates) => (coordinates.resolve(origin, yaw, pitch), None),
+            CommandArg::Entity(destination) => {
+                let destination = destination.get_entities(&sender).await?.remove(0);
+                (destination.get_position(), Some(get_display_name(&destination)))
+            },
+            _ => return Err(CommandError::InvalidUsage { command: e.get_command().clone() }),
+        };
+
+        for target in targets.iter() {
+            teleport(target, position).await;
+        }
+
+        let subject = match targets.as_slice() {
+            [target] => get_display_name(target),
+            targets => format!("{} entities", targets.len()),
+        };
+        let message = match destination_name {
+            Some(destination) => format!("Teleported {subject} to {destination}"),
+            None => format!("Teleported {subject} to {:.6}, {:.6}, {:.6}", position.x, position.y, position.z),
+        };
+        Ok(CommandSuccess::new(targets.len() as i32).with_message(&message))
+    })
+}
+
+async fn teleport(target: &SelectorTarget, position: Vec3d) {
+    for world in THE_SERVER.get_worlds() {
+        let world = world.lock().await;
+        let mut tracker = world.get_entity_tracker().lock().unwrap();
+        if tracker.is_tracked(target.get_entity_id()) {
+            tracker.update_position(target.get_entity_id(), position, false);
+            break;
+        }
+    }
+    if let Some(player) = target.get_player() {
+        player.synchronize_position(position).await;
+    }
+}
+
+fn command_give(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let sender = e.get_sender();
+        let args = e.get_parsed_args();
+        let (selector, item, count) = match args {
+            [CommandArg::Entity(selector), CommandArg::Item(item)] => (selector, item, 1),
+            [CommandArg::Entity(selector), CommandArg::Item(item), CommandArg::Int(count)] => (selector, item, *count),
+            _ => return Err(CommandError::InvalidUsage { command: e.get_command().clone() }),
+        };
+        //TODO: send item components once slots support them
+        if !item.get_components().is_empty() {
+            return Err("Items with components can't be given yet".into());
+        }
+        let name = item.get_item().to_string();
+        let item_id = BUILTIN_REGISTRIES.get("minecraft:item")
+            .and_then(|items| items.get_protocol_id(&name))
+            .ok_or(format!("Unknown item '{name}'"))?;
+        let max_stack_size = inventory::get_max_stack_size(&name);
+        if count > max_stack_size * 100 {
+            return Err(format!("Can't give more than {} of [{name}]", max_stack_size * 100).into());
+        }
+
+        let players = resolve_players(&sender, Some(selector)).await?;
+        for player in players.iter() {
+            let packets: Vec<CSetContainerSlot> = {
+                let mut inventory = player.get_inventory().lock().unwrap();
+                //TODO: drop the items which don't fit
+                let (changed, _) = inventory.add_item(item_id, count, max_stack_size);
+                let state_id = inventory.next_state_id();
+                changed.into_iter()
+                    .map(|slot| CSetContainerSlot::new(
+                        0,
+                        VarInt::new(state_id),
+                        slot as i16,
+                        *inventory.get_slot(slot).unwrap()
+                    ))
+                    .collect()
+            };
+            for packet in packets {
+                player.queue_send_packet(packet).await;
+            }
+        }
+
+        let message = match players.as_slice() {
+            [player] => format!("Gave {count} [{name}] to {}", player.get_name()),
+            players => format!("Gave {count} [{name}] to {} players", players.len()),
+        };
+        Ok(CommandSuccess::new(players.len() as i32).with_message(&message))
+    })
+}
+
+fn command_gamemode(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let sender = e.get_sender();
+        let args = e.get_parsed_args();
+        let Some(CommandArg::GameMode(game_mode)) = args.first() else {
+            return Err(CommandError::InvalidUsage { command: e.get_command().clone() });
+        };
+        let players = resolve_players(&sender, get_entity(args.get(1))).await?;
+        let mut feedback = Vec::new();
+        for player in players.into_iter().filter(|player| player.get_game_mode() != *game_mode) {
+            set_game_mode(&player, *game_mode).await;
+            let is_sender = sender.get_player().is_some_and(|sender| Arc::ptr_eq(&sender, &player));
+            if is_sender {
+                feedback.push(format!("Set own game mode to {game_mode}"));
+            } else {
+                player.send_message(format!("Your game mode has been updated to {game_mode}")).await;
+                feedback.push(format!("Set {}'s game mode to {game_mode}", player.get_name()));
+            }
+        }
+        Ok(feedback.iter().fold(
+            CommandSuccess::new(feedback.len() as i32),
+            |success, message| success.with_message(message)
+        ))
+    })
+}
+
+async fn set_game_mode(player: &Player, game_mode: Gamemode) {
+    player.set_game_mode(game_mode);
+    player.queue_send_packet(CGameEvent::new(GAME_EVENT_CHANGE_GAME_MODE, game_mode.get_id() as f32)).await;
+    let entry = player.get_info_entry();
+    for other in THE_SERVER.get_players_async().await.iter().filter_map(std::sync::Weak::upgrade) {
+        other.queue_send_packet(CPlayerInfoUpdate::new(
+            PlayerInfoActions::UPDATE_GAME_MODE,
+            vec![entry.clone()]
+        )).await;
+    }
+}
+
+fn command_kill(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let sender = e.get_sender();
+        let args = e.get_parsed_args();
+        let targets = match get_entity(args.first()) {
+            Some(selector) => selector.get_entities(&sender).await?,
+            None => {
+                let player = sender.get_player().ok_or("An entity is required to run this command here")?;
+                kill_player(&player).await;
+                return Ok(CommandSuccess::new(1).with_message(&format!("Killed {}", player.get_name())));
+            },
+        };
+        for target in targets.iter() {
+            match target.get_player() {
+                Some(player) => kill_player(&player).await,
+                None => for world in THE_SERVER.get_worlds() {
+                    let world = world.lock().await;
+                    if world.get_entity_tracker().lock().unwrap().untrack(target.get_entity_id()).is_some() {
+                        break;
+                    }
+                },
+            }
+        }
+        let message = match targets.as_slice() {
+            [target] => format!("Killed {}", get_display_name(target)),
+            targets => format!("Killed {} entities", targets.len()),
+        };
+        Ok(CommandSuccess::new(targets.len() as i32).with_message(&message))
+    })
+}
+
+async fn kill_player(player: &Player) {
+    //TODO: food levels once players have them
+    player.queue_send_packet(CSetHealth::new(0.0, VarInt::new(20), 5.0)).await;
+}
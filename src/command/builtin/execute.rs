@@ -0,0 +1,145 @@
+use crate::command::argument::ArgType;
+use crate::command::command_tree::{CommandNode, Redirect};
+use crate::command::{self, Command, CommandArg, CommandError, CommandFuture, CommandSuccess};
+use crate::event::events::command::CommandEvent;
+
+use super::{literal, permission, required};
+
+pub(super) fn get_commands() -> Vec<Command> {
+    let targets = || required("targets", ArgType::Entity { single: false, players_only: false });
+    vec![
+        Command::new(
+            "execute",
+            Some("Runs a command as other entities or from somewhere else, or if a condition holds."),
+            CommandNode::multi_usage("execute", &[
+                &[literal("run")],
+                &[literal("as"), targets()],
+                &[literal("at"), targets()],
+                &[literal("positioned"), required("pos", ArgType::Vec3)],
+                &[literal("if"), literal("entity"), targets()],
+                &[literal("unless"), literal("entity"), targets()],
+            ])
+                .with_redirect(&["run"], Redirect::Root, false)
+                .with_redirect(&["as", "targets"], Redirect::Command, false)
+                .with_redirect(&["at", "targets"], Redirect::Command, false)
+                .with_redirect(&["positioned", "pos"], Redirect::Command, false)
+                .with_redirect(&["if", "entity", "targets"], Redirect::Command, true)
+                .with_redirect(&["unless", "entity", "targets"], Redirect::Command, true),
+            Some(&permission("execute")),
+            &[],
+            command_execute
+        ),
+    ]
+}
+
+/// Each subcommand changes or filters the senders, until `run` runs the command
+/// once for each sender. The subcommands are chained by redirects, so they come nested.
+//TODO: `if block` once worlds can look up blocks
+fn command_execute(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let mut senders = vec![e.get_sender().clone()];
+        let mut args = e.get_parsed_args();
+        loop {
+            let [CommandArg::Subcommand(subcommand, rest)] = args else {
+                return Err(CommandError::InvalidUsage { command: e.get_command().clone() });
+            };
+            let mut next = Vec::new();
+            let remaining = match (subcommand.as_str(), rest.as_slice()) {
+                ("run", [CommandArg::String(input)]) => {
+                    let input = input.strip_prefix('/').unwrap_or(input);
+                    let mut successes = 0;
+                    for sender in senders {
+                        if command::dispatch(sender, input).await.is_ok() {
+                            successes += 1;
+                        }
+                    }
+                    //The commands already gave their own feedback
+                    return Ok(CommandSuccess::new(successes).with_broadcast(false));
+                },
+                ("as", [CommandArg::Entity(selector), remaining @ ..]) => {
+                    for sender in senders {
+                        for target in selector.resolve(&sender).await {
+                            next.push(sender.clone().with_executor(target));
+                        }
+                    }
+                    remaining
+                },
+                ("at", [CommandArg::Entity(selector), remaining @ ..]) => {
+                    for sender in senders {
+                        for target in selector.resolve(&sender).await {
+                            let (position, yaw, pitch) = target.get_location().await;
+                            next.push(sender.clone().with_location(position, yaw, pitch));
+                        }
+                    }
+                    remaining
+                },
+                ("positioned", [CommandArg::Vec3(coordinates), remaining @ ..]) => {
+                    for sender in senders {
+                        let (origin, yaw, pitch) = sender.get_location().await;
+                        next.push(sender.with_location(coordinates.resolve(origin, yaw, pitch), yaw, pitch));
+                    }
+                    remaining
+                },
+                (condition @ ("if" | "unless"), [CommandArg::Subcommand(_, test)]) => {
+                    let [CommandArg::Entity(selector), remaining @ ..] = test.as_slice() else {
+                        return Err(CommandError::InvalidUsage { command: e.get_command().clone() });
+                    };
+                    let mut found = 0;
+                    for sender in senders {
+                        let entities = selector.resolve(&sender).await.len();
+                        if (entities > 0) == (condition == "if") {
+                            found += entities.max(1);
+                            next.push(sender);
+                        }
+                    }
+                    //Without anything after it, the condition is the result
+                    if remaining.is_empty() {
+                        return match (found, condition) {
+                            (0, _) => Err("Test failed".into()),
+                            (count, "if") => Ok(CommandSuccess::new(count as i32)
+                                .with_message(&format!("Test passed, count: {count}"))
+                                .with_broadcast(false)),
+                            (count, _) => Ok(CommandSuccess::new(count as i32)
+                                .with_message("Test passed")
+                                .with_broadcast(false)),
+                        };
+                    }
+                    remaining
+                },
+                _ => return Err(CommandError::InvalidUsage { command: e.get_command().clone() }),
+            };
+            senders = next;
+            if senders.is_empty() {
+                return Ok(CommandSuccess::new(0).with_broadcast(false));
+            }
+            args = remaining;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subcommands() {
+        let execute = get_commands().remove(0);
+        let parse = |input: &str| Command::parse_input(input, execute.get_usage_tree()).unwrap().1;
+        let args = parse("execute as @a at @s run say hi");
+        let [CommandArg::Subcommand(name, rest)] = args.as_slice() else { panic!("{args:?}") };
+        assert_eq!(name, "as");
+        let [CommandArg::Entity(_), CommandArg::Subcommand(name, rest)] = rest.as_slice() else { panic!("{rest:?}") };
+        assert_eq!(name, "at");
+        let [CommandArg::Entity(_), CommandArg::Subcommand(name, rest)] = rest.as_slice() else { panic!("{rest:?}") };
+        assert_eq!(name, "run");
+        assert_eq!(rest.as_slice(), &[CommandArg::String("say hi".to_string())]);
+
+        let args = parse("execute if entity @e[type=pig]");
+        let [CommandArg::Subcommand(name, rest)] = args.as_slice() else { panic!("{args:?}") };
+        assert_eq!(name, "if");
+        assert!(matches!(rest.as_slice(), [CommandArg::Subcommand(test, args)] if test == "entity" && args.len() == 1));
+
+        assert!(Command::parse_input("execute as @a", execute.get_usage_tree()).is_err());
+        assert!(Command::parse_input("execute run", execute.get_usage_tree()).is_err());
+    }
+}
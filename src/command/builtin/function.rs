@@ -0,0 +1,88 @@
+use server_macros::command;
+
+use crate::command::argument::ArgType;
+use crate::command::command_tree::CommandNode;
+use crate::command::function::{suggest_functions, FunctionArgument};
+use crate::command::{Command, CommandArg, CommandError, CommandFuture, CommandResult, CommandSender, CommandSuccess};
+use crate::event::events::command::CommandEvent;
+use crate::FUNCTION_MAP;
+
+use super::{literal, permission, required};
+
+pub(super) fn get_commands() -> Vec<Command> {
+    let function = || required("function", ArgType::Function);
+    let time = || required("time", ArgType::Time { min: 0 });
+    vec![
+        Command::new(
+            "schedule",
+            Some("Runs a function after some time."),
+            CommandNode::multi_usage("schedule", &[
+                &[literal("function"), function(), time()],
+                &[literal("function"), function(), time(), literal("append")],
+                &[literal("function"), function(), time(), literal("replace")],
+                &[literal("clear"), function()],
+            ]),
+            Some(&permission("schedule")),
+            &[],
+            command_schedule
+        ).with_suggestions("function", suggest_functions),
+    ]
+}
+
+fn unknown(function: &FunctionArgument) -> String {
+    match function.is_tag() {
+        true => format!("Unknown function tag '{}'", function.get_id()),
+        false => format!("Unknown function '{}'", function.get_id()),
+    }
+}
+
+/// Runs a function, or every function of a tag.
+#[command(permission = "minecraft.command.function", suggestions(name = suggest_functions))]
+async fn function(sender: &CommandSender, name: FunctionArgument) -> CommandResult {
+    let functions = match FUNCTION_MAP.lock().await.as_ref() {
+        Some(function_map) => function_map.resolve(&name),
+        None => None,
+    }.ok_or_else(|| unknown(&name))?;
+    let mut commands = 0;
+    for function in functions.iter() {
+        commands += function.run(sender).await?;
+    }
+    let message = match functions.as_slice() {
+        [function] => format!("Executed {commands} commands from function '{}'", function.get_id()),
+        functions => format!("Executed {commands} commands from {} functions", functions.len()),
+    };
+    Ok(CommandSuccess::new(commands as i32).with_message(&message))
+}
+
+fn command_schedule(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let Some(CommandArg::Subcommand(action, args)) = e.get_parsed_args().first() else {
+            return Err(CommandError::InvalidUsage { command: e.get_command().clone() });
+        };
+        let mut function_map = FUNCTION_MAP.lock().await;
+        let function_map = function_map.as_mut().ok_or("Functions aren't loaded")?;
+        match (action.as_str(), args.as_slice()) {
+            ("function", [CommandArg::Function(function), CommandArg::Time(time), mode @ ..]) => {
+                if *time == 0 {
+                    return Err("Can't schedule for current tick".into());
+                }
+                if function_map.resolve(function).is_none() {
+                    return Err(unknown(function).into());
+                }
+                let replace = !matches!(mode, [CommandArg::Subcommand(mode, _)] if mode == "append");
+                let tick = function_map.schedule(function.clone(), *time as i64, replace);
+                let message = match function.is_tag() {
+                    true => format!("Scheduled tag '{}' in {time} ticks at gametime {tick}", function.get_id()),
+                    false => format!("Scheduled function '{}' in {time} ticks at gametime {tick}", function.get_id()),
+                };
+                Ok(CommandSuccess::new(tick as i32).with_message(&message))
+            },
+            ("clear", [CommandArg::Function(function)]) => match function_map.clear_schedule(function) {
+                0 => Err(format!("No schedule with id {function}").into()),
+                removed => Ok(CommandSuccess::new(removed as i32)
+                    .with_message(&format!("Removed {removed} schedule(s) with id {function}"))),
+            },
+            _ => Err(CommandError::InvalidUsage { command: e.get_command().clone() }),
+        }
+    })
+}
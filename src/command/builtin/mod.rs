@@ -0,0 +1,98 @@
+//! The vanilla commands, which need `minecraft.command.<name>` permissions
+
+use std::collections::HashSet;
+use std::sync::{Arc, LazyLock};
+
+use crate::player::Player;
+
+use super::argument::{ArgType, Argument};
+use super::selector::{EntitySelector, SelectorTarget};
+use super::{Command, CommandArg, CommandSender};
+
+pub mod access;
+pub mod chat;
+pub mod entity;
+pub mod execute;
+pub mod function;
+pub mod permission;
+pub mod server;
+pub mod world;
+
+/// Every builtin command, with those of the `#[command]` functions
+pub fn get_commands() -> Vec<Command> {
+    let mut commands = Vec::new();
+    commands.append(&mut access::get_commands());
+    commands.append(&mut entity::get_commands());
+    commands.append(&mut execute::get_commands());
+    commands.append(&mut function::get_commands());
+    commands.append(&mut permission::get_commands());
+    commands.append(&mut server::get_commands());
+    commands.append(&mut world::get_commands());
+    commands.append(&mut super::parameter::get_registered_commands());
+    commands
+}
+
+//The names and aliases of the builtin commands
+static BUILTIN_LABELS: LazyLock<HashSet<String>> = LazyLock::new(|| {
+    get_commands().iter()
+        .flat_map(|command| std::iter::once(command.get_name().to_string()).chain(command.get_aliases().iter().cloned()))
+        .collect()
+});
+
+/// Whether a builtin command has the label as its name or an alias
+pub fn is_builtin(label: &str) -> bool {
+    BUILTIN_LABELS.contains(label)
+}
+
+fn permission(command: &str) -> String {
+    format!("minecraft.command.{command}")
+}
+
+fn literal(name: &str) -> Argument {
+    Argument::new(name.to_string(), ArgType::Subcommand, true)
+}
+
+fn required(name: &str, arg_type: ArgType) -> Argument {
+    Argument::new(name.to_string(), arg_type, true)
+}
+
+fn optional(name: &str, arg_type: ArgType) -> Argument {
+    Argument::new(name.to_string(), arg_type, false)
+}
+
+/// Resolves a selector of players, or the sender if there is none
+async fn resolve_players(
+    sender: &CommandSender,
+    selector: Option<&EntitySelector>
+) -> Result<Vec<Arc<Player>>, String> {
+    match selector {
+        Some(selector) => Ok(selector.get_entities(sender).await?.iter()
+            .filter_map(SelectorTarget::get_player)
+            .collect()),
+        None => sender.get_player()
+            .map(|player| vec![player])
+            .ok_or("A player is required to run this command here".to_string()),
+    }
+}
+
+/// The player's name, otherwise the entity type
+fn get_display_name(target: &SelectorTarget) -> String {
+    match target.get_name() {
+        Some(name) => name.to_string(),
+        None => target.get_entity_type().trim_start_matches("minecraft:").to_string(),
+    }
+}
+
+fn get_entity(arg: Option<&CommandArg>) -> Option<&EntitySelector> {
+    match arg {
+        Some(CommandArg::Entity(selector)) => Some(selector),
+        _ => None,
+    }
+}
+
+fn get_string(arg: Option<&CommandArg>) -> Option<&str> {
+    match arg {
+        Some(CommandArg::String(string)) => Some(string),
+        _ => None,
+    }
+}
@@ -0,0 +1,170 @@
+use std::sync::Weak;
+
+use uuid::Uuid;
+
+use crate::command::argument::{ArgType, StringArgProperties, StringType};
+use crate::command::brigadier;
+use crate::command::command_tree::CommandNode;
+use crate::command::parameter::get_args;
+use crate::command::{Command, CommandArg, CommandError, CommandFuture, CommandSuccess};
+use crate::event::events::command::CommandEvent;
+use crate::permission::{PermissionError, PermissionHolderId};
+use crate::THE_SERVER;
+
+use super::{literal, optional, permission, required};
+
+pub(super) fn get_commands() -> Vec<Command> {
+    let word = || ArgType::String { properties: None };
+    let node = || required("node", ArgType::String {
+        properties: Some(StringArgProperties::new(StringType::Phrase))
+    });
+    let world = || optional("world", word());
+    let mut usages: Vec<Vec<_>> = vec![
+        vec![literal("groups")],
+        vec![literal("reload")],
+    ];
+    for (kind, name) in [("user", "player"), ("group", "group")] {
+        let holder = || [literal(kind), required(name, word())];
+        for action in ["add", "remove"] {
+            usages.push([holder().as_slice(), &[literal(action), node(), world()]].concat());
+            usages.push([holder().as_slice(), &[literal("parent"), literal(action), required("parent", word())]].concat());
+        }
+        usages.push([holder().as_slice(), &[literal("info")]].concat());
+    }
+    for action in ["create", "delete"] {
+        usages.push(vec![literal("group"), required("group", word()), literal(action)]);
+    }
+    let usages: Vec<&[_]> = usages.iter().map(Vec::as_slice).collect();
+    vec![
+        Command::new(
+            "permission",
+            Some("Manages the permissions of players and groups. Nodes like `foo.*` must be quoted."),
+            CommandNode::multi_usage("permission", &usages),
+            Some(&permission("permission")),
+            &["perm".to_string()],
+            command_permission
+        ),
+    ]
+}
+
+/// A player by UUID, by name if they are online, or by the name they had when
+/// their permissions were last changed
+async fn find_user(name: &str) -> Result<(Uuid, String), CommandError> {
+    if let Ok(uuid) = Uuid::parse_str(name) {
+        return Ok((uuid, name.to_string()));
+    }
+    if let Some(player) = THE_SERVER.get_player_by_name_async(name).await.and_then(|player| player.upgrade()) {
+        return Ok((player.get_uuid(), player.get_name().to_string()));
+    }
+    THE_SERVER.get_permission_provider().find_user(name)
+        .map(|uuid| (uuid, name.to_string()))
+        .ok_or_else(|| format!("Unknown player '{name}'").into())
+}
+
+/// Resends the commands to the players whose permissions may have changed
+async fn refresh(holder: Option<&PermissionHolderId>) {
+    for player in THE_SERVER.get_players_async().await.iter().filter_map(Weak::upgrade) {
+        let affected = match holder {
+            Some(PermissionHolderId::User(uuid)) => player.get_uuid() == *uuid,
+            _ => true,
+        };
+        if affected {
+            brigadier::send_commands(&player).await;
+        }
+    }
+}
+
+fn to_command_error(e: PermissionError) -> CommandError {
+    e.to_string().into()
+}
+
+fn command_permission(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let provider = THE_SERVER.get_permission_provider();
+        let args = get_args(&e);
+        let words: Vec<&str> = args.iter()
+            .map(|arg| match arg {
+                CommandArg::Subcommand(word, _) | CommandArg::String(word) => word.as_str(),
+                _ => "",
+            })
+            .collect();
+
+        let (holder, display) = match words.as_slice() {
+            ["groups"] => {
+                let groups = provider.get_groups();
+                let message = format!("There are {} groups: {}", groups.len(), groups.join(", "));
+                return Ok(CommandSuccess::new(groups.len() as i32).with_message(&message).with_broadcast(false));
+            },
+            ["reload"] => {
+                provider.reload().map_err(to_command_error)?;
+                refresh(None).await;
+                return Ok(CommandSuccess::new(1).with_message("Reloaded the permissions"));
+            },
+            ["group", group, "create"] => {
+                provider.create_group(group).map_err(to_command_error)?;
+                return Ok(CommandSuccess::new(1).with_message(&format!("Created group {group}")));
+            },
+            ["group", group, "delete"] => {
+                provider.delete_group(group).map_err(to_command_error)?;
+                refresh(None).await;
+                return Ok(CommandSuccess::new(1).with_message(&format!("Deleted group {group}")));
+            },
+            ["user", name, ..] => {
+                let (uuid, name) = find_user(name).await?;
+                (PermissionHolderId::User(uuid), name)
+            },
+            ["group", name, ..] => (PermissionHolderId::Group(name.to_string()), format!("group {name}")),
+            _ => return Err(CommandError::InvalidUsage { command: e.get_command().clone() }),
+        };
+
+        let message = match &words[2..] {
+            ["info"] => {
+                let Some(info) = provider.get_holder(&holder) else {
+                    return match holder {
+                        PermissionHolderId::User(_) => Ok(CommandSuccess::new(0)
+                            .with_message(&format!("{display} has no permissions of their own"))
+                            .with_broadcast(false)),
+                        PermissionHolderId::Group(group) => Err(PermissionError::UnknownGroup(group).to_string().into()),
+                    };
+                };
+                let mut success = CommandSuccess::new(info.get_permissions().len() as i32)
+                    .with_message(&format!("Permissions of {display}: {}", info.get_permissions().join(", ")))
+                    .with_message(&format!("Parents: {}", info.get_parents().join(", ")))
+                    .with_broadcast(false);
+                for (world, nodes) in info.get_world_permissions() {
+                    success = success.with_message(&format!("In {world}: {}", nodes.join(", ")));
+                }
+                return Ok(success);
+            },
+            ["add", node, world @ ..] => {
+                let world = world.first().copied();
+                match provider.add_permission(&holder, node, world).map_err(to_command_error)? {
+                    true => format!("Set {node} for {display}{}", in_world(world)),
+                    false => return Err(format!("Nothing changed. {display} already has {node}{}", in_world(world)).into()),
+                }
+            },
+            ["remove", node, world @ ..] => {
+                let world = world.first().copied();
+                match provider.remove_permission(&holder, node, world).map_err(to_command_error)? {
+                    true => format!("Unset {node} for {display}{}", in_world(world)),
+                    false => return Err(format!("Nothing changed. {node} isn't set for {display}{}", in_world(world)).into()),
+                }
+            },
+            ["parent", "add", group] => match provider.add_parent(&holder, group).map_err(to_command_error)? {
+                true => format!("{display} now inherits from group {group}"),
+                false => return Err(format!("Nothing changed. {display} already inherits from group {group}").into()),
+            },
+            ["parent", "remove", group] => match provider.remove_parent(&holder, group).map_err(to_command_error)? {
+                true => format!("{display} no longer inherits from group {group}"),
+                false => return Err(format!("Nothing changed. {display} doesn't inherit from group {group}").into()),
+            },
+            _ => return Err(CommandError::InvalidUsage { command: e.get_command().clone() }),
+        };
+        refresh(Some(&holder)).await;
+        Ok(CommandSuccess::new(1).with_message(&message))
+    })
+}
+
+fn in_world(world: Option<&str>) -> String {
+    world.map(|world| format!(" in {world}")).unwrap_or_default()
+}
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+
+use server_macros::command;
+
+use crate::command::argument::{ArgType, StringArgProperties, StringType};
+use crate::command::command_tree::CommandNode;
+use crate::command::parameter::GreedyString;
+use crate::command::{Command, CommandArg, CommandError, CommandFuture, CommandResult, CommandSuccess};
+use crate::player::Player;
+use crate::event::events::command::CommandEvent;
+use crate::{COMMAND_MAP, THE_SERVER};
+
+use super::{get_string, literal, optional, permission};
+
+pub(super) fn get_commands() -> Vec<Command> {
+    vec![
+        Command::new(
+            "help",
+            Some("Shows how to use commands."),
+            CommandNode::single_usage("help", &[
+                optional("command", ArgType::String {
+                    properties: Some(StringArgProperties::new(StringType::Greedy))
+                }),
+            ]),
+            Some(&permission("help")),
+            &[],
+            command_help
+        ),
+        Command::new(
+            "list",
+            Some("Lists the players on the server."),
+            CommandNode::multi_usage("list", &[&[], &[literal("uuids")]]),
+            Some(&permission("list")),
+            &[],
+            command_list
+        ),
+        Command::new(
+            "stop",
+            Some("Stops the server."),
+            CommandNode::single_usage("stop", &[]),
+            Some(&permission("stop")),
+            &[],
+            command_stop
+        ),
+        Command::new(
+            "reload",
+            Some("Reloads the server."),
+            CommandNode::single_usage("reload", &[]),
+            Some(&permission("reload")),
+            &[],
+            command_reload
+        ),
+    ]
+}
+
+/// Lists the usages of every command the sender may use, or those of one command
+fn command_help(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let sender = e.get_sender();
+        let label = get_string(e.get_parsed_args().first());
+        let unknown = || CommandError::from("Unknown command or insufficient permissions");
+        let command_map = COMMAND_MAP.lock().await;
+        let command_map = command_map.as_ref().ok_or_else(unknown)?;
+        let commands: Vec<&Command> = match label {
+            Some(label) => command_map.get_command(label.trim_start_matches('/'))
+                .filter(|command| command.can_use(sender))
+                .map(|command| vec![command])
+                .ok_or_else(unknown)?,
+            None => {
+                let mut commands: Vec<&Command> = command_map.get_commands()
+                    .filter(|command| command.can_use(sender))
+                    .collect();
+                commands.sort_by(|a, b| a.get_name().cmp(b.get_name()));
+                commands
+            },
+        };
+        Ok(commands.iter()
+            .flat_map(|command| command.get_usages())
+            .fold(CommandSuccess::new(commands.len() as i32), |success, usage| {
+                success.with_message(&usage.to_string())
+            })
+            .with_broadcast(false))
+    })
+}
+
+fn command_list(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let uuids = matches!(e.get_parsed_args().first(), Some(CommandArg::Subcommand(name, _)) if name == "uuids");
+        let players: Vec<String> = THE_SERVER.get_players_async().await.iter()
+            .filter_map(std::sync::Weak::upgrade)
+            .map(|player| match uuids {
+                true => format!("{} ({})", player.get_name(), player.get_uuid()),
+                false => player.get_name().to_string(),
+            })
+            .collect();
+        let message = format!(
+            "There are {} of a max of {} players online: {}",
+            players.len(),
+            THE_SERVER.get_max_players(),
+            players.join(", ")
+        );
+        Ok(CommandSuccess::new(players.len() as i32).with_message(&message).with_broadcast(false))
+    })
+}
+
+/// Disconnects players from the server.
+#[command(permission = "minecraft.command.kick")]
+async fn kick(targets: Vec<Arc<Player>>, reason: Option<GreedyString>) -> CommandResult {
+    let reason = reason.map_or("Kicked by an operator".to_string(), |reason| reason.0);
+    let mut feedback = Vec::new();
+    for player in targets {
+        player.disconnect(&reason).await;
+        feedback.push(format!("Kicked {}: {reason}", player.get_name()));
+    }
+    Ok(feedback.iter().fold(
+        CommandSuccess::new(feedback.len() as i32),
+        |success, message| success.with_message(message)
+    ))
+}
+
+fn command_stop(_: CommandEvent) -> CommandFuture {
+    let _ = crate::STOP_SIGNAL.get().unwrap().send(true);
+    Box::pin(std::future::ready(Ok(CommandSuccess::new(1).with_message("Stopping the server"))))
+}
+
+fn command_reload(_: CommandEvent) -> CommandFuture {
+    let _ = crate::STOP_SIGNAL.get().unwrap().send(false);
+    Box::pin(std::future::ready(Ok(CommandSuccess::new(1).with_message("Reloading!"))))
+}
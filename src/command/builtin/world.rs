@@ -0,0 +1,163 @@
+use server_macros::command;
+
+use crate::command::argument::ArgType;
+use crate::command::command_tree::CommandNode;
+use crate::command::{Command, CommandArg, CommandError, CommandFuture, CommandSuccess};
+use crate::event::events::command::CommandEvent;
+use crate::game::difficulty::Difficulty;
+use crate::packet::play::CChangeDifficulty;
+use crate::world::world::{Weather, TICKS_PER_DAY, TIME_DAY, TIME_MIDNIGHT, TIME_NIGHT, TIME_NOON};
+use crate::THE_SERVER;
+
+use super::{literal, optional, permission, required};
+
+/// How long the weather lasts if no duration is given
+const DEFAULT_WEATHER_DURATION: i32 = 6000;
+
+pub(super) fn get_commands() -> Vec<Command> {
+    let time = || required("time", ArgType::Time { min: 0 });
+    let duration = || optional("duration", ArgType::Time { min: 1 });
+    vec![
+        Command::new(
+            "time",
+            Some("Changes or queries the time of day."),
+            CommandNode::multi_usage("time", &[
+                &[literal("set"), literal("day")],
+                &[literal("set"), literal("noon")],
+                &[literal("set"), literal("night")],
+                &[literal("set"), literal("midnight")],
+                &[literal("set"), time()],
+                &[literal("add"), time()],
+                &[literal("query"), literal("daytime")],
+                &[literal("query"), literal("gametime")],
+                &[literal("query"), literal("day")],
+            ]),
+            Some(&permission("time")),
+            &[],
+            command_time
+        ),
+        Command::new(
+            "weather",
+            Some("Sets the weather."),
+            CommandNode::multi_usage("weather", &[
+                &[literal("clear"), duration()],
+                &[literal("rain"), duration()],
+                &[literal("thunder"), duration()],
+            ]),
+            Some(&permission("weather")),
+            &[],
+            command_weather
+        ),
+        Command::new(
+            "difficulty",
+            Some("Sets or queries the difficulty."),
+            CommandNode::multi_usage("difficulty", &[
+                &[],
+                &[literal("peaceful")],
+                &[literal("easy")],
+                &[literal("normal")],
+                &[literal("hard")],
+            ]),
+            Some(&permission("difficulty")),
+            &[],
+            command_difficulty
+        ),
+    ]
+}
+
+fn command_time(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let Some(CommandArg::Subcommand(action, args)) = e.get_parsed_args().first() else {
+            return Err(CommandError::InvalidUsage { command: e.get_command().clone() });
+        };
+        let worlds: Vec<_> = THE_SERVER.get_worlds().collect();
+        let first = match worlds.first() {
+            Some(world) => {
+                let world = world.lock().await;
+                (world.get_world_time(), world.get_world_age())
+            },
+            None => (0, 0),
+        };
+        let time = match (action.as_str(), args.first()) {
+            ("query", Some(CommandArg::Subcommand(query, _))) => {
+                let (world_time, world_age) = first;
+                let time = match query.as_str() {
+                    "daytime" => world_time,
+                    "gametime" => world_age % i32::MAX as i64,
+                    _ => world_age / TICKS_PER_DAY,
+                };
+                return Ok(CommandSuccess::new(time as i32)
+                    .with_message(&format!("The time is {time}"))
+                    .with_broadcast(false));
+            },
+            ("set", Some(CommandArg::Subcommand(name, _))) => match name.as_str() {
+                "day" => TIME_DAY,
+                "noon" => TIME_NOON,
+                "night" => TIME_NIGHT,
+                _ => TIME_MIDNIGHT,
+            },
+            ("set", Some(CommandArg::Time(time))) => *time as i64,
+            ("add", Some(CommandArg::Time(time))) => first.0 + *time as i64,
+            _ => return Err(CommandError::InvalidUsage { command: e.get_command().clone() }),
+        };
+        for world in worlds {
+            world.lock().await.set_world_time(time).await;
+        }
+        Ok(CommandSuccess::new((time % TICKS_PER_DAY) as i32).with_message(&format!("Set the time to {time}")))
+    })
+}
+
+fn command_weather(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let Some(CommandArg::Subcommand(name, args)) = e.get_parsed_args().first() else {
+            return Err(CommandError::InvalidUsage { command: e.get_command().clone() });
+        };
+        let (weather, message) = match name.as_str() {
+            "clear" => (Weather::Clear, "Set the weather to clear"),
+            "rain" => (Weather::Rain, "Set the weather to rain"),
+            "thunder" => (Weather::Thunder, "Set the weather to rain & thunder"),
+            _ => return Err(CommandError::InvalidUsage { command: e.get_command().clone() }),
+        };
+        let duration = match args.first() {
+            Some(CommandArg::Time(duration)) => *duration,
+            _ => DEFAULT_WEATHER_DURATION,
+        };
+        for world in THE_SERVER.get_worlds() {
+            world.lock().await.set_weather(weather, duration as i64).await;
+        }
+        Ok(CommandSuccess::new(duration).with_message(message))
+    })
+}
+
+fn command_difficulty(e: CommandEvent) -> CommandFuture {
+    Box::pin(async move {
+        let difficulty = match e.get_parsed_args().first() {
+            Some(CommandArg::Subcommand(name, _)) => name.parse::<Difficulty>().ok(),
+            _ => None,
+        };
+        let current = THE_SERVER.get_difficulty();
+        let Some(difficulty) = difficulty else {
+            return Ok(CommandSuccess::new(current.get_id() as i32)
+                .with_message(&format!("The difficulty is {current}"))
+                .with_broadcast(false));
+        };
+        if difficulty == current {
+            return Err(format!("The difficulty did not change; it is already set to {current}").into());
+        }
+        THE_SERVER.set_difficulty(difficulty);
+        for player in THE_SERVER.get_players_async().await.iter().filter_map(std::sync::Weak::upgrade) {
+            player.queue_send_packet(CChangeDifficulty::new(difficulty.get_id(), false)).await;
+        }
+        Ok(CommandSuccess::new(difficulty.get_id() as i32)
+            .with_message(&format!("The difficulty has been set to {difficulty}")))
+    })
+}
+
+/// Shows the world seed.
+#[command(permission = "minecraft.command.seed")]
+fn seed() -> CommandSuccess {
+    let seed = THE_SERVER.get_properties().get_level_seed();
+    CommandSuccess::new(seed as i32)
+        .with_message(&format!("Seed: [{seed}]"))
+        .with_broadcast(false)
+}
@@ -1,22 +1,33 @@
 
+use std::future::Future;
+use std::pin::Pin;
 use std::str::{FromStr, ParseBoolError};
 use std::{error::Error, fmt::Debug};
 use std::fmt::{Arguments, Display};
-use std::sync::Weak;
+use std::sync::{Arc, Weak};
 use itertools::EitherOrBoth;
 
 use std::collections::{HashMap, HashSet, VecDeque};
 
 
 use itertools::Itertools;
+use quartz_nbt::NbtCompound;
+use uuid::Uuid;
 use yaml_rust::scanner::Token;
 
 use crate::command::argument::ArgType;
+use crate::data_types::text_component::{Formatting, Nbt};
+use crate::game::gamemode::Gamemode;
+use crate::data_types::{Suggestion, TextComponent, Vec3d};
 use crate::COMMAND_MAP;
 use crate::{data_types::{Angle, Identifier}, event::{self, events::command::CommandEvent, EventResult}, player::Player, CONSOLE, RUNTIME, THE_SERVER};
 
 use super::argument::Argument;
-use super::command_tree::CommandNode;
+use super::command_tree::{CommandNode, Redirect};
+use super::coordinates::Coordinates;
+use super::function::FunctionArgument;
+use super::resource::{BlockStateArgument, ItemArgument};
+use super::selector::{EntitySelector, SelectorTarget};
 
 #[derive(Debug, Clone)]
 pub struct Command {
@@ -25,9 +36,25 @@ pub struct Command {
     usages: CommandNode,
     aliases: Vec<String>,
     permission: Option<String>,
-    func: fn(&mut CommandEvent) -> EventResult,
+    func: CommandHandler,
+    suggestions: HashMap<String, SuggestionProvider>,
 }
 
+/// Runs a command after it was parsed and no listener cancelled it.
+/// Handlers which don't await anything can return `Box::pin(std::future::ready(result))`.
+pub type CommandHandler = fn(CommandEvent) -> CommandFuture;
+
+pub type CommandFuture = Pin<Box<dyn Future<Output = CommandResult> + Send>>;
+
+pub type CommandResult = Result<CommandSuccess, CommandError>;
+
+/// Players with this permission are told what others did with commands, like ops in vanilla
+pub const FEEDBACK_PERMISSION: &str = "minecraft.admin.command_feedback";
+
+/// Suggests values for an argument while a player types it.
+/// Receives the text of the argument typed so far.
+pub type SuggestionProvider = fn(&CommandSender, &str) -> Vec<Suggestion>;
+
 impl Command {
     pub fn get_name(&self) -> &str {
         &self.name
@@ -37,8 +64,15 @@ impl Command {
         self.description.as_ref()
     }
 
-    pub fn get_usages(&self) -> Vec<CommandUsage> {
-        todo!()
+    /// Every way to use the command, starting with its name
+    pub fn get_usages(&self) -> Vec<CommandUsage<'_>> {
+        let root = vec![vec![self.usages.get_argument()]];
+        let mut out = Vec::new();
+        if ends_usage(&self.usages) {
+            out.push(CommandUsage { usage: root.clone(), redirect: None });
+        }
+        get_usages(&self.usages, root, &mut out);
+        out
     }
 
     pub fn get_aliases(&self) -> &[String] {
@@ -49,153 +83,178 @@ impl Command {
         self.permission.as_ref()
     }
 
-    pub fn get_func(&self) -> fn(&mut CommandEvent) -> EventResult {
+    pub fn get_func(&self) -> CommandHandler {
         self.func
     }
-}
 
-fn get_usages<'a>(
-    node: &'a CommandNode, 
-    prefix: Vec<Vec<&'a Argument>>
-) -> Vec<Vec<Vec<&'a Argument>>> {
-    let mut out = Vec::new();
-    
-    #[derive(PartialEq, PartialOrd, Eq, Ord)]
-    struct GroupedTreeNode<'a> {
-        last: bool,
-        args: Vec<&'a Argument>,
-        children: Vec<Self>,
-    }
-
-    impl<'a> GroupedTreeNode<'a> {
-        fn compare(&self, other: &Self) -> bool {
-            if self.last && other.last {
-                return true;
-            } else if self.last || other.last {
-                return false;
-            } else {
-                return self.children.iter()
-                    .zip(other.children.iter())
-                    .map(|(a, b)| a.compare(b))
-                    .fold(true, |a, e| a & e);
-            }
-        }
+    pub fn get_usage_tree(&self) -> &CommandNode {
+        &self.usages
     }
 
-    impl<'a> From<&'a CommandNode> for GroupedTreeNode<'a> {
-        fn from(node: &'a CommandNode) -> Self {
-            Self {
-                last: node.is_last(),
-                args: vec![node.get_argument()],
-                children: 
-                if node.is_last() { vec![] } else {
-                    node.get_children().iter().map(|child| {
-                        child.into()
-                    }).sorted().collect()
-                }
-            }
-        }
+    /// Sets the suggestions of the argument named `argument`.
+    /// Clients ask the server for suggestions of such arguments.
+    pub fn with_suggestions(mut self, argument: &str, provider: SuggestionProvider) -> Self {
+        self.suggestions.insert(argument.to_string(), provider);
+        self
     }
 
-    fn group_args<'a>(node: &'a CommandNode) -> GroupedTreeNode<'a> {
-
-
-        fn group<'a>(node: GroupedTreeNode<'a>) -> GroupedTreeNode<'a> {
-            if node.last {
-                return node;
-            }
-            
-            let (last, args, mut children) = 
-                (
-                    node.last,
-                    node.args, 
-                    node.children.into_iter()
-                        .map(|child| group(child))
-                        .sorted()
-                        .collect::<Vec<_>>()
-                );
-
-            children.dedup_by(|a, b| {
-                if a.compare(b) {
-                    b.args.append(&mut a.args);
-                    true
-                } else {
-                    false
-                }
-            });
+    pub fn get_suggestion_provider(&self, argument: &str) -> Option<SuggestionProvider> {
+        self.suggestions.get(argument).copied()
+    }
 
-            GroupedTreeNode { last, args, children }
+    /// Whether `sender` has the permission of this command
+    pub fn can_use(&self, sender: &CommandSender) -> bool {
+        match &self.permission {
+            Some(permission) => sender.has_permission(permission),
+            None => true,
         }
+    }
+}
 
-        let mut grouped_tree = GroupedTreeNode {
-            last: node.is_last(),
-            args: vec![node.get_argument()],
-            children: vec![],
-        };
+/// Adds a usage for each way down from `node`. Sibling literals with the same
+/// arguments after them are grouped, like `/weather (clear|rain|thunder)`.
+fn get_usages<'a>(
+    node: &'a CommandNode,
+    prefix: Vec<Vec<&'a Argument>>,
+    out: &mut Vec<CommandUsage<'a>>,
+) {
+    fn same_shape(a: &CommandNode, b: &CommandNode) -> bool {
+        a.is_last() == b.is_last()
+            && a.get_redirect() == b.get_redirect()
+            && a.get_children().len() == b.get_children().len()
+            && a.get_children().iter().zip(b.get_children()).all(|(a, b)| {
+                a.get_argument() == b.get_argument() && same_shape(a, b)
+            })
+    }
 
-        for child in node.get_children() {
-            grouped_tree.children.push(child.into());
+    let mut groups: Vec<(&CommandNode, Vec<&Argument>)> = Vec::new();
+    for child in node.get_children() {
+        let grouped = groups.iter_mut().find(|(first, _)| {
+            first.get_argument().get_type().is_literal()
+                && child.get_argument().get_type().is_literal()
+                && same_shape(first, child)
+        });
+        match grouped {
+            Some((_, args)) => args.push(child.get_argument()),
+            None => groups.push((child, vec![child.get_argument()])),
         }
-
-        
-
-        grouped_tree
     }
 
-    node.get_children().iter()
-        .for_each(|child| {
-            let mut new = prefix.clone();
-            new.push(vec![child.get_argument()]);
-            if child.is_last() {
-                out.push(new);
-            }
-            else {
-                child.get_children().iter()
-                    .for_each(|ch| {
-                    out.append(&mut get_usages(ch, new.clone()));
-                })
-            }
-        });
-    out
+    for (child, args) in groups {
+        let mut usage = prefix.clone();
+        usage.push(args);
+        if ends_usage(child) {
+            out.push(CommandUsage { usage: usage.clone(), redirect: child.get_redirect() });
+        }
+        get_usages(child, usage, out);
+    }
 }
 
+/// Whether a usage ends at `node`. Optional arguments after it are shown in the longer usage instead.
+fn ends_usage(node: &CommandNode) -> bool {
+    node.get_children().is_empty()
+        || node.is_last() && node.get_children().iter().any(|child| child.get_argument().is_mandatory())
+}
 
+/// One way to use a command, where each position lists the arguments which
+/// may be used there.
+#[derive(Debug, Clone, PartialEq)]
 pub struct CommandUsage<'a> {
     usage: Vec<Vec<&'a Argument>>,
+    redirect: Option<Redirect>,
 }
 
-#[derive(Debug, Clone)]
+impl<'a> CommandUsage<'a> {
+    pub fn get_arguments(&self) -> &[Vec<&'a Argument>] {
+        &self.usage
+    }
+
+    /// Where the usage goes on after its last argument
+    pub fn get_redirect(&self) -> Option<Redirect> {
+        self.redirect
+    }
+}
+
+impl Display for CommandUsage<'_> {
+    /// Literals are written as is, other arguments as `<name>`, or `[<name>]` if they are optional.
+    /// Redirects end in `-> <command>`, or `...` for any command, like vanilla.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let usage = self.usage.iter().map(|args| {
+            let args = args.iter().map(|arg| match (arg.get_type().is_literal(), arg.is_mandatory()) {
+                (true, _) => arg.get_name().to_string(),
+                (false, true) => format!("<{}>", arg.get_name()),
+                (false, false) => format!("[<{}>]", arg.get_name()),
+            });
+            match args.len() {
+                1 => args.collect::<String>(),
+                _ => format!("({})", args.collect::<Vec<_>>().join("|")),
+            }
+        }).join(" ");
+        match self.redirect {
+            Some(Redirect::Command) => write!(f, "/{usage} -> {}", self.usage[0][0].get_name()),
+            Some(Redirect::Root) => write!(f, "/{usage} ..."),
+            None => write!(f, "/{usage}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParseError {
-    UnbalancedExpression { message: String },
-    UnpairedQuote { message: String },
-    MissingArguments { message: String },
-    TooManyArguments,
-    IncorrectArguments { message: String },
-    Other { message: String }
+    UnbalancedExpression { message: String, cursor: usize },
+    UnpairedQuote { message: String, cursor: usize },
+    MissingArguments { message: String, cursor: usize },
+    TooManyArguments { cursor: usize },
+    IncorrectArguments { message: String, cursor: usize },
+    Other { message: String, cursor: usize }
 }
 
+impl ParseError {
+    /// The byte offset into the input where the error was found
+    pub fn get_cursor(&self) -> usize {
+        match self {
+            ParseError::UnbalancedExpression { cursor, .. } 
+            | ParseError::UnpairedQuote { cursor, .. } 
+            | ParseError::MissingArguments { cursor, .. } 
+            | ParseError::TooManyArguments { cursor } 
+            | ParseError::IncorrectArguments { cursor, .. } 
+            | ParseError::Other { cursor, .. } => *cursor,
+        }
+    }
 
+    /// Up to 10 characters of `input` before the cursor, marked like vanilla does
+    pub fn get_context(&self, input: &str) -> String {
+        let cursor = self.get_cursor().min(input.len());
+        let before: Vec<char> = input[..cursor].chars().collect();
+        let skip = before.len().saturating_sub(10);
+        format!(
+            "{}{}<--[HERE]",
+            if skip > 0 { "..." } else { "" },
+            before[skip..].iter().collect::<String>()
+        )
+    }
+}
 
 impl Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ParseError::UnbalancedExpression { 
-                message 
+                message, ..
             } => write!(f, "Unbalanced Expression: {message}."),
             ParseError::UnpairedQuote {
-                message
+                message, ..
             } => write!(f, "Unpaired Quote: {message}."),
             ParseError::Other { 
-                message 
+                message, ..
             } => write!(f, "Other: {message}."),
             ParseError::MissingArguments { 
-                message 
+                message, ..
             } => write!(f, "Missing arguments: {message}"),
-            ParseError::TooManyArguments => write!(f, "Too many arguments"),
+            ParseError::TooManyArguments { .. } => write!(f, "Too many arguments"),
             ParseError::IncorrectArguments { 
-                message 
+                message, ..
             } => write!(f, "Incorrect Arguments: {message}"),
-        }
+        }?;
+        write!(f, " at position {}", self.get_cursor())
     }
 }
 
@@ -206,7 +265,7 @@ impl Command {
         usages: CommandNode, 
         permission: Option<&str>, 
         aliases: &[String],
-        func: fn(&mut CommandEvent) -> EventResult,
+        func: CommandHandler,
     ) -> Self {
         Self {
             name: name.to_string(),
@@ -215,173 +274,242 @@ impl Command {
             aliases: aliases.to_vec(),
             permission: permission.map(str::to_string),
             func: func,
+            suggestions: HashMap::new(),
         }
     }
 
-    pub fn execute(e: &mut CommandEvent) {
-        crate::RUNTIME.block_on(run_command(e));
+    /// Runs the command of an event which was already parsed
+    pub async fn execute(e: CommandEvent) -> CommandResult {
+        run_command(e).await
     }
 
+    /// Parses `input`, without the leading `/`, against the usages of a command.
+    /// Returns the label the command was called by and the parsed arguments.
     pub fn parse_input(
-        input: &mut String, 
+        input: &str, 
         usages: &CommandNode
     ) -> Result<(String, Vec<CommandArg>), ParseError> {
+        super::parser::parse(input, usages)
+    }
+}
 
-        
+pub trait TraitCommand {
+    fn execute(&self, event: &mut CommandEvent) -> bool;
+}
 
-        let label = match parse_next_arg(
-            input, 
-            &[usages.get_argument()]
-        )? {
-            CommandArg::Command(label) => Ok(label),
-            _ => Err(ParseError::Other { message: "Input empty".into() }),
-        }?;
+#[derive(Debug, Clone)]
+pub enum CommandSender {
+    Console,
+    Player {
+        player: Weak<Player>,
+    },
+    /// Another sender, run as an entity or from somewhere else by `/execute` and functions
+    Context(Box<CommandContext>),
+}
 
-        let chars = input.chars();
+/// What `/execute` and functions change about who runs a command.
+/// Permissions are always those of the source.
+#[derive(Debug, Clone)]
+pub struct CommandContext {
+    source: CommandSender,
+    executor: Option<SelectorTarget>,
+    location: Option<(Vec3d, f64, f64)>,
+    suppress_output: bool,
+    function_depth: usize,
+}
 
-        let mut tree = usages.clone();
+impl CommandContext {
+    pub fn get_source(&self) -> &CommandSender {
+        &self.source
+    }
 
-        let mut args: Vec<CommandArg> = vec![];
-        let mut remaining: String = chars
-            .skip_while(|c| c.is_whitespace())
-            .collect();
+    pub fn get_executor(&self) -> Option<&SelectorTarget> {
+        self.executor.as_ref()
+    }
+}
 
-        while !remaining.is_empty() {
-            let result = parse_next_arg(
-                &mut remaining, 
-                tree.get_expecting().as_slice()
-            )?;
-            
-            //tree = ;
-            args.push(result); 
+impl CommandSender {
+    fn into_context(self) -> CommandContext {
+        match self {
+            CommandSender::Context(context) => *context,
+            source => CommandContext {
+                source,
+                executor: None,
+                location: None,
+                suppress_output: false,
+                function_depth: 0,
+            },
         }
-
-        
-        
-        
-        Ok((label, args))
     }
-}
 
+    /// Runs as `executor`, which is `@s` and gets the feedback. Players also
+    /// become what `get_player` returns.
+    pub fn with_executor(self, executor: SelectorTarget) -> Self {
+        let mut context = self.into_context();
+        context.executor = Some(executor);
+        CommandSender::Context(Box::new(context))
+    }
 
+    /// Runs from `position`, looking at `yaw` and `pitch`
+    pub fn with_location(self, position: Vec3d, yaw: f64, pitch: f64) -> Self {
+        let mut context = self.into_context();
+        context.location = Some((position, yaw, pitch));
+        CommandSender::Context(Box::new(context))
+    }
 
-fn parse_next_arg(
-    remaining: &mut String, 
-    expecting: &[&Argument],
-) -> Result<CommandArg, ParseError> {
+    /// Nobody gets feedback or errors, like for commands in functions
+    pub fn with_suppressed_output(self) -> Self {
+        let mut context = self.into_context();
+        context.suppress_output = true;
+        CommandSender::Context(Box::new(context))
+    }
 
-    fn eat_token(remaining: &mut String) -> String {
-        let mut chars = remaining.chars();
-        let token: String = chars
-            .take_while_ref(|c| !c.is_whitespace())
-            .collect();
-        *remaining = chars.collect();
-        token
-    }   
-    
-    
-    let token = eat_token(remaining);
-    for arg in expecting {
-        match arg.get_type() {
-            ArgType::Command { label } => todo!(),
-            ArgType::Subcommand => {
+    /// Runs inside one more function
+    pub fn with_function_call(self) -> Self {
+        let mut context = self.into_context();
+        context.function_depth += 1;
+        CommandSender::Context(Box::new(context))
+    }
 
-            },
-            ArgType::Bool => {
-                if let Ok(val) = token.parse() {
-                    return Ok(CommandArg::Bool(val));
-                }
-            },
-            ArgType::Float { bounds } => {
-                if let Ok(val) = token.parse::<f32>() {
-                    if let Some(props) = bounds {
-                        if props.get_min() < val && val < props.get_max() {
-                            return Ok(CommandArg::Float(val))
-                        }
-                    } else {
-                        return Ok(CommandArg::Float(val))
-                    }
-                }
-            },
-            ArgType::Double { bounds } => {
-                if let Ok(val) = token.parse::<f64>() {
-                    if let Some(props) = bounds {
-                        if props.get_min() < val && val < props.get_max() {
-                            return Ok(CommandArg::Double(val))
-                        }
-                    } else {
-                        return Ok(CommandArg::Double(val))
-                    }
-                }
-            },
-            ArgType::Int { bounds } => 
-                if let Ok(val) = token.parse::<i32>() {
-                    if let Some(props) = bounds {
-                        if props.get_min() < val && val < props.get_max() {
-                            return Ok(CommandArg::Int(val))
-                        }
-                    } else {
-                        return Ok(CommandArg::Int(val))
-                    }
-                },
-            ArgType::Long { bounds } => todo!(),
-            ArgType::String { properties } => todo!(),
+    /// How many functions are running inside each other
+    pub fn get_function_depth(&self) -> usize {
+        match self {
+            CommandSender::Context(context) => context.function_depth,
+            _ => 0,
         }
     }
-    return Err(ParseError::IncorrectArguments { message: format!(
-        "Invalid argument {token}, expecting {}.",
-        expecting.iter().map(|arg| arg.get_name()).join("|")),
-    })
-}
 
-pub trait TraitCommand {
-    fn execute(&self, event: &mut CommandEvent) -> bool;
-}
-
-#[derive(Debug, Clone)]
-pub enum CommandSender {
-    Console,
-    Player {
-        player: Weak<Player>,
-    },
-}
+    pub fn is_output_suppressed(&self) -> bool {
+        matches!(self, CommandSender::Context(context) if context.suppress_output)
+    }
 
-impl CommandSender {
     pub fn get_name(&self) -> Option<String> {
         match self {
-            CommandSender::Console => Some("@".to_owned()),
+            CommandSender::Console => Some("Server".to_owned()),
             CommandSender::Player { player } => 
             match player.upgrade() {
                 Some(arc) => Some(arc.get_name().to_string()),
                 None => None,
+            },
+            CommandSender::Context(context) => match &context.executor {
+                Some(executor) => Some(executor.get_name()
+                    .unwrap_or(executor.get_entity_type().trim_start_matches("minecraft:"))
+                    .to_string()),
+                None => context.source.get_name(),
+            },
+        }
+    }
+
+    pub fn get_player(&self) -> Option<Arc<Player>> {
+        match self {
+            CommandSender::Console => None,
+            CommandSender::Player { player } => player.upgrade(),
+            CommandSender::Context(context) => match &context.executor {
+                Some(executor) => executor.get_player(),
+                None => context.source.get_player(),
+            },
+        }
+    }
+
+    /// The UUID of the entity running the command, which `@s` selects
+    pub fn get_uuid(&self) -> Option<Uuid> {
+        match self {
+            CommandSender::Console => None,
+            CommandSender::Player { player } => player.upgrade().map(|player| player.get_uuid()),
+            CommandSender::Context(context) => match &context.executor {
+                Some(executor) => Some(executor.get_uuid()),
+                None => context.source.get_uuid(),
+            },
+        }
+    }
+
+    /// Where the sender is, with the yaw and pitch it is looking at in degrees
+    pub async fn get_location(&self) -> (Vec3d, f64, f64) {
+        //TODO: use the world spawn as the console's location
+        let player = match self {
+            CommandSender::Console => return (Vec3d::new(0.0, 0.0, 0.0), 0.0, 0.0),
+            CommandSender::Player { player } => player.upgrade(),
+            CommandSender::Context(context) => {
+                return match (context.location, &context.executor) {
+                    (Some(location), _) => location,
+                    (None, Some(executor)) => executor.get_location().await,
+                    (None, None) => Box::pin(context.source.get_location()).await,
+                };
+            },
+        };
+        let Some(player) = player else {
+            return (Vec3d::new(0.0, 0.0, 0.0), 0.0, 0.0);
+        };
+        for world in THE_SERVER.get_worlds() {
+            let world = world.lock().await;
+            let tracker = world.get_entity_tracker().lock().unwrap();
+            if let Some(entity) = tracker.get_entity(player.get_entity_id()) {
+                return (entity.get_position(), entity.get_yaw(), entity.get_pitch());
             }
         }
+        (Vec3d::new(0.0, 0.0, 0.0), 0.0, 0.0)
+    }
+
+    /// The console has every permission
+    pub fn has_permission(&self, permission: &str) -> bool {
+        match self {
+            CommandSender::Console => true,
+            CommandSender::Player { player } => player.upgrade()
+                .is_some_and(|player| player.has_permission(permission)),
+            CommandSender::Context(context) => context.source.has_permission(permission),
+        }
     }
 
     pub fn send_message(&self, message: String) {
+        self.send_text(TextComponent::builder().text(&message).build());
+    }
+
+    /// The console only gets the plain text. Executors which aren't players get nothing.
+    pub fn send_text(&self, message: TextComponent<Nbt>) {
         match self {
             CommandSender::Console => {
                 RUNTIME.spawn(
-                    CONSOLE.get().unwrap().println(message)
+                    CONSOLE.get().unwrap().println(message.to_plain_string())
                 );
             },
             CommandSender::Player { player } => {
                 match player.upgrade() {
                     Some(arc) => {
                         RUNTIME.spawn(async move { 
-                            arc.send_message(message).await 
+                            arc.send_text(message).await 
                         });
                     },
                     None => (),
                 };
                 
             },
+            CommandSender::Context(context) => match (context.suppress_output, &context.executor) {
+                (true, _) => (),
+                (false, Some(executor)) => if let Some(player) = executor.get_player() {
+                    RUNTIME.spawn(async move {
+                        player.send_text(message).await
+                    });
+                },
+                (false, None) => context.source.send_text(message),
+            },
         }
     }
 }
 
 
 
+/// A command or alias with the label is already registered
+#[derive(Debug)]
+pub struct CommandExistsError(pub String);
+
+impl Error for CommandExistsError {}
+
+impl Display for CommandExistsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "There already is a command /{}", self.0)
+    }
+}
+
 pub struct CommandMapBuilder {
     commands: Vec<Command>,
 }
@@ -389,21 +517,19 @@ pub struct CommandMapBuilder {
 impl CommandMapBuilder {
     pub fn new() -> Self {
         Self {
-            commands: vec![
-                Command::new(
-                    "stop", 
-                    Some("Stops the server."), 
-                    CommandNode::single_usage("stop", &vec![]),
-                    Some("stop"), 
-                    &[],
-                    command_stop
-                ),
-            ],
+            commands: super::builtin::get_commands(),
         }
     }
 
-    pub fn register_command(&mut self, cmd: Command) {
+    /// Fails if the name or one of the aliases is taken, so a command can't replace another one
+    pub fn register_command(&mut self, cmd: Command) -> Result<(), CommandExistsError> {
+        let taken = std::iter::once(&cmd.name).chain(cmd.aliases.iter())
+            .find(|label| self.commands.iter().any(|command| command.name == **label || command.aliases.contains(label)));
+        if let Some(label) = taken {
+            return Err(CommandExistsError(label.clone()));
+        }
         self.commands.push(cmd);
+        Ok(())
     }
 
     pub fn build(self) -> CommandMap {
@@ -426,48 +552,137 @@ impl CommandMapBuilder {
     }
 }
 
-async fn run_command(e: &mut CommandEvent) -> EventResult {
+/// Runs a command typed by a player or the console, without the leading `/`,
+/// and sends the sender its feedback or error.
+pub async fn dispatch(sender: CommandSender, input: &str) -> CommandResult {
+    let result = parse_and_run(sender.clone(), input).await;
+    send_result(&sender, &result).await;
+    result
+}
 
-    async fn run(evt: &mut CommandEvent) -> EventResult {
-        match event::listen(THE_SERVER.get_event_manager(), evt) {
-            EventResult::Deny => EventResult::Deny,
-            _ => (evt.get_command().func)(evt)
-        }
+/// Runs a command which was parsed already, like a line of a function,
+/// and sends the sender its feedback or error.
+pub async fn dispatch_parsed(e: CommandEvent) -> CommandResult {
+    let sender = e.get_sender().clone();
+    let result = run_command(e).await;
+    send_result(&sender, &result).await;
+    result
+}
+
+async fn parse_and_run(sender: CommandSender, input: &str) -> CommandResult {
+    let mut tokens = input.split_whitespace();
+    let label = tokens.next().ok_or(CommandError::NotFound)?;
+    let command = match COMMAND_MAP.lock().await.as_ref() {
+        Some(command_map) => command_map.get_command(label).cloned(),
+        None => None,
+    }.ok_or(CommandError::NotFound)?;
+    let (label, parsed) = Command::parse_input(input, command.get_usage_tree())
+        .map_err(|error| CommandError::ParseError { error, input: input.to_string() })?;
+    let e = CommandEvent::new(
+        sender, 
+        command, 
+        label, 
+        tokens.map(str::to_string).collect()
+    ).with_parsed_args(parsed);
+    run_command(e).await
+}
+
+async fn run_command(mut e: CommandEvent) -> CommandResult {
+    if !e.get_command().can_use(e.get_sender()) {
+        return Err(CommandError::PermissionError);
+    }
+    if event::listen(THE_SERVER.get_event_manager(), &mut e).await == EventResult::Deny {
+        return Err(CommandError::EventCancelled);
     }
+    (e.get_command().func)(e).await
+}
 
-    match e.get_sender() {
-        CommandSender::Console => {
-            run(e).await
+/// Feedback goes to the sender, and to everyone with `FEEDBACK_PERMISSION` if the
+/// command changed something. Errors are red.
+async fn send_result(sender: &CommandSender, result: &CommandResult) {
+    match result {
+        Ok(success) => {
+            for feedback in success.get_feedback() {
+                sender.send_text(feedback.clone());
+            }
+            if success.is_broadcast() && !sender.is_output_suppressed() {
+                broadcast_feedback(sender, success.get_feedback()).await;
+            }
         },
-        CommandSender::Player { player } => {
-            match player.upgrade() {
-                Some(arc) => {
-                    match &e.get_command().permission {
-                        Some(permission) => {
-                            if arc.has_permission(permission.as_str()) {
-                                run(e).await
-                            } else {
-                                return EventResult::Deny;
-                            }
-                        },
-                        None => run(e).await
-                    }
-                    
-                },
-                None => EventResult::Deny,
+        //Whoever cancelled the event tells the sender why
+        Err(CommandError::EventCancelled) => (),
+        Err(error) => {
+            for line in error.to_text_components() {
+                sender.send_text(line);
             }
         },
     }
 }
 
-fn command_stop(_: &mut CommandEvent) -> EventResult {
-    let _ = crate::STOP_SIGNAL.get().unwrap().send(true);
-    EventResult::Default
+/// Tells the others who may see command feedback, and the console, like `[Steve: Set the time to 1000]`
+async fn broadcast_feedback(sender: &CommandSender, feedback: &[TextComponent<Nbt>]) {
+    let name = sender.get_name().unwrap_or_default();
+    let sender_uuid = sender.get_player().map(|player| player.get_uuid());
+    let mut receivers: Vec<CommandSender> = THE_SERVER.get_players_async().await.iter()
+        .filter_map(Weak::upgrade)
+        .filter(|player| Some(player.get_uuid()) != sender_uuid && player.has_permission(FEEDBACK_PERMISSION))
+        .map(|player| CommandSender::Player { player: Arc::downgrade(&player) })
+        .collect();
+    if sender_uuid.is_some() {
+        receivers.push(CommandSender::Console);
+    }
+    for message in feedback {
+        let message = TextComponent::builder()
+            .text(&format!("[{name}: {}]", message.to_plain_string()))
+            .formatting(Formatting::builder().color(0x7).italic(true).build())
+            .build();
+        for receiver in receivers.iter() {
+            receiver.send_text(message.clone());
+        }
+    }
+}
+
+/// What a command did when it succeeded: how many things it affected, and
+/// the messages for its sender.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandSuccess {
+    count: i32,
+    feedback: Vec<TextComponent<Nbt>>,
+    broadcast: bool,
 }
 
-fn command_reload(_: &mut CommandEvent) -> EventResult {
-    let _ = crate::STOP_SIGNAL.get().unwrap().send(false);
-    EventResult::Default
+impl CommandSuccess {
+    pub fn new(count: i32) -> Self {
+        Self { count, feedback: vec![], broadcast: true }
+    }
+
+    pub fn with_feedback(mut self, feedback: TextComponent<Nbt>) -> Self {
+        self.feedback.push(feedback);
+        self
+    }
+
+    pub fn with_message(self, message: &str) -> Self {
+        self.with_feedback(TextComponent::builder().text(message).build())
+    }
+
+    /// Whether others who may see command feedback are told, which commands
+    /// that only query something turn off
+    pub fn with_broadcast(mut self, broadcast: bool) -> Self {
+        self.broadcast = broadcast;
+        self
+    }
+
+    pub fn get_count(&self) -> i32 {
+        self.count
+    }
+
+    pub fn get_feedback(&self) -> &[TextComponent<Nbt>] {
+        &self.feedback
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        self.broadcast
+    }
 }
 
 pub struct CommandMap {
@@ -476,6 +691,10 @@ pub struct CommandMap {
 }
 
 impl CommandMap {
+    pub fn get_commands(&self) -> impl Iterator<Item = &Command> {
+        self.commands.values()
+    }
+
     pub fn get_command(&self, label: &str) -> Option<&Command> {
         match self.commands.get(label) {
             Some(cmd) => Some(cmd),
@@ -492,7 +711,7 @@ impl CommandMap {
 #[derive(Debug)]
 pub enum CommandError {
     EventCancelled,
-    ParseError { error: ParseError },
+    ParseError { error: ParseError, input: String },
     PermissionError,
     InvalidUsage { command: Command },
     Other { message: String },
@@ -501,6 +720,43 @@ pub enum CommandError {
 
 impl Error for CommandError {}
 
+impl CommandError {
+    /// The error in red, with the input up to the error below it for parse errors
+    pub fn to_text_components(&self) -> Vec<TextComponent<Nbt>> {
+        let red = || Formatting::builder().color(0xc).build();
+        let mut out = vec![
+            TextComponent::builder().text(&self.to_string()).formatting(red()).build()
+        ];
+        if let CommandError::ParseError { error, input } = self {
+            let context = error.get_context(input);
+            let context = context.strip_suffix("<--[HERE]").unwrap_or(&context);
+            out.push(TextComponent::builder()
+                .text(context)
+                .formatting(Formatting::builder().color(0x7).build())
+                .add_extra(TextComponent::builder()
+                    .text("<--[HERE]")
+                    .formatting(Formatting::builder().color(0xc).italic(true).build())
+                    .build()
+                )
+                .build()
+            );
+        }
+        out
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Other { message }
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::Other { message: message.to_string() }
+    }
+}
+
 impl Display for CommandError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -521,7 +777,7 @@ impl Display for CommandError {
             CommandError::Other { message } => {
                 f.write_fmt(format_args!("{message}"))
             }
-            CommandError::ParseError { error } => {
+            CommandError::ParseError { error, .. } => {
                 write!(f, "Error while parsing command: {error}.")
             }
             CommandError::NotFound => {
@@ -534,6 +790,7 @@ impl Display for CommandError {
 
 
 
+#[derive(Debug, Clone, PartialEq)]
 pub enum CommandArg {
     Command(String),
     Subcommand(String, Vec<CommandArg>),
@@ -543,8 +800,70 @@ pub enum CommandArg {
     Int(i32),
     Long(i64),
     String(String),
-
+    Entity(EntitySelector),
+    BlockPos(Coordinates),
+    Vec3(Coordinates),
+    BlockState(BlockStateArgument),
+    Item(ItemArgument),
+    Identifier(Identifier),
+    Nbt(NbtCompound),
+    /// A duration in ticks
+    Time(i32),
+    Uuid(Uuid),
+    Component(TextComponent<Nbt>),
+    GameMode(Gamemode),
+    Function(FunctionArgument),
 }
 
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usages() {
+        let usages = |name: &str| -> Vec<String> {
+            let command = super::super::builtin::get_commands().into_iter()
+                .find(|command| command.get_name() == name)
+                .unwrap();
+            command.get_usages().iter().map(ToString::to_string).collect()
+        };
+        assert_eq!(usages("weather"), vec!["/weather (clear|rain|thunder) [<duration>]"]);
+        assert_eq!(usages("time"), vec![
+            "/time set (day|noon|night|midnight)",
+            "/time set <time>",
+            "/time add <time>",
+            "/time query (daytime|gametime|day)",
+        ]);
+        assert_eq!(usages("list"), vec!["/list", "/list uuids"]);
+        assert_eq!(usages("gamemode"), vec!["/gamemode <gamemode> [<targets>]"]);
+        assert_eq!(usages("execute"), vec![
+            "/execute run ...",
+            "/execute (as|at) <targets> -> execute",
+            "/execute positioned <pos> -> execute",
+            "/execute (if|unless) entity <targets> -> execute",
+        ]);
+    }
+    #[test]
+    fn taken_labels() {
+        let command = |name: &str, aliases: &[String]| Command::new(
+            name,
+            None,
+            CommandNode::single_usage(name, &[]),
+            None,
+            aliases,
+            |_| Box::pin(std::future::ready(Ok(CommandSuccess::new(1))))
+        );
+        let mut builder = CommandMapBuilder::new();
+        assert!(matches!(builder.register_command(command("op", &[])), Err(CommandExistsError(label)) if label == "op"));
+        assert!(builder.register_command(command("paint", &["tell".to_string()])).is_err());
+        assert!(builder.register_command(command("paint", &["p".to_string()])).is_ok());
+        assert!(builder.register_command(command("p", &[])).is_err());
+
+        let map = builder.build();
+        assert!(map.get_command("op").unwrap().get_permission().is_some());
+        assert_eq!(map.get_command("p").unwrap().get_name(), "paint");
+    }
+}
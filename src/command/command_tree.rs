@@ -1,17 +1,24 @@
-use std::{
-    hash::Hash, 
-    ptr::NonNull
-};
+use std::hash::Hash;
 
 use super::argument::{ArgType, Argument};
 
 
 
+/// Where parsing goes on after a node, like the redirects of vanilla's command graph
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Redirect {
+    /// The arguments after the command's name again, like `/execute as <targets> ...`
+    Command,
+    /// Any command, which is the rest of the input, like `/execute run ...`
+    Root,
+}
+
 #[derive(Clone, Debug, Eq)]
 pub struct CommandNode {
     last: bool,
     arg: Argument,
     children: Vec<CommandNode>,
+    redirect: Option<Redirect>,
 }
 
 
@@ -19,7 +26,7 @@ pub struct CommandNode {
 
 impl PartialEq for CommandNode {
     fn eq(&self, other: &Self) -> bool {
-        self.arg == other.arg && self.is_last() == other.is_last()
+        self.arg == other.arg && self.is_last() == other.is_last() && self.redirect == other.redirect
     }
 }
 
@@ -30,51 +37,17 @@ impl Hash for CommandNode {
 }
 
 impl FromIterator<Argument> for Option<CommandNode> {
+    /// Chains the arguments, each one the only child of the one before it.
     fn from_iter<I: IntoIterator<Item = Argument>>(iter: I) -> Self {
-        
-        struct CommandNodeList {
-            head: Option<CommandNode>,
-            tail: Option<NonNull<CommandNode>>,
-        }
-
-        impl CommandNodeList {
-            fn new() -> Self {
-                Self {
-                    head: None, tail: None,
-                }
-            }
-
-            fn root(self) -> Option<CommandNode> {
-                self.head
-            }
-
-            fn extend(&mut self, mut node: CommandNode) {
-                if let Some(mut tail) = self.tail {
-                    unsafe {    
-                        let node_ptr = &mut node as *mut CommandNode;
-                        tail.as_mut().add_child(node);
-                        self.tail = NonNull::new(node_ptr);
-                    }
-                } else {
-                    let node_ptr = &mut node as *mut CommandNode;
-                    self.head = Some(node);
-                    self.tail = NonNull::new(node_ptr);
-                }
-            }
-        }
-
-        let mut into_iter = iter.into_iter().peekable();
-
-        let mut list = CommandNodeList::new();
-
-        while let Some(arg) = into_iter.next() {
-            list.extend(CommandNode {
-                last: into_iter.peek().is_none(),
+        let args: Vec<Argument> = iter.into_iter().collect();
+        args.into_iter().rev().fold(None, |child, arg| {
+            Some(CommandNode {
+                last: child.is_none(),
                 arg: arg,
-                children: vec![]
+                children: child.into_iter().collect(),
+                redirect: None,
             })
-        }
-        list.root()
+        })
     }
 }
 
@@ -82,9 +55,36 @@ impl CommandNode {
     pub fn is_last(&self) -> bool {
         self.last
     }
+
+    /// Whether the command can end after this node, because no more arguments are needed
+    pub fn is_executable(&self) -> bool {
+        match self.redirect {
+            Some(_) => self.last,
+            None => self.last || self.children.iter().all(|child| !child.get_argument().is_mandatory()),
+        }
+    }
+
+    pub fn get_redirect(&self) -> Option<Redirect> {
+        self.redirect
+    }
+
+    /// Redirects the node at the end of `path`, the names of the arguments after the command's name.
+    /// Whether the command can also end there is `executable`.
+    /// Panics if there is no such node, trees with redirects are written out in the code.
+    pub fn with_redirect(mut self, path: &[&str], redirect: Redirect, executable: bool) -> Self {
+        let mut node = &mut self;
+        for name in path {
+            node = node.children.iter_mut()
+                .find(|child| child.arg.get_name() == *name)
+                .unwrap_or_else(|| panic!("No argument '{name}' to redirect"));
+        }
+        node.redirect = Some(redirect);
+        node.last = executable;
+        self
+    }
     pub fn single_usage(label: &str, args: &[Argument]) -> Self {
         Self {
-            last: args.len() > 0,
+            last: args.is_empty(),
             arg: Argument::new(
                 label.to_owned(), 
                 ArgType::Command { label: label.to_string() }, 
@@ -95,10 +95,25 @@ impl CommandNode {
                     vec![x]
                 } else {
                     vec![]
-                }
+                },
+            redirect: None,
         }
     }
 
+    /// A tree with a branch for each usage, where usages starting with the
+    /// same arguments share their nodes.
+    pub fn multi_usage(label: &str, usages: &[&[Argument]]) -> Self {
+        let mut root = Self::single_usage(label, &[]);
+        root.last = usages.iter().any(|args| args.is_empty());
+        for args in usages {
+            let branch: Option<CommandNode> = args.iter().cloned().collect();
+            if let Some(branch) = branch {
+                root.add_child(branch);
+            }
+        }
+        root
+    }
+
     pub fn get_argument(&self) -> &Argument {
         &self.arg
     }
@@ -116,10 +131,16 @@ impl CommandNode {
             .map(|child| child.get_argument()).collect()
     }
 
+    /// Adds `child`, merging it into an existing child with the same argument.
+    /// Returns whether it was merged.
     pub fn add_child(&mut self, child: CommandNode) -> bool {
-        match self.children.iter().position(|p| p == &child) {
+        match self.children.iter().position(|p| p.arg == child.arg) {
             Some(idx) => {
-                self.children.get_mut(idx).unwrap().add_child(child);
+                let existing = self.children.get_mut(idx).unwrap();
+                existing.last |= child.last;
+                for grandchild in child.children {
+                    existing.add_child(grandchild);
+                }
                 true
             },
             None => {
@@ -0,0 +1,201 @@
+use crate::data_types::{BlockPos, Vec3d};
+
+use super::parser::StringReader;
+use super::ParseError;
+
+/// A coordinate which is either absolute or relative to the sender with `~`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorldCoordinate {
+    value: f64,
+    relative: bool,
+}
+
+impl WorldCoordinate {
+    pub fn new(value: f64, relative: bool) -> Self {
+        Self { value, relative }
+    }
+
+    pub fn get_value(&self) -> f64 {
+        self.value
+    }
+
+    pub fn is_relative(&self) -> bool {
+        self.relative
+    }
+
+    /// Resolves the coordinate against the sender's coordinate on the same axis
+    pub fn resolve(&self, origin: f64) -> f64 {
+        match self.relative {
+            true => origin + self.value,
+            false => self.value,
+        }
+    }
+}
+
+/// The position argument of commands like `/tp` and `/setblock`.
+///
+/// `~` coordinates are relative to the sender's position on each axis, while
+/// `^left ^up ^forwards` are relative to where the sender is looking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coordinates {
+    World([WorldCoordinate; 3]),
+    Local([f64; 3]),
+}
+
+impl Coordinates {
+    /// Reads three coordinates. Absolute coordinates must be integers for block
+    /// positions, otherwise whole numbers are moved to the middle of the block
+    /// on the x and z axes.
+    pub fn parse(reader: &mut StringReader, block: bool) -> Result<Self, ParseError> {
+        let start = reader.get_cursor();
+        if reader.peek() == Some('^') {
+            let mut local = [0.0; 3];
+            for (axis, value) in local.iter_mut().enumerate() {
+                if axis > 0 {
+                    expect_space(reader, start)?;
+                }
+                if reader.peek() != Some('^') {
+                    return Err(ParseError::IncorrectArguments {
+                        message: "Cannot mix world & local coordinates (everything must either use ^ or not)".to_string(),
+                        cursor: reader.get_cursor(),
+                    });
+                }
+                reader.skip();
+                *value = read_offset(reader)?;
+            }
+            return Ok(Coordinates::Local(local));
+        }
+
+        let mut world = [WorldCoordinate::new(0.0, false); 3];
+        for (axis, coordinate) in world.iter_mut().enumerate() {
+            if axis > 0 {
+                expect_space(reader, start)?;
+            }
+            *coordinate = match reader.peek() {
+                Some('^') => return Err(ParseError::IncorrectArguments {
+                    message: "Cannot mix world & local coordinates (everything must either use ^ or not)".to_string(),
+                    cursor: reader.get_cursor(),
+                }),
+                Some('~') => {
+                    reader.skip();
+                    WorldCoordinate::new(read_offset(reader)?, true)
+                },
+                _ => {
+                    let cursor = reader.get_cursor();
+                    let number = reader.read_number();
+                    let incorrect = |message: String| ParseError::IncorrectArguments { message, cursor };
+                    if number.is_empty() {
+                        return Err(incorrect("Expected a coordinate".to_string()));
+                    }
+                    let value = match block {
+                        true => number.parse::<i32>()
+                            .map_err(|_| incorrect(format!("Invalid integer '{number}'")))? as f64,
+                        false => number.parse::<f64>()
+                            .map_err(|_| incorrect(format!("Invalid double '{number}'")))?,
+                    };
+                    //Whole numbers point at the middle of a block, except for the height
+                    let centered = !block && axis != 1 && !number.contains('.');
+                    WorldCoordinate::new(if centered { value + 0.5 } else { value }, false)
+                },
+            };
+        }
+        Ok(Coordinates::World(world))
+    }
+
+    /// The position relative to `origin`, where `yaw` and `pitch` are the
+    /// sender's rotation in degrees.
+    pub fn resolve(&self, origin: Vec3d, yaw: f64, pitch: f64) -> Vec3d {
+        match self {
+            Coordinates::World([x, y, z]) => Vec3d::new(
+                x.resolve(origin.x),
+                y.resolve(origin.y),
+                z.resolve(origin.z),
+            ),
+            Coordinates::Local([left, up, forwards]) => {
+                let (yaw_sin, yaw_cos) = (yaw + 90.0).to_radians().sin_cos();
+                let (pitch_sin, pitch_cos) = (-pitch).to_radians().sin_cos();
+                let (up_sin, up_cos) = (-pitch + 90.0).to_radians().sin_cos();
+                let forwards_axis = Vec3d::new(yaw_cos * pitch_cos, pitch_sin, yaw_sin * pitch_cos);
+                let up_axis = Vec3d::new(yaw_cos * up_cos, up_sin, yaw_sin * up_cos);
+                //The cross product of the two points right
+                let left_axis = Vec3d::new(
+                    forwards_axis.y * up_axis.z - forwards_axis.z * up_axis.y,
+                    forwards_axis.z * up_axis.x - forwards_axis.x * up_axis.z,
+                    forwards_axis.x * up_axis.y - forwards_axis.y * up_axis.x,
+                ) * -1.0;
+                origin + forwards_axis * *forwards + up_axis * *up + left_axis * *left
+            },
+        }
+    }
+
+    /// The block containing the resolved position
+    pub fn resolve_block(&self, origin: Vec3d, yaw: f64, pitch: f64) -> BlockPos {
+        let position = self.resolve(origin, yaw, pitch);
+        BlockPos {
+            x: position.x.floor() as i32,
+            y: position.y.floor() as i32,
+            z: position.z.floor() as i32,
+        }
+    }
+}
+
+fn expect_space(reader: &mut StringReader, start: usize) -> Result<(), ParseError> {
+    if reader.peek() != Some(' ') {
+        return Err(ParseError::IncorrectArguments {
+            message: "Incomplete (expected 3 coordinates)".to_string(),
+            cursor: start,
+        });
+    }
+    reader.skip();
+    Ok(())
+}
+
+/// The number after `~` or `^`, which may be left out for no offset
+fn read_offset(reader: &mut StringReader) -> Result<f64, ParseError> {
+    let cursor = reader.get_cursor();
+    let number = reader.read_number();
+    if number.is_empty() {
+        return Ok(0.0);
+    }
+    number.parse::<f64>().map_err(|_| ParseError::IncorrectArguments {
+        message: format!("Invalid double '{number}'"),
+        cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str, block: bool) -> Result<Coordinates, ParseError> {
+        Coordinates::parse(&mut StringReader::new(input), block)
+    }
+
+    #[test]
+    fn world_coordinates() {
+        let origin = Vec3d::new(10.0, 64.0, -5.0);
+        let position = parse("~ ~1.5 3", false).unwrap().resolve(origin, 0.0, 0.0);
+        assert_eq!((position.x, position.y, position.z), (10.0, 65.5, 3.5));
+
+        let block = parse("~-1 70 ~", true).unwrap().resolve_block(origin, 0.0, 0.0);
+        assert_eq!((block.x, block.y, block.z), (9, 70, -5));
+
+        assert!(parse("1.5 2 3", true).is_err());
+        assert!(parse("1 2", false).is_err());
+        assert!(parse("~ ^ ~", false).is_err());
+    }
+
+    #[test]
+    fn local_coordinates() {
+        let origin = Vec3d::new(0.0, 0.0, 0.0);
+        //Facing south, forwards is +z and left is +x
+        let position = parse("^1 ^ ^2", false).unwrap().resolve(origin, 0.0, 0.0);
+        assert!((position.x - 1.0).abs() < 1e-9);
+        assert!(position.y.abs() < 1e-9);
+        assert!((position.z - 2.0).abs() < 1e-9);
+
+        //Looking straight up, forwards is +y
+        let position = parse("^ ^ ^3", false).unwrap().resolve(origin, 0.0, -90.0);
+        assert!((position.y - 3.0).abs() < 1e-9);
+    }
+}
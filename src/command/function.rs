@@ -0,0 +1,545 @@
+//! Datapack functions: `.mcfunction` files compiled against the commands, the
+//! `#minecraft:load` and `#minecraft:tick` function tags, and scheduled functions
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::data_types::{Identifier, Suggestion};
+use crate::event::events::command::CommandEvent;
+use crate::{FUNCTION_MAP, THE_SERVER};
+
+use super::parser::StringReader;
+use super::resource;
+use super::{Command, CommandArg, CommandMap, CommandSender, ParseError};
+
+/// The data which is baked into the server
+const GENERATED_DATA: &str = "generated/data";
+
+/// Each datapack is a directory in here, inside the world's directory, with a `data` directory
+const DATAPACKS: &str = "datapacks";
+
+/// Functions can't run inside each other deeper than this
+pub const MAX_FUNCTION_DEPTH: usize = 64;
+
+/// Run once the functions are loaded
+pub const LOAD_TAG: &str = "minecraft:load";
+
+/// Run every tick
+pub const TICK_TAG: &str = "minecraft:tick";
+
+/// A function, or a function tag if it starts with `#`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionArgument {
+    id: Identifier,
+    tag: bool,
+}
+
+impl FunctionArgument {
+    pub fn new(id: Identifier, tag: bool) -> Self {
+        Self { id, tag }
+    }
+
+    pub fn parse(reader: &mut StringReader) -> Result<Self, ParseError> {
+        let tag = reader.peek() == Some('#');
+        if tag {
+            reader.skip();
+        }
+        Ok(Self { id: resource::read_resource(reader, None)?, tag })
+    }
+
+    pub fn get_id(&self) -> &Identifier {
+        &self.id
+    }
+
+    pub fn is_tag(&self) -> bool {
+        self.tag
+    }
+}
+
+impl Display for FunctionArgument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.tag {
+            true => write!(f, "#{}", self.id),
+            false => write!(f, "{}", self.id),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FunctionError {
+    Io(std::io::Error),
+    UnknownCommand { line: usize, label: String },
+    ParseError { line: usize, error: ParseError },
+    /// Macro lines, and commands written with `/`
+    Unsupported { line: usize, message: String },
+}
+
+impl Error for FunctionError {}
+
+impl Display for FunctionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FunctionError::Io(e) => write!(f, "{e}"),
+            FunctionError::UnknownCommand { line, label } => {
+                write!(f, "Unknown command '{label}' on line {line}")
+            },
+            FunctionError::ParseError { line, error } => write!(f, "{error} on line {line}"),
+            FunctionError::Unsupported { line, message } => write!(f, "{message} on line {line}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for FunctionError {
+    fn from(e: std::io::Error) -> Self {
+        FunctionError::Io(e)
+    }
+}
+
+/// A line of a function, parsed against its command when the function was loaded
+#[derive(Debug, Clone)]
+struct FunctionLine {
+    command: Command,
+    label: String,
+    args: Vec<String>,
+    parsed: Vec<CommandArg>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    id: String,
+    lines: Vec<FunctionLine>,
+}
+
+impl Function {
+    /// Compiles the source of a `.mcfunction` file, which fails if any command
+    /// doesn't parse.
+    pub fn compile(id: &str, source: &str, command_map: &CommandMap) -> Result<Self, FunctionError> {
+        let mut lines = Vec::new();
+        for (line, input) in join_lines(source) {
+            if input.starts_with('$') {
+                return Err(FunctionError::Unsupported { line, message: "Macro lines aren't supported".to_string() });
+            }
+            if input.starts_with('/') {
+                return Err(FunctionError::Unsupported { line, message: "Commands must not start with '/'".to_string() });
+            }
+            let mut tokens = input.split_whitespace();
+            let label = tokens.next().unwrap_or_default();
+            let command = command_map.get_command(label)
+                .ok_or_else(|| FunctionError::UnknownCommand { line, label: label.to_string() })?;
+            let (label, parsed) = Command::parse_input(&input, command.get_usage_tree())
+                .map_err(|error| FunctionError::ParseError { line, error })?;
+            lines.push(FunctionLine {
+                command: command.clone(),
+                label,
+                args: tokens.map(str::to_string).collect(),
+                parsed,
+            });
+        }
+        Ok(Self { id: id.to_string(), lines })
+    }
+
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    /// How many commands the function has
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Runs the commands in order, as `sender` but without feedback.
+    /// Returns how many commands were run.
+    pub async fn run(&self, sender: &CommandSender) -> Result<usize, String> {
+        if sender.get_function_depth() >= MAX_FUNCTION_DEPTH {
+            return Err(format!("Functions can't run more than {MAX_FUNCTION_DEPTH} deep"));
+        }
+        let sender = sender.clone().with_suppressed_output().with_function_call();
+        for line in self.lines.iter() {
+            let e = CommandEvent::new(
+                sender.clone(),
+                line.command.clone(),
+                line.label.clone(),
+                line.args.clone()
+            ).with_parsed_args(line.parsed.clone());
+            //Commands which fail don't stop the function
+            let _ = super::dispatch_parsed(e).await;
+        }
+        Ok(self.lines.len())
+    }
+}
+
+/// The commands of a function with their line numbers, starting at 1. Lines
+/// starting with `#` are comments, and lines ending with `\` continue on the next line.
+fn join_lines(source: &str) -> Vec<(usize, String)> {
+    let mut out = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim();
+        let (number, mut joined) = match current.take() {
+            Some(continued) => continued,
+            None if line.is_empty() || line.starts_with('#') => continue,
+            None => (index + 1, String::new()),
+        };
+        match line.strip_suffix('\\') {
+            Some(line) => {
+                joined.push_str(line);
+                current = Some((number, joined));
+            },
+            None => {
+                joined.push_str(line);
+                out.push((number, joined));
+            },
+        }
+    }
+    out.extend(current);
+    out
+}
+
+/// A function waiting for its tick to run
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledFunction {
+    function: FunctionArgument,
+    tick: i64,
+}
+
+/// The loaded functions and function tags, and the functions scheduled to run
+pub struct FunctionMap {
+    functions: HashMap<String, Arc<Function>>,
+    tags: HashMap<String, Vec<String>>,
+    scheduled: Vec<ScheduledFunction>,
+    ticks: i64,
+}
+
+impl FunctionMap {
+    /// Loads the functions and function tags of each data directory, where later
+    /// directories override the functions of earlier ones. Functions which don't
+    /// compile against `command_map` are left out.
+    pub fn load(data_directories: &[PathBuf], command_map: &CommandMap) -> Self {
+        let mut functions = HashMap::new();
+        let mut tags: HashMap<String, Vec<TagEntry>> = HashMap::new();
+        for data in data_directories {
+            for (namespace, path) in get_namespaces(data) {
+                for (name, file) in find_files(&path, &["function", "functions"], "mcfunction") {
+                    let id = format!("{namespace}:{name}");
+                    let function = std::fs::read_to_string(&file)
+                        .map_err(FunctionError::from)
+                        .and_then(|source| Function::compile(&id, &source, command_map));
+                    match function {
+                        Ok(function) => {
+                            functions.insert(id, Arc::new(function));
+                        },
+                        Err(e) => log::error!("Couldn't load function {id}: {e}"),
+                    }
+                }
+                let tag_path = path.join("tags");
+                for (name, file) in find_files(&tag_path, &["function", "functions"], "json") {
+                    let id = format!("{namespace}:{name}");
+                    match read_tag(&file) {
+                        Ok((true, entries)) => {
+                            tags.insert(id, entries);
+                        },
+                        Ok((false, mut entries)) => tags.entry(id).or_default().append(&mut entries),
+                        Err(e) => log::error!("Couldn't load function tag {id}: {e}"),
+                    }
+                }
+            }
+        }
+
+        let tags = tags.keys()
+            .map(|id| {
+                let mut resolved = Vec::new();
+                resolve_tag(id, &tags, &functions, &mut HashSet::new(), &mut resolved);
+                (id.clone(), resolved)
+            })
+            .collect();
+        Self { functions, tags, scheduled: vec![], ticks: 0 }
+    }
+
+    pub fn get_function(&self, id: &str) -> Option<Arc<Function>> {
+        self.functions.get(id).cloned()
+    }
+
+    pub fn get_function_ids(&self) -> impl Iterator<Item = &String> {
+        self.functions.keys()
+    }
+
+    pub fn get_tag_ids(&self) -> impl Iterator<Item = &String> {
+        self.tags.keys()
+    }
+
+    /// The function, or the functions of the tag. `None` if it doesn't exist.
+    pub fn resolve(&self, function: &FunctionArgument) -> Option<Vec<Arc<Function>>> {
+        let id = function.get_id().to_string();
+        match function.is_tag() {
+            true => self.tags.get(&id).map(|ids| {
+                ids.iter().filter_map(|id| self.get_function(id)).collect()
+            }),
+            false => self.get_function(&id).map(|function| vec![function]),
+        }
+    }
+
+    /// How many ticks passed since the functions were loaded
+    pub fn get_ticks(&self) -> i64 {
+        self.ticks
+    }
+
+    pub fn get_scheduled(&self) -> &[ScheduledFunction] {
+        &self.scheduled
+    }
+
+    /// Runs `function` in `delay` ticks, replacing its other schedules if `replace`.
+    /// Returns the tick it runs at.
+    pub fn schedule(&mut self, function: FunctionArgument, delay: i64, replace: bool) -> i64 {
+        if replace {
+            self.scheduled.retain(|scheduled| scheduled.function != function);
+        }
+        let tick = self.ticks + delay;
+        self.scheduled.push(ScheduledFunction { function, tick });
+        tick
+    }
+
+    /// Returns how many schedules of `function` were removed
+    pub fn clear_schedule(&mut self, function: &FunctionArgument) -> usize {
+        let before = self.scheduled.len();
+        self.scheduled.retain(|scheduled| scheduled.function != *function);
+        before - self.scheduled.len()
+    }
+
+    /// Advances a tick. Returns the functions which are due, then those of `#minecraft:tick`.
+    fn tick(&mut self) -> Vec<Arc<Function>> {
+        self.ticks += 1;
+        let ticks = self.ticks;
+        let (due, scheduled) = std::mem::take(&mut self.scheduled).into_iter()
+            .partition::<Vec<_>, _>(|scheduled| scheduled.tick <= ticks);
+        self.scheduled = scheduled;
+        let tick = FunctionArgument::new(Identifier::new(TICK_TAG).unwrap(), true);
+        due.iter()
+            .map(|scheduled| &scheduled.function)
+            .chain(std::iter::once(&tick))
+            .filter_map(|function| self.resolve(function))
+            .flatten()
+            .collect()
+    }
+}
+
+/// The generated data, then the `data` directory of each datapack by name
+pub fn get_data_directories() -> Vec<PathBuf> {
+    let directory = Path::new(THE_SERVER.get_properties().get_level_name()).join(DATAPACKS);
+    let mut datapacks: Vec<PathBuf> = std::fs::read_dir(directory).into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path().join("data"))
+        .filter(|path| path.is_dir())
+        .collect();
+    datapacks.sort();
+    datapacks.insert(0, PathBuf::from(GENERATED_DATA));
+    datapacks
+}
+
+/// Runs the functions of a tag as the server
+pub async fn run_tag(tag: &str) {
+    let tag = FunctionArgument::new(Identifier::new(tag).unwrap(), true);
+    let functions = match FUNCTION_MAP.lock().await.as_ref() {
+        Some(function_map) => function_map.resolve(&tag).unwrap_or_default(),
+        None => return,
+    };
+    for function in functions {
+        let _ = function.run(&CommandSender::Console).await;
+    }
+}
+
+/// Runs the scheduled functions which are due and `#minecraft:tick` as the server
+pub async fn tick() {
+    let functions = match FUNCTION_MAP.lock().await.as_mut() {
+        Some(function_map) => function_map.tick(),
+        None => return,
+    };
+    for function in functions {
+        let _ = function.run(&CommandSender::Console).await;
+    }
+}
+
+/// Suggests the functions and function tags
+pub fn suggest_functions(_: &CommandSender, _: &str) -> Vec<Suggestion> {
+    //Suggestions can't wait for functions which are being loaded or run
+    let Ok(function_map) = FUNCTION_MAP.try_lock() else {
+        return vec![];
+    };
+    let Some(function_map) = function_map.as_ref() else {
+        return vec![];
+    };
+    let mut ids: Vec<String> = function_map.get_function_ids().cloned()
+        .chain(function_map.get_tag_ids().map(|id| format!("#{id}")))
+        .collect();
+    ids.sort();
+    ids.iter().map(|id| Suggestion::from(id.as_str())).collect()
+}
+
+/// The directories of the namespaces in a data directory, by name
+fn get_namespaces(data: &Path) -> Vec<(String, PathBuf)> {
+    std::fs::read_dir(data).into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| (entry.file_name().to_string_lossy().to_string(), entry.path()))
+        .collect()
+}
+
+/// The files with `extension` in any of the `directories` of `path`, with
+/// their path from there without the extension, like `foo/bar`.
+fn find_files(path: &Path, directories: &[&str], extension: &str) -> Vec<(String, PathBuf)> {
+    fn walk(root: &Path, directory: &Path, extension: &str, out: &mut Vec<(String, PathBuf)>) {
+        for entry in std::fs::read_dir(directory).into_iter().flatten().filter_map(Result::ok) {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(root, &path, extension, out);
+            } else if path.extension().is_some_and(|e| e == extension) {
+                let name = path.strip_prefix(root).unwrap().with_extension("");
+                let name = name.components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                out.push((name, path));
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    for directory in directories {
+        let root = path.join(directory);
+        walk(&root, &root, extension, &mut out);
+    }
+    out
+}
+
+/// An entry of a tag, a function or another tag with `#`
+#[derive(Debug, Clone)]
+struct TagEntry {
+    id: String,
+    required: bool,
+}
+
+/// Reads whether a tag replaces the tags before it, and its entries
+fn read_tag(path: &Path) -> Result<(bool, Vec<TagEntry>), String> {
+    let file = std::fs::File::open(path).map_err(|e| e.to_string())?;
+    let json: serde_json::Value = serde_json::from_reader(file).map_err(|e| e.to_string())?;
+    let replace = json["replace"].as_bool().unwrap_or(false);
+    let entries = json["values"].as_array()
+        .ok_or("Expected a list of values")?
+        .iter()
+        .map(|value| match value {
+            serde_json::Value::String(id) => Ok(TagEntry { id: id.clone(), required: true }),
+            value => match value["id"].as_str() {
+                Some(id) => Ok(TagEntry {
+                    id: id.to_string(),
+                    required: value["required"].as_bool().unwrap_or(true),
+                }),
+                None => Err(format!("Invalid tag entry {value}")),
+            },
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok((replace, entries))
+}
+
+/// Adds the functions of a tag to `out` in order, once each
+fn resolve_tag(
+    id: &str,
+    tags: &HashMap<String, Vec<TagEntry>>,
+    functions: &HashMap<String, Arc<Function>>,
+    visited: &mut HashSet<String>,
+    out: &mut Vec<String>,
+) {
+    if !visited.insert(id.to_string()) {
+        return;
+    }
+    for entry in tags.get(id).into_iter().flatten() {
+        match entry.id.strip_prefix('#') {
+            Some(tag) if tags.contains_key(&normalize(tag)) => {
+                resolve_tag(&normalize(tag), tags, functions, visited, out)
+            },
+            None if functions.contains_key(&normalize(&entry.id)) => {
+                let function = normalize(&entry.id);
+                if !out.contains(&function) {
+                    out.push(function);
+                }
+            },
+            _ if entry.required => log::error!("Unknown entry '{}' in function tag {id}", entry.id),
+            _ => (),
+        }
+    }
+}
+
+/// Adds the `minecraft` namespace to ids without one
+fn normalize(id: &str) -> String {
+    match id.contains(':') {
+        true => id.to_string(),
+        false => format!("minecraft:{id}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::CommandMapBuilder;
+
+    #[test]
+    fn lines() {
+        let source = "# a comment\n\nsay hello \\\n   world\n  kill @e[type=pig]\n";
+        assert_eq!(join_lines(source), vec![
+            (3, "say hello world".to_string()),
+            (5, "kill @e[type=pig]".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn load() {
+        let data = std::env::temp_dir().join(format!("functions-{}", uuid::Uuid::new_v4()));
+        let write = |path: &str, contents: &str| {
+            let path = data.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write("test/function/hello.mcfunction", "say hello\nsay \\\n  world");
+        write("test/function/nested/bye.mcfunction", "say bye");
+        write("test/function/broken.mcfunction", "say hi\nnot_a_command");
+        write("test/tags/function/all.json", r##"{"values": ["test:hello", "#test:more"]}"##);
+        write("test/tags/function/more.json", r#"{"values": ["test:nested/bye", "test:hello", {"id": "test:missing", "required": false}]}"#);
+
+        let function_map = FunctionMap::load(&[data.clone()], &CommandMapBuilder::new().build());
+        std::fs::remove_dir_all(&data).unwrap();
+
+        assert_eq!(function_map.get_function("test:hello").unwrap().len(), 2);
+        assert!(function_map.get_function("test:nested/bye").is_some());
+        assert!(function_map.get_function("test:broken").is_none());
+
+        let all = FunctionArgument::new(Identifier::new("test:all").unwrap(), true);
+        let ids: Vec<String> = function_map.resolve(&all).unwrap().iter()
+            .map(|function| function.get_id().to_string())
+            .collect();
+        assert_eq!(ids, vec!["test:hello", "test:nested/bye"]);
+    }
+
+    #[test]
+    fn schedule() {
+        let mut function_map = FunctionMap {
+            functions: HashMap::new(),
+            tags: HashMap::new(),
+            scheduled: vec![],
+            ticks: 0,
+        };
+        let function = FunctionArgument::new(Identifier::new("test:hello").unwrap(), false);
+        assert_eq!(function_map.schedule(function.clone(), 5, false), 5);
+        function_map.schedule(function.clone(), 10, false);
+        assert_eq!(function_map.get_scheduled().len(), 2);
+        function_map.schedule(function.clone(), 3, true);
+        assert_eq!(function_map.get_scheduled().len(), 1);
+        for _ in 0..3 {
+            function_map.tick();
+        }
+        assert!(function_map.get_scheduled().is_empty());
+        assert_eq!(function_map.clear_schedule(&function), 0);
+    }
+}
@@ -1,5 +1,13 @@
 pub mod command;
 pub mod command_tree;
 pub mod argument;
+pub mod brigadier;
+pub mod parser;
+pub mod selector;
+pub mod coordinates;
+pub mod resource;
+pub mod builtin;
+pub mod parameter;
+pub mod function;
 
 pub use command::*;
\ No newline at end of file
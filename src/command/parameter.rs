@@ -0,0 +1,356 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use itertools::Itertools;
+use quartz_nbt::NbtCompound;
+use uuid::Uuid;
+
+use crate::data_types::text_component::Nbt;
+use crate::data_types::{BlockPos, Identifier, TextComponent, Vec3d};
+use crate::event::events::command::CommandEvent;
+use crate::game::gamemode::Gamemode;
+use crate::player::Player;
+
+use super::argument::{ArgType, Argument, StringArgProperties, StringType};
+use super::coordinates::Coordinates;
+use super::function::FunctionArgument;
+use super::resource::{BlockStateArgument, ItemArgument};
+use super::selector::{EntitySelector, SelectorTarget};
+use super::{Command, CommandArg, CommandError, CommandResult, CommandSender, CommandSuccess};
+
+#[doc(hidden)]
+pub use inventory;
+
+/// The builder of a `#[command]` function's `Command`, which `#[command]` submits
+pub struct RegisteredCommand(pub fn() -> Command);
+
+inventory::collect!(RegisteredCommand);
+
+/// The commands of every registered `#[command]` function, sorted by name
+pub fn get_registered_commands() -> Vec<Command> {
+    inventory::iter::<RegisteredCommand>.into_iter()
+        .map(|command| (command.0)())
+        .sorted_by(|a, b| a.get_name().cmp(b.get_name()))
+        .collect()
+}
+
+/// Types which `#[command]` functions can take as parameters.
+/// Use `#[derive(CommandEnum)]` for enums of literals.
+#[async_trait]
+pub trait CommandParameter: Sized + Send {
+    /// The arguments which may be used for the parameter, more than one for literals
+    fn get_arguments(name: &str) -> Vec<Argument>;
+
+    /// Converts the parsed argument, resolving it against the sender if needed
+    async fn from_arg(arg: CommandArg, sender: &CommandSender) -> Result<Self, String>;
+
+    /// The value when the argument is left out, only optional parameters have one
+    fn missing() -> Option<Self> {
+        None
+    }
+}
+
+/// What `#[command]` functions may return. A `String` is feedback for the sender.
+pub trait IntoCommandResult {
+    fn into_command_result(self) -> CommandResult;
+}
+
+impl IntoCommandResult for () {
+    fn into_command_result(self) -> CommandResult {
+        Ok(CommandSuccess::new(1))
+    }
+}
+
+impl IntoCommandResult for String {
+    fn into_command_result(self) -> CommandResult {
+        Ok(CommandSuccess::new(1).with_message(&self))
+    }
+}
+
+impl IntoCommandResult for CommandSuccess {
+    fn into_command_result(self) -> CommandResult {
+        Ok(self)
+    }
+}
+
+impl<T: IntoCommandResult, E: Into<CommandError>> IntoCommandResult for Result<T, E> {
+    fn into_command_result(self) -> CommandResult {
+        self.map_err(Into::into).and_then(T::into_command_result)
+    }
+}
+
+/// A string which takes the rest of the input
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreedyString(pub String);
+
+/// A duration in ticks, like `3d`, `10s` or `20t`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ticks(pub i32);
+
+/// The parsed arguments in order, with the arguments after literals flattened
+pub fn get_args(e: &CommandEvent) -> Vec<CommandArg> {
+    flatten(e.get_parsed_args().to_vec())
+}
+
+/// Moves the arguments nested in literals after them
+fn flatten(args: Vec<CommandArg>) -> Vec<CommandArg> {
+    args.into_iter().flat_map(|arg| match arg {
+        CommandArg::Subcommand(name, rest) => {
+            let mut out = vec![CommandArg::Subcommand(name, vec![])];
+            out.append(&mut flatten(rest));
+            out
+        },
+        arg => vec![arg],
+    }).collect()
+}
+
+/// Adds each of `alternatives` to each usage
+pub fn extend_usages(usages: Vec<Vec<Argument>>, alternatives: Vec<Argument>) -> Vec<Vec<Argument>> {
+    usages.into_iter()
+        .flat_map(|usage| alternatives.iter().map(move |alternative| {
+            let mut usage = usage.clone();
+            usage.push(alternative.clone());
+            usage
+        }))
+        .collect()
+}
+
+/// Reads the next argument for a parameter of type `T`
+pub async fn next_arg<T: CommandParameter>(
+    args: &mut std::vec::IntoIter<CommandArg>,
+    name: &str,
+    sender: &CommandSender
+) -> Result<T, String> {
+    match args.next() {
+        Some(arg) => T::from_arg(arg, sender).await,
+        None => T::missing().ok_or(format!("Missing argument <{name}>")),
+    }
+}
+
+fn mismatch(arg: &CommandArg) -> String {
+    format!("Unexpected argument {arg:?}")
+}
+
+fn single(name: &str, arg_type: ArgType) -> Vec<Argument> {
+    vec![Argument::new(name.to_string(), arg_type, true)]
+}
+
+#[async_trait]
+impl<T: CommandParameter> CommandParameter for Option<T> {
+    fn get_arguments(name: &str) -> Vec<Argument> {
+        T::get_arguments(name).into_iter()
+            .map(|arg| Argument::new(arg.get_name().to_string(), arg.get_type().clone(), false))
+            .collect()
+    }
+
+    async fn from_arg(arg: CommandArg, sender: &CommandSender) -> Result<Self, String> {
+        T::from_arg(arg, sender).await.map(Some)
+    }
+
+    fn missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+/// Parameters which are read straight from one kind of argument
+macro_rules! simple_parameter {
+    ($type:ty, $arg_type:expr, $variant:ident) => {
+        #[async_trait]
+        impl CommandParameter for $type {
+            fn get_arguments(name: &str) -> Vec<Argument> {
+                single(name, $arg_type)
+            }
+
+            async fn from_arg(arg: CommandArg, _: &CommandSender) -> Result<Self, String> {
+                match arg {
+                    CommandArg::$variant(value) => Ok(value.into()),
+                    arg => Err(mismatch(&arg)),
+                }
+            }
+        }
+    };
+}
+
+simple_parameter!(bool, ArgType::Bool, Bool);
+simple_parameter!(i32, ArgType::Int { bounds: None }, Int);
+simple_parameter!(i64, ArgType::Long { bounds: None }, Long);
+simple_parameter!(f32, ArgType::Float { bounds: None }, Float);
+simple_parameter!(f64, ArgType::Double { bounds: None }, Double);
+simple_parameter!(String, ArgType::String { properties: None }, String);
+simple_parameter!(Gamemode, ArgType::GameMode, GameMode);
+simple_parameter!(Coordinates, ArgType::Vec3, Vec3);
+simple_parameter!(BlockStateArgument, ArgType::BlockState, BlockState);
+simple_parameter!(ItemArgument, ArgType::Item, Item);
+simple_parameter!(Identifier, ArgType::Identifier { registry: None }, Identifier);
+simple_parameter!(NbtCompound, ArgType::Nbt, Nbt);
+simple_parameter!(Uuid, ArgType::Uuid, Uuid);
+simple_parameter!(TextComponent<Nbt>, ArgType::Component, Component);
+simple_parameter!(FunctionArgument, ArgType::Function, Function);
+simple_parameter!(EntitySelector, ArgType::Entity { single: false, players_only: false }, Entity);
+
+#[async_trait]
+impl CommandParameter for GreedyString {
+    fn get_arguments(name: &str) -> Vec<Argument> {
+        single(name, ArgType::String { properties: Some(StringArgProperties::new(StringType::Greedy)) })
+    }
+
+    async fn from_arg(arg: CommandArg, _: &CommandSender) -> Result<Self, String> {
+        match arg {
+            CommandArg::String(value) => Ok(GreedyString(value)),
+            arg => Err(mismatch(&arg)),
+        }
+    }
+}
+
+#[async_trait]
+impl CommandParameter for Ticks {
+    fn get_arguments(name: &str) -> Vec<Argument> {
+        single(name, ArgType::Time { min: 0 })
+    }
+
+    async fn from_arg(arg: CommandArg, _: &CommandSender) -> Result<Self, String> {
+        match arg {
+            CommandArg::Time(ticks) => Ok(Ticks(ticks)),
+            arg => Err(mismatch(&arg)),
+        }
+    }
+}
+
+/// A position resolved against the sender's location
+#[async_trait]
+impl CommandParameter for Vec3d {
+    fn get_arguments(name: &str) -> Vec<Argument> {
+        single(name, ArgType::Vec3)
+    }
+
+    async fn from_arg(arg: CommandArg, sender: &CommandSender) -> Result<Self, String> {
+        match arg {
+            CommandArg::Vec3(coordinates) => {
+                let (origin, yaw, pitch) = sender.get_location().await;
+                Ok(coordinates.resolve(origin, yaw, pitch))
+            },
+            arg => Err(mismatch(&arg)),
+        }
+    }
+}
+
+#[async_trait]
+impl CommandParameter for BlockPos {
+    fn get_arguments(name: &str) -> Vec<Argument> {
+        single(name, ArgType::BlockPos)
+    }
+
+    async fn from_arg(arg: CommandArg, sender: &CommandSender) -> Result<Self, String> {
+        match arg {
+            CommandArg::BlockPos(coordinates) => {
+                let (origin, yaw, pitch) = sender.get_location().await;
+                Ok(coordinates.resolve_block(origin, yaw, pitch))
+            },
+            arg => Err(mismatch(&arg)),
+        }
+    }
+}
+
+/// Selectors are resolved, and must find something
+async fn get_entities(arg: CommandArg, sender: &CommandSender) -> Result<Vec<SelectorTarget>, String> {
+    match arg {
+        CommandArg::Entity(selector) => selector.get_entities(sender).await,
+        arg => Err(mismatch(&arg)),
+    }
+}
+
+#[async_trait]
+impl CommandParameter for SelectorTarget {
+    fn get_arguments(name: &str) -> Vec<Argument> {
+        single(name, ArgType::Entity { single: true, players_only: false })
+    }
+
+    async fn from_arg(arg: CommandArg, sender: &CommandSender) -> Result<Self, String> {
+        Ok(get_entities(arg, sender).await?.remove(0))
+    }
+}
+
+#[async_trait]
+impl CommandParameter for Vec<SelectorTarget> {
+    fn get_arguments(name: &str) -> Vec<Argument> {
+        single(name, ArgType::Entity { single: false, players_only: false })
+    }
+
+    async fn from_arg(arg: CommandArg, sender: &CommandSender) -> Result<Self, String> {
+        get_entities(arg, sender).await
+    }
+}
+
+#[async_trait]
+impl CommandParameter for Arc<Player> {
+    fn get_arguments(name: &str) -> Vec<Argument> {
+        single(name, ArgType::Entity { single: true, players_only: true })
+    }
+
+    async fn from_arg(arg: CommandArg, sender: &CommandSender) -> Result<Self, String> {
+        get_entities(arg, sender).await?.iter()
+            .find_map(SelectorTarget::get_player)
+            .ok_or("No player was found".to_string())
+    }
+}
+
+#[async_trait]
+impl CommandParameter for Vec<Arc<Player>> {
+    fn get_arguments(name: &str) -> Vec<Argument> {
+        single(name, ArgType::Entity { single: false, players_only: true })
+    }
+
+    async fn from_arg(arg: CommandArg, sender: &CommandSender) -> Result<Self, String> {
+        Ok(get_entities(arg, sender).await?.iter()
+            .filter_map(SelectorTarget::get_player)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use server_macros::{command, CommandEnum};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, CommandEnum)]
+    #[command(crate = "crate")]
+    enum Shape {
+        Square,
+        RoundedCorner,
+    }
+
+    /// Draws a shape.
+    #[command(name = "draw", aliases = ["d"], register = false, crate = "crate")]
+    fn draw(shape: Shape, size: i32, label: Option<GreedyString>) -> String {
+        format!("{shape:?} {size} {:?}", label.map(|label| label.0))
+    }
+
+    #[tokio::test]
+    async fn typed_command() {
+        let command = draw_command();
+        assert_eq!(command.get_description().map(String::as_str), Some("Draws a shape."));
+        assert_eq!(command.get_aliases(), ["d"]);
+        let usages: Vec<String> = command.get_usages().iter().map(ToString::to_string).collect();
+        assert_eq!(usages, vec!["/draw (square|rounded_corner) <size> [<label>]"]);
+
+        let (_, args) = super::super::Command::parse_input("draw rounded_corner 3 a b", command.get_usage_tree()).unwrap();
+        let mut args = flatten(args).into_iter();
+        let sender = CommandSender::Console;
+        assert_eq!(next_arg::<Shape>(&mut args, "shape", &sender).await, Ok(Shape::RoundedCorner));
+        assert_eq!(next_arg::<i32>(&mut args, "size", &sender).await, Ok(3));
+        assert_eq!(next_arg::<Option<GreedyString>>(&mut args, "label", &sender).await, Ok(Some(GreedyString("a b".to_string()))));
+        assert_eq!(next_arg::<Option<i32>>(&mut args, "extra", &sender).await, Ok(None));
+    }
+
+    #[test]
+    fn registered_commands() {
+        let commands = get_registered_commands();
+        let names: Vec<&str> = commands.iter().map(Command::get_name).collect();
+        assert!(names.contains(&"seed") && names.contains(&"ban-ip"));
+        assert!(!names.contains(&"draw"));
+        assert!(names.is_sorted());
+        let function = commands.iter().find(|command| command.get_name() == "function").unwrap();
+        assert!(function.get_suggestion_provider("name").is_some());
+    }
+}
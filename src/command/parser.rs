@@ -0,0 +1,497 @@
+use itertools::Itertools;
+
+use uuid::Uuid;
+
+use crate::data_types::text_component::Nbt;
+use crate::data_types::TextComponent;
+use crate::game::gamemode::Gamemode;
+
+use super::argument::{ArgType, Argument, Numeric, NumericArgProperties, StringType};
+use super::command_tree::{CommandNode, Redirect};
+use super::coordinates::Coordinates;
+use super::function::FunctionArgument;
+use super::resource::{self, BlockStateArgument, ItemArgument};
+use super::selector::EntitySelector;
+use super::{CommandArg, ParseError};
+
+/// Reads command input, keeping track of the cursor for errors.
+#[derive(Debug, Clone)]
+pub struct StringReader<'a> {
+    input: &'a str,
+    cursor: usize,
+}
+
+impl<'a> StringReader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, cursor: 0 }
+    }
+
+    /// Byte offset into the input
+    pub fn get_cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor;
+    }
+
+    pub fn get_input(&self) -> &'a str {
+        self.input
+    }
+
+    pub fn get_remaining(&self) -> &'a str {
+        &self.input[self.cursor..]
+    }
+
+    pub fn can_read(&self) -> bool {
+        self.cursor < self.input.len()
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.get_remaining().chars().next()
+    }
+
+    pub fn skip(&mut self) {
+        if let Some(c) = self.peek() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    pub fn read_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.cursor;
+        while let Some(c) = self.peek().filter(|c| predicate(*c)) {
+            self.cursor += c.len_utf8();
+        }
+        &self.input[start..self.cursor]
+    }
+
+    /// Brigadier only allows these characters in unquoted strings
+    pub fn is_allowed_in_unquoted(c: char) -> bool {
+        c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '+')
+    }
+
+    pub fn read_unquoted(&mut self) -> &'a str {
+        self.read_while(Self::is_allowed_in_unquoted)
+    }
+
+    /// Reads a resource location like `minecraft:pig`, or a tag like `#minecraft:skeletons`
+    pub fn read_identifier(&mut self) -> &'a str {
+        self.read_while(|c| Self::is_allowed_in_unquoted(c) || matches!(c, ':' | '/' | '#'))
+    }
+
+    /// Skips spaces between the parts of an argument, like selector options
+    pub fn skip_whitespace(&mut self) {
+        self.read_while(char::is_whitespace);
+    }
+
+    /// Reads the next character if it is `c`
+    pub fn expect(&mut self, c: char) -> Result<(), ParseError> {
+        if self.peek() != Some(c) {
+            return Err(ParseError::IncorrectArguments {
+                message: format!("Expected '{c}'"),
+                cursor: self.cursor,
+            });
+        }
+        self.skip();
+        Ok(())
+    }
+
+    /// Reads up to the next whitespace
+    pub fn read_word(&mut self) -> &'a str {
+        self.read_while(|c| !c.is_whitespace())
+    }
+
+    pub fn read_remaining(&mut self) -> &'a str {
+        let remaining = self.get_remaining();
+        self.cursor = self.input.len();
+        remaining
+    }
+
+    /// Reads a string in `"` or `'` quotes, where `\` escapes the quote and itself.
+    pub fn read_quoted(&mut self) -> Result<String, ParseError> {
+        let start = self.cursor;
+        let Some(quote) = self.peek().filter(|c| *c == '"' || *c == '\'') else {
+            return Err(ParseError::IncorrectArguments {
+                message: "Expected quote to start a string".to_string(),
+                cursor: self.cursor,
+            });
+        };
+        self.skip();
+        let mut out = String::new();
+        let mut escaped = false;
+        while let Some(c) = self.peek() {
+            self.skip();
+            if escaped {
+                if c != quote && c != '\\' {
+                    return Err(ParseError::IncorrectArguments {
+                        message: format!("Invalid escape sequence '\\{c}' in quoted string"),
+                        cursor: self.cursor - c.len_utf8(),
+                    });
+                }
+                out.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == quote {
+                return Ok(out);
+            } else {
+                out.push(c);
+            }
+        }
+        Err(ParseError::UnpairedQuote {
+            message: "Unclosed quoted string".to_string(),
+            cursor: start,
+        })
+    }
+
+    /// Reads a quoted string if the next character is a quote, otherwise an unquoted one.
+    pub fn read_string(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some('"' | '\'') => self.read_quoted(),
+            _ => Ok(self.read_unquoted().to_string()),
+        }
+    }
+
+    pub fn read_number(&mut self) -> &'a str {
+        self.read_while(|c| c.is_ascii_digit() || c == '.' || c == '-')
+    }
+}
+
+/// Parses the input of a command, without the leading `/`, against the command's tree.
+/// Returns the label the command was called by and the parsed arguments.
+pub fn parse(input: &str, tree: &CommandNode) -> Result<(String, Vec<CommandArg>), ParseError> {
+    let mut reader = StringReader::new(input.trim_end());
+    let label = reader.read_word();
+    if label.is_empty() {
+        return Err(ParseError::MissingArguments {
+            message: "Expected a command".to_string(),
+            cursor: 0,
+        });
+    }
+    let args = parse_children(&mut reader, tree, tree)?;
+    Ok((label.to_string(), args))
+}
+
+/// Tries each child of `node` in turn, literals first, and returns the
+/// error which got furthest into the input if none of them match.
+/// A node redirected back to the command goes on with the children of `tree`, the command's root,
+/// and one redirected to any command takes the rest of the input as that command.
+fn parse_children(reader: &mut StringReader, node: &CommandNode, tree: &CommandNode) -> Result<Vec<CommandArg>, ParseError> {
+    let children = match node.get_redirect() {
+        Some(Redirect::Command) => tree.get_children(),
+        _ => node.get_children(),
+    };
+    if !reader.can_read() {
+        if node.is_executable() {
+            return Ok(vec![]);
+        }
+        let expecting = match node.get_redirect() {
+            Some(Redirect::Root) => "<command>".to_string(),
+            _ => children.iter().map(|child| format!("<{}>", child.get_argument().get_name())).join("|"),
+        };
+        return Err(ParseError::MissingArguments {
+            message: format!("expecting {expecting}"),
+            cursor: reader.get_cursor(),
+        });
+    }
+    if reader.peek() != Some(' ') {
+        return Err(ParseError::IncorrectArguments {
+            message: "Expected whitespace to end one argument, but found trailing data".to_string(),
+            cursor: reader.get_cursor(),
+        });
+    }
+    reader.skip();
+    if node.get_redirect() == Some(Redirect::Root) {
+        let command = reader.get_remaining();
+        reader.set_cursor(reader.get_input().len());
+        return Ok(vec![CommandArg::String(command.to_string())]);
+    }
+    if children.is_empty() {
+        return Err(ParseError::TooManyArguments { cursor: reader.get_cursor() });
+    }
+
+    let start = reader.get_cursor();
+    let mut furthest: Option<ParseError> = None;
+    let children = children.iter()
+        .sorted_by_key(|child| !child.get_argument().get_type().is_literal());
+    for child in children {
+        reader.set_cursor(start);
+        let result = parse_argument(reader, child.get_argument())
+            .and_then(|arg| Ok((arg, parse_children(reader, child, tree)?)));
+        match result {
+            Ok((CommandArg::Subcommand(name, _), rest)) => return Ok(vec![CommandArg::Subcommand(name, rest)]),
+            Ok((arg, mut rest)) => {
+                rest.insert(0, arg);
+                return Ok(rest);
+            },
+            Err(e) => {
+                if furthest.as_ref().map_or(true, |f| e.get_cursor() > f.get_cursor()) {
+                    furthest = Some(e);
+                }
+            },
+        }
+    }
+    Err(furthest.unwrap())
+}
+
+/// Parses a single argument at the cursor
+pub fn parse_argument(reader: &mut StringReader, argument: &Argument) -> Result<CommandArg, ParseError> {
+    let start = reader.get_cursor();
+    let incorrect = |message: String| ParseError::IncorrectArguments { message, cursor: start };
+    match argument.get_type() {
+        ArgType::Command { .. } | ArgType::Subcommand => {
+            let word = reader.read_unquoted();
+            if word != argument.get_name() {
+                return Err(incorrect(format!("Unknown argument '{word}', expecting {}", argument.get_name())));
+            }
+            match argument.get_type() {
+                ArgType::Command { .. } => Ok(CommandArg::Command(word.to_string())),
+                _ => Ok(CommandArg::Subcommand(word.to_string(), vec![])),
+            }
+        },
+        ArgType::Bool => match reader.read_unquoted() {
+            "true" => Ok(CommandArg::Bool(true)),
+            "false" => Ok(CommandArg::Bool(false)),
+            word => Err(incorrect(format!("Invalid boolean, expected 'true' or 'false' but found '{word}'"))),
+        },
+        ArgType::Float { bounds } => {
+            parse_number(reader, "float", bounds.as_ref()).map(CommandArg::Float)
+        },
+        ArgType::Double { bounds } => {
+            parse_number(reader, "double", bounds.as_ref()).map(CommandArg::Double)
+        },
+        ArgType::Int { bounds } => {
+            parse_number(reader, "integer", bounds.as_ref()).map(CommandArg::Int)
+        },
+        ArgType::Long { bounds } => {
+            parse_number(reader, "long", bounds.as_ref()).map(CommandArg::Long)
+        },
+        ArgType::String { properties } => {
+            let string = match properties.as_ref().map(|p| p.get_type()) {
+                Some(StringType::Word) | None => reader.read_unquoted().to_string(),
+                Some(StringType::Phrase) => reader.read_string()?,
+                Some(StringType::Greedy) => reader.read_remaining().to_string(),
+            };
+            if string.is_empty() && reader.get_cursor() == start {
+                return Err(incorrect("Expected string".to_string()));
+            }
+            Ok(CommandArg::String(string))
+        },
+        ArgType::Entity { single, players_only } => {
+            let selector = EntitySelector::parse(reader)?;
+            if *single && !selector.is_single() {
+                return Err(incorrect(
+                    "Only one entity is allowed, but the provided selector allows more than one".to_string()
+                ));
+            }
+            if *players_only && !selector.is_players_only() {
+                return Err(incorrect(
+                    "Only players may be affected by this command, but the provided selector includes entities".to_string()
+                ));
+            }
+            Ok(CommandArg::Entity(selector))
+        },
+        ArgType::BlockPos => Coordinates::parse(reader, true).map(CommandArg::BlockPos),
+        ArgType::Vec3 => Coordinates::parse(reader, false).map(CommandArg::Vec3),
+        ArgType::BlockState => BlockStateArgument::parse(reader).map(CommandArg::BlockState),
+        ArgType::Item => ItemArgument::parse(reader).map(CommandArg::Item),
+        ArgType::Identifier { registry } => {
+            resource::read_resource(reader, registry.as_deref()).map(CommandArg::Identifier)
+        },
+        ArgType::Nbt => resource::read_compound(reader).map(CommandArg::Nbt),
+        ArgType::Time { min } => parse_time(reader, *min).map(CommandArg::Time),
+        ArgType::Uuid => {
+            let uuid = reader.read_while(|c| c.is_ascii_hexdigit() || c == '-');
+            Uuid::parse_str(uuid)
+                .map(CommandArg::Uuid)
+                .map_err(|_| incorrect(format!("Invalid UUID '{uuid}'")))
+        },
+        ArgType::Component => parse_component(reader).map(CommandArg::Component),
+        ArgType::GameMode => {
+            let name = reader.read_unquoted();
+            Gamemode::ALL.into_iter()
+                .find(|game_mode| game_mode.get_name() == name)
+                .map(CommandArg::GameMode)
+                .ok_or_else(|| incorrect(format!("Unknown game mode: {name}")))
+        },
+        ArgType::Function => FunctionArgument::parse(reader).map(CommandArg::Function),
+    }
+}
+
+/// Parses a duration with an optional unit of days, seconds or ticks into ticks
+fn parse_time(reader: &mut StringReader, min: i32) -> Result<i32, ParseError> {
+    let start = reader.get_cursor();
+    let incorrect = |message: String| ParseError::IncorrectArguments { message, cursor: start };
+    let number = reader.read_number();
+    let Ok(value) = number.parse::<f32>() else {
+        return Err(incorrect(format!("Invalid float '{number}'")));
+    };
+    let multiplier = match reader.peek() {
+        Some('d') => 24000.0,
+        Some('s') => 20.0,
+        Some('t') => 1.0,
+        Some(' ') | None => 1.0,
+        Some(_) => return Err(incorrect("Invalid unit".to_string())),
+    };
+    if reader.peek().is_some_and(|c| c != ' ') {
+        reader.skip();
+    }
+    let ticks = (value * multiplier).round() as i32;
+    if ticks < min {
+        return Err(incorrect(format!("Tick count must not be less than {min}, found {ticks}")));
+    }
+    Ok(ticks)
+}
+
+/// Parses a text component as JSON, where strings are plain text and arrays
+/// are appended to the first element.
+fn parse_component(reader: &mut StringReader) -> Result<TextComponent<Nbt>, ParseError> {
+    let start = reader.get_cursor();
+    let incorrect = |message: String| ParseError::IncorrectArguments { message, cursor: start };
+    let mut stream = serde_json::Deserializer::from_str(reader.get_remaining())
+        .into_iter::<serde_json::Value>();
+    let value = match stream.next() {
+        Some(Ok(value)) => value,
+        Some(Err(e)) => return Err(incorrect(format!("Invalid chat component: {e}"))),
+        None => return Err(incorrect("Expected a chat component".to_string())),
+    };
+    reader.set_cursor(start + stream.byte_offset());
+    serde_json::from_value(normalize_component(value))
+        .map_err(|e| incorrect(format!("Invalid chat component: {e}")))
+}
+
+fn normalize_component(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(text) => json!({ "text": text }),
+        serde_json::Value::Array(mut values) if !values.is_empty() => {
+            let extra: Vec<serde_json::Value> = values.drain(1..).map(normalize_component).collect();
+            let mut first = normalize_component(values.remove(0));
+            if let Some(object) = first.as_object_mut().filter(|_| !extra.is_empty()) {
+                object.entry("extra")
+                    .or_insert_with(|| serde_json::Value::Array(vec![]))
+                    .as_array_mut()
+                    .map(|array| array.extend(extra));
+            }
+            first
+        },
+        value => value,
+    }
+}
+
+/// Parses a number, where the bounds are inclusive.
+fn parse_number<T>(
+    reader: &mut StringReader,
+    name: &str,
+    bounds: Option<&NumericArgProperties<T>>
+) -> Result<T, ParseError>
+where
+    T: Numeric + std::str::FromStr + PartialOrd,
+{
+    let start = reader.get_cursor();
+    let number = reader.read_number();
+    let incorrect = |message: String| ParseError::IncorrectArguments { message, cursor: start };
+    if number.is_empty() {
+        return Err(incorrect(format!("Expected {name}")));
+    }
+    let Ok(value) = number.parse::<T>() else {
+        return Err(incorrect(format!("Invalid {name} '{number}'")));
+    };
+    if let Some(bounds) = bounds {
+        if value < bounds.get_min() {
+            return Err(incorrect(format!("{name} must not be less than {}, found {value}", bounds.get_min())));
+        }
+        if value > bounds.get_max() {
+            return Err(incorrect(format!("{name} must not be more than {}, found {value}", bounds.get_max())));
+        }
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::argument::StringArgProperties;
+
+    use super::*;
+
+    fn arg(name: &str, arg_type: ArgType) -> Argument {
+        Argument::new(name.to_string(), arg_type, true)
+    }
+
+    fn tree() -> CommandNode {
+        let phrase = StringArgProperties::new(StringType::Phrase);
+        let greedy = StringArgProperties::new(StringType::Greedy);
+        let mut tree = CommandNode::single_usage("give", &[
+            arg("count", ArgType::Int { bounds: Some(NumericArgProperties::new(1, 64)) }),
+            arg("name", ArgType::String { properties: Some(phrase) }),
+        ]);
+        for usage in [
+            [arg("reason", ArgType::Subcommand), arg("text", ArgType::String { properties: Some(greedy) })],
+            [arg("all", ArgType::Subcommand), arg("amount", ArgType::Long { bounds: None })],
+        ] {
+            tree.add_child(CommandNode::single_usage("give", &usage).get_children()[0].clone());
+        }
+        tree
+    }
+
+    #[test]
+    fn parse_arguments() {
+        let tree = tree();
+        assert_eq!(
+            parse("give 64 \"a \\\"b\\\"\"", &tree).unwrap(),
+            ("give".to_string(), vec![CommandArg::Int(64), CommandArg::String("a \"b\"".to_string())])
+        );
+        assert_eq!(
+            parse("give all 5000000000", &tree).unwrap().1,
+            vec![CommandArg::Subcommand("all".to_string(), vec![CommandArg::Long(5000000000)])]
+        );
+        assert_eq!(
+            parse("give reason because of reasons", &tree).unwrap().1,
+            vec![CommandArg::Subcommand(
+                "reason".to_string(),
+                vec![CommandArg::String("because of reasons".to_string())]
+            )]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        let tree = tree();
+        assert_eq!(parse("give 1 \"open", &tree).unwrap_err().get_cursor(), 7);
+        assert!(matches!(parse("give 1", &tree).unwrap_err(), ParseError::MissingArguments { cursor: 6, .. }));
+        assert!(matches!(parse("give 1 a b", &tree).unwrap_err(), ParseError::TooManyArguments { cursor: 9 }));
+        assert!(matches!(parse("give all x", &tree).unwrap_err(), ParseError::IncorrectArguments { cursor: 9, .. }));
+        //Bounds are inclusive
+        assert!(parse("give 64 a", &tree).is_ok());
+        assert!(matches!(parse("give 65 a", &tree).unwrap_err(), ParseError::IncorrectArguments { cursor: 5, .. }));
+    }
+
+    #[test]
+    fn parse_extended_arguments() {
+        fn parse_one(input: &str, arg_type: ArgType) -> Result<(CommandArg, &str), ParseError> {
+            let mut reader = StringReader::new(input);
+            let arg = parse_argument(&mut reader, &arg("value", arg_type));
+            arg.map(|arg| (arg, reader.get_remaining()))
+        }
+
+        assert_eq!(parse_one("1.5d", ArgType::Time { min: 0 }).unwrap().0, CommandArg::Time(36000));
+        assert_eq!(parse_one("20", ArgType::Time { min: 0 }).unwrap().0, CommandArg::Time(20));
+        assert!(parse_one("-1s", ArgType::Time { min: 0 }).is_err());
+
+        let uuid = "069a79f4-44e9-4726-a5be-fca90e38aaf5";
+        assert_eq!(parse_one(uuid, ArgType::Uuid).unwrap().0, CommandArg::Uuid(Uuid::parse_str(uuid).unwrap()));
+
+        let (nbt, rest) = parse_one("{a:1b,b:\"}\"} x", ArgType::Nbt).unwrap();
+        assert!(matches!(nbt, CommandArg::Nbt(compound) if compound.len() == 2));
+        assert_eq!(rest, " x");
+
+        let (component, rest) = parse_one("[\"a\", {\"text\": \"b\"}] x", ArgType::Component).unwrap();
+        let CommandArg::Component(component) = component else { panic!() };
+        assert_eq!(component.get_text().map(String::as_str), Some("a"));
+        assert_eq!(component.get_extra().map(Vec::len), Some(1));
+        assert_eq!(rest, " x");
+
+        let dimension = ArgType::Identifier { registry: Some("minecraft:dimension_type".to_string()) };
+        assert!(parse_one("overworld", dimension.clone()).is_ok());
+        assert!(parse_one("nether_roof", dimension).is_err());
+    }
+}
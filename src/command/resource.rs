@@ -0,0 +1,230 @@
+use std::collections::BTreeMap;
+
+use quartz_nbt::{snbt, NbtCompound, NbtTag};
+
+use crate::data_types::Identifier;
+use crate::{BUILTIN_REGISTRIES, SERVER_REGISTRY};
+
+use super::parser::StringReader;
+use super::ParseError;
+
+/// Whether `id` is an entry of `registry`, which is either one of the builtin
+/// registries like `minecraft:block` or a registry synced to clients like
+/// `minecraft:dimension_type`.
+pub fn registry_contains(registry: &Identifier, id: &Identifier) -> bool {
+    if let Some(mapping) = BUILTIN_REGISTRIES.get(&registry.to_string()) {
+        return mapping.contains(&id.to_string());
+    }
+    SERVER_REGISTRY.get(&registry.get_id())
+        .is_some_and(|entries| entries.contains_key(&id.to_string()))
+}
+
+/// Reads an identifier, which must be an entry of `registry` if there is one
+pub fn read_resource(
+    reader: &mut StringReader,
+    registry: Option<&str>,
+) -> Result<Identifier, ParseError> {
+    let start = reader.get_cursor();
+    let incorrect = |message: String| ParseError::IncorrectArguments { message, cursor: start };
+    let id = reader.read_while(|c| StringReader::is_allowed_in_unquoted(c) || matches!(c, ':' | '/'));
+    if id.is_empty() {
+        return Err(incorrect("Expected an identifier".to_string()));
+    }
+    let identifier = Identifier::new(id).map_err(|_| incorrect(format!("Invalid identifier '{id}'")))?;
+    if let Some(registry) = registry {
+        let registry = Identifier::new(registry).map_err(|_| incorrect(format!("Invalid registry '{registry}'")))?;
+        if !registry_contains(&registry, &identifier) {
+            return Err(incorrect(format!("Can't find element '{identifier}' of type '{registry}'")));
+        }
+    }
+    Ok(identifier)
+}
+
+/// A block with its state properties and block entity data, as in `stone_stairs[facing=east]{}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockStateArgument {
+    block: Identifier,
+    properties: BTreeMap<String, String>,
+    nbt: Option<NbtCompound>,
+}
+
+impl BlockStateArgument {
+    pub fn parse(reader: &mut StringReader) -> Result<Self, ParseError> {
+        let block = read_resource(reader, Some("minecraft:block"))?;
+        let mut properties = BTreeMap::new();
+        if reader.peek() == Some('[') {
+            reader.skip();
+            reader.skip_whitespace();
+            while reader.peek() != Some(']') {
+                let cursor = reader.get_cursor();
+                let key = reader.read_unquoted();
+                if key.is_empty() {
+                    return Err(ParseError::IncorrectArguments {
+                        message: format!("Expected a property of block {block}"),
+                        cursor,
+                    });
+                }
+                reader.skip_whitespace();
+                reader.expect('=')?;
+                reader.skip_whitespace();
+                let value = reader.read_unquoted();
+                if properties.insert(key.to_string(), value.to_string()).is_some() {
+                    return Err(ParseError::IncorrectArguments {
+                        message: format!("Property '{key}' can only be set once for block {block}"),
+                        cursor,
+                    });
+                }
+                reader.skip_whitespace();
+                if reader.peek() != Some(',') {
+                    break;
+                }
+                reader.skip();
+                reader.skip_whitespace();
+            }
+            reader.expect(']')?;
+        }
+        let nbt = match reader.peek() {
+            Some('{') => Some(read_compound(reader)?),
+            _ => None,
+        };
+        Ok(Self { block, properties, nbt })
+    }
+
+    pub fn get_block(&self) -> &Identifier {
+        &self.block
+    }
+
+    pub fn get_properties(&self) -> &BTreeMap<String, String> {
+        &self.properties
+    }
+
+    pub fn get_nbt(&self) -> Option<&NbtCompound> {
+        self.nbt.as_ref()
+    }
+}
+
+/// An item with the components which differ from its defaults, as in `diamond_sword[damage=5]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemArgument {
+    item: Identifier,
+    components: BTreeMap<String, NbtTag>,
+}
+
+impl ItemArgument {
+    pub fn parse(reader: &mut StringReader) -> Result<Self, ParseError> {
+        let item = read_resource(reader, Some("minecraft:item"))?;
+        let mut components = BTreeMap::new();
+        if reader.peek() == Some('[') {
+            reader.skip();
+            reader.skip_whitespace();
+            while reader.peek() != Some(']') {
+                let cursor = reader.get_cursor();
+                let component = read_resource(reader, Some("minecraft:data_component_type"))
+                    .map_err(|_| ParseError::IncorrectArguments {
+                        message: "Unknown component".to_string(),
+                        cursor,
+                    })?;
+                reader.skip_whitespace();
+                reader.expect('=')?;
+                reader.skip_whitespace();
+                let value = read_tag(reader)?;
+                if components.insert(component.to_string(), value).is_some() {
+                    return Err(ParseError::IncorrectArguments {
+                        message: format!("Component '{component}' can only be specified once"),
+                        cursor,
+                    });
+                }
+                reader.skip_whitespace();
+                if reader.peek() != Some(',') {
+                    break;
+                }
+                reader.skip();
+                reader.skip_whitespace();
+            }
+            reader.expect(']')?;
+        }
+        Ok(Self { item, components })
+    }
+
+    pub fn get_item(&self) -> &Identifier {
+        &self.item
+    }
+
+    /// The components by their identifier
+    pub fn get_components(&self) -> &BTreeMap<String, NbtTag> {
+        &self.components
+    }
+}
+
+/// Reads an SNBT compound like `{Count:1b}`
+pub fn read_compound(reader: &mut StringReader) -> Result<NbtCompound, ParseError> {
+    let start = reader.get_cursor();
+    match snbt::parse_and_size(reader.get_remaining()) {
+        Ok((compound, size)) => {
+            reader.set_cursor(start + size);
+            Ok(compound)
+        },
+        Err(e) => Err(ParseError::IncorrectArguments {
+            message: format!("Invalid NBT: {e}"),
+            cursor: start,
+        }),
+    }
+}
+
+/// Reads any SNBT value, which ends at the first `,` or `]` outside of brackets and quotes
+pub fn read_tag(reader: &mut StringReader) -> Result<NbtTag, ParseError> {
+    let start = reader.get_cursor();
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut escaped = false;
+    while let Some(c) = reader.peek() {
+        match (quote, c) {
+            (Some(_), _) if escaped => escaped = false,
+            (Some(_), '\\') => escaped = true,
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '{' | '[') => depth += 1,
+            (None, ',' | ']') if depth == 0 => break,
+            (None, '}' | ']') => depth -= 1,
+            (None, _) => (),
+        }
+        reader.skip();
+    }
+    let value = reader.get_input()[start..reader.get_cursor()].trim_end();
+    snbt::parse(&format!("{{value:{value}}}"))
+        .ok()
+        .and_then(|mut compound| compound.inner_mut().remove("value"))
+        .ok_or(ParseError::IncorrectArguments {
+            message: format!("Invalid NBT value '{value}'"),
+            cursor: start,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn block_state() {
+        let mut reader = StringReader::new("oak_stairs[facing=east, half=top]{Lock:\"key\"} rest");
+        let state = BlockStateArgument::parse(&mut reader).unwrap();
+        assert_eq!(state.get_block().to_string(), "minecraft:oak_stairs");
+        assert_eq!(state.get_properties().get("facing").map(String::as_str), Some("east"));
+        assert_eq!(state.get_nbt().unwrap().get::<_, &str>("Lock").unwrap(), "key");
+        assert_eq!(reader.get_remaining(), " rest");
+
+        assert!(BlockStateArgument::parse(&mut StringReader::new("not_a_block")).is_err());
+    }
+
+    #[test]
+    fn item() {
+        let mut reader = StringReader::new("minecraft:diamond_sword[damage=5,custom_name='\"Edge, of ]\"']");
+        let item = ItemArgument::parse(&mut reader).unwrap();
+        assert_eq!(item.get_item().to_string(), "minecraft:diamond_sword");
+        assert_eq!(item.get_components().len(), 2);
+        assert!(!reader.can_read());
+
+        assert!(ItemArgument::parse(&mut StringReader::new("stick[not_a_component=1]")).is_err());
+    }
+}
@@ -0,0 +1,655 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Weak};
+
+use uuid::Uuid;
+
+use crate::data_types::Vec3d;
+use crate::entity::EnumEntityType;
+use crate::player::Player;
+use crate::THE_SERVER;
+
+use super::parser::StringReader;
+use super::{CommandSender, ParseError};
+
+const PLAYER_TYPE: &str = "minecraft:player";
+
+/// The longest name a player can have
+const MAX_NAME_LENGTH: usize = 16;
+
+/// A range like `1..5`, `..5`, `1..` or `3`, where both ends are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberRange<T> {
+    min: Option<T>,
+    max: Option<T>,
+}
+
+impl<T: PartialOrd + Copy + std::str::FromStr> NumberRange<T> {
+    pub fn new(min: Option<T>, max: Option<T>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn get_min(&self) -> Option<T> {
+        self.min
+    }
+
+    pub fn get_max(&self) -> Option<T> {
+        self.max
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.min.map_or(true, |min| min <= value) && self.max.map_or(true, |max| value <= max)
+    }
+
+    pub fn parse(reader: &mut StringReader) -> Result<Self, ParseError> {
+        let start = reader.get_cursor();
+        let text = reader.read_number();
+        let invalid = || ParseError::IncorrectArguments {
+            message: format!("Invalid range '{text}'"),
+            cursor: start,
+        };
+        let parse = |bound: &str| match bound {
+            "" => Ok(None),
+            bound => bound.parse::<T>().map(Some).map_err(|_| invalid()),
+        };
+        let range = match text.split_once("..") {
+            Some((min, max)) => Self::new(parse(min)?, parse(max)?),
+            None => {
+                let exact = parse(text)?;
+                Self::new(exact, exact)
+            },
+        };
+        match (range.min, range.max) {
+            (None, None) => Err(invalid()),
+            (Some(min), Some(max)) if min > max => Err(ParseError::IncorrectArguments {
+                message: "The minimum of the range must not be larger than the maximum".to_string(),
+                cursor: start,
+            }),
+            _ => Ok(range),
+        }
+    }
+}
+
+/// A value of a selector option which can be negated with `!`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Negatable<T> {
+    value: T,
+    negated: bool,
+}
+
+impl<T> Negatable<T> {
+    pub fn new(value: T, negated: bool) -> Self {
+        Self { value, negated }
+    }
+
+    pub fn get_value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn is_negated(&self) -> bool {
+        self.negated
+    }
+
+    fn test(&self, matches: bool) -> bool {
+        matches != self.negated
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorKind {
+    /// `@p`
+    NearestPlayer,
+    /// `@a`
+    AllPlayers,
+    /// `@r`
+    RandomPlayer,
+    /// `@s`
+    Executor,
+    /// `@e`
+    AllEntities,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorSort {
+    Nearest,
+    Furthest,
+    Random,
+    Arbitrary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectorTargets {
+    Selector(SelectorKind),
+    Player(String),
+    Uuid(Uuid),
+}
+
+/// An entity argument: a selector like `@e[type=pig,limit=2]`, a player name or a UUID.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntitySelector {
+    targets: SelectorTargets,
+    names: Vec<Negatable<String>>,
+    types: Vec<Negatable<String>>,
+    distance: Option<NumberRange<f64>>,
+    position: [Option<f64>; 3],
+    volume: [Option<f64>; 3],
+    limit: Option<usize>,
+    sort: Option<SelectorSort>,
+    game_modes: Vec<Negatable<i32>>,
+    tags: Vec<Negatable<String>>,
+    scores: Vec<(String, NumberRange<i32>)>,
+}
+
+/// A candidate for a selector. Players also have a game mode and a handle to the player.
+#[derive(Debug, Clone)]
+pub struct SelectorTarget {
+    entity_id: i32,
+    uuid: Uuid,
+    name: Option<String>,
+    entity_type: String,
+    position: Vec3d,
+    game_mode: Option<i32>,
+    tags: HashSet<String>,
+    scores: HashMap<String, i32>,
+    player: Option<Weak<Player>>,
+}
+
+impl SelectorTarget {
+    pub fn new(entity_id: i32, uuid: Uuid, entity_type: &str, position: Vec3d) -> Self {
+        Self {
+            entity_id,
+            uuid,
+            name: None,
+            entity_type: entity_type.to_string(),
+            position,
+            game_mode: None,
+            tags: HashSet::new(),
+            scores: HashMap::new(),
+            player: None,
+        }
+    }
+
+    pub fn with_player(mut self, player: Weak<Player>, name: &str, game_mode: i32) -> Self {
+        self.player = Some(player);
+        self.name = Some(name.to_string());
+        self.game_mode = Some(game_mode);
+        self
+    }
+
+    pub fn with_tags(mut self, tags: HashSet<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    pub fn with_scores(mut self, scores: HashMap<String, i32>) -> Self {
+        self.scores = scores;
+        self
+    }
+
+    pub fn get_entity_id(&self) -> i32 {
+        self.entity_id
+    }
+
+    pub fn get_uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn get_entity_type(&self) -> &str {
+        &self.entity_type
+    }
+
+    pub fn get_position(&self) -> Vec3d {
+        self.position
+    }
+
+    pub fn is_player(&self) -> bool {
+        self.entity_type == PLAYER_TYPE
+    }
+
+    pub fn get_player(&self) -> Option<Arc<Player>> {
+        self.player.as_ref()?.upgrade()
+    }
+
+    /// Where the entity is now, with its yaw and pitch. Entities which aren't
+    /// tracked anymore stay where they were selected, without rotation.
+    pub async fn get_location(&self) -> (Vec3d, f64, f64) {
+        for world in THE_SERVER.get_worlds() {
+            let world = world.lock().await;
+            let tracker = world.get_entity_tracker().lock().unwrap();
+            if let Some(entity) = tracker.get_entity(self.entity_id) {
+                return (entity.get_position(), entity.get_yaw(), entity.get_pitch());
+            }
+        }
+        (self.position, 0.0, 0.0)
+    }
+}
+
+/// `minecraft:pig` for `EnumEntityType::Pig`
+pub fn get_entity_type_id(entity_type: EnumEntityType) -> String {
+    serde_json::to_value(entity_type).ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn with_namespace(id: &str) -> String {
+    if id.contains(':') { id.to_string() } else { format!("minecraft:{id}") }
+}
+
+fn distance(a: Vec3d, b: Vec3d) -> f64 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
+}
+
+impl EntitySelector {
+    fn new(targets: SelectorTargets) -> Self {
+        Self {
+            targets,
+            names: vec![],
+            types: vec![],
+            distance: None,
+            position: [None; 3],
+            volume: [None; 3],
+            limit: None,
+            sort: None,
+            game_modes: vec![],
+            tags: vec![],
+            scores: vec![],
+        }
+    }
+
+    pub fn get_targets(&self) -> &SelectorTargets {
+        &self.targets
+    }
+
+    /// Whether the selector can select more than one entity
+    pub fn is_single(&self) -> bool {
+        match self.targets {
+            SelectorTargets::Selector(SelectorKind::AllPlayers | SelectorKind::AllEntities) => self.limit == Some(1),
+            _ => true,
+        }
+    }
+
+    /// Whether the selector can only select players
+    pub fn is_players_only(&self) -> bool {
+        match self.targets {
+            SelectorTargets::Selector(SelectorKind::AllEntities | SelectorKind::Executor) => {
+                self.types.iter().any(|t| !t.is_negated() && t.get_value() == PLAYER_TYPE)
+            },
+            SelectorTargets::Uuid(_) => false,
+            _ => true,
+        }
+    }
+
+    /// Parses a selector, player name or UUID at the cursor
+    pub fn parse(reader: &mut StringReader) -> Result<Self, ParseError> {
+        let start = reader.get_cursor();
+        if reader.peek() != Some('@') {
+            let name = reader.read_unquoted();
+            if let Ok(uuid) = Uuid::parse_str(name) {
+                return Ok(Self::new(SelectorTargets::Uuid(uuid)));
+            }
+            if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+                return Err(ParseError::IncorrectArguments {
+                    message: "Invalid name or UUID".to_string(),
+                    cursor: start,
+                });
+            }
+            return Ok(Self::new(SelectorTargets::Player(name.to_string())));
+        }
+        reader.skip();
+        let kind = match reader.peek() {
+            Some('p') => SelectorKind::NearestPlayer,
+            Some('a') => SelectorKind::AllPlayers,
+            Some('r') => SelectorKind::RandomPlayer,
+            Some('s') => SelectorKind::Executor,
+            Some('e') => SelectorKind::AllEntities,
+            _ => return Err(ParseError::IncorrectArguments {
+                message: "Unknown selector type".to_string(),
+                cursor: start,
+            }),
+        };
+        reader.skip();
+        let mut selector = Self::new(SelectorTargets::Selector(kind));
+        if reader.peek() == Some('[') {
+            reader.skip();
+            selector.parse_options(reader)?;
+        }
+        Ok(selector)
+    }
+
+    fn parse_options(&mut self, reader: &mut StringReader) -> Result<(), ParseError> {
+        reader.skip_whitespace();
+        while reader.peek() != Some(']') {
+            let start = reader.get_cursor();
+            let option = reader.read_unquoted();
+            reader.skip_whitespace();
+            reader.expect('=')?;
+            reader.skip_whitespace();
+            let negated = reader.peek() == Some('!');
+            if negated {
+                reader.skip();
+                reader.skip_whitespace();
+            }
+            let value_start = reader.get_cursor();
+            let incorrect = |message: String, cursor: usize| ParseError::IncorrectArguments { message, cursor };
+            if negated && !matches!(option, "name" | "type" | "gamemode" | "tag") {
+                return Err(incorrect(format!("Option '{option}' can't be negated"), start));
+            }
+
+            match option {
+                "name" => {
+                    if !negated && self.names.iter().any(|name| !name.is_negated()) {
+                        return Err(incorrect("Option 'name' isn't applicable here".to_string(), start));
+                    }
+                    self.names.push(Negatable::new(reader.read_string()?, negated));
+                },
+                "type" => {
+                    if !negated && self.types.iter().any(|t| !t.is_negated()) {
+                        return Err(incorrect("Option 'type' isn't applicable here".to_string(), start));
+                    }
+                    let entity_type = reader.read_identifier();
+                    if entity_type.is_empty() || entity_type.starts_with('#') {
+                        return Err(incorrect(format!("Unknown entity type '{entity_type}'"), value_start));
+                    }
+                    self.types.push(Negatable::new(with_namespace(entity_type), negated));
+                },
+                "distance" => {
+                    let range = NumberRange::<f64>::parse(reader)?;
+                    if range.get_min().is_some_and(|min| min < 0.0) {
+                        return Err(incorrect("Distance cannot be negative".to_string(), value_start));
+                    }
+                    self.distance = Some(range);
+                },
+                "x" | "y" | "z" | "dx" | "dy" | "dz" => {
+                    let number = reader.read_number();
+                    let value = number.parse::<f64>()
+                        .map_err(|_| incorrect(format!("Invalid double '{number}'"), value_start))?;
+                    let axis = match option.trim_start_matches('d') {
+                        "x" => 0,
+                        "y" => 1,
+                        _ => 2,
+                    };
+                    match option.starts_with('d') {
+                        true => self.volume[axis] = Some(value),
+                        false => self.position[axis] = Some(value),
+                    }
+                },
+                "limit" => {
+                    let number = reader.read_number();
+                    match number.parse::<usize>() {
+                        Ok(limit) if limit >= 1 => self.limit = Some(limit),
+                        _ => return Err(incorrect("Limit must be at least 1".to_string(), value_start)),
+                    }
+                },
+                "sort" => {
+                    self.sort = Some(match reader.read_unquoted() {
+                        "nearest" => SelectorSort::Nearest,
+                        "furthest" => SelectorSort::Furthest,
+                        "random" => SelectorSort::Random,
+                        "arbitrary" => SelectorSort::Arbitrary,
+                        sort => return Err(incorrect(format!("Invalid or unknown sort type '{sort}'"), value_start)),
+                    });
+                },
+                "gamemode" => {
+                    if !negated && self.game_modes.iter().any(|mode| !mode.is_negated()) {
+                        return Err(incorrect("Option 'gamemode' isn't applicable here".to_string(), start));
+                    }
+                    let game_mode = match reader.read_unquoted() {
+                        "survival" => 0,
+                        "creative" => 1,
+                        "adventure" => 2,
+                        "spectator" => 3,
+                        mode => return Err(incorrect(format!("Invalid or unknown game mode '{mode}'"), value_start)),
+                    };
+                    self.game_modes.push(Negatable::new(game_mode, negated));
+                },
+                "tag" => self.tags.push(Negatable::new(reader.read_unquoted().to_string(), negated)),
+                "scores" => {
+                    reader.expect('{')?;
+                    reader.skip_whitespace();
+                    while reader.peek() != Some('}') {
+                        let objective = reader.read_unquoted().to_string();
+                        reader.skip_whitespace();
+                        reader.expect('=')?;
+                        reader.skip_whitespace();
+                        self.scores.push((objective, NumberRange::<i32>::parse(reader)?));
+                        reader.skip_whitespace();
+                        if reader.peek() == Some(',') {
+                            reader.skip();
+                            reader.skip_whitespace();
+                        } else if reader.peek() != Some('}') {
+                            return Err(incorrect("Expected '}'".to_string(), reader.get_cursor()));
+                        }
+                    }
+                    reader.skip();
+                },
+                _ => return Err(incorrect(format!("Unknown option '{option}'"), start)),
+            }
+
+            reader.skip_whitespace();
+            match reader.peek() {
+                Some(',') => {
+                    reader.skip();
+                    reader.skip_whitespace();
+                },
+                Some(']') => (),
+                _ => return Err(incorrect("Expected end of options".to_string(), reader.get_cursor())),
+            }
+        }
+        reader.skip();
+        Ok(())
+    }
+
+    /// Selects from `candidates`. `origin` is where the command is run from
+    /// and `executor` is the entity running it, if any.
+    pub fn select(
+        &self,
+        origin: Vec3d,
+        executor: Option<Uuid>,
+        candidates: Vec<SelectorTarget>
+    ) -> Vec<SelectorTarget> {
+        let kind = match &self.targets {
+            SelectorTargets::Player(name) => {
+                return candidates.into_iter()
+                    .filter(|target| target.is_player() && target.get_name().is_some_and(|n| n.eq_ignore_ascii_case(name)))
+                    .take(1)
+                    .collect();
+            },
+            SelectorTargets::Uuid(uuid) => {
+                return candidates.into_iter().filter(|target| target.uuid == *uuid).take(1).collect();
+            },
+            SelectorTargets::Selector(kind) => *kind,
+        };
+
+        let origin = Vec3d::new(
+            self.position[0].unwrap_or(origin.x),
+            self.position[1].unwrap_or(origin.y),
+            self.position[2].unwrap_or(origin.z),
+        );
+        let mut selected: Vec<SelectorTarget> = candidates.into_iter()
+            .filter(|target| match kind {
+                SelectorKind::AllEntities => true,
+                SelectorKind::Executor => executor == Some(target.uuid),
+                _ => target.is_player(),
+            })
+            .filter(|target| self.matches(target, origin))
+            .collect();
+
+        let (sort, limit) = match kind {
+            SelectorKind::NearestPlayer => (SelectorSort::Nearest, Some(1)),
+            SelectorKind::RandomPlayer => (SelectorSort::Random, Some(1)),
+            _ => (SelectorSort::Arbitrary, None),
+        };
+        match self.sort.unwrap_or(sort) {
+            SelectorSort::Nearest => selected.sort_by(|a, b| {
+                distance(a.position, origin).total_cmp(&distance(b.position, origin))
+            }),
+            SelectorSort::Furthest => selected.sort_by(|a, b| {
+                distance(b.position, origin).total_cmp(&distance(a.position, origin))
+            }),
+            SelectorSort::Random => selected.sort_by_cached_key(|_| Uuid::new_v4()),
+            SelectorSort::Arbitrary => (),
+        }
+        if let Some(limit) = self.limit.or(limit) {
+            selected.truncate(limit);
+        }
+        selected
+    }
+
+    fn matches(&self, target: &SelectorTarget, origin: Vec3d) -> bool {
+        if let Some(range) = &self.distance {
+            if !range.contains(distance(target.position, origin)) {
+                return false;
+            }
+        }
+        if self.volume.iter().any(Option::is_some) {
+            let position = [target.position.x, target.position.y, target.position.z];
+            let origin = [origin.x, origin.y, origin.z];
+            //The volume covers whole blocks from the origin
+            let inside = (0..3).all(|axis| {
+                let size = self.volume[axis].unwrap_or(0.0);
+                let min = origin[axis] + size.min(0.0);
+                let max = origin[axis] + size.max(0.0) + 1.0;
+                min <= position[axis] && position[axis] < max
+            });
+            if !inside {
+                return false;
+            }
+        }
+        let name = target.get_name().unwrap_or_default();
+        self.names.iter().all(|n| n.test(n.get_value() == name))
+            && self.types.iter().all(|t| t.test(*t.get_value() == target.entity_type))
+            && self.game_modes.iter().all(|mode| {
+                mode.test(target.game_mode == Some(*mode.get_value()))
+            })
+            && self.tags.iter().all(|tag| match tag.get_value().as_str() {
+                //`tag=` selects entities without tags, `tag=!` those with any
+                "" => tag.test(target.tags.is_empty()),
+                value => tag.test(target.tags.contains(value)),
+            })
+            && self.scores.iter().all(|(objective, range)| {
+                target.scores.get(objective).is_some_and(|score| range.contains(*score))
+            })
+    }
+
+    /// Resolves the selector like `resolve`, but finding nothing is an error
+    pub async fn get_entities(&self, sender: &CommandSender) -> Result<Vec<SelectorTarget>, String> {
+        let targets = self.resolve(sender).await;
+        match (targets.is_empty(), self.is_players_only()) {
+            (true, true) => Err("No player was found".to_string()),
+            (true, false) => Err("No entity was found".to_string()),
+            (false, _) => Ok(targets),
+        }
+    }
+
+    /// Selects from the players on the server and the entities in its worlds,
+    /// relative to the position of `sender`.
+    pub async fn resolve(&self, sender: &CommandSender) -> Vec<SelectorTarget> {
+        let mut candidates: Vec<SelectorTarget> = Vec::new();
+        let mut players: HashMap<i32, Arc<Player>> = HashMap::new();
+        for player in THE_SERVER.get_players_async().await.iter().filter_map(Weak::upgrade) {
+            //Players get an entity id once they are in the play state
+            if player.get_entity_id() != -1 {
+                players.insert(player.get_entity_id(), player);
+            }
+        }
+
+        for world in THE_SERVER.get_worlds() {
+            let world = world.lock().await;
+            let tracker = world.get_entity_tracker().lock().unwrap();
+            for entity in tracker.get_entities() {
+                let mut target = SelectorTarget::new(
+                    entity.get_entity_id(),
+                    entity.get_uuid(),
+                    get_entity_type_id(entity.get_entity_type()).as_str(),
+                    entity.get_position()
+                ).with_tags(entity.get_tags().clone());
+                if let Some(player) = players.remove(&entity.get_entity_id()) {
+                    target = player_target(&player, target);
+                }
+                candidates.push(target);
+            }
+        }
+        //Players who aren't in a world yet
+        for player in players.into_values() {
+            let target = SelectorTarget::new(
+                player.get_entity_id(),
+                player.get_uuid(),
+                PLAYER_TYPE,
+                Vec3d::new(0.0, 0.0, 0.0)
+            );
+            candidates.push(player_target(&player, target));
+        }
+
+        let (origin, _, _) = sender.get_location().await;
+        self.select(origin, sender.get_uuid(), candidates)
+    }
+}
+
+fn player_target(player: &Arc<Player>, target: SelectorTarget) -> SelectorTarget {
+    let game_mode = player.get_game_mode().get_id() as i32;
+    //TODO: fill in scores once there is a scoreboard
+    target.with_player(Arc::downgrade(player), player.get_name(), game_mode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<EntitySelector, ParseError> {
+        EntitySelector::parse(&mut StringReader::new(input))
+    }
+
+    fn candidates() -> Vec<SelectorTarget> {
+        let steve = SelectorTarget::new(1, Uuid::new_v4(), PLAYER_TYPE, Vec3d::new(10.0, 0.0, 0.0))
+            .with_player(Weak::new(), "Steve", 0)
+            .with_tags(HashSet::from(["builder".to_string()]));
+        let alex = SelectorTarget::new(2, Uuid::new_v4(), PLAYER_TYPE, Vec3d::new(3.0, 0.0, 0.0))
+            .with_player(Weak::new(), "Alex", 1)
+            .with_scores(HashMap::from([("kills".to_string(), 5)]));
+        let pig = SelectorTarget::new(3, Uuid::new_v4(), "minecraft:pig", Vec3d::new(1.0, 0.0, 0.0));
+        vec![steve, alex, pig]
+    }
+
+    fn select(input: &str) -> Vec<i32> {
+        parse(input).unwrap()
+            .select(Vec3d::new(0.0, 0.0, 0.0), None, candidates())
+            .iter()
+            .map(SelectorTarget::get_entity_id)
+            .collect()
+    }
+
+    #[test]
+    fn selector_kinds() {
+        assert_eq!(select("@p"), vec![2]);
+        assert_eq!(select("@a[sort=furthest]"), vec![1, 2]);
+        assert_eq!(select("@e[sort=nearest]"), vec![3, 2, 1]);
+        assert_eq!(select("@e[type=!player]"), vec![3]);
+        assert_eq!(select("@s"), Vec::<i32>::new());
+        assert_eq!(select("steve"), vec![1]);
+    }
+
+    #[test]
+    fn selector_filters() {
+        assert_eq!(select("@a[distance=..5]"), vec![2]);
+        assert_eq!(select("@a[gamemode=!survival]"), vec![2]);
+        assert_eq!(select("@e[tag=builder]"), vec![1]);
+        assert_eq!(select("@e[tag=,sort=nearest]"), vec![3, 2]);
+        assert_eq!(select("@a[scores={kills=1..}]"), vec![2]);
+        assert_eq!(select("@e[x=9,dx=2,sort=nearest]"), vec![1]);
+        assert_eq!(select("@e[name=!Alex, limit=1, sort=nearest]"), vec![3]);
+    }
+
+    #[test]
+    fn selector_errors() {
+        assert!(parse("@q").is_err());
+        assert!(parse("@e[limit=0]").is_err());
+        assert!(parse("@e[distance=!1]").is_err());
+        assert!(parse("@e[name=a,name=b]").is_err());
+        assert!(parse("@e[unknown=1]").is_err());
+        assert!(!parse("@a").unwrap().is_single());
+        assert!(parse("@e[type=player,limit=1]").unwrap().is_single());
+    }
+}
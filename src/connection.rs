@@ -74,10 +74,47 @@ impl From<tokio::io::Error> for ConnectionError {
     }
 }
 
+/// The write half and state of a connection. Players keep a clone so they can send packets
+/// while the connection is locked waiting for the next packet to arrive.
+#[derive(Clone)]
+pub struct ConnectionWriter {
+    write: Arc<tokio::sync::Mutex<OwnedWriteHalf>>,
+    state: Arc<std::sync::Mutex<ConnectionState>>,
+}
+
+impl ConnectionWriter {
+    pub fn get_connection_state(&self) -> ConnectionState {
+        *self.state.lock().unwrap()
+    }
+
+    pub async fn send_packet(&self, packet: impl Clientbound) -> Result<(), ConnectionError> {
+        self.send_raw(packet.to_be_bytes().as_slice()).await
+    }
+
+    /// Writes already serialized packets, shutting the connection down if it fails
+    pub async fn send_raw(&self, bytes: &[u8]) -> Result<(), ConnectionError> {
+        let mut write = self.write.lock().await;
+        match timeout(TIMEOUT, write.write_all(bytes)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => {
+                let _ = timeout(TIMEOUT * 3, write.shutdown()).await;
+                Err(e)?
+            },
+            Err(e) => {
+                let _ = timeout(TIMEOUT * 3, write.shutdown()).await;
+                Err(e)?
+            },
+        }
+    }
+
+    pub async fn shutdown(&self) {
+        let _ = timeout(TIMEOUT * 3, self.write.lock().await.shutdown()).await;
+    }
+}
+
 pub struct Connection {
     read: OwnedReadHalf,
-    write: OwnedWriteHalf,
-    state: ConnectionState,
+    writer: ConnectionWriter,
     compressed: bool,
     addr: SocketAddr,
     owner: Option<Weak<Player>>, 
@@ -88,7 +125,7 @@ pub struct Connection {
 impl Debug for Connection {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Connection")
-        .field("state", &self.state)
+        .field("state", &self.get_connection_state())
         .field("compressed", &self.compressed)
         .field("addr", &self.addr)
         .field_with("owner", |f| match &self.owner {
@@ -101,7 +138,7 @@ impl Debug for Connection {
                         .field("name", &player.get_name())
                         .field("uuid", &player.get_uuid())
                         .field("data", player.get_data())
-                        .field("permissions", player.get_permissions())
+                        .field("world", &player.get_world_name())
                         .finish()
                 },
                 None => Ok(()),
@@ -117,8 +154,10 @@ impl Connection {
         let (read, write) = stream.into_split();
         Self {
             read: read, 
-            write: write, 
-            state: ConnectionState::Handshake, 
+            writer: ConnectionWriter {
+                write: Arc::new(tokio::sync::Mutex::new(write)),
+                state: Arc::new(std::sync::Mutex::new(ConnectionState::Handshake)),
+            },
             compressed: false, 
             addr: addr, 
             owner: None,
@@ -152,34 +191,34 @@ impl Connection {
     }
 
     pub async fn set_connection_state(&mut self, state: ConnectionState) {
-        self.state = state;
+        *self.writer.state.lock().unwrap() = state;
     }
 
     pub fn get_connection_state(&self) -> ConnectionState {
-        self.state
+        self.writer.get_connection_state()
+    }
+
+    /// The writer sharing this connection's write half, which doesn't wait for reads
+    pub fn get_writer(&self) -> ConnectionWriter {
+        self.writer.clone()
     }
 
     pub async fn drop(&mut self) {
-        let _ = tokio::time::timeout(crate::TIMEOUT * 3, self.write.shutdown());
+        self.writer.shutdown().await;
     }
 
     pub async fn send_packet(&mut self, packet: impl Clientbound) -> Result<(), ConnectionError> {
-
-        
-        match timeout(TIMEOUT, self.write.write_all(packet.to_be_bytes().as_slice())).await {
-            Ok(result) => match result {
-                Ok(_) => Ok(()),
-                Err(e) => {
-                    
-                    self.err_sending_packet_drop(&packet).await;
-                    Err(e)?
-                }
-            },
-            Err(e) => {
-                self.err_sending_packet_drop(&packet).await;
-                Err(e)?
-            }
+        let result = self.writer.send_raw(packet.to_be_bytes().as_slice()).await;
+        if result.is_err() {
+            self.err_sending_packet_drop(&packet).await;
         }
+        result
+    }
+
+    /// Writes already serialized packets, such as those built up in a
+    /// player's send queue.
+    pub async fn send_raw(&mut self, bytes: &[u8]) -> Result<(), ConnectionError> {
+        self.writer.send_raw(bytes).await
     }
 
     async fn err_sending_packet_drop(&mut self, packet: &(impl Clientbound + std::fmt::Debug)) {
@@ -239,7 +278,7 @@ impl Connection {
         trace!("Packet id: {packet_id}");
 
         trace!("Creating packet...");
-        Ok(packet::create_packet(packet_id, self.state, &mut iter)?)
+        Ok(packet::create_packet(packet_id, self.get_connection_state(), &mut iter)?)
         //drop(lock)
     }
     
@@ -1,19 +1,27 @@
 use std::{io::Write};
+use std::time::Duration;
 
 
 use chrono::Local;
 use log::{info, Level, SetLoggerError};
 
 
-use rustyline::{history::FileHistory, DefaultEditor, Editor, ExternalPrinter};
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{history::FileHistory, CompletionType, Config, Context, Editor, ExternalPrinter, Helper};
 use tokio::sync::{broadcast, mpsc, Mutex};
 
+use crate::command::{self, brigadier, CommandSender};
 use crate::COMMAND_MAP;
 
-
+/// Where console input is remembered between runs
+const HISTORY_FILE: &str = "console_history.txt";
 
 pub struct Console {
-    editor: Mutex<Editor<(), FileHistory>>,
+    editor: Mutex<Editor<CommandHelper, FileHistory>>,
     printer: Mutex<Box<dyn ExternalPrinter + Send + Sync>>,
     logger: ConsoleLogger,
     stop_signal_rx: Mutex<broadcast::Receiver<bool>>,
@@ -24,7 +32,14 @@ impl Console {
     pub fn new(sender: mpsc::Sender<String>, stop: broadcast::Receiver<bool>) -> 
         rustyline::Result<Self> 
     {
-        let mut editor = DefaultEditor::new()?;
+        let config = Config::builder()
+            .completion_type(CompletionType::List)
+            .auto_add_history(false)
+            .build();
+        let mut editor = Editor::with_config(config)?;
+        editor.set_helper(Some(CommandHelper { online: vec![] }));
+        //There is no history on the first run
+        let _ = editor.load_history(HISTORY_FILE);
         let printer = editor.create_external_printer()?;  
          
         Ok(Console{
@@ -56,7 +71,7 @@ impl Console {
             .map(|()| log::set_max_level(log::LevelFilter::Debug))
     }
 
-    pub async fn start(&self) -> rustyline::Result<()> {
+    pub async fn start(&'static self) -> rustyline::Result<()> {
         loop {
             match self.stop_signal_rx.lock().await.try_recv() {
                 Ok(true) => break,
@@ -65,25 +80,44 @@ impl Console {
                 _ => break,
             }
 
+            //Commands are registered once the server is enabled
             if !crate::THE_SERVER.is_running() {
+                tokio::time::sleep(Duration::from_millis(50)).await;
                 continue;
             }
 
-            let mut editor_lock = 
-                self.editor.lock().await;
-            match editor_lock.readline("# ") {
-                Ok(line) => {
+            //Completion can't wait for the player list, so it gets who was online when the line started
+            let online = brigadier::get_online_names().await;
+            //Reading blocks, so it mustn't hold up the runtime's workers
+            let line = tokio::task::spawn_blocking(move || {
+                let mut editor_lock = self.editor.blocking_lock();
+                if let Some(helper) = editor_lock.helper_mut() {
+                    helper.online = online;
+                }
+                let line = editor_lock.readline("# ")?;
+                if !line.trim().is_empty() {
                     editor_lock.add_history_entry(line.as_str())?;
-                    let command_map_lock = 
-                        COMMAND_MAP.lock().await;
-                    
-                    match line.to_lowercase().as_str() {
-                        "stop" => {
-                            
-                            
-                        },
-                        _ => println!("Unknown command")
+                    if let Err(e) = editor_lock.save_history(HISTORY_FILE) {
+                        log::warn!("Failed to save console history: {e}");
+                    }
+                }
+                Ok::<String, ReadlineError>(line)
+            }).await.expect("Console input panicked");
+
+            match line {
+                Ok(line) => {
+                    let line = line.trim();
+                    if line.is_empty() {
+                        continue;
                     }
+                    let input = line.strip_prefix('/').unwrap_or(line);
+                    //Errors are sent to the sender by the dispatcher
+                    let _ = command::dispatch(CommandSender::Console, input).await;
+                },
+                //Ctrl-C or Ctrl-D
+                Err(ReadlineError::Interrupted | ReadlineError::Eof) => {
+                    stop_server().await;
+                    break;
                 },
                 Err(e)=> Err(e)?,
             }
@@ -92,6 +126,46 @@ impl Console {
     }
 }
 
+/// Completes console input from the tree of each command
+pub struct CommandHelper {
+    online: Vec<String>,
+}
+
+impl Completer for CommandHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        //Completion runs on the blocking thread reading the line
+        let command_map_lock = COMMAND_MAP.blocking_lock();
+        let Some(command_map) = command_map_lock.as_ref() else {
+            return Ok((pos, vec![]));
+        };
+        let (start, suggestions) = brigadier::get_suggestions(
+            command_map,
+            &CommandSender::Console,
+            &line[..pos],
+            &self.online
+        );
+        let candidates = suggestions.iter()
+            .map(|suggestion| Pair {
+                display: suggestion.get_text().to_string(),
+                replacement: suggestion.get_text().to_string(),
+            })
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for CommandHelper {
+    type Hint = String;
+}
+
+impl Highlighter for CommandHelper {}
+
+impl Validator for CommandHelper {}
+
+impl Helper for CommandHelper {}
+
 async fn stop_server() {
     info!("Stopping server...");
     crate::THE_SERVER.get_players_async().await.into_iter().for_each(|weak| {
@@ -33,11 +33,11 @@ pub struct BlockPos {
 
 
 
-const MIN_26BIT: i32 = -2^25 - 1; //One less because exclusive ranges are experimental in match statements
-const MAX_26BIT: i32 = 2^25; //One more because exclusive ranges are experimental in match statements
+const MIN_26BIT: i32 = -(1 << 25) - 1; //One less because exclusive ranges are experimental in match statements
+const MAX_26BIT: i32 = 1 << 25; //One more because exclusive ranges are experimental in match statements
 
-const MIN_12BIT: i32 = -2^11 - 1; //One less because...
-const MAX_12BIT: i32 = 2^11; //One more...
+const MIN_12BIT: i32 = -(1 << 11) - 1; //One less because...
+const MAX_12BIT: i32 = 1 << 11; //One more...
 
 impl BlockPos {
     pub fn new(x: i32, y: i32, z: i32) -> Result<Self, InvalidPositionError> {
@@ -64,6 +64,13 @@ impl BlockPos {
     }
 }
 
+impl BlockPos {
+    /// The position moved by the offset, which isn't checked
+    pub fn offset(&self, x: i32, y: i32, z: i32) -> Self {
+        Self { x: self.x + x, y: self.y + y, z: self.z + z }
+    }
+}
+
 impl FromProtocol for BlockPos {
     fn from_protocol_iter(iter: &mut impl Iterator<Item = u8>) -> Result<Self, ProtocolError> 
         where Self: Sized {
@@ -71,12 +78,12 @@ impl FromProtocol for BlockPos {
             if bytes.len() < 8 {
                 return Err(ProtocolError::IterEndError);
             }
-            let val = u64::from_be_bytes(bytes.try_into().unwrap());
-            let fff: u64 = 0xfff;
+            //Shifting the signed value back sign extends each field
+            let val = i64::from_be_bytes(bytes.try_into().unwrap());
             let x = val >> 38;
-            let y = val & fff;
-            let z = (val & 0x3FFFFFF) << 12;
-            Ok(BlockPos::new(x as i32, y as i32, z as i32).unwrap())
+            let y = val << 52 >> 52;
+            let z = val << 26 >> 38;
+            Ok(BlockPos { x: x as i32, y: y as i32, z: z as i32 })
     }
 }
 
@@ -85,4 +92,20 @@ impl ToProtocol for BlockPos {
     fn to_protocol_bytes(&self) -> Vec<u8> {
         (((self.x as i64 & 0x3FFFFFF) << 38) | ((self.z as i64 & 0x3FFFFFF) << 12) | (self.y as i64 & 0xFFF)).to_be_bytes().to_vec()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn protocol() {
+        for (x, y, z) in [(0, 0, 0), (18357644, 831, -20882616), (-1, -64, 33554431)] {
+            let position = BlockPos::new(x, y, z).unwrap();
+            let bytes = position.to_protocol_bytes();
+            let decoded = BlockPos::from_protocol_iter(&mut bytes.into_iter()).unwrap();
+            assert_eq!((decoded.x, decoded.y, decoded.z), (x, y, z));
+        }
+        assert!(BlockPos::new(1 << 25, 0, 0).is_err());
+    }
+}
@@ -0,0 +1,50 @@
+use uuid::Uuid;
+
+use super::{ToProtocol, VarInt};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+///A player's chat session as sent in `SPlayerSession` and the tab list.
+///
+///`public_key` is the DER encoded X.509 public key the player signs messages with,
+///`key_signature` is Mojang's signature of the key.
+pub struct ChatSessionData {
+    session_id: Uuid,
+    expires_at: i64,
+    public_key: Vec<u8>,
+    key_signature: Vec<u8>,
+}
+
+impl ChatSessionData {
+    pub fn new(session_id: Uuid, expires_at: i64, public_key: Vec<u8>, key_signature: Vec<u8>) -> Self {
+        Self { session_id, expires_at, public_key, key_signature }
+    }
+
+    pub fn get_session_id(&self) -> Uuid {
+        self.session_id
+    }
+
+    /// Milliseconds since the unix epoch
+    pub fn get_expires_at(&self) -> i64 {
+        self.expires_at
+    }
+
+    pub fn get_public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    pub fn get_key_signature(&self) -> &[u8] {
+        &self.key_signature
+    }
+}
+
+impl ToProtocol for ChatSessionData {
+    fn to_protocol_bytes(&self) -> Vec<u8> {
+        let mut out = self.session_id.to_protocol_bytes();
+        out.append(&mut self.expires_at.to_protocol_bytes());
+        out.append(&mut VarInt::new(self.public_key.len() as i32).to_protocol_bytes());
+        out.extend(self.public_key.iter());
+        out.append(&mut VarInt::new(self.key_signature.len() as i32).to_protocol_bytes());
+        out.extend(self.key_signature.iter());
+        out
+    }
+}
@@ -0,0 +1,190 @@
+use super::text_component::Nbt;
+use super::{Identifier, TextComponent, ToProtocol, VarInt};
+
+const NODE_ROOT: u8 = 0x00;
+const NODE_LITERAL: u8 = 0x01;
+const NODE_ARGUMENT: u8 = 0x02;
+const FLAG_EXECUTABLE: u8 = 0x04;
+const FLAG_REDIRECT: u8 = 0x08;
+const FLAG_SUGGESTIONS: u8 = 0x10;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+///The parser of an argument node, as an id in the `minecraft:command_argument_type` registry
+///followed by its properties
+pub struct ArgumentParser {
+    id: i32,
+    properties: Vec<u8>,
+}
+
+impl ArgumentParser {
+    pub fn new(id: i32, properties: Vec<u8>) -> Self {
+        Self { id, properties }
+    }
+
+    pub fn get_id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl ToProtocol for ArgumentParser {
+    fn to_protocol_bytes(&self) -> Vec<u8> {
+        let mut out = VarInt::new(self.id).to_protocol_bytes();
+        out.extend(self.properties.iter());
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandGraphNodeType {
+    Root,
+    Literal { name: String },
+    Argument {
+        name: String,
+        parser: ArgumentParser,
+        suggestions: Option<Identifier>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+///A node of the Brigadier command graph sent in `CCommands`.
+///
+///Children and redirects are indices into the list of nodes sent with the packet.
+pub struct CommandGraphNode {
+    node_type: CommandGraphNodeType,
+    executable: bool,
+    children: Vec<i32>,
+    redirect: Option<i32>,
+}
+
+impl CommandGraphNode {
+    pub fn new(node_type: CommandGraphNodeType, executable: bool) -> Self {
+        Self { node_type, executable, children: vec![], redirect: None }
+    }
+
+    pub fn root() -> Self {
+        Self::new(CommandGraphNodeType::Root, false)
+    }
+
+    pub fn get_type(&self) -> &CommandGraphNodeType {
+        &self.node_type
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.executable
+    }
+
+    pub fn get_children(&self) -> &[i32] {
+        &self.children
+    }
+
+    pub fn add_child(&mut self, child: i32) {
+        self.children.push(child);
+    }
+
+    pub fn get_redirect(&self) -> Option<i32> {
+        self.redirect
+    }
+
+    pub fn set_redirect(&mut self, redirect: Option<i32>) {
+        self.redirect = redirect;
+    }
+}
+
+impl ToProtocol for CommandGraphNode {
+    fn to_protocol_bytes(&self) -> Vec<u8> {
+        let mut flags = match self.node_type {
+            CommandGraphNodeType::Root => NODE_ROOT,
+            CommandGraphNodeType::Literal { .. } => NODE_LITERAL,
+            CommandGraphNodeType::Argument { suggestions: Some(_), .. } => NODE_ARGUMENT | FLAG_SUGGESTIONS,
+            CommandGraphNodeType::Argument { .. } => NODE_ARGUMENT,
+        };
+        if self.executable {
+            flags |= FLAG_EXECUTABLE;
+        }
+        if self.redirect.is_some() {
+            flags |= FLAG_REDIRECT;
+        }
+
+        let mut out = vec![flags];
+        out.append(&mut VarInt::new(self.children.len() as i32).to_protocol_bytes());
+        self.children.iter().for_each(|child| {
+            out.append(&mut VarInt::new(*child).to_protocol_bytes())
+        });
+        if let Some(redirect) = self.redirect {
+            out.append(&mut VarInt::new(redirect).to_protocol_bytes());
+        }
+        match &self.node_type {
+            CommandGraphNodeType::Root => (),
+            CommandGraphNodeType::Literal { name } => out.append(&mut name.to_protocol_bytes()),
+            CommandGraphNodeType::Argument { name, parser, suggestions } => {
+                out.append(&mut name.to_protocol_bytes());
+                out.append(&mut parser.to_protocol_bytes());
+                if let Some(suggestions) = suggestions {
+                    out.append(&mut suggestions.to_protocol_bytes());
+                }
+            },
+        }
+        out
+    }
+}
+
+impl ToProtocol for Vec<CommandGraphNode> {
+    fn to_protocol_bytes(&self) -> Vec<u8> {
+        let mut out = VarInt::new(self.len() as i32).to_protocol_bytes();
+        self.iter().for_each(|node| {
+            out.append(&mut node.to_protocol_bytes())
+        });
+        out
+    }
+}
+
+#[derive(Debug, Clone)]
+///A match sent in `CCommandSuggestionsResponse`
+pub struct Suggestion {
+    text: String,
+    tooltip: Option<TextComponent<Nbt>>,
+}
+
+impl Suggestion {
+    pub fn new(text: &str, tooltip: Option<TextComponent<Nbt>>) -> Self {
+        Self { text: text.to_string(), tooltip }
+    }
+
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn get_tooltip(&self) -> Option<&TextComponent<Nbt>> {
+        self.tooltip.as_ref()
+    }
+}
+
+impl From<&str> for Suggestion {
+    fn from(value: &str) -> Self {
+        Self::new(value, None)
+    }
+}
+
+impl ToProtocol for Suggestion {
+    fn to_protocol_bytes(&self) -> Vec<u8> {
+        let mut out = self.text.to_protocol_bytes();
+        match &self.tooltip {
+            Some(tooltip) => {
+                out.append(&mut true.to_protocol_bytes());
+                out.append(&mut tooltip.to_protocol_bytes());
+            },
+            None => out.append(&mut false.to_protocol_bytes()),
+        }
+        out
+    }
+}
+
+impl ToProtocol for Vec<Suggestion> {
+    fn to_protocol_bytes(&self) -> Vec<u8> {
+        let mut out = VarInt::new(self.len() as i32).to_protocol_bytes();
+        self.iter().for_each(|suggestion| {
+            out.append(&mut suggestion.to_protocol_bytes())
+        });
+        out
+    }
+}
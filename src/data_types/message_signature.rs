@@ -0,0 +1,124 @@
+use server_util::error::ProtocolError;
+
+use super::{FromProtocol, Optional, ToProtocol, VarInt};
+
+pub const MESSAGE_SIGNATURE_LENGTH: usize = 256;
+
+/// The number of messages a client acknowledges in chat packets
+pub const LAST_SEEN_LENGTH: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+///A 256 byte RSA signature of a chat message
+pub struct MessageSignature {
+    bytes: [u8; MESSAGE_SIGNATURE_LENGTH],
+}
+
+impl MessageSignature {
+    pub fn new(bytes: [u8; MESSAGE_SIGNATURE_LENGTH]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn get_bytes(&self) -> &[u8; MESSAGE_SIGNATURE_LENGTH] {
+        &self.bytes
+    }
+}
+
+impl FromProtocol for MessageSignature {
+    fn from_protocol_iter(iter: &mut impl Iterator<Item = u8>) -> Result<Self, ProtocolError>
+        where Self: Sized {
+            let raw = iter.take(MESSAGE_SIGNATURE_LENGTH).collect::<Vec<u8>>();
+            match raw.try_into() {
+                Ok(bytes) => Ok(MessageSignature { bytes }),
+                Err(_) => Err(ProtocolError::IterEndError),
+            }
+    }
+}
+
+impl ToProtocol for MessageSignature {
+    #[inline]
+    fn to_protocol_bytes(&self) -> Vec<u8> {
+        self.bytes.to_vec()
+    }
+}
+
+impl Optional for MessageSignature {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+///The fixed 20 bit set of acknowledged messages sent with chat messages and commands
+pub struct AcknowledgedMessages {
+    bits: [u8; 3],
+}
+
+impl AcknowledgedMessages {
+    pub fn is_acknowledged(&self, index: usize) -> bool {
+        index < LAST_SEEN_LENGTH && self.bits[index / 8] & (1 << (index % 8)) != 0
+    }
+
+    pub fn count(&self) -> u32 {
+        self.bits.iter().map(|byte| byte.count_ones()).sum()
+    }
+}
+
+impl FromProtocol for AcknowledgedMessages {
+    fn from_protocol_iter(iter: &mut impl Iterator<Item = u8>) -> Result<Self, ProtocolError>
+        where Self: Sized {
+            let raw = iter.take(3).collect::<Vec<u8>>();
+            match raw.try_into() {
+                Ok(bits) => Ok(AcknowledgedMessages { bits }),
+                Err(_) => Err(ProtocolError::IterEndError),
+            }
+    }
+}
+
+impl ToProtocol for AcknowledgedMessages {
+    #[inline]
+    fn to_protocol_bytes(&self) -> Vec<u8> {
+        self.bits.to_vec()
+    }
+}
+
+#[derive(Debug, Clone)]
+///An entry in the previous messages of `CPlayerChatMessage`
+pub enum PreviousMessage {
+    ///The id of a signature the client has already been sent
+    Id(i32),
+    Signature(MessageSignature),
+}
+
+impl ToProtocol for PreviousMessage {
+    fn to_protocol_bytes(&self) -> Vec<u8> {
+        match self {
+            PreviousMessage::Id(id) => VarInt::new(id + 1).to_protocol_bytes(),
+            PreviousMessage::Signature(signature) => {
+                let mut out = vec![0u8];
+                out.extend(signature.get_bytes());
+                out
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+///The signed arguments of `SSignedChatCommand`, by argument name
+pub struct ArgumentSignatures {
+    signatures: Vec<(String, MessageSignature)>,
+}
+
+impl ArgumentSignatures {
+    pub fn get_signatures(&self) -> &[(String, MessageSignature)] {
+        &self.signatures
+    }
+}
+
+impl FromProtocol for ArgumentSignatures {
+    fn from_protocol_iter(iter: &mut impl Iterator<Item = u8>) -> Result<Self, ProtocolError>
+        where Self: Sized {
+            let len = VarInt::from_protocol_iter(iter)?.get();
+            let mut signatures = Vec::new();
+            for _ in 0..len {
+                let name = String::from_protocol_iter(iter)?;
+                signatures.push((name, MessageSignature::from_protocol_iter(iter)?));
+            }
+            Ok(ArgumentSignatures { signatures })
+    }
+}
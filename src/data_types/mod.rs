@@ -7,17 +7,22 @@ pub mod tags;
 
 pub mod angle;
 pub mod block_position;
+pub mod chat_session;
 pub mod chunk;
+pub mod command_graph;
 pub mod datapack;
 pub mod death_location;
 pub mod identifier;
 pub mod identifier_array;
 pub mod prefixed_byte_array;
 pub mod inferred_byte_array;
+pub mod message_signature;
 pub mod nbt;
+pub mod player_info;
 pub mod position;
 pub mod property_array;
 pub mod rotation;
+pub mod slot;
 pub mod statistic_array;
 pub mod tag;
 pub mod text_component;
@@ -34,17 +39,29 @@ pub mod data_types;
 pub use {
     angle::Angle,
     block_position::BlockPos,
+    chat_session::ChatSessionData,
     chunk::ProtocolChunk,
+    command_graph::ArgumentParser,
+    command_graph::CommandGraphNode,
+    command_graph::CommandGraphNodeType,
+    command_graph::Suggestion,
     death_location::DeathLocation,
     identifier::Identifier, 
     identifier_array::IdentifierArray,
     prefixed_byte_array::PrefixedByteArray,
     inferred_byte_array::InferredByteArray,
+    message_signature::AcknowledgedMessages,
+    message_signature::ArgumentSignatures,
+    message_signature::MessageSignature,
+    message_signature::PreviousMessage,
 
     nbt::NBT,
+    player_info::PlayerInfoActions,
+    player_info::PlayerInfoEntry,
     position::Pos,
     property_array::Property,
     property_array::PropertyArray,
+    slot::Slot,
     statistic_array::Statistic,
     statistic_array::StatisticArray,
     text_component::TextComponent,
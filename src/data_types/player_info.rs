@@ -0,0 +1,119 @@
+use std::ops::BitOr;
+
+use uuid::Uuid;
+
+use super::text_component::Nbt;
+use super::{ChatSessionData, PropertyArray, TextComponent, ToProtocol, VarInt};
+
+/// The set of actions carried by a `CPlayerInfoUpdate` packet.
+/// 
+/// Actions are written for each entry in the order of their bits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PlayerInfoActions(u8);
+
+impl PlayerInfoActions {
+    pub const ADD_PLAYER: Self = Self(0x01);
+    pub const INITIALIZE_CHAT: Self = Self(0x02);
+    pub const UPDATE_GAME_MODE: Self = Self(0x04);
+    pub const UPDATE_LISTED: Self = Self(0x08);
+    pub const UPDATE_LATENCY: Self = Self(0x10);
+    pub const UPDATE_DISPLAY_NAME: Self = Self(0x20);
+
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for PlayerInfoActions {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayerInfoEntry {
+    uuid: Uuid,
+    name: String,
+    properties: PropertyArray,
+    game_mode: i32,
+    listed: bool,
+    latency: i32,
+    display_name: Option<TextComponent<Nbt>>,
+    chat_session: Option<ChatSessionData>,
+}
+
+impl PlayerInfoEntry {
+    pub fn new(
+        uuid: Uuid, 
+        name: &str, 
+        properties: PropertyArray, 
+        game_mode: i32, 
+        listed: bool, 
+        latency: i32, 
+        display_name: Option<TextComponent<Nbt>>
+    ) -> Self {
+        Self {
+            uuid,
+            name: name.to_string(),
+            properties,
+            game_mode,
+            listed,
+            latency,
+            display_name,
+            chat_session: None,
+        }
+    }
+
+    /// Sets the chat session sent with `PlayerInfoActions::INITIALIZE_CHAT`
+    pub fn with_chat_session(mut self, chat_session: Option<ChatSessionData>) -> Self {
+        self.chat_session = chat_session;
+        self
+    }
+
+    pub fn get_uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    /// Writes the UUID followed by the data for each action in `actions`.
+    pub fn to_protocol_bytes_for(&self, actions: PlayerInfoActions) -> Vec<u8> {
+        let mut out = self.uuid.to_protocol_bytes();
+        if actions.contains(PlayerInfoActions::ADD_PLAYER) {
+            out.append(&mut self.name.to_protocol_bytes());
+            out.append(&mut self.properties.to_protocol_bytes());
+        }
+        if actions.contains(PlayerInfoActions::INITIALIZE_CHAT) {
+            match &self.chat_session {
+                Some(chat_session) => {
+                    out.append(&mut true.to_protocol_bytes());
+                    out.append(&mut chat_session.to_protocol_bytes());
+                },
+                None => out.append(&mut false.to_protocol_bytes()),
+            }
+        }
+        if actions.contains(PlayerInfoActions::UPDATE_GAME_MODE) {
+            out.append(&mut VarInt::new(self.game_mode).to_protocol_bytes());
+        }
+        if actions.contains(PlayerInfoActions::UPDATE_LISTED) {
+            out.append(&mut self.listed.to_protocol_bytes());
+        }
+        if actions.contains(PlayerInfoActions::UPDATE_LATENCY) {
+            out.append(&mut VarInt::new(self.latency).to_protocol_bytes());
+        }
+        if actions.contains(PlayerInfoActions::UPDATE_DISPLAY_NAME) {
+            match &self.display_name {
+                Some(display_name) => {
+                    out.append(&mut true.to_protocol_bytes());
+                    out.append(&mut display_name.to_protocol_bytes());
+                },
+                None => out.append(&mut false.to_protocol_bytes()),
+            }
+        }
+        out
+    }
+}
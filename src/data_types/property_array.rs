@@ -3,7 +3,7 @@ use server_util::error::ProtocolError;
 
 use super::{FromProtocol, ToProtocol, VarInt};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Property {
     name: String,
     value: String,
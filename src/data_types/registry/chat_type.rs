@@ -11,6 +11,53 @@ pub struct Decoration {
     parameters: Vec<String>,
 }
 
+impl Decoration {
+    pub fn get_translation_key(&self) -> &str {
+        &self.translation_key
+    }
+
+    pub fn get_style(&self) -> Option<&TextComponent<Json>> {
+        self.style.as_ref()
+    }
+
+    pub fn get_parameters(&self) -> &[String] {
+        &self.parameters
+    }
+
+    /// Applies the decoration without any styling, for the console and logs.
+    /// 
+    /// Only the vanilla translation keys are known, anything else
+    /// falls back to the parameters separated by spaces.
+    pub fn format_plain(&self, sender: &str, target: Option<&str>, content: &str) -> String {
+        let args: Vec<&str> = self.parameters.iter()
+            .map(|parameter| match parameter.as_str() {
+                "sender" => sender,
+                "target" => target.unwrap_or(""),
+                _ => content,
+            })
+            .collect();
+        let pattern = match self.translation_key.as_str() {
+            "chat.type.text" => "<%s> %s",
+            "chat.type.announcement" => "[%s] %s",
+            "chat.type.emote" => "* %s %s",
+            "commands.message.display.incoming" => "%s whispers to you: %s",
+            "commands.message.display.outgoing" => "You whisper to %s: %s",
+            "chat.type.team.text" => "%s <%s> %s",
+            "chat.type.team.sent" => "-> %s <%s> %s",
+            _ => return args.join(" "),
+        };
+        let mut out = String::with_capacity(pattern.len() + content.len());
+        let mut args = args.into_iter();
+        for (i, part) in pattern.split("%s").enumerate() {
+            if i > 0 {
+                out += args.next().unwrap_or("");
+            }
+            out += part;
+        }
+        out
+    }
+}
+
 //TODO: probably replace this with a unified text component struct
 // but this isn't even used by default for the vanilla registry for 1.20.4
 // but may be in the future
@@ -31,3 +78,21 @@ pub struct Style {
 
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_plain() {
+        let decoration: Decoration = serde_json::from_str(
+            r#"{"translation_key": "chat.type.text", "parameters": ["sender", "content"]}"#
+        ).unwrap();
+        assert_eq!(decoration.format_plain("Steve", None, "hi"), "<Steve> hi");
+
+        let decoration: Decoration = serde_json::from_str(
+            r#"{"translation_key": "custom.key", "parameters": ["sender", "content"]}"#
+        ).unwrap();
+        assert_eq!(decoration.format_plain("Steve", None, "hi"), "Steve hi");
+    }
+}
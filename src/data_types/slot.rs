@@ -0,0 +1,46 @@
+use super::{ToProtocol, VarInt};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+///An item stack in a container, where `item_id` is an id in the `minecraft:item` registry.
+///
+///Stacks are sent with the default components of their item.
+pub struct Slot {
+    item_id: i32,
+    count: i32,
+}
+
+impl Slot {
+    pub fn new(item_id: i32, count: i32) -> Self {
+        Self { item_id, count }
+    }
+
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count <= 0
+    }
+
+    pub fn get_item_id(&self) -> i32 {
+        self.item_id
+    }
+
+    pub fn get_count(&self) -> i32 {
+        self.count
+    }
+}
+
+impl ToProtocol for Slot {
+    fn to_protocol_bytes(&self) -> Vec<u8> {
+        if self.is_empty() {
+            return VarInt::new(0).to_protocol_bytes();
+        }
+        let mut out = VarInt::new(self.count).to_protocol_bytes();
+        out.append(&mut VarInt::new(self.item_id).to_protocol_bytes());
+        //No components added or removed
+        out.append(&mut VarInt::new(0).to_protocol_bytes());
+        out.append(&mut VarInt::new(0).to_protocol_bytes());
+        out
+    }
+}
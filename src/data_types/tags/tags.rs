@@ -36,7 +36,21 @@ pub struct Mapping {
 }
 
 impl Mapping {
-    
+    pub fn get_protocol_id(&self, name: &str) -> Option<i32> {
+        self.mappings.get(name).copied()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.mappings.contains_key(name)
+    }
+
+    pub fn get_names(&self) -> impl Iterator<Item = &String> {
+        self.mappings.keys()
+    }
+
+    pub fn get_name(&self, protocol_id: i32) -> Option<&str> {
+        self.mappings.iter().find(|(_, id)| **id == protocol_id).map(|(name, _)| name.as_str())
+    }
 }
 
 impl Mapping {
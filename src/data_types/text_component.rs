@@ -10,18 +10,18 @@ use super::{ToProtocol, NBT};
 
 trait TextComponentType: Default + Debug + Clone {}
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct Nbt;
 
 impl TextComponentType for Nbt {}
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct Json;
 
 impl TextComponentType for Json {}
 
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 //TODO: Implement serverbound `nbt` content
 pub struct TextComponent<T> 
     where T: TextComponentType
@@ -60,13 +60,13 @@ pub struct TextComponent<T>
     extra: Option<Vec<TextComponent<T>>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct ClickEvent {
     action: ClickEventAction,
     value: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum ClickEventAction {
     OpenUrl,
@@ -87,7 +87,7 @@ impl ClickEvent {
 
 
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct HoverEvent<T> 
     where T: TextComponentType
 {
@@ -95,7 +95,7 @@ pub struct HoverEvent<T>
     contents: Contents<T>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum HoverEventAction {
     ShowText,
@@ -103,7 +103,7 @@ pub enum HoverEventAction {
     ShowEntity,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum Contents<T> 
     where T: TextComponentType
@@ -239,6 +239,18 @@ impl<T> TextComponent<T>
         self.extra.is_some()
     }
 
+    /// The text of this component and its extras without formatting, for logs and the console
+    pub fn to_plain_string(&self) -> String {
+        let mut out = self.text.clone()
+            .or(self.translate.clone())
+            .or(self.keybind.clone())
+            .unwrap_or_default();
+        for extra in self.extra.iter().flatten() {
+            out.push_str(&extra.to_plain_string());
+        }
+        out
+    }
+
 }
 
 impl TextComponent<Nbt> {
@@ -268,7 +280,7 @@ impl ToProtocol for TextComponent<Json> {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct Formatting {
     #[serde(skip_serializing_if = "Option::is_none")]
     color: Option<String>,
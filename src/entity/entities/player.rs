@@ -195,6 +195,11 @@ impl EntityPlayer {
     fn player_tick_function(&mut self) {
 
     }
+
+    /// 0 survival, 1 creative, 2 adventure, 3 spectator
+    pub fn get_player_game_type(&self) -> i32 {
+        self.player_game_type
+    }
 }
 
 pub trait WeakEntity {
@@ -1,10 +1,24 @@
-use std::{alloc::Allocator, any::{Any, TypeId}, collections::{HashMap, HashSet}, hash::Hash, marker::PhantomData, sync::RwLock};
+use std::{any::{Any, TypeId}, collections::HashMap, future::Future, pin::Pin, sync::{atomic::{AtomicU64, Ordering}, Arc, RwLock, Weak}};
+
+use crate::player::Player;
 
 pub use super::events::{
     command::CommandEvent, 
     on_disable::EventOnDisable, 
     on_enable::EventOnEnable, 
-    player_login::EventPlayerLogin
+    player_chat::AsyncPlayerChatEvent,
+    player_login::EventPlayerLogin,
+    player_join::PlayerJoinEvent,
+    player_quit::PlayerQuitEvent,
+    player_kick::PlayerKickEvent,
+    player_respawn::PlayerRespawnEvent,
+    player_changed_world::PlayerChangedWorldEvent,
+    block_break::BlockBreakEvent,
+    block_place::BlockPlaceEvent,
+    block_damage::BlockDamageEvent,
+    block_physics::BlockPhysicsEvent,
+    player_interact::{InteractAction, PlayerInteractEvent},
+    plugin_event::PluginEvent,
 };
 
 
@@ -18,12 +32,25 @@ pub enum Event {
     OnDisable { e: EventOnDisable },
     PlayerLogin { e: EventPlayerLogin },
     Command { e: CommandEvent },
+    PlayerChat { e: AsyncPlayerChatEvent },
+    PlayerJoin { e: PlayerJoinEvent },
+    PlayerQuit { e: PlayerQuitEvent },
+    PlayerKick { e: PlayerKickEvent },
+    PlayerRespawn { e: PlayerRespawnEvent },
+    PlayerChangedWorld { e: PlayerChangedWorldEvent },
+    BlockBreak { e: BlockBreakEvent },
+    BlockPlace { e: BlockPlaceEvent },
+    BlockDamage { e: BlockDamageEvent },
+    BlockPhysics { e: BlockPhysicsEvent },
+    PlayerInteract { e: PlayerInteractEvent },
+    Plugin { e: PluginEvent },
 }
 
 
 
 pub struct EventManager {
-    event_map: RwLock<HashMap<TypeId, Box<HandlerList<dyn TraitEvent>>>>,
+    //Each value is the `HandlerList<E>` of the event type with the key's `TypeId`
+    event_map: RwLock<HashMap<TypeId, Box<dyn AnyHandlerList>>>,
 }
 
 
@@ -35,80 +62,124 @@ impl EventManager {
         }
     }
 
-    pub fn get_event_map(&self) -> &RwLock<HashMap<TypeId, Box<HandlerList<dyn TraitEvent>>>> {
-        &self.event_map
+    /// Registers a handler of the event `E`, which may also be a category of events like `dyn PlayerEvent`.
+    /// Returns the id to unregister the handler with.
+    pub fn register_event_handler<E: TraitEvent + ?Sized>(&self, handler: EventHandler<E>) -> HandlerId {
+        let id = handler.id;
+        self.event_map.write().unwrap()
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(HandlerList::<E>::new()))
+            .as_any_mut()
+            .downcast_mut::<HandlerList<E>>()
+            .expect("handler list of another event type")
+            .register(handler);
+        id
     }
 
-    pub fn register_event_handler<E: TraitEvent + PartialEq + Clone + 'static + ?Sized>(&self, handler: EventHandler<E>) {
-        let mut lock = self.event_map.write().unwrap();
-        match lock.get_mut(&TypeId::of::<E>()) {
-            Some(list) => {
-                unsafe { &mut *(list as *mut dyn Any as *mut Box<HandlerList<E>>) }.register(handler);
-            },
-            None => {
-                let mut list = HandlerList::new();
-                list.register(handler);
-                lock.insert(TypeId::of::<E>(), unsafe {Box::from_raw(Box::into_raw(Box::new(list)) as *mut HandlerList<dyn TraitEvent>)});
+    /// Returns false if no handler was registered with the id
+    pub fn unregister(&self, id: HandlerId) -> bool {
+        self.event_map.write().unwrap().values_mut().any(|list| list.unregister(id))
+    }
+
+    /// Unregisters every handler of the owner, like a plugin being disabled.
+    /// Returns how many handlers were unregistered.
+    pub fn unregister_owner(&self, owner: &str) -> usize {
+        self.event_map.write().unwrap().values_mut().map(|list| list.unregister_owner(owner)).sum()
+    }
+
+    /// The handlers of the event and of its parents, in the order they run
+    fn get_handlers<E: TraitEvent>(&self) -> Vec<BoundHandler<E>> {
+        let map = self.event_map.read().unwrap();
+        let mut handlers = map.get(&TypeId::of::<E>())
+            .and_then(|list| list.as_any().downcast_ref::<HandlerList<E>>())
+            .map_or(Vec::new(), |list| list.bind(|e| e));
+        for parent in E::get_parents() {
+            if let Some(list) = map.get(&parent.type_id) {
+                handlers.extend((parent.bind)(list.as_any()));
             }
         }
+        //Handlers of the event and its parents with the same priority run in the order they were registered
+        handlers.sort_by_key(|handler| (handler.priority.get_order(), handler.id));
+        handlers
     }
 }
 
 
 
-pub fn listen<E: TraitEvent + Clone + 'static>(manager: &EventManager, e: &mut E) -> EventResult {
-    match manager.get_event_map().read().unwrap().get(&TypeId::of::<E>()) {
-        Some(ref_box) => {
-            let list = unsafe { &*(ref_box as *const dyn Any as *const Box<HandlerList<E>>) };
-            let mut result: EventResult = EventResult::Default;
-            
-            fn handle<E: TraitEvent + Clone>(
-                handler: &EventHandler<E>, 
-                result: &mut EventResult, 
-                e: &mut E
-            ) {
-                match (handler.func)(e) {
-                    EventResult::Deny => *result = EventResult::Deny,
-                    EventResult::Default => (),
-                    EventResult::Allow => *result = EventResult::Allow,
-                }
-            }
-            
-            for handler in list.lowest.iter() {
-                handle(handler, &mut result, e);
-            }
-            for handler in list.low.iter() {
-                handle(handler, &mut result, e);
-            }
-            for handler in list.normal.iter() {
-                handle(handler, &mut result, e);
-            }
-            for handler in list.high.iter() {
-                handle(handler, &mut result, e);
-            }
-            for handler in list.highest.iter() {
-                handle(handler, &mut result, e);
-            }                
-            for handler in list.monitor.iter() {
-                handle(handler, &mut result, e);
+/// Runs the handlers of the event and of its parents from `Lowest` to `Monitor` priority,
+/// awaiting async handlers in turn.
+/// 
+/// A handler returning `EventResult::Deny` cancels a `Cancellable` event and `EventResult::Allow`
+/// uncancels it. The result is the event's final state: `Deny` if it ended up cancelled, otherwise
+/// the last result besides `Default`. Events which can't be cancelled may still be denied, like logins.
+pub async fn listen<E: TraitEvent>(manager: &EventManager, e: &mut E) -> EventResult {
+    //The handlers are copied out of the map, so the lock isn't held across awaits
+    //and handlers may register or unregister handlers themselves
+    let handlers = manager.get_handlers::<E>();
+
+    let mut result = EventResult::Default;
+    for handler in handlers.iter() {
+        let cancelled = match e.as_cancellable() {
+            Some(e) => e.is_cancelled(),
+            None => result == EventResult::Deny,
+        };
+        if handler.ignore_cancelled && cancelled {
+            continue;
+        }
+        let handler_result = (handler.call)(e).await;
+        if handler_result != EventResult::Default {
+            result = handler_result;
+            if let Some(e) = e.as_cancellable() {
+                e.set_cancelled(handler_result == EventResult::Deny);
             }
-            result
-        },
-        None => EventResult::Default,
+        }
+    }
+    //Handlers may also have changed the cancelled state themselves
+    match e.as_cancellable() {
+        Some(e) if e.is_cancelled() => EventResult::Deny,
+        Some(_) if result == EventResult::Deny => EventResult::Default,
+        _ => result,
+    }
+}
+
+
+/// What the event manager needs of a `HandlerList` without knowing its event type
+trait AnyHandlerList: private::Sealed + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn unregister(&mut self, id: HandlerId) -> bool;
+    fn unregister_owner(&mut self, owner: &str) -> usize;
+}
+
+impl<E: TraitEvent + ?Sized> AnyHandlerList for HandlerList<E> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn unregister(&mut self, id: HandlerId) -> bool {
+        HandlerList::unregister(self, id)
+    }
+
+    fn unregister_owner(&mut self, owner: &str) -> usize {
+        HandlerList::unregister_owner(self, owner)
     }
 }
 
 
 pub struct HandlerList<E: TraitEvent + ?Sized> {
-    highest: Vec<EventHandler<E>>,
-    high: Vec<EventHandler<E>>,
-    normal: Vec<EventHandler<E>>,
-    low: Vec<EventHandler<E>>,
-    lowest: Vec<EventHandler<E>>,
-    monitor: Vec<EventHandler<E>>,
+    highest: Vec<Arc<EventHandler<E>>>,
+    high: Vec<Arc<EventHandler<E>>>,
+    normal: Vec<Arc<EventHandler<E>>>,
+    low: Vec<Arc<EventHandler<E>>>,
+    lowest: Vec<Arc<EventHandler<E>>>,
+    monitor: Vec<Arc<EventHandler<E>>>,
 }
 
-impl<E: 'static + TraitEvent + PartialEq + ?Sized> HandlerList<E> {
+impl<E: TraitEvent + ?Sized> HandlerList<E> {
     pub fn new() -> Self {
         Self { 
             highest: Vec::new(), 
@@ -120,7 +191,7 @@ impl<E: 'static + TraitEvent + PartialEq + ?Sized> HandlerList<E> {
         }
     }
     
-    pub fn get_handlers(&self, priority: &EventPriority) -> &Vec<EventHandler<E>> {
+    pub fn get_handlers(&self, priority: &EventPriority) -> &Vec<Arc<EventHandler<E>>> {
         match priority {
             EventPriority::Monitor => &self.monitor,
             EventPriority::Lowest => &self.lowest,
@@ -131,7 +202,7 @@ impl<E: 'static + TraitEvent + PartialEq + ?Sized> HandlerList<E> {
         }
     }
 
-    pub fn get_handlers_mut(&mut self, priority: &EventPriority) -> &mut Vec<EventHandler<E>> {
+    pub fn get_handlers_mut(&mut self, priority: &EventPriority) -> &mut Vec<Arc<EventHandler<E>>> {
         match priority {
             EventPriority::Monitor => &mut self.monitor,
             EventPriority::Lowest => &mut self.lowest,
@@ -143,69 +214,218 @@ impl<E: 'static + TraitEvent + PartialEq + ?Sized> HandlerList<E> {
     }
 
     pub fn register(&mut self, handler: EventHandler<E>) {
-        let vec = match &handler.priority {
-            EventPriority::Monitor => &mut self.monitor,
-            EventPriority::Lowest => &mut self.lowest,
-            EventPriority::Low => &mut self.low,
-            EventPriority::Normal => &mut self.normal,
-            EventPriority::High => &mut self.high,
-            EventPriority::Highest => &mut self.highest,
-        };
-        if !vec.contains(&handler) {
-            vec.push(handler)
+        let priority = handler.priority;
+        self.get_handlers_mut(&priority).push(Arc::new(handler));
+    }
+
+    /// Returns false if no handler has the id
+    pub fn unregister(&mut self, id: HandlerId) -> bool {
+        for priority in EventPriority::ORDER.iter() {
+            let handlers = self.get_handlers_mut(priority);
+            if let Some(index) = handlers.iter().position(|handler| handler.id == id) {
+                handlers.remove(index);
+                return true;
+            }
         }
+        false
+    }
+
+    /// Returns how many handlers the owner had
+    pub fn unregister_owner(&mut self, owner: &str) -> usize {
+        let mut removed = 0;
+        for priority in EventPriority::ORDER.iter() {
+            let handlers = self.get_handlers_mut(priority);
+            let before = handlers.len();
+            handlers.retain(|handler| handler.owner != owner);
+            removed += before - handlers.len();
+        }
+        removed
+    }
+
+    /// The handlers for events of type `C`, which is `E` itself or one of its children
+    fn bind<C: TraitEvent>(&self, upcast: for<'a> fn(&'a mut C) -> &'a mut E) -> Vec<BoundHandler<C>> {
+        EventPriority::ORDER.iter()
+            .flat_map(|priority| self.get_handlers(priority).iter())
+            .map(|handler| BoundHandler::new(handler.clone(), upcast))
+            .collect()
     }
 }
 
-impl<E: TraitEvent> private::Sealed for HandlerList<E> {}
+impl<E: TraitEvent + ?Sized> private::Sealed for HandlerList<E> {}
+
 
-impl<E: TraitEvent> HandlerList<E>
-{
-    fn unregister_all(&mut self) {
-        self.monitor.clear();
-        self.lowest.clear();
-        self.low.clear();
-        self.normal.clear();
-        self.high.clear();
-        self.highest.clear();
+/// A category of events the event belongs to, like all player events.
+/// The handlers registered for the category run for the event too.
+pub struct EventParent<E: TraitEvent> {
+    type_id: TypeId,
+    bind: Box<dyn Fn(&dyn Any) -> Vec<BoundHandler<E>>>,
+}
+
+impl<E: TraitEvent> EventParent<E> {
+    /// `upcast` turns the event into the category, which is usually a trait object like `dyn PlayerEvent`
+    pub fn new<P: TraitEvent + ?Sized>(upcast: for<'a> fn(&'a mut E) -> &'a mut P) -> Self {
+        Self {
+            type_id: TypeId::of::<P>(),
+            bind: Box::new(move |list| {
+                list.downcast_ref::<HandlerList<P>>().map_or(Vec::new(), |list| list.bind(upcast))
+            }),
+        }
+    }
+}
+
+impl<E: PlayerEvent> EventParent<E> {
+    pub fn player() -> Self {
+        Self::new::<dyn PlayerEvent>(|e| e)
+    }
+}
+
+
+/// A handler of an event or of one of its parents, ready to run for the event `E`
+struct BoundHandler<E: TraitEvent> {
+    id: HandlerId,
+    priority: EventPriority,
+    ignore_cancelled: bool,
+    call: Box<dyn for<'a> Fn(&'a mut E) -> EventFuture<'a> + Send + Sync>,
+}
+
+impl<E: TraitEvent> BoundHandler<E> {
+    fn new<P: TraitEvent + ?Sized>(handler: Arc<EventHandler<P>>, upcast: for<'a> fn(&'a mut E) -> &'a mut P) -> Self {
+        Self {
+            id: handler.id,
+            priority: handler.priority,
+            ignore_cancelled: handler.ignore_cancelled,
+            call: Box::new(move |e| handler.call(upcast(e))),
+        }
     }
 }
 
 
-#[derive(PartialEq, Eq)]
+pub type EventFuture<'a> = Pin<Box<dyn Future<Output = EventResult> + Send + 'a>>;
+
+enum HandlerFunc<E: TraitEvent + ?Sized> {
+    Sync(Box<dyn Fn(&mut E) -> EventResult + Send + Sync>),
+    Async(Box<dyn for<'a> Fn(&'a mut E) -> EventFuture<'a> + Send + Sync>),
+    //Monitor handlers only see the outcome, they can't change the event or its result
+    Monitor(Box<dyn Fn(&E) + Send + Sync>),
+}
+
+/// The owner of the handlers the server registers itself
+pub const SERVER_OWNER: &str = "server";
+
+/// Identifies a registered handler, to unregister it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HandlerId(u64);
+
+static NEXT_HANDLER_ID: AtomicU64 = AtomicU64::new(0);
+
 pub struct EventHandler<E: TraitEvent + ?Sized> {
+    id: HandlerId,
+    owner: String,
     priority: EventPriority,
-    func: fn(&mut E) -> EventResult,
+    ignore_cancelled: bool,
+    func: HandlerFunc<E>,
 }
 
 
-impl<E: TraitEvent> EventHandler<E> {
-    pub fn new(priority: EventPriority, func: fn(&mut E) -> EventResult) -> Self {
+impl<E: TraitEvent + ?Sized> EventHandler<E> {
+    fn with_func(owner: &str, priority: EventPriority, func: HandlerFunc<E>) -> Self {
         Self { 
+            id: HandlerId(NEXT_HANDLER_ID.fetch_add(1, Ordering::Relaxed)),
+            owner: owner.to_string(),
             priority: priority, 
+            ignore_cancelled: false,
             func: func,
         }
     }
-}
 
+    /// `owner` is the id of the plugin registering the handler, to unregister all its handlers at once
+    /// 
+    /// ### Panics
+    /// If the priority is `Monitor`, use `EventHandler::monitor` for those
+    pub fn new(owner: &str, priority: EventPriority, func: impl Fn(&mut E) -> EventResult + Send + Sync + 'static) -> Self {
+        assert!(priority != EventPriority::Monitor, "Monitor handlers can't change the event, use EventHandler::monitor");
+        Self::with_func(owner, priority, HandlerFunc::Sync(Box::new(func)))
+    }
 
+    /// A handler which is awaited before the handlers after it run
+    /// 
+    /// ### Panics
+    /// If the priority is `Monitor`, use `EventHandler::monitor` for those
+    pub fn new_async(
+        owner: &str,
+        priority: EventPriority,
+        func: impl for<'a> Fn(&'a mut E) -> EventFuture<'a> + Send + Sync + 'static
+    ) -> Self {
+        assert!(priority != EventPriority::Monitor, "Monitor handlers can't change the event, use EventHandler::monitor");
+        Self::with_func(owner, priority, HandlerFunc::Async(Box::new(func)))
+    }
 
-pub trait TraitEvent: std::fmt::Debug {}
+    /// A handler which runs after all others and only observes the event
+    pub fn monitor(owner: &str, func: impl Fn(&E) + Send + Sync + 'static) -> Self {
+        Self::with_func(owner, EventPriority::Monitor, HandlerFunc::Monitor(Box::new(func)))
+    }
+
+    /// Skips the handler if an earlier handler cancelled the event
+    pub fn with_ignore_cancelled(mut self, ignore_cancelled: bool) -> Self {
+        self.ignore_cancelled = ignore_cancelled;
+        self
+    }
+
+    pub fn get_id(&self) -> HandlerId {
+        self.id
+    }
+
+    pub fn get_owner(&self) -> &str {
+        &self.owner
+    }
 
-impl PartialEq for dyn TraitEvent {
-    fn eq(&self, other: &Self) -> bool {
-        self.type_id() == other.type_id()
+    pub fn get_priority(&self) -> EventPriority {
+        self.priority
+    }
+
+    pub fn is_ignore_cancelled(&self) -> bool {
+        self.ignore_cancelled
+    }
+
+    fn call<'a>(&self, e: &'a mut E) -> EventFuture<'a> {
+        match &self.func {
+            HandlerFunc::Sync(func) => Box::pin(std::future::ready(func(e))),
+            HandlerFunc::Async(func) => func(e),
+            HandlerFunc::Monitor(func) => {
+                func(e);
+                Box::pin(std::future::ready(EventResult::Default))
+            },
+        }
     }
 }
 
 
-impl Hash for dyn TraitEvent {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.type_id().hash(state);
+
+pub trait TraitEvent: std::fmt::Debug + Send + Sync + 'static {
+    /// The categories the event belongs to, whose handlers run for it too
+    fn get_parents() -> Vec<EventParent<Self>> where Self: Sized {
+        Vec::new()
     }
+
+    /// Events which can be cancelled return themselves
+    fn as_cancellable(&mut self) -> Option<&mut dyn Cancellable> {
+        None
+    }
+}
+
+/// Implemented by the events which handlers can cancel, which stops what caused them
+pub trait Cancellable: TraitEvent {
+    fn is_cancelled(&self) -> bool;
+    fn set_cancelled(&mut self, cancelled: bool);
 }
 
-#[derive(Eq, PartialEq, Hash, Copy, Clone)]
+/// Implemented by the events about a player, so all of them can be handled at once
+/// with a handler of `dyn PlayerEvent`
+pub trait PlayerEvent: TraitEvent {
+    fn get_player(&self) -> Weak<Player>;
+}
+
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone)]
 pub enum EventPriority {
     Monitor,
     Lowest,
@@ -215,14 +435,155 @@ pub enum EventPriority {
     Highest,
 }
 
+impl EventPriority {
+    /// The priorities in the order their handlers run
+    pub const ORDER: [EventPriority; 6] = [
+        EventPriority::Lowest,
+        EventPriority::Low,
+        EventPriority::Normal,
+        EventPriority::High,
+        EventPriority::Highest,
+        EventPriority::Monitor,
+    ];
+
+    /// The position of the priority in `EventPriority::ORDER`
+    pub fn get_order(&self) -> usize {
+        EventPriority::ORDER.iter().position(|priority| priority == self).unwrap()
+    }
+}
+
 impl Default for EventPriority {
     fn default() -> Self {
         EventPriority::Normal
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EventResult {
     Deny,
     Default,
     Allow,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestEvent {
+        calls: Vec<&'static str>,
+    }
+
+    impl TraitEvent for TestEvent {
+        fn get_parents() -> Vec<EventParent<Self>> {
+            vec![EventParent::player()]
+        }
+    }
+
+    impl PlayerEvent for TestEvent {
+        fn get_player(&self) -> Weak<Player> {
+            Weak::new()
+        }
+    }
+
+    #[test]
+    fn handlers() {
+        let manager = EventManager::new();
+        manager.register_event_handler(EventHandler::monitor("a", |e: &TestEvent| assert_eq!(e.calls, ["low", "async"])));
+        manager.register_event_handler(EventHandler::new("b", EventPriority::High, |e: &mut TestEvent| {
+            e.calls.push("ignored");
+            EventResult::Allow
+        }).with_ignore_cancelled(true));
+        manager.register_event_handler(EventHandler::new_async("a", EventPriority::Normal, |e: &mut TestEvent| Box::pin(async move {
+            e.calls.push("async");
+            EventResult::Deny
+        })));
+        let low = manager.register_event_handler(EventHandler::new("b", EventPriority::Low, |e: &mut TestEvent| {
+            e.calls.push("low");
+            EventResult::Allow
+        }));
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let mut e = TestEvent { calls: vec![] };
+        assert_eq!(runtime.block_on(listen(&manager, &mut e)), EventResult::Deny);
+        assert_eq!(e.calls, ["low", "async"]);
+
+        assert!(manager.unregister(low));
+        assert!(!manager.unregister(low));
+        assert_eq!(manager.unregister_owner("a"), 2);
+        let mut e = TestEvent { calls: vec![] };
+        assert_eq!(runtime.block_on(listen(&manager, &mut e)), EventResult::Allow);
+        assert_eq!(e.calls, ["ignored"]);
+    }
+
+    #[derive(Debug)]
+    struct CancellableEvent {
+        cancelled: bool,
+    }
+
+    impl TraitEvent for CancellableEvent {
+        fn as_cancellable(&mut self) -> Option<&mut dyn Cancellable> {
+            Some(self)
+        }
+    }
+
+    impl Cancellable for CancellableEvent {
+        fn is_cancelled(&self) -> bool {
+            self.cancelled
+        }
+
+        fn set_cancelled(&mut self, cancelled: bool) {
+            self.cancelled = cancelled;
+        }
+    }
+
+    #[test]
+    fn cancel() {
+        let manager = EventManager::new();
+        manager.register_event_handler(EventHandler::new("a", EventPriority::Low, |e: &mut CancellableEvent| {
+            e.set_cancelled(true);
+            EventResult::Default
+        }));
+        manager.register_event_handler(EventHandler::new("a", EventPriority::Normal, |_: &mut CancellableEvent| {
+            panic!("the event was cancelled")
+        }).with_ignore_cancelled(true));
+        let allow = manager.register_event_handler(EventHandler::new("a", EventPriority::High, |_: &mut CancellableEvent| EventResult::Allow));
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let mut e = CancellableEvent { cancelled: false };
+        assert_eq!(runtime.block_on(listen(&manager, &mut e)), EventResult::Allow);
+        assert!(!e.is_cancelled());
+
+        manager.unregister(allow);
+        manager.register_event_handler(EventHandler::new("a", EventPriority::High, |_: &mut CancellableEvent| EventResult::Deny));
+        manager.register_event_handler(EventHandler::new("a", EventPriority::Highest, |e: &mut CancellableEvent| {
+            e.set_cancelled(false);
+            EventResult::Default
+        }));
+        let mut e = CancellableEvent { cancelled: false };
+        assert_eq!(runtime.block_on(listen(&manager, &mut e)), EventResult::Default);
+        assert!(!e.is_cancelled());
+    }
+
+    #[test]
+    fn parents() {
+        let manager = EventManager::new();
+        manager.register_event_handler(EventHandler::new("a", EventPriority::High, |e: &mut TestEvent| {
+            e.calls.push("high");
+            EventResult::Default
+        }));
+        manager.register_event_handler(EventHandler::new("a", EventPriority::Low, |e: &mut dyn PlayerEvent| {
+            assert!(e.get_player().upgrade().is_none());
+            EventResult::Deny
+        }));
+        manager.register_event_handler(EventHandler::monitor("a", |e: &dyn PlayerEvent| {
+            assert!(e.get_player().upgrade().is_none());
+        }));
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let mut e = TestEvent { calls: vec![] };
+        assert_eq!(runtime.block_on(listen(&manager, &mut e)), EventResult::Deny);
+        assert_eq!(e.calls, ["high"]);
+        assert_eq!(manager.unregister_owner("a"), 3);
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::Weak;
+
+use crate::data_types::{BlockPos, Slot};
+use crate::{event::{Cancellable, EventParent, PlayerEvent, TraitEvent}, player::Player};
+
+/// Fired when a player breaks a block, before it is removed.
+/// 
+/// Cancelling the event keeps the block, and the player's client is told so.
+#[derive(Debug, Clone)]
+pub struct BlockBreakEvent {
+    player: Weak<Player>,
+    position: BlockPos,
+    block_state: Option<i32>,
+    drops: Vec<Slot>,
+    exp_to_drop: i32,
+    cancelled: bool,
+}
+
+impl BlockBreakEvent {
+    //TODO: the drops and experience of the block, once there are loot tables
+    pub fn new(player: Weak<Player>, position: BlockPos, block_state: Option<i32>) -> Self {
+        Self { player, position, block_state, drops: vec![], exp_to_drop: 0, cancelled: false }
+    }
+
+    pub fn get_player(&self) -> Weak<Player> {
+        self.player.clone()
+    }
+
+    pub fn get_position(&self) -> BlockPos {
+        self.position
+    }
+
+    /// None if the server doesn't know the block
+    pub fn get_block_state(&self) -> Option<i32> {
+        self.block_state
+    }
+
+    pub fn get_drops(&self) -> &[Slot] {
+        &self.drops
+    }
+
+    pub fn get_drops_mut(&mut self) -> &mut Vec<Slot> {
+        &mut self.drops
+    }
+
+    pub fn get_exp_to_drop(&self) -> i32 {
+        self.exp_to_drop
+    }
+
+    pub fn set_exp_to_drop(&mut self, exp_to_drop: i32) {
+        self.exp_to_drop = exp_to_drop;
+    }
+}
+
+impl TraitEvent for BlockBreakEvent {
+    fn get_parents() -> Vec<EventParent<Self>> {
+        vec![EventParent::player()]
+    }
+
+    fn as_cancellable(&mut self) -> Option<&mut dyn Cancellable> {
+        Some(self)
+    }
+}
+
+impl Cancellable for BlockBreakEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+impl PlayerEvent for BlockBreakEvent {
+    fn get_player(&self) -> Weak<Player> {
+        self.get_player()
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Weak;
+
+use crate::data_types::{BlockPos, Slot};
+use crate::{event::{Cancellable, EventParent, PlayerEvent, TraitEvent}, player::Player};
+
+/// Fired when a player starts digging a block.
+/// 
+/// Cancelling the event stops the block from being broken if it breaks instantly.
+#[derive(Debug, Clone)]
+pub struct BlockDamageEvent {
+    player: Weak<Player>,
+    position: BlockPos,
+    item: Slot,
+    insta_break: bool,
+    cancelled: bool,
+}
+
+impl BlockDamageEvent {
+    pub fn new(player: Weak<Player>, position: BlockPos, item: Slot, insta_break: bool) -> Self {
+        Self { player, position, item, insta_break, cancelled: false }
+    }
+
+    pub fn get_player(&self) -> Weak<Player> {
+        self.player.clone()
+    }
+
+    pub fn get_position(&self) -> BlockPos {
+        self.position
+    }
+
+    /// What the player digs with
+    pub fn get_item(&self) -> Slot {
+        self.item
+    }
+
+    /// Whether the block breaks right away, like it does in creative mode
+    pub fn is_insta_break(&self) -> bool {
+        self.insta_break
+    }
+
+    pub fn set_insta_break(&mut self, insta_break: bool) {
+        self.insta_break = insta_break;
+    }
+}
+
+impl TraitEvent for BlockDamageEvent {
+    fn get_parents() -> Vec<EventParent<Self>> {
+        vec![EventParent::player()]
+    }
+
+    fn as_cancellable(&mut self) -> Option<&mut dyn Cancellable> {
+        Some(self)
+    }
+}
+
+impl Cancellable for BlockDamageEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+impl PlayerEvent for BlockDamageEvent {
+    fn get_player(&self) -> Weak<Player> {
+        self.get_player()
+    }
+}
@@ -0,0 +1,55 @@
+use crate::data_types::BlockPos;
+use crate::event::{Cancellable, TraitEvent};
+
+/// Fired for each neighbor of a block which changed, which may react to it.
+/// 
+/// Cancelling the event stops the neighbor from reacting, once blocks react to their neighbors.
+#[derive(Debug, Clone)]
+pub struct BlockPhysicsEvent {
+    world: String,
+    position: BlockPos,
+    block_state: Option<i32>,
+    source: BlockPos,
+    cancelled: bool,
+}
+
+impl BlockPhysicsEvent {
+    pub fn new(world: &str, position: BlockPos, block_state: Option<i32>, source: BlockPos) -> Self {
+        Self { world: world.to_string(), position, block_state, source, cancelled: false }
+    }
+
+    /// The level name of the world
+    pub fn get_world(&self) -> &str {
+        &self.world
+    }
+
+    pub fn get_position(&self) -> BlockPos {
+        self.position
+    }
+
+    /// None if the server doesn't know the block
+    pub fn get_block_state(&self) -> Option<i32> {
+        self.block_state
+    }
+
+    /// The block which changed
+    pub fn get_source(&self) -> BlockPos {
+        self.source
+    }
+}
+
+impl TraitEvent for BlockPhysicsEvent {
+    fn as_cancellable(&mut self) -> Option<&mut dyn Cancellable> {
+        Some(self)
+    }
+}
+
+impl Cancellable for BlockPhysicsEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
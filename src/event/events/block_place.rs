@@ -0,0 +1,74 @@
+use std::sync::Weak;
+
+use crate::data_types::{BlockPos, Slot};
+use crate::game::hand::Hand;
+use crate::{event::{Cancellable, EventParent, PlayerEvent, TraitEvent}, player::Player};
+
+/// Fired when a player places the block they hold.
+/// 
+/// Cancelling the event removes the block the player's client placed.
+///
+/// Not fired yet, since the server doesn't know which block state an item places.
+#[derive(Debug, Clone)]
+pub struct BlockPlaceEvent {
+    player: Weak<Player>,
+    position: BlockPos,
+    placed_against: BlockPos,
+    item: Slot,
+    hand: Hand,
+    cancelled: bool,
+}
+
+impl BlockPlaceEvent {
+    pub fn new(player: Weak<Player>, position: BlockPos, placed_against: BlockPos, item: Slot, hand: Hand) -> Self {
+        Self { player, position, placed_against, item, hand, cancelled: false }
+    }
+
+    pub fn get_player(&self) -> Weak<Player> {
+        self.player.clone()
+    }
+
+    pub fn get_position(&self) -> BlockPos {
+        self.position
+    }
+
+    /// The block the player clicked
+    pub fn get_placed_against(&self) -> BlockPos {
+        self.placed_against
+    }
+
+    /// The block item being placed
+    pub fn get_item(&self) -> Slot {
+        self.item
+    }
+
+    pub fn get_hand(&self) -> Hand {
+        self.hand
+    }
+}
+
+impl TraitEvent for BlockPlaceEvent {
+    fn get_parents() -> Vec<EventParent<Self>> {
+        vec![EventParent::player()]
+    }
+
+    fn as_cancellable(&mut self) -> Option<&mut dyn Cancellable> {
+        Some(self)
+    }
+}
+
+impl Cancellable for BlockPlaceEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+impl PlayerEvent for BlockPlaceEvent {
+    fn get_player(&self) -> Weak<Player> {
+        self.get_player()
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{command::{Command, CommandSender}, event::TraitEvent};
+use crate::{command::{Command, CommandArg, CommandSender}, event::{Cancellable, TraitEvent}};
 
 #[derive(Debug, Clone)]
 pub struct CommandEvent {
@@ -6,11 +6,19 @@ pub struct CommandEvent {
     command: Command,
     label: String,
     args: Vec<String>,
+    parsed_args: Vec<CommandArg>,
+    cancelled: bool,
 }
 
 impl CommandEvent {
     pub fn new(sender: CommandSender, command: Command, label: String, args: Vec<String>) -> Self {
-        Self { sender, command, label, args }
+        Self { sender, command, label, args, parsed_args: vec![], cancelled: false }
+    }
+
+    /// Sets the arguments as parsed against the command's usages
+    pub fn with_parsed_args(mut self, parsed_args: Vec<CommandArg>) -> Self {
+        self.parsed_args = parsed_args;
+        self
     }
     
     pub fn get_sender(&self) -> &CommandSender {
@@ -28,6 +36,24 @@ impl CommandEvent {
     pub fn get_args(&self) -> &[String] {
         &self.args
     }
+
+    pub fn get_parsed_args(&self) -> &[CommandArg] {
+        &self.parsed_args
+    }
+}
+
+impl TraitEvent for CommandEvent {
+    fn as_cancellable(&mut self) -> Option<&mut dyn Cancellable> {
+        Some(self)
+    }
 }
 
-impl TraitEvent for CommandEvent {}
\ No newline at end of file
+impl Cancellable for CommandEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
\ No newline at end of file
@@ -2,4 +2,15 @@ pub mod on_enable;
 pub mod on_disable;
 pub mod command;
 pub mod player_login;
-
+pub mod player_chat;
+pub mod player_join;
+pub mod player_quit;
+pub mod player_kick;
+pub mod player_respawn;
+pub mod player_changed_world;
+pub mod block_break;
+pub mod block_place;
+pub mod block_damage;
+pub mod block_physics;
+pub mod player_interact;
+pub mod plugin_event;
@@ -0,0 +1,37 @@
+use std::sync::Weak;
+
+use crate::{event::{EventParent, PlayerEvent, TraitEvent}, player::Player};
+
+/// Fired after a player was moved to another world
+#[derive(Debug, Clone)]
+pub struct PlayerChangedWorldEvent {
+    player: Weak<Player>,
+    from: Option<String>,
+}
+
+impl PlayerChangedWorldEvent {
+    pub fn new(player: Weak<Player>, from: Option<String>) -> Self {
+        Self { player, from }
+    }
+
+    pub fn get_player(&self) -> Weak<Player> {
+        self.player.clone()
+    }
+
+    /// The level name of the world the player was in
+    pub fn get_from(&self) -> Option<&str> {
+        self.from.as_deref()
+    }
+}
+
+impl TraitEvent for PlayerChangedWorldEvent {
+    fn get_parents() -> Vec<EventParent<Self>> {
+        vec![EventParent::player()]
+    }
+}
+
+impl PlayerEvent for PlayerChangedWorldEvent {
+    fn get_player(&self) -> Weak<Player> {
+        self.get_player()
+    }
+}
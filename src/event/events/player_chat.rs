@@ -0,0 +1,83 @@
+use std::sync::Weak;
+
+use crate::{event::{Cancellable, EventParent, PlayerEvent, TraitEvent}, player::Player};
+
+/// Fired on the chat thread for every chat message a player sends.
+/// 
+/// Cancelling the event stops the message from being broadcast. 
+/// Changing the message means it can no longer be sent as signed chat.
+#[derive(Debug, Clone)]
+pub struct AsyncPlayerChatEvent {
+    player: Weak<Player>,
+    message: String,
+    chat_type: String,
+    recipients: Vec<Weak<Player>>,
+    cancelled: bool,
+}
+
+impl AsyncPlayerChatEvent {
+    pub fn new(player: Weak<Player>, message: &str, recipients: Vec<Weak<Player>>) -> Self {
+        Self {
+            player,
+            message: message.to_string(),
+            chat_type: "minecraft:chat".to_string(),
+            recipients,
+            cancelled: false,
+        }
+    }
+
+    pub fn get_player(&self) -> Weak<Player> {
+        self.player.clone()
+    }
+
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn set_message(&mut self, message: &str) {
+        self.message = message.to_string();
+    }
+
+    /// The entry of the `minecraft:chat_type` registry used to decorate the message
+    pub fn get_chat_type(&self) -> &str {
+        &self.chat_type
+    }
+
+    pub fn set_chat_type(&mut self, chat_type: &str) {
+        self.chat_type = chat_type.to_string();
+    }
+
+    pub fn get_recipients(&self) -> &[Weak<Player>] {
+        &self.recipients
+    }
+
+    pub fn get_recipients_mut(&mut self) -> &mut Vec<Weak<Player>> {
+        &mut self.recipients
+    }
+}
+
+impl TraitEvent for AsyncPlayerChatEvent {
+    fn get_parents() -> Vec<EventParent<Self>> {
+        vec![EventParent::player()]
+    }
+
+    fn as_cancellable(&mut self) -> Option<&mut dyn Cancellable> {
+        Some(self)
+    }
+}
+
+impl Cancellable for AsyncPlayerChatEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+impl PlayerEvent for AsyncPlayerChatEvent {
+    fn get_player(&self) -> Weak<Player> {
+        self.get_player()
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::Weak;
+
+use crate::block::block_state::Facing;
+use crate::data_types::{BlockPos, Slot, Vec3d};
+use crate::game::hand::Hand;
+use crate::{event::{Cancellable, EventParent, PlayerEvent, TraitEvent}, player::Player};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InteractAction {
+    LeftClickBlock,
+    RightClickBlock,
+    RightClickAir,
+}
+
+/// Fired when a player clicks a block, or right clicks the air.
+/// 
+/// Cancelling the event stops the block from being dug, used or placed against,
+/// and the player's client is told so.
+#[derive(Debug, Clone)]
+pub struct PlayerInteractEvent {
+    player: Weak<Player>,
+    action: InteractAction,
+    hand: Hand,
+    item: Slot,
+    block: Option<(BlockPos, Facing)>,
+    cursor_position: Option<Vec3d>,
+    cancelled: bool,
+}
+
+impl PlayerInteractEvent {
+    pub fn new(player: Weak<Player>, action: InteractAction, hand: Hand, item: Slot) -> Self {
+        Self { player, action, hand, item, block: None, cursor_position: None, cancelled: false }
+    }
+
+    /// The block clicked and its face which was clicked
+    pub fn with_block(mut self, position: BlockPos, face: Facing) -> Self {
+        self.block = Some((position, face));
+        self
+    }
+
+    /// Where on the block face the player clicked, from 0 to 1 on each axis
+    pub fn with_cursor_position(mut self, cursor_position: Vec3d) -> Self {
+        self.cursor_position = Some(cursor_position);
+        self
+    }
+
+    pub fn get_player(&self) -> Weak<Player> {
+        self.player.clone()
+    }
+
+    pub fn get_action(&self) -> InteractAction {
+        self.action
+    }
+
+    pub fn get_hand(&self) -> Hand {
+        self.hand
+    }
+
+    pub fn get_item(&self) -> Slot {
+        self.item
+    }
+
+    pub fn get_block(&self) -> Option<BlockPos> {
+        self.block.map(|(position, _)| position)
+    }
+
+    pub fn get_face(&self) -> Option<Facing> {
+        self.block.map(|(_, face)| face)
+    }
+
+    pub fn get_cursor_position(&self) -> Option<Vec3d> {
+        self.cursor_position
+    }
+}
+
+impl TraitEvent for PlayerInteractEvent {
+    fn get_parents() -> Vec<EventParent<Self>> {
+        vec![EventParent::player()]
+    }
+
+    fn as_cancellable(&mut self) -> Option<&mut dyn Cancellable> {
+        Some(self)
+    }
+}
+
+impl Cancellable for PlayerInteractEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+impl PlayerEvent for PlayerInteractEvent {
+    fn get_player(&self) -> Weak<Player> {
+        self.get_player()
+    }
+}
@@ -0,0 +1,49 @@
+use std::sync::Weak;
+
+use crate::data_types::text_component::{Formatting, Nbt, TextComponent};
+use crate::{event::{EventParent, PlayerEvent, TraitEvent}, player::Player};
+
+/// Fired once a player is in the play state and everyone can see them in the tab list.
+/// 
+/// The join message is broadcast after the event, unless it was set to `None`.
+#[derive(Debug, Clone)]
+pub struct PlayerJoinEvent {
+    player: Weak<Player>,
+    join_message: Option<TextComponent<Nbt>>,
+}
+
+impl PlayerJoinEvent {
+    pub fn new(player: Weak<Player>, name: &str) -> Self {
+        Self {
+            player,
+            join_message: Some(TextComponent::builder()
+                .text(&format!("{name} joined the game"))
+                .formatting(Formatting::builder().color(0xe).build())
+                .build()),
+        }
+    }
+
+    pub fn get_player(&self) -> Weak<Player> {
+        self.player.clone()
+    }
+
+    pub fn get_join_message(&self) -> Option<&TextComponent<Nbt>> {
+        self.join_message.as_ref()
+    }
+
+    pub fn set_join_message(&mut self, join_message: Option<TextComponent<Nbt>>) {
+        self.join_message = join_message;
+    }
+}
+
+impl TraitEvent for PlayerJoinEvent {
+    fn get_parents() -> Vec<EventParent<Self>> {
+        vec![EventParent::player()]
+    }
+}
+
+impl PlayerEvent for PlayerJoinEvent {
+    fn get_player(&self) -> Weak<Player> {
+        self.get_player()
+    }
+}
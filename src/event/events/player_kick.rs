@@ -0,0 +1,69 @@
+use std::sync::Weak;
+
+use crate::data_types::text_component::{Nbt, TextComponent};
+use crate::{event::{Cancellable, EventParent, PlayerEvent, TraitEvent}, player::Player};
+
+/// Fired when the server disconnects a player who joined, for any reason but a lost connection.
+/// 
+/// Cancelling the event keeps the player online. The leave message becomes
+/// the message of the `PlayerQuitEvent` which follows.
+#[derive(Debug, Clone)]
+pub struct PlayerKickEvent {
+    player: Weak<Player>,
+    reason: TextComponent<Nbt>,
+    leave_message: Option<TextComponent<Nbt>>,
+    cancelled: bool,
+}
+
+impl PlayerKickEvent {
+    pub fn new(player: Weak<Player>, reason: TextComponent<Nbt>, leave_message: Option<TextComponent<Nbt>>) -> Self {
+        Self { player, reason, leave_message, cancelled: false }
+    }
+
+    pub fn get_player(&self) -> Weak<Player> {
+        self.player.clone()
+    }
+
+    /// What the player is shown on the disconnect screen
+    pub fn get_reason(&self) -> &TextComponent<Nbt> {
+        &self.reason
+    }
+
+    pub fn set_reason(&mut self, reason: TextComponent<Nbt>) {
+        self.reason = reason;
+    }
+
+    pub fn get_leave_message(&self) -> Option<&TextComponent<Nbt>> {
+        self.leave_message.as_ref()
+    }
+
+    pub fn set_leave_message(&mut self, leave_message: Option<TextComponent<Nbt>>) {
+        self.leave_message = leave_message;
+    }
+}
+
+impl TraitEvent for PlayerKickEvent {
+    fn get_parents() -> Vec<EventParent<Self>> {
+        vec![EventParent::player()]
+    }
+
+    fn as_cancellable(&mut self) -> Option<&mut dyn Cancellable> {
+        Some(self)
+    }
+}
+
+impl Cancellable for PlayerKickEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
+
+impl PlayerEvent for PlayerKickEvent {
+    fn get_player(&self) -> Weak<Player> {
+        self.get_player()
+    }
+}
@@ -1,6 +1,6 @@
 use std::{net::SocketAddr, sync::Weak};
 
-use crate::{data_types::text_component::{Nbt, TextComponent}, event::TraitEvent, player::Player};
+use crate::{data_types::text_component::{Nbt, TextComponent}, event::{EventParent, PlayerEvent, TraitEvent}, player::Player};
 
 #[derive(Debug, Clone)]
 pub struct EventPlayerLogin {
@@ -28,6 +28,14 @@ impl EventPlayerLogin {
         self.result = result;
     }
 
+    pub fn allow(&mut self) {
+        self.result = PlayerLoginResult::Allowed;
+    }
+
+    pub fn get_result(&self) -> &PlayerLoginResult {
+        &self.result
+    }
+
     pub fn get_player(&self) -> Weak<Player> {
         self.player.clone()
     }
@@ -49,7 +57,17 @@ impl EventPlayerLogin {
     }
 }
 
-impl TraitEvent for EventPlayerLogin {}
+impl TraitEvent for EventPlayerLogin {
+    fn get_parents() -> Vec<EventParent<Self>> {
+        vec![EventParent::player()]
+    }
+}
+
+impl PlayerEvent for EventPlayerLogin {
+    fn get_player(&self) -> Weak<Player> {
+        self.get_player()
+    }
+}
 
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
@@ -62,6 +80,17 @@ pub enum PlayerLoginResult {
 }
 
 impl PlayerLoginResult {
+    /// What the player is told when they are kicked, None if they may join
+    pub fn get_message(&self) -> Option<TextComponent<Nbt>> {
+        match self {
+            PlayerLoginResult::Allowed => None,
+            PlayerLoginResult::KickBanned { message } => Some(message.clone().unwrap_or_else(Self::default_ban_message)),
+            PlayerLoginResult::KickFull { message } => Some(message.clone().unwrap_or_else(Self::default_kick_full_message)),
+            PlayerLoginResult::KickOther { message } => Some(message.clone().unwrap_or_else(Self::default_kick_message)),
+            PlayerLoginResult::KickWhitelist { message } => Some(message.clone().unwrap_or_else(Self::default_kick_whitelist_message)),
+        }
+    }
+
     //TODO: translations
     pub fn default_ban_message() -> TextComponent<Nbt> {
         TextComponent::builder().text("The Ban Hammer has spoken!").build()
@@ -0,0 +1,52 @@
+use std::sync::Weak;
+
+use crate::data_types::text_component::{Formatting, Nbt, TextComponent};
+use crate::{event::{EventParent, PlayerEvent, TraitEvent}, player::Player};
+
+/// Fired when a player who joined leaves, whether they quit, lost the connection or were kicked.
+/// 
+/// The player is still online during the event. The quit message is broadcast
+/// to the others afterwards, unless it was set to `None`.
+#[derive(Debug, Clone)]
+pub struct PlayerQuitEvent {
+    player: Weak<Player>,
+    quit_message: Option<TextComponent<Nbt>>,
+}
+
+impl PlayerQuitEvent {
+    pub fn new(player: Weak<Player>, quit_message: Option<TextComponent<Nbt>>) -> Self {
+        Self { player, quit_message }
+    }
+
+    /// The vanilla message, `<name> left the game`
+    pub fn default_quit_message(name: &str) -> TextComponent<Nbt> {
+        TextComponent::builder()
+            .text(&format!("{name} left the game"))
+            .formatting(Formatting::builder().color(0xe).build())
+            .build()
+    }
+
+    pub fn get_player(&self) -> Weak<Player> {
+        self.player.clone()
+    }
+
+    pub fn get_quit_message(&self) -> Option<&TextComponent<Nbt>> {
+        self.quit_message.as_ref()
+    }
+
+    pub fn set_quit_message(&mut self, quit_message: Option<TextComponent<Nbt>>) {
+        self.quit_message = quit_message;
+    }
+}
+
+impl TraitEvent for PlayerQuitEvent {
+    fn get_parents() -> Vec<EventParent<Self>> {
+        vec![EventParent::player()]
+    }
+}
+
+impl PlayerEvent for PlayerQuitEvent {
+    fn get_player(&self) -> Weak<Player> {
+        self.get_player()
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::Weak;
+
+use crate::data_types::Vec3d;
+use crate::{event::{EventParent, PlayerEvent, TraitEvent}, player::Player};
+
+/// Fired when a player clicks respawn on the death screen, before they are respawned
+#[derive(Debug, Clone)]
+pub struct PlayerRespawnEvent {
+    player: Weak<Player>,
+    respawn_position: Vec3d,
+}
+
+impl PlayerRespawnEvent {
+    pub fn new(player: Weak<Player>, respawn_position: Vec3d) -> Self {
+        Self { player, respawn_position }
+    }
+
+    pub fn get_player(&self) -> Weak<Player> {
+        self.player.clone()
+    }
+
+    /// Where in their world the player respawns
+    pub fn get_respawn_position(&self) -> Vec3d {
+        self.respawn_position
+    }
+
+    pub fn set_respawn_position(&mut self, respawn_position: Vec3d) {
+        self.respawn_position = respawn_position;
+    }
+}
+
+impl TraitEvent for PlayerRespawnEvent {
+    fn get_parents() -> Vec<EventParent<Self>> {
+        vec![EventParent::player()]
+    }
+}
+
+impl PlayerEvent for PlayerRespawnEvent {
+    fn get_player(&self) -> Weak<Player> {
+        self.get_player()
+    }
+}
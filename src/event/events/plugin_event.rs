@@ -0,0 +1,53 @@
+use crate::event::{Cancellable, TraitEvent};
+
+/// Used for plugins which emit their own events to be handled by other plugins.
+/// 
+/// Plugins written in Rust can define their own event types instead, this is for plugins
+/// which can't, like scripts. Handlers tell the events apart by their name.
+#[derive(Debug, Clone)]
+pub struct PluginEvent {
+    plugin: String,
+    name: String,
+    data: serde_json::Value,
+    cancelled: bool,
+}
+
+impl PluginEvent {
+    /// `name` should be namespaced by the plugin, like `myplugin:game_start`
+    pub fn new(plugin: &str, name: &str, data: serde_json::Value) -> Self {
+        Self { plugin: plugin.to_string(), name: name.to_string(), data, cancelled: false }
+    }
+
+    /// The id of the plugin which emitted the event
+    pub fn get_plugin(&self) -> &str {
+        &self.plugin
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_data(&self) -> &serde_json::Value {
+        &self.data
+    }
+
+    pub fn get_data_mut(&mut self) -> &mut serde_json::Value {
+        &mut self.data
+    }
+}
+
+impl TraitEvent for PluginEvent {
+    fn as_cancellable(&mut self) -> Option<&mut dyn Cancellable> {
+        Some(self)
+    }
+}
+
+impl Cancellable for PluginEvent {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    fn set_cancelled(&mut self, cancelled: bool) {
+        self.cancelled = cancelled;
+    }
+}
@@ -1,6 +1,6 @@
 pub mod event;
 pub mod events;
 pub mod handler_list;
-pub mod event2;
+pub mod named;
 
 pub use event::*;
\ No newline at end of file
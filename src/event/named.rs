@@ -0,0 +1,113 @@
+use std::sync::{Arc, Weak};
+
+use serde_json::{json, Value};
+
+use crate::data_types::BlockPos;
+use crate::player::Player;
+use crate::THE_SERVER;
+
+use super::event::{
+    AsyncPlayerChatEvent, BlockBreakEvent, BlockDamageEvent, BlockPlaceEvent, CommandEvent, EventHandler,
+    EventPriority, EventResult, HandlerId, PlayerEvent, PlayerInteractEvent, PlayerJoinEvent, PlayerKickEvent,
+    PlayerQuitEvent, PlayerRespawnEvent, PluginEvent, TraitEvent
+};
+
+/// Handles an event registered by name, by WASM plugins and scripts which can't name Rust types.
+/// Receives the event's data as JSON. What monitor handlers return is ignored.
+pub type NamedHandler = Arc<dyn Fn(Value) -> EventResult + Send + Sync>;
+
+/// The names events can be handled by. `player` handles all the events about a player.
+pub const EVENT_NAMES: [&str; 12] = [
+    "player",
+    "player_join",
+    "player_quit",
+    "player_respawn",
+    "player_kick",
+    "player_chat",
+    "player_interact",
+    "block_break",
+    "block_place",
+    "block_damage",
+    "command",
+    "plugin",
+];
+
+/// Returns None if there is no event with the name
+pub fn register_named_handler(owner: &str, event: &str, priority: EventPriority, handler: NamedHandler) -> Option<HandlerId> {
+    let registration = Registration { owner, priority, handler };
+    let id = match event {
+        "player" => registration.register::<dyn PlayerEvent>(describe_player),
+        "player_join" => registration.register::<PlayerJoinEvent>(describe_player),
+        "player_quit" => registration.register::<PlayerQuitEvent>(describe_player),
+        "player_respawn" => registration.register::<PlayerRespawnEvent>(describe_player),
+        "player_kick" => registration.register(|e: &PlayerKickEvent| json!({
+            "player": get_player_name(e.get_player()),
+            "reason": e.get_reason().to_plain_string(),
+        })),
+        "player_chat" => registration.register(|e: &AsyncPlayerChatEvent| json!({
+            "player": get_player_name(e.get_player()),
+            "message": e.get_message(),
+        })),
+        "player_interact" => registration.register(|e: &PlayerInteractEvent| json!({
+            "player": get_player_name(e.get_player()),
+            "action": format!("{:?}", e.get_action()),
+            "block": e.get_block().map(describe_position),
+        })),
+        "block_break" => registration.register(|e: &BlockBreakEvent| json!({
+            "player": get_player_name(e.get_player()),
+            "position": describe_position(e.get_position()),
+            "state": e.get_block_state(),
+        })),
+        "block_place" => registration.register(|e: &BlockPlaceEvent| json!({
+            "player": get_player_name(e.get_player()),
+            "position": describe_position(e.get_position()),
+        })),
+        "block_damage" => registration.register(|e: &BlockDamageEvent| json!({
+            "player": get_player_name(e.get_player()),
+            "position": describe_position(e.get_position()),
+        })),
+        "command" => registration.register(|e: &CommandEvent| json!({
+            "sender": e.get_sender().get_name(),
+            "label": e.get_label(),
+            "args": e.get_args(),
+        })),
+        "plugin" => registration.register(|e: &PluginEvent| json!({
+            "plugin": e.get_plugin(),
+            "name": e.get_name(),
+            "data": e.get_data(),
+        })),
+        _ => return None,
+    };
+    Some(id)
+}
+
+struct Registration<'a> {
+    owner: &'a str,
+    priority: EventPriority,
+    handler: NamedHandler,
+}
+
+impl Registration<'_> {
+    fn register<E: TraitEvent + ?Sized>(self, describe: fn(&E) -> Value) -> HandlerId {
+        let handler = self.handler;
+        let handler = match self.priority {
+            EventPriority::Monitor => EventHandler::monitor(self.owner, move |e: &E| {
+                handler(describe(e));
+            }),
+            priority => EventHandler::new(self.owner, priority, move |e: &mut E| handler(describe(e))),
+        };
+        THE_SERVER.register_event_handler::<E>(handler)
+    }
+}
+
+fn describe_player<E: PlayerEvent + ?Sized>(e: &E) -> Value {
+    json!({ "player": get_player_name(e.get_player()) })
+}
+
+fn describe_position(position: BlockPos) -> Value {
+    json!({ "x": position.x, "y": position.y, "z": position.z })
+}
+
+fn get_player_name(player: Weak<Player>) -> Option<String> {
+    player.upgrade().map(|player| player.get_name().to_string())
+}
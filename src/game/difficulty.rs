@@ -0,0 +1,80 @@
+use std::error::Error;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    Peaceful,
+    Easy,
+    Normal,
+    Hard,
+}
+
+#[derive(Debug)]
+pub struct InvalidDifficultyError {}
+
+impl Error for InvalidDifficultyError {}
+
+impl std::fmt::Display for InvalidDifficultyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Not a valid difficulty.")
+    }
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [Difficulty::Peaceful, Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// The id sent to clients
+    pub fn get_id(&self) -> u8 {
+        *self as u8
+    }
+
+    /// The name used in commands and server.properties
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Difficulty::Peaceful => "peaceful",
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = InvalidDifficultyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" | "peaceful" => Ok(Difficulty::Peaceful),
+            "1" | "easy" => Ok(Difficulty::Easy),
+            "2" | "normal" => Ok(Difficulty::Normal),
+            "3" | "hard" => Ok(Difficulty::Hard),
+            _ => Err(InvalidDifficultyError {}),
+        }
+    }
+}
+
+impl std::fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difficulty::Peaceful => write!(f, "Peaceful"),
+            Difficulty::Easy => write!(f, "Easy"),
+            Difficulty::Normal => write!(f, "Normal"),
+            Difficulty::Hard => write!(f, "Hard"),
+        }
+    }
+}
+
+impl Serialize for Difficulty {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.get_name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Difficulty {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -1,5 +1,9 @@
 use std::error::Error;
+use std::str::FromStr;
 
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Gamemode {
     Survival,
     Creative,
@@ -19,6 +23,8 @@ impl std::fmt::Display for InvalidGamemodeError {
 }
 
 impl Gamemode {
+    pub const ALL: [Gamemode; 4] = [Gamemode::Survival, Gamemode::Creative, Gamemode::Adventure, Gamemode::Spectator];
+
     pub fn from_str(the_str: &str) -> Result<Self, InvalidGamemodeError> {
         match the_str {
             "0" | "s"   | "survival"    | "Survival"    => Ok(Gamemode::Survival),
@@ -28,4 +34,55 @@ impl Gamemode {
             _ => Err(InvalidGamemodeError{})
         }
     }
-}
\ No newline at end of file
+
+    pub fn from_id(id: i32) -> Option<Self> {
+        Self::ALL.get(usize::try_from(id).ok()?).copied()
+    }
+
+    /// The id sent to clients
+    pub fn get_id(&self) -> u8 {
+        *self as u8
+    }
+
+    /// The name used in commands and server.properties
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Gamemode::Survival => "survival",
+            Gamemode::Creative => "creative",
+            Gamemode::Adventure => "adventure",
+            Gamemode::Spectator => "spectator",
+        }
+    }
+}
+
+impl FromStr for Gamemode {
+    type Err = InvalidGamemodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Gamemode::from_str(s)
+    }
+}
+
+impl std::fmt::Display for Gamemode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Gamemode::Survival => write!(f, "Survival Mode"),
+            Gamemode::Creative => write!(f, "Creative Mode"),
+            Gamemode::Adventure => write!(f, "Adventure Mode"),
+            Gamemode::Spectator => write!(f, "Spectator Mode"),
+        }
+    }
+}
+
+impl Serialize for Gamemode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.get_name())
+    }
+}
+
+impl<'de> Deserialize<'de> for Gamemode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
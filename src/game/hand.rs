@@ -0,0 +1,16 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hand {
+    MainHand,
+    OffHand,
+}
+
+impl Hand {
+    /// From the id clients send
+    pub fn from_id(id: i32) -> Option<Self> {
+        match id {
+            0 => Some(Hand::MainHand),
+            1 => Some(Hand::OffHand),
+            _ => None,
+        }
+    }
+}
@@ -1 +1,3 @@
-pub mod gamemode;
\ No newline at end of file
+pub mod gamemode;
+pub mod difficulty;
+pub mod hand;
\ No newline at end of file
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use crate::data_types::Slot;
+use crate::game::hand::Hand;
+
+/// The max stack size of each item, from its default components
+static MAX_STACK_SIZES: LazyLock<HashMap<String, i32>> = LazyLock::new(|| {
+    let items: HashMap<String, serde_json::Value> =
+        serde_json::from_str(include_str!("../generated/reports/items.json")).unwrap();
+    items.into_iter()
+        .map(|(item, value)| {
+            let max_stack_size = value["components"]["minecraft:max_stack_size"].as_i64().unwrap_or(64);
+            (item, max_stack_size as i32)
+        })
+        .collect()
+});
+
+pub fn get_max_stack_size(item: &str) -> i32 {
+    MAX_STACK_SIZES.get(item).copied().unwrap_or(64)
+}
+
+/// The slots of window `0`: crafting, armor, main inventory, hotbar and offhand
+pub const PLAYER_INVENTORY_SIZE: usize = 46;
+
+const HOTBAR_START: usize = 36;
+const HOTBAR_SIZE: usize = 9;
+const OFFHAND_SLOT: usize = 45;
+
+/// Hotbar slots come first when picking up items
+const PICKUP_ORDER: [std::ops::RangeInclusive<usize>; 2] = [36..=44, 9..=35];
+
+/// The items in a player's inventory, as last sent to the client.
+#[derive(Debug)]
+pub struct PlayerInventory {
+    slots: [Slot; PLAYER_INVENTORY_SIZE],
+    state_id: i32,
+    //The hotbar slot the player holds, from 0 to 8
+    selected: usize,
+}
+
+impl PlayerInventory {
+    pub fn new() -> Self {
+        Self { slots: [Slot::empty(); PLAYER_INVENTORY_SIZE], state_id: 0, selected: 0 }
+    }
+
+    pub fn get_slot(&self, slot: usize) -> Option<&Slot> {
+        self.slots.get(slot)
+    }
+
+    pub fn get_selected_slot(&self) -> usize {
+        self.selected
+    }
+
+    /// Returns false if `selected` isn't a hotbar slot
+    pub fn set_selected_slot(&mut self, selected: usize) -> bool {
+        if selected >= HOTBAR_SIZE {
+            return false;
+        }
+        self.selected = selected;
+        true
+    }
+
+    /// The item in the hand
+    pub fn get_held_item(&self, hand: Hand) -> Slot {
+        match hand {
+            Hand::MainHand => self.slots[HOTBAR_START + self.selected],
+            Hand::OffHand => self.slots[OFFHAND_SLOT],
+        }
+    }
+
+    /// The state id to send with the next change, which the client echoes back
+    pub fn next_state_id(&mut self) -> i32 {
+        self.state_id = self.state_id.wrapping_add(1);
+        self.state_id
+    }
+
+    /// Adds `count` of an item like picking it up, first onto stacks of the same
+    /// item, then into empty slots. Returns the slots which changed and how many
+    /// items didn't fit.
+    pub fn add_item(&mut self, item_id: i32, mut count: i32, max_stack_size: i32) -> (Vec<usize>, i32) {
+        let mut changed = Vec::new();
+        let slots = || PICKUP_ORDER.iter().cloned().flatten();
+        for slot in slots() {
+            let stack = &mut self.slots[slot];
+            if count > 0 && !stack.is_empty() && stack.get_item_id() == item_id && stack.get_count() < max_stack_size {
+                let added = count.min(max_stack_size - stack.get_count());
+                *stack = Slot::new(item_id, stack.get_count() + added);
+                count -= added;
+                changed.push(slot);
+            }
+        }
+        for slot in slots() {
+            let stack = &mut self.slots[slot];
+            if count > 0 && stack.is_empty() {
+                let added = count.min(max_stack_size);
+                *stack = Slot::new(item_id, added);
+                count -= added;
+                changed.push(slot);
+            }
+        }
+        (changed, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_item() {
+        let mut inventory = PlayerInventory::new();
+        let (changed, left) = inventory.add_item(1, 70, 64);
+        assert_eq!((changed, left), (vec![36, 37], 0));
+        assert_eq!(inventory.get_slot(37).unwrap().get_count(), 6);
+
+        //Tops up the existing stack before using a new slot
+        let (changed, _) = inventory.add_item(1, 60, 64);
+        assert_eq!(changed, vec![37, 38]);
+
+        let (_, left) = inventory.add_item(2, 64 * 40, 64);
+        assert_eq!(left, 64 * 40 - 64 * 33);
+        assert_eq!(get_max_stack_size("minecraft:diamond_sword"), 1);
+    }
+}
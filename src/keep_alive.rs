@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+/// How often a keep alive is sent to the client.
+pub const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long the client has to answer a keep alive before it is kicked.
+pub const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// At most this many unanswered keep alives are remembered, oldest first.
+const MAX_PENDING: usize = 8;
+
+/// What a player's keep alive needs on this tick
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeepAliveStatus {
+    /// Nothing to send yet
+    Waiting,
+    /// A keep alive with this id should be sent
+    Due(i64),
+    /// The client didn't answer in time
+    TimedOut,
+}
+
+/// Tracks the keep alives sent to a player and the ping derived from the replies.
+///
+/// Replies do not have to arrive in the order the keep alives were sent,
+/// as long as each one is answered before `KEEP_ALIVE_TIMEOUT`.
+#[derive(Debug)]
+pub struct KeepAlive {
+    pending: VecDeque<(i64, Instant)>,
+    last_sent: Option<Instant>,
+    ping: Option<Duration>,
+}
+
+impl KeepAlive {
+    pub fn new() -> Self {
+        Self {
+            pending: VecDeque::with_capacity(MAX_PENDING),
+            last_sent: None,
+            ping: None,
+        }
+    }
+
+    /// Returns a new random keep alive id if one is due. The reply is only waited for
+    /// once `sent` is called, so an id which couldn't be sent is never expected.
+    pub fn next_id(&mut self, now: Instant) -> Option<i64> {
+        if let Some(last_sent) = self.last_sent {
+            if now.duration_since(last_sent) < KEEP_ALIVE_INTERVAL {
+                return None;
+            }
+        }
+        self.last_sent = Some(now);
+        Some(Uuid::new_v4().as_u64_pair().0 as i64)
+    }
+
+    /// Starts waiting for the reply to a keep alive, `sent` being when it was written
+    pub fn sent(&mut self, id: i64, sent: Instant) {
+        if self.pending.len() == MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back((id, sent));
+    }
+
+    /// Checks for a timeout before handing out the next id. A timeout is only reported once.
+    pub fn poll(&mut self, now: Instant) -> KeepAliveStatus {
+        if self.is_timed_out(now) {
+            self.pending.clear();
+            return KeepAliveStatus::TimedOut;
+        }
+        match self.next_id(now) {
+            Some(id) => KeepAliveStatus::Due(id),
+            None => KeepAliveStatus::Waiting,
+        }
+    }
+
+    /// Handles a reply from the client, returning the round trip time
+    /// or `None` if the id was never sent or was already answered.
+    pub fn receive(&mut self, id: i64, now: Instant) -> Option<Duration> {
+        let index = self.pending.iter().position(|(pending_id, _)| *pending_id == id)?;
+        let (_, sent) = self.pending.remove(index)?;
+        let round_trip = now.duration_since(sent);
+        self.ping = Some(match self.ping {
+            //Same smoothing as vanilla
+            Some(ping) => (ping * 3 + round_trip) / 4,
+            None => round_trip,
+        });
+        Some(round_trip)
+    }
+
+    /// Whether the oldest unanswered keep alive has been waiting longer than `KEEP_ALIVE_TIMEOUT`
+    pub fn is_timed_out(&self, now: Instant) -> bool {
+        match self.pending.front() {
+            Some((_, sent)) => now.duration_since(*sent) > KEEP_ALIVE_TIMEOUT,
+            None => false,
+        }
+    }
+
+    /// The smoothed ping in milliseconds, or 0 if the client has not answered yet.
+    pub fn get_ping(&self) -> i32 {
+        match self.ping {
+            Some(ping) => ping.as_millis().min(i32::MAX as u128) as i32,
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn out_of_order_replies() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new();
+        let first = keep_alive.next_id(start).unwrap();
+        keep_alive.sent(first, start);
+        assert!(keep_alive.next_id(start + Duration::from_secs(1)).is_none());
+        let second = keep_alive.next_id(start + KEEP_ALIVE_INTERVAL).unwrap();
+        keep_alive.sent(second, start + KEEP_ALIVE_INTERVAL);
+
+        let later = start + KEEP_ALIVE_INTERVAL + Duration::from_millis(100);
+        assert_eq!(keep_alive.receive(second, later), Some(Duration::from_millis(100)));
+        assert!(keep_alive.receive(second, later).is_none());
+        assert!(keep_alive.receive(first, later).is_some());
+        assert!(!keep_alive.is_timed_out(start + KEEP_ALIVE_TIMEOUT * 2));
+    }
+
+    #[test]
+    fn times_out() {
+        let start = Instant::now();
+        let mut keep_alive = KeepAlive::new();
+        let id = keep_alive.next_id(start).unwrap();
+        //Keep alives which were never sent aren't waited for
+        assert!(!keep_alive.is_timed_out(start + KEEP_ALIVE_TIMEOUT * 2));
+
+        keep_alive.sent(id, start);
+        assert!(!keep_alive.is_timed_out(start + KEEP_ALIVE_TIMEOUT));
+        let later = start + KEEP_ALIVE_TIMEOUT + Duration::from_secs(1);
+        assert_eq!(keep_alive.poll(later), KeepAliveStatus::TimedOut);
+        assert!(!keep_alive.is_timed_out(later));
+    }
+}
@@ -25,11 +25,15 @@ use std::net::SocketAddr;
 use std::time::Duration;
 
 use command::{Command, CommandMap, CommandMapBuilder};
+use command::function::{self as functions, FunctionMap};
 use data_types::registry::{registry, NBTifiedRegistryEntry, RegistryEntry};
 use data_types::tag::TagRegistry;
+use data_types::tags::tags::{self, Mapping};
 use event::events::on_disable::EventOnDisable;
 use event::events::on_enable::EventOnEnable;
 use event::{EventHandler, EventPriority, EventResult};
+use plugins::PluginManager;
+use scripting::ScriptManager;
 use tokio::runtime::Runtime;
 use tokio::net::TcpListener;
 use tokio::sync::{broadcast, mpsc};
@@ -60,10 +64,14 @@ mod console;
 mod entity;
 mod nbt;
 mod item;
+mod inventory;
 mod command;
 mod event;
 mod plugins;
+mod scripting;
 mod block;
+mod keep_alive;
+mod permission;
 
 //const MTU: usize = 1500;
 
@@ -98,6 +106,11 @@ pub static REGISTRIES_JSON: LazyLock<String> = LazyLock::new(|| {
     include_str!("../generated/reports/registries.json").to_owned()
 });
 
+/// The builtin registries from `registries.json`, like `minecraft:block`, by name
+pub static BUILTIN_REGISTRIES: LazyLock<HashMap<String, Mapping>> = LazyLock::new(|| {
+    tags::read_registry_json()
+});
+
 pub type Registry = HashMap<String, HashMap<String, RegistryEntry>>;
 
 pub static SERVER_REGISTRY: LazyLock<Registry> = LazyLock::new(|| {
@@ -124,8 +137,17 @@ pub static REGISTRY_TAGS: LazyLock<Vec<TagRegistry>> = LazyLock::new(|| {
     TAGS.iter().map(|tags| TagRegistry::new(tags)).collect()
 });
 
+#[cfg(not(test))]
 pub static THE_SERVER: LazyLock<Server> = LazyLock::new(|| {
-    Server::new(ServerProperties::load_server_properties().unwrap())
+    Server::new(ServerProperties::load_server_properties().unwrap(), std::path::PathBuf::from("."))
+});
+
+//Tests keep the files the server writes out of the working directory
+#[cfg(test)]
+pub static THE_SERVER: LazyLock<Server> = LazyLock::new(|| {
+    let directory = std::env::temp_dir().join(format!("rustmcsrv-test-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    Server::new(ServerProperties::default(), directory)
 });
 
 pub static RUNTIME: LazyLock<Runtime> = LazyLock::new(|| {
@@ -148,6 +170,18 @@ pub(crate) static STOP_SIGNAL: OnceLock<broadcast::Sender<bool>> =
 pub static COMMAND_MAP: LazyLock<tokio::sync::Mutex<Option<CommandMap>>> = 
     LazyLock::new(|| tokio::sync::Mutex::new(None));
 
+/// The datapack functions, compiled against the commands once those are registered
+pub static FUNCTION_MAP: LazyLock<tokio::sync::Mutex<Option<FunctionMap>>> = 
+    LazyLock::new(|| tokio::sync::Mutex::new(None));
+
+/// The native and WASM plugins, loaded when the server enables and unloaded when it disables
+pub static PLUGIN_MANAGER: LazyLock<tokio::sync::Mutex<Option<PluginManager>>> = 
+    LazyLock::new(|| tokio::sync::Mutex::new(None));
+
+/// The scripts, loaded when the server enables and reloaded when they change
+pub static SCRIPT_MANAGER: LazyLock<tokio::sync::Mutex<Option<ScriptManager>>> = 
+    LazyLock::new(|| tokio::sync::Mutex::new(None));
+
 #[tokio::main]
 async fn main() {
     info!("Hello, World!");
@@ -173,6 +207,10 @@ async fn main() {
     RUNTIME.spawn(connection_listener());
 
     RUNTIME.spawn(chat::chat_thread());
+
+    if THE_SERVER.get_properties().is_online_mode() {
+        RUNTIME.spawn(chat::secure_chat::load_profile_keys());
+    }
     
     RUNTIME.spawn(console(console_rx));
 
@@ -181,16 +219,39 @@ async fn main() {
 }
 
 async fn enable() {
-    let command_map_builder = CommandMapBuilder::new();
-    //TODO: load plugins, load commands from plugins, register events
+    let mut plugin_manager = PluginManager::new(plugins::PLUGINS_DIRECTORY);
+    plugin_manager.load_plugins();
+    plugin_manager.enable_plugins();
+
+    let mut command_map_builder = CommandMapBuilder::new();
+    for command in plugin_manager.get_commands() {
+        if let Err(e) = command_map_builder.register_command(command) {
+            log::error!("Couldn't register a plugin command: {e}");
+        }
+    }
+    *PLUGIN_MANAGER.lock().await = Some(plugin_manager);
+
+    let mut script_manager = ScriptManager::new(scripting::SCRIPTS_DIRECTORY);
+    script_manager.load_scripts();
+    for command in scripting::script::get_commands() {
+        if let Err(e) = command_map_builder.register_command(command) {
+            log::error!("Couldn't register a script command: {e}");
+        }
+    }
+    *SCRIPT_MANAGER.lock().await = Some(script_manager);
     THE_SERVER.get_event_manager().register_event_handler::<EventOnEnable>(
-        EventHandler::new(EventPriority::Normal, test_on_enable)
-    );
-    *COMMAND_MAP.lock().await = Some(command_map_builder.build());
-    event::listen::<EventOnEnable>(
-        THE_SERVER.get_event_manager(), 
-        &mut EventOnEnable::new()
+        EventHandler::new(event::SERVER_OWNER, EventPriority::Normal, test_on_enable)
     );
+    let command_map = command_map_builder.build();
+    let function_map = FunctionMap::load(&functions::get_data_directories(), &command_map);
+    *COMMAND_MAP.lock().await = Some(command_map);
+    *FUNCTION_MAP.lock().await = Some(function_map);
+    //The server enables either way, so a denied event is only worth a warning
+    if event::listen(THE_SERVER.get_event_manager(), &mut EventOnEnable::new()).await == EventResult::Deny {
+        log::warn!("An EventOnEnable handler denied the event, which can't be cancelled");
+    }
+    THE_SERVER.set_running(true);
+    functions::run_tag(functions::LOAD_TAG).await;
 }
 
 async fn scheduler(mut stop: broadcast::Receiver<bool>) {
@@ -217,6 +278,10 @@ async fn scheduler(mut stop: broadcast::Receiver<bool>) {
         }
 
         THE_SERVER.tick_worlds().await;
+        functions::tick().await;
+        scripting::script_manager::tick().await;
+        THE_SERVER.tick_scheduler();
+        THE_SERVER.tick_players().await;
         interval.tick().await;
         //Tick each world thread
     }
@@ -232,12 +297,22 @@ fn test_on_enable(e: &mut EventOnEnable) -> EventResult {
 
 
 async fn disable() {
-    event::listen::<EventOnDisable>(
-        THE_SERVER.get_event_manager(), 
-        &mut EventOnDisable::new()
-    );
+    THE_SERVER.set_running(false);
+    if event::listen(THE_SERVER.get_event_manager(), &mut EventOnDisable::new()).await == EventResult::Deny {
+        log::warn!("An EventOnDisable handler denied the event, which can't be cancelled");
+    }
+
+    if let Some(mut script_manager) = SCRIPT_MANAGER.lock().await.take() {
+        script_manager.unload_scripts();
+    }
+
+    //Unloading the plugins drops their libraries too
+    if let Some(mut plugin_manager) = PLUGIN_MANAGER.lock().await.take() {
+        plugin_manager.disable_plugins();
+    }
 
     *COMMAND_MAP.lock().await = None;
+    *FUNCTION_MAP.lock().await = None;
 
     //TODO: Save Worlds
 }
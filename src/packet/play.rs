@@ -120,6 +120,37 @@ pub struct CBlockUpdate {
     block_id: VarInt,
 }
 
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x0b)]
+/// ## Change Difficulty
+pub struct CChangeDifficulty {
+    difficulty: u8,
+    locked: bool,
+}
+
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x10)]
+/// ## Command Suggestions Response
+/// `start` and `length` are the part of the request text which the matches replace.
+pub struct CCommandSuggestionsResponse {
+    transaction_id: VarInt,
+    start: VarInt,
+    length: VarInt,
+    matches: Vec<Suggestion>,
+}
+
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x11)]
+/// ## Commands
+/// The Brigadier graph of the commands the player can use.
+pub struct CCommands {
+    nodes: Vec<CommandGraphNode>,
+    root_index: VarInt,
+}
+
 #[derive(CPacket, Debug)]
 #[state(Play)]
 #[id(0x12)]
@@ -127,6 +158,19 @@ pub struct CCloseContainer {
     window_id: u8,
 }
 
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x15)]
+/// ## Set Container Slot
+/// Window `0` is the player's inventory, where slots `9` to `35` are the main
+/// inventory and `36` to `44` are the hotbar.
+pub struct CSetContainerSlot {
+    window_id: u8,
+    state_id: VarInt,
+    slot: i16,
+    slot_data: Slot,
+}
+
 #[derive(CPacket, Debug)]
 #[state(Play)]
 #[id(0x1d)]
@@ -136,6 +180,76 @@ pub struct CDisconnect_Play {
     reason: TextComponent<Nbt>,
 }
 
+#[derive(Debug)]
+/// ## Disguised Chat Message
+/// Chat which is not signed, shown with the decoration of `chat_type`.
+pub struct CDisguisedChatMessage {
+    message: TextComponent<Nbt>,
+    chat_type: i32,
+    sender_name: TextComponent<Nbt>,
+    target_name: Option<TextComponent<Nbt>>,
+}
+
+const CDISGUISEDCHATMESSAGE_ID: i32 = 0x1e;
+
+impl Packet for CDisguisedChatMessage {
+    fn get_id(&self) -> i32 where Self: Sized {
+        CDISGUISEDCHATMESSAGE_ID
+    }
+
+    fn get_associated_state(&self) -> ConnectionState {
+        ConnectionState::Play
+    }
+}
+
+impl Clientbound for CDisguisedChatMessage {
+    fn to_be_bytes(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = self.message.to_protocol_bytes();
+        //Registry id + 1, 0 would be an inline chat type
+        data.append(&mut VarInt::new(self.chat_type + 1).to_protocol_bytes());
+        data.append(&mut self.sender_name.to_protocol_bytes());
+        match &self.target_name {
+            Some(target_name) => {
+                data.push(1u8);
+                data.append(&mut target_name.to_protocol_bytes());
+            },
+            None => data.push(0u8),
+        }
+
+        let mut out: Vec<u8> = VarInt::new(data.len() as i32 + 1).to_protocol_bytes();
+        out.push(CDISGUISEDCHATMESSAGE_ID as u8);
+        out.append(&mut data);
+        out
+    }
+}
+
+impl CDisguisedChatMessage {
+    /// `chat_type` is the network id of the chat type, see [`chat::get_chat_type_id`](crate::chat::get_chat_type_id)
+    pub fn new(
+        message: TextComponent<Nbt>,
+        chat_type: i32,
+        sender_name: TextComponent<Nbt>,
+        target_name: Option<TextComponent<Nbt>>
+    ) -> Self {
+        Self { message, chat_type, sender_name, target_name }
+    }
+}
+
+/// ## Game Event
+/// Events used by commands:
+/// `1` - End raining
+/// `2` - Begin raining
+/// `3` - Change game mode, `value` is the game mode
+/// `7` - Rain level change, `value` is from `0` to `1`
+/// `8` - Thunder level change, `value` is from `0` to `1`
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x22)]
+pub struct CGameEvent {
+    event: u8,
+    value: f32,
+}
+
 #[derive(CPacket, Debug)]
 #[state(Play)]
 #[id(0x25)]
@@ -187,6 +301,42 @@ pub struct CLogin_Play {
     //and many more
 }
 
+/// Deltas are `current * 4096 - previous * 4096`, so an entity can move
+/// at most 8 blocks per packet. Larger moves must use `CTeleportEntity`.
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x2e)]
+pub struct CUpdateEntityPosition {
+    entity_id: VarInt,
+    delta_x: i16,
+    delta_y: i16,
+    delta_z: i16,
+    on_ground: bool,
+}
+
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x2f)]
+pub struct CUpdateEntityPositionAndRotation {
+    entity_id: VarInt,
+    delta_x: i16,
+    delta_y: i16,
+    delta_z: i16,
+    yaw: Angle,
+    pitch: Angle,
+    on_ground: bool,
+}
+
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x30)]
+pub struct CUpdateEntityRotation {
+    entity_id: VarInt,
+    yaw: Angle,
+    pitch: Angle,
+    on_ground: bool,
+}
+
 #[derive(CPacket, Debug)]
 #[state(Play)]
 #[id(0x38)]
@@ -201,6 +351,104 @@ pub struct CPlayerAbilities {
     fov_modifier: f32,
 }
 
+#[derive(Debug)]
+/// ## Player Chat Message
+/// Chat sent by a player, which the client verifies if it is signed.
+pub struct CPlayerChatMessage {
+    sender: Uuid,
+    index: i32,
+    signature: Option<MessageSignature>,
+    message: String,
+    timestamp: i64,
+    salt: i64,
+    previous_messages: Vec<PreviousMessage>,
+    unsigned_content: Option<TextComponent<Nbt>>,
+    chat_type: i32,
+    sender_name: TextComponent<Nbt>,
+    target_name: Option<TextComponent<Nbt>>,
+}
+
+const CPLAYERCHATMESSAGE_ID: i32 = 0x39;
+
+impl Packet for CPlayerChatMessage {
+    fn get_id(&self) -> i32 where Self: Sized {
+        CPLAYERCHATMESSAGE_ID
+    }
+
+    fn get_associated_state(&self) -> ConnectionState {
+        ConnectionState::Play
+    }
+}
+
+impl Clientbound for CPlayerChatMessage {
+    fn to_be_bytes(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = self.sender.to_protocol_bytes();
+        data.append(&mut VarInt::new(self.index).to_protocol_bytes());
+        data.append(&mut create_option(self.signature.clone()));
+        data.append(&mut self.message.to_protocol_bytes());
+        data.append(&mut self.timestamp.to_protocol_bytes());
+        data.append(&mut self.salt.to_protocol_bytes());
+        data.append(&mut VarInt::new(self.previous_messages.len() as i32).to_protocol_bytes());
+        self.previous_messages.iter()
+            .for_each(|previous| data.append(&mut previous.to_protocol_bytes()));
+        match &self.unsigned_content {
+            Some(unsigned_content) => {
+                data.push(1u8);
+                data.append(&mut unsigned_content.to_protocol_bytes());
+            },
+            None => data.push(0u8),
+        }
+        data.append(&mut VarInt::new(0).to_protocol_bytes()); //Filter type: pass through
+        //Registry id + 1, 0 would be an inline chat type
+        data.append(&mut VarInt::new(self.chat_type + 1).to_protocol_bytes());
+        data.append(&mut self.sender_name.to_protocol_bytes());
+        match &self.target_name {
+            Some(target_name) => {
+                data.push(1u8);
+                data.append(&mut target_name.to_protocol_bytes());
+            },
+            None => data.push(0u8),
+        }
+
+        let mut out: Vec<u8> = VarInt::new(data.len() as i32 + 1).to_protocol_bytes();
+        out.push(CPLAYERCHATMESSAGE_ID as u8);
+        out.append(&mut data);
+        out
+    }
+}
+
+impl CPlayerChatMessage {
+    /// `index` counts the messages sent by `sender` to this client, starting at 0.\
+    /// `chat_type` is the network id of the chat type, see [`chat::get_chat_type_id`](crate::chat::get_chat_type_id)
+    pub fn new(
+        sender: Uuid,
+        index: i32,
+        signature: Option<MessageSignature>,
+        message: String,
+        timestamp: i64,
+        salt: i64,
+        previous_messages: Vec<PreviousMessage>,
+        unsigned_content: Option<TextComponent<Nbt>>,
+        chat_type: i32,
+        sender_name: TextComponent<Nbt>,
+        target_name: Option<TextComponent<Nbt>>,
+    ) -> Self {
+        Self {
+            sender,
+            index,
+            signature,
+            message,
+            timestamp,
+            salt,
+            previous_messages,
+            unsigned_content,
+            chat_type,
+            sender_name,
+            target_name,
+        }
+    }
+}
+
 /// Flags (If the value of the byte is masked, it's a relative offset, otherwise it's absolute):
 /// `0x01` - X
 /// `0x02` - Y
@@ -220,6 +468,120 @@ pub struct CSynchronizePlayerPosition {
     teleport_id: VarInt,
 }
 
+#[derive(Debug)]
+/// ## Player Info Remove
+/// Removes players from the tab list. Entities belonging to these players
+/// should be removed from the client with `CRemoveEntities` first.
+pub struct CPlayerInfoRemove {
+    uuids: Vec<Uuid>,
+}
+
+const CPLAYERINFOREMOVE_ID: i32 = 0x3d;
+
+impl Packet for CPlayerInfoRemove {
+    fn get_id(&self) -> i32 where Self: Sized {
+        CPLAYERINFOREMOVE_ID
+    }
+
+    fn get_associated_state(&self) -> ConnectionState {
+        ConnectionState::Play
+    }
+}
+
+impl Clientbound for CPlayerInfoRemove {
+    fn to_be_bytes(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = VarInt::new(self.uuids.len() as i32).to_protocol_bytes();
+        self.uuids.iter().for_each(|uuid| data.append(&mut uuid.to_protocol_bytes()));
+
+        let mut out: Vec<u8> = VarInt::new(data.len() as i32 + 1).to_protocol_bytes();
+        out.push(CPLAYERINFOREMOVE_ID as u8);
+        out.append(&mut data);
+        out
+    }
+}
+
+impl CPlayerInfoRemove {
+    pub fn new(uuids: Vec<Uuid>) -> Self {
+        Self { uuids }
+    }
+}
+
+#[derive(Debug)]
+/// ## Player Info Update
+/// Every entry must contain data for every action in `actions`.\
+/// See [`PlayerInfoActions`] for the action flags.
+pub struct CPlayerInfoUpdate {
+    actions: PlayerInfoActions,
+    entries: Vec<PlayerInfoEntry>,
+}
+
+const CPLAYERINFOUPDATE_ID: i32 = 0x3e;
+
+impl Packet for CPlayerInfoUpdate {
+    fn get_id(&self) -> i32 where Self: Sized {
+        CPLAYERINFOUPDATE_ID
+    }
+
+    fn get_associated_state(&self) -> ConnectionState {
+        ConnectionState::Play
+    }
+}
+
+impl Clientbound for CPlayerInfoUpdate {
+    fn to_be_bytes(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = vec![self.actions.bits()];
+        data.append(&mut VarInt::new(self.entries.len() as i32).to_protocol_bytes());
+        self.entries.iter().for_each(|entry| {
+            data.append(&mut entry.to_protocol_bytes_for(self.actions))
+        });
+
+        let mut out: Vec<u8> = VarInt::new(data.len() as i32 + 1).to_protocol_bytes();
+        out.push(CPLAYERINFOUPDATE_ID as u8);
+        out.append(&mut data);
+        out
+    }
+}
+
+impl CPlayerInfoUpdate {
+    pub fn new(actions: PlayerInfoActions, entries: Vec<PlayerInfoEntry>) -> Self {
+        Self { actions, entries }
+    }
+}
+
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x42)]
+pub struct CRemoveEntities {
+    entity_ids: Vec<VarInt>,
+}
+
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x47)]
+/// ## Respawn
+/// Moves the player to another dimension, or respawns them after they died.
+/// `data_kept` is a bit mask of `0x01` to keep attributes and `0x02` to keep metadata.
+pub struct CRespawn {
+    dimension_type: VarInt,
+    dimension_name: Identifier,
+    hashed_seed: i64,
+    game_mode: u8,
+    previous_game_mode: i8,
+    is_debug: bool,
+    is_flat: bool,
+    death_location: Option<DeathLocation>,
+    portal_cooldown: VarInt,
+    data_kept: u8,
+}
+
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x48)]
+pub struct CSetHeadRotation {
+    entity_id: VarInt,
+    head_yaw: Angle,
+}
+
 #[derive(CPacket, Debug)]
 #[state(Play)]
 #[id(0x4d)]
@@ -265,6 +627,28 @@ pub struct CSetHeldItem {
     slot: u8,
 }
 
+/// Velocity is in units of 1/8000 of a block per tick.
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x5a)]
+pub struct CSetEntityVelocity {
+    entity_id: VarInt,
+    velocity_x: i16,
+    velocity_y: i16,
+    velocity_z: i16,
+}
+
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x5d)]
+/// ## Set Health
+/// A health of `0` or less shows the death screen.
+pub struct CSetHealth {
+    health: f32,
+    food: VarInt,
+    food_saturation: f32,
+}
+
 #[derive(CPacket, Debug)]
 #[state(Play)]
 #[id(0x64)]
@@ -282,6 +666,96 @@ pub struct CSystemChatMessage {
 }
 
 
+#[derive(CPacket, Debug)]
+#[state(Play)]
+#[id(0x70)]
+pub struct CTeleportEntity {
+    entity_id: VarInt,
+    x: f64,
+    y: f64,
+    z: f64,
+    yaw: Angle,
+    pitch: Angle,
+    on_ground: bool,
+}
+
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x03)]
+/// ## Acknowledge Message
+/// Sent when the client has seen too many messages without sending one itself.
+pub struct SAcknowledgeMessage {
+    message_count: VarInt,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x04)]
+/// ## Chat Command
+/// A command typed in chat without the leading `/`, which has no signed arguments.
+pub struct SChatCommand {
+    command: String,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x05)]
+/// ## Signed Chat Command
+/// A command with arguments the player signed, such as the message of `/msg`.
+pub struct SSignedChatCommand {
+    command: String,
+    timestamp: i64,
+    salt: i64,
+    argument_signatures: ArgumentSignatures,
+    message_count: VarInt,
+    acknowledged: AcknowledgedMessages,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x06)]
+/// ## Chat Message
+pub struct SChatMessage {
+    message: String,
+    timestamp: i64,
+    salt: i64,
+    signature: Option<MessageSignature>,
+    message_count: VarInt,
+    acknowledged: AcknowledgedMessages,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x07)]
+/// ## Player Session
+/// The player's chat session, which is needed to verify their messages.
+pub struct SPlayerSession {
+    session_id: Uuid,
+    expires_at: i64,
+    public_key: PrefixedByteArray,
+    key_signature: PrefixedByteArray,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x09)]
+/// ## Client Status
+/// `action_id` is `0` to respawn after dying and `1` to request the statistics.
+pub struct SClientStatus {
+    action_id: VarInt,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x0b)]
+/// ## Command Suggestions Request
+/// Sent while typing a command, `text` includes the leading `/`.
+pub struct SCommandSuggestionsRequest {
+    transaction_id: VarInt,
+    text: String,
+}
+
 #[derive(SPacket, Debug)]
 #[state(Play)]
 #[id(0x18)]
@@ -290,3 +764,98 @@ pub struct SKeepAlive_Play {
     keep_alive_id: i64,
 }
 
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x00)]
+pub struct SConfirmTeleportation {
+    teleport_id: VarInt,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x24)]
+/// ## Player Action
+/// `status` is `0` when the player starts digging, `1` when they stop and `2` when they
+/// finished, or one of the actions to drop or swap items. Digging is acknowledged with `sequence`.
+pub struct SPlayerAction {
+    status: VarInt,
+    location: BlockPos,
+    face: i8,
+    sequence: VarInt,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x2f)]
+/// ## Set Held Item
+/// The hotbar slot the player selected, from 0 to 8.
+pub struct SSetHeldItem {
+    slot: i16,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x38)]
+/// ## Use Item On
+/// Sent when the player right clicks a block, which places it if they hold one.
+pub struct SUseItemOn {
+    hand: VarInt,
+    location: BlockPos,
+    face: VarInt,
+    cursor_x: f32,
+    cursor_y: f32,
+    cursor_z: f32,
+    inside_block: bool,
+    sequence: VarInt,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x39)]
+/// ## Use Item
+/// Sent when the player right clicks without looking at a block.
+pub struct SUseItem {
+    hand: VarInt,
+    sequence: VarInt,
+    yaw: f32,
+    pitch: f32,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x1a)]
+pub struct SSetPlayerPosition {
+    x: f64,
+    feet_y: f64,
+    z: f64,
+    on_ground: bool,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x1b)]
+pub struct SSetPlayerPositionAndRotation {
+    x: f64,
+    feet_y: f64,
+    z: f64,
+    yaw: f32,
+    pitch: f32,
+    on_ground: bool,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x1c)]
+pub struct SSetPlayerRotation {
+    yaw: f32,
+    pitch: f32,
+    on_ground: bool,
+}
+
+#[derive(SPacket, Debug)]
+#[state(Play)]
+#[id(0x1d)]
+pub struct SSetPlayerOnGround {
+    on_ground: bool,
+}
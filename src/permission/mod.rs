@@ -0,0 +1,5 @@
+pub mod permission;
+pub mod provider;
+
+pub use permission::*;
+pub use provider::*;
@@ -0,0 +1,419 @@
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fmt::Display;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The group everyone is in
+pub const DEFAULT_GROUP: &str = "default";
+
+/// The commands vanilla lets everyone use, which the default group gets when the permissions are created
+pub const DEFAULT_PERMISSIONS: [&str; 5] = [
+    "minecraft.command.help",
+    "minecraft.command.list",
+    "minecraft.command.me",
+    "minecraft.command.msg",
+    "minecraft.command.trigger",
+];
+
+#[derive(Debug)]
+pub enum PermissionError {
+    InvalidNode(String),
+    UnknownGroup(String),
+    GroupExists(String),
+    /// Making a group its own parent, directly or through other groups
+    Cycle(String),
+    /// The permission provider can't change permissions
+    Unsupported,
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl Error for PermissionError {}
+
+impl Display for PermissionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionError::InvalidNode(node) => write!(f, "Invalid permission '{node}'"),
+            PermissionError::UnknownGroup(group) => write!(f, "Unknown group '{group}'"),
+            PermissionError::GroupExists(group) => write!(f, "The group '{group}' already exists"),
+            PermissionError::Cycle(group) => write!(f, "The group '{group}' would inherit from itself"),
+            PermissionError::Unsupported => write!(f, "The permission provider can't change permissions"),
+            PermissionError::Io(e) => write!(f, "{e}"),
+            PermissionError::Json(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for PermissionError {
+    fn from(e: std::io::Error) -> Self {
+        PermissionError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for PermissionError {
+    fn from(e: serde_json::Error) -> Self {
+        PermissionError::Json(e)
+    }
+}
+
+/// Whose permissions are changed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PermissionHolderId {
+    User(Uuid),
+    Group(String),
+}
+
+impl Display for PermissionHolderId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionHolderId::User(uuid) => write!(f, "{uuid}"),
+            PermissionHolderId::Group(group) => write!(f, "group {group}"),
+        }
+    }
+}
+
+/// Checks that `node` is like `foo.bar`, `foo.*` or `*`, optionally negated with `-`
+pub fn validate_node(node: &str) -> Result<(), PermissionError> {
+    let permission = node.strip_prefix('-').unwrap_or(node);
+    let valid = permission.split('.').enumerate().all(|(index, segment)| {
+        let last = index == permission.split('.').count() - 1;
+        !segment.is_empty()
+            && (segment == "*" && last || segment.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-')))
+    });
+    match valid {
+        true => Ok(()),
+        false => Err(PermissionError::InvalidNode(node.to_string())),
+    }
+}
+
+/// The value of the most specific of `nodes` which matches `permission`, if any.
+/// Exact nodes beat wildcards, longer wildcards beat shorter ones, and negated
+/// nodes beat others which are as specific.
+pub fn find_node<'a>(nodes: impl IntoIterator<Item = &'a String>, permission: &str) -> Option<bool> {
+    nodes.into_iter()
+        .filter_map(|node| {
+            let (value, node) = match node.strip_prefix('-') {
+                Some(node) => (false, node),
+                None => (true, node.as_str()),
+            };
+            let specificity = match node.strip_suffix('*') {
+                _ if node == permission => usize::MAX,
+                Some(prefix) if permission.starts_with(prefix) => prefix.len() + 1,
+                _ => return None,
+            };
+            Some((specificity, !value, value))
+        })
+        .max_by_key(|(specificity, negated, _)| (*specificity, *negated))
+        .map(|(_, _, value)| value)
+}
+
+/// The permissions of a user or group, which it inherits from its parent groups
+/// unless they are set here
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PermissionHolder {
+    /// The last name of a user, to find them while they are offline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    permissions: Vec<String>,
+    /// Permissions which only apply in a world, by the level name of the world
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    worlds: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    parents: Vec<String>,
+}
+
+impl PermissionHolder {
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn get_permissions(&self) -> &[String] {
+        &self.permissions
+    }
+
+    pub fn get_world_permissions(&self) -> &BTreeMap<String, Vec<String>> {
+        &self.worlds
+    }
+
+    pub fn get_parents(&self) -> &[String] {
+        &self.parents
+    }
+
+    fn get_nodes_mut(&mut self, world: Option<&str>) -> &mut Vec<String> {
+        match world {
+            Some(world) => self.worlds.entry(world.to_string()).or_default(),
+            None => &mut self.permissions,
+        }
+    }
+
+    /// The permissions of the world win over the others
+    fn find(&self, world: Option<&str>, permission: &str) -> Option<bool> {
+        world.and_then(|world| self.worlds.get(world))
+            .and_then(|nodes| find_node(nodes, permission))
+            .or_else(|| find_node(&self.permissions, permission))
+    }
+}
+
+/// The groups and users as they are saved
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PermissionData {
+    #[serde(default)]
+    groups: BTreeMap<String, PermissionHolder>,
+    #[serde(default)]
+    users: BTreeMap<Uuid, PermissionHolder>,
+}
+
+impl PermissionData {
+    /// Reads the data from a JSON file, or creates it with a default group which has the `DEFAULT_PERMISSIONS`
+    pub fn load(path: &Path) -> Result<Self, PermissionError> {
+        if !path.exists() {
+            let mut data = Self::default();
+            data.create_group(DEFAULT_GROUP)?;
+            let default = PermissionHolderId::Group(DEFAULT_GROUP.to_string());
+            for node in DEFAULT_PERMISSIONS {
+                data.add_permission(&default, node, None)?;
+            }
+            data.save(path)?;
+            return Ok(data);
+        }
+        Ok(serde_json::from_reader(std::fs::File::open(path)?)?)
+    }
+
+    /// Writes to a file next to `path` first, so the data is never half written
+    pub fn save(&self, path: &Path) -> Result<(), PermissionError> {
+        let temporary = path.with_extension("json.tmp");
+        std::fs::write(&temporary, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
+    /// Whether the user has `permission`, from their own permissions, then their groups
+    /// in order, then the default group. Nothing matching means they don't.
+    pub fn has_permission(&self, user: Uuid, world: Option<&str>, permission: &str) -> bool {
+        let mut visited = HashSet::new();
+        self.users.get(&user)
+            .and_then(|holder| self.find(holder, world, permission, &mut visited))
+            .or_else(|| {
+                let group = self.groups.get(DEFAULT_GROUP)?;
+                visited.insert(DEFAULT_GROUP.to_string()).then_some(())?;
+                self.find(group, world, permission, &mut visited)
+            })
+            .unwrap_or(false)
+    }
+
+    fn find(
+        &self,
+        holder: &PermissionHolder,
+        world: Option<&str>,
+        permission: &str,
+        visited: &mut HashSet<String>,
+    ) -> Option<bool> {
+        holder.find(world, permission).or_else(|| {
+            holder.parents.iter().find_map(|parent| {
+                let group = self.groups.get(parent)?;
+                visited.insert(parent.clone()).then_some(())?;
+                self.find(group, world, permission, visited)
+            })
+        })
+    }
+
+    pub fn get_groups(&self) -> impl Iterator<Item = &String> {
+        self.groups.keys()
+    }
+
+    pub fn get_holder(&self, id: &PermissionHolderId) -> Option<&PermissionHolder> {
+        match id {
+            PermissionHolderId::User(uuid) => self.users.get(uuid),
+            PermissionHolderId::Group(group) => self.groups.get(group),
+        }
+    }
+
+    /// Finds a user by the name they had when their permissions last changed
+    pub fn find_user(&self, name: &str) -> Option<Uuid> {
+        self.users.iter()
+            .find(|(_, holder)| holder.name.as_deref().is_some_and(|n| n.eq_ignore_ascii_case(name)))
+            .map(|(uuid, _)| *uuid)
+    }
+
+    /// Users are created when they are first changed
+    fn get_holder_mut(&mut self, id: &PermissionHolderId) -> Result<&mut PermissionHolder, PermissionError> {
+        match id {
+            PermissionHolderId::User(uuid) => Ok(self.users.entry(*uuid).or_default()),
+            PermissionHolderId::Group(group) => self.groups.get_mut(group)
+                .ok_or_else(|| PermissionError::UnknownGroup(group.clone())),
+        }
+    }
+
+    /// Remembers the name of a user, returns whether it changed
+    pub fn set_user_name(&mut self, user: Uuid, name: &str) -> bool {
+        let holder = self.users.entry(user).or_default();
+        let changed = holder.name.as_deref() != Some(name);
+        holder.name = Some(name.to_string());
+        changed
+    }
+
+    /// Sets `node`, which replaces the same node or its negation. Returns whether anything changed.
+    pub fn add_permission(
+        &mut self,
+        id: &PermissionHolderId,
+        node: &str,
+        world: Option<&str>
+    ) -> Result<bool, PermissionError> {
+        validate_node(node)?;
+        let nodes = self.get_holder_mut(id)?.get_nodes_mut(world);
+        if nodes.iter().any(|n| n == node) {
+            return Ok(false);
+        }
+        let permission = node.strip_prefix('-').unwrap_or(node);
+        nodes.retain(|n| n.strip_prefix('-').unwrap_or(n) != permission);
+        nodes.push(node.to_string());
+        Ok(true)
+    }
+
+    /// Unsets `node` whether it is negated or not. Returns whether it was set.
+    pub fn remove_permission(
+        &mut self,
+        id: &PermissionHolderId,
+        node: &str,
+        world: Option<&str>
+    ) -> Result<bool, PermissionError> {
+        let holder = self.get_holder_mut(id)?;
+        let permission = node.strip_prefix('-').unwrap_or(node);
+        let nodes = holder.get_nodes_mut(world);
+        let before = nodes.len();
+        nodes.retain(|n| n.strip_prefix('-').unwrap_or(n) != permission);
+        let removed = nodes.len() != before;
+        holder.worlds.retain(|_, nodes| !nodes.is_empty());
+        Ok(removed)
+    }
+
+    /// Makes `id` inherit from `group`. Returns whether it didn't already.
+    pub fn add_parent(&mut self, id: &PermissionHolderId, group: &str) -> Result<bool, PermissionError> {
+        if !self.groups.contains_key(group) {
+            return Err(PermissionError::UnknownGroup(group.to_string()));
+        }
+        if let PermissionHolderId::Group(name) = id {
+            if name == group || self.inherits(group, name) {
+                return Err(PermissionError::Cycle(name.clone()));
+            }
+        }
+        let holder = self.get_holder_mut(id)?;
+        if holder.parents.iter().any(|parent| parent == group) {
+            return Ok(false);
+        }
+        holder.parents.push(group.to_string());
+        Ok(true)
+    }
+
+    /// Returns whether `id` inherited from `group`
+    pub fn remove_parent(&mut self, id: &PermissionHolderId, group: &str) -> Result<bool, PermissionError> {
+        let holder = self.get_holder_mut(id)?;
+        let before = holder.parents.len();
+        holder.parents.retain(|parent| parent != group);
+        Ok(holder.parents.len() != before)
+    }
+
+    /// Whether `group` inherits from `ancestor`
+    fn inherits(&self, group: &str, ancestor: &str) -> bool {
+        let mut stack = vec![group];
+        let mut visited = HashSet::new();
+        while let Some(group) = stack.pop() {
+            if group == ancestor {
+                return true;
+            }
+            if visited.insert(group) {
+                stack.extend(self.groups.get(group).into_iter().flat_map(|g| g.parents.iter().map(String::as_str)));
+            }
+        }
+        false
+    }
+
+    pub fn create_group(&mut self, group: &str) -> Result<(), PermissionError> {
+        if self.groups.contains_key(group) {
+            return Err(PermissionError::GroupExists(group.to_string()));
+        }
+        self.groups.insert(group.to_string(), PermissionHolder::default());
+        Ok(())
+    }
+
+    /// Deletes the group and removes it from the parents of everyone else
+    pub fn delete_group(&mut self, group: &str) -> Result<(), PermissionError> {
+        if self.groups.remove(group).is_none() {
+            return Err(PermissionError::UnknownGroup(group.to_string()));
+        }
+        for holder in self.groups.values_mut().chain(self.users.values_mut()) {
+            holder.parents.retain(|parent| parent != group);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nodes() {
+        let nodes: Vec<String> = ["minecraft.command.*", "-minecraft.command.stop", "*"]
+            .iter().map(|node| node.to_string()).collect();
+        assert_eq!(find_node(&nodes, "minecraft.command.tp"), Some(true));
+        assert_eq!(find_node(&nodes, "minecraft.command.stop"), Some(false));
+        assert_eq!(find_node(&nodes, "other.thing"), Some(true));
+        assert_eq!(find_node(&nodes[..2], "other.thing"), None);
+
+        assert!(validate_node("-foo.bar_baz.*").is_ok());
+        assert!(validate_node("foo.*.bar").is_err());
+        assert!(validate_node("foo..bar").is_err());
+    }
+
+    #[test]
+    fn inheritance() {
+        let mut data = PermissionData::default();
+        let steve = Uuid::new_v4();
+        let user = PermissionHolderId::User(steve);
+        let default = PermissionHolderId::Group(DEFAULT_GROUP.to_string());
+        let admin = PermissionHolderId::Group("admin".to_string());
+        data.create_group(DEFAULT_GROUP).unwrap();
+        data.create_group("moderator").unwrap();
+        data.create_group("admin").unwrap();
+        data.add_permission(&default, "minecraft.command.help", None).unwrap();
+        data.add_permission(&PermissionHolderId::Group("moderator".to_string()), "minecraft.command.kick", None).unwrap();
+        data.add_permission(&admin, "minecraft.command.*", None).unwrap();
+        data.add_permission(&admin, "-minecraft.command.stop", Some("lobby")).unwrap();
+        data.add_parent(&admin, "moderator").unwrap();
+        assert!(matches!(
+            data.add_parent(&PermissionHolderId::Group("moderator".to_string()), "admin"),
+            Err(PermissionError::Cycle(_))
+        ));
+
+        assert!(data.has_permission(steve, None, "minecraft.command.help"));
+        assert!(!data.has_permission(steve, None, "minecraft.command.kick"));
+
+        data.add_parent(&user, "admin").unwrap();
+        assert!(data.has_permission(steve, None, "minecraft.command.stop"));
+        assert!(!data.has_permission(steve, Some("lobby"), "minecraft.command.stop"));
+
+        data.add_permission(&user, "-minecraft.command.kick", None).unwrap();
+        assert!(!data.has_permission(steve, None, "minecraft.command.kick"));
+        data.add_permission(&user, "minecraft.command.kick", None).unwrap();
+        assert_eq!(data.get_holder(&user).unwrap().get_permissions(), ["minecraft.command.kick"]);
+
+        data.delete_group("admin").unwrap();
+        assert!(!data.has_permission(steve, None, "minecraft.command.stop"));
+    }
+    #[test]
+    fn default_permissions() {
+        let path = std::env::temp_dir().join(format!("permissions-{}.json", Uuid::new_v4()));
+        let data = PermissionData::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        //Anyone who isn't an op may run /help, but not /stop
+        let steve = Uuid::new_v4();
+        assert!(data.has_permission(steve, None, "minecraft.command.help"));
+        assert!(data.has_permission(steve, Some("world"), "minecraft.command.msg"));
+        assert!(!data.has_permission(steve, None, "minecraft.command.stop"));
+    }
+}
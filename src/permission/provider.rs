@@ -0,0 +1,147 @@
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use uuid::Uuid;
+
+use super::permission::{PermissionData, PermissionError, PermissionHolder, PermissionHolderId};
+
+/// Where the server keeps permissions by default
+pub const PERMISSIONS_FILE: &str = "permissions.json";
+
+/// Answers permission checks. Plugins can replace the server's provider with
+/// `Server::set_permission_provider`. Providers which can't be changed from
+/// the server only need `has_permission`.
+pub trait PermissionProvider: Send + Sync {
+    /// Whether the user has `permission` in `world`, the level name of the world they are in
+    fn has_permission(&self, user: Uuid, world: Option<&str>, permission: &str) -> bool;
+
+    /// Called when a player joins, so the user can be found by name
+    fn set_user_name(&self, _user: Uuid, _name: &str) {}
+
+    fn get_groups(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn get_holder(&self, _id: &PermissionHolderId) -> Option<PermissionHolder> {
+        None
+    }
+
+    /// Finds a user who isn't online by name
+    fn find_user(&self, _name: &str) -> Option<Uuid> {
+        None
+    }
+
+    /// Returns whether anything changed
+    fn add_permission(&self, _id: &PermissionHolderId, _node: &str, _world: Option<&str>) -> Result<bool, PermissionError> {
+        Err(PermissionError::Unsupported)
+    }
+
+    /// Returns whether anything changed
+    fn remove_permission(&self, _id: &PermissionHolderId, _node: &str, _world: Option<&str>) -> Result<bool, PermissionError> {
+        Err(PermissionError::Unsupported)
+    }
+
+    /// Returns whether anything changed
+    fn add_parent(&self, _id: &PermissionHolderId, _group: &str) -> Result<bool, PermissionError> {
+        Err(PermissionError::Unsupported)
+    }
+
+    /// Returns whether anything changed
+    fn remove_parent(&self, _id: &PermissionHolderId, _group: &str) -> Result<bool, PermissionError> {
+        Err(PermissionError::Unsupported)
+    }
+
+    fn create_group(&self, _group: &str) -> Result<(), PermissionError> {
+        Err(PermissionError::Unsupported)
+    }
+
+    fn delete_group(&self, _group: &str) -> Result<(), PermissionError> {
+        Err(PermissionError::Unsupported)
+    }
+
+    /// Reads the permissions again from wherever they are kept
+    fn reload(&self) -> Result<(), PermissionError> {
+        Ok(())
+    }
+}
+
+/// Keeps permissions in a JSON file, which is saved after every change
+pub struct FilePermissionProvider {
+    path: PathBuf,
+    data: RwLock<PermissionData>,
+}
+
+impl FilePermissionProvider {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, PermissionError> {
+        let path = path.into();
+        let data = PermissionData::load(&path)?;
+        Ok(Self { path, data: RwLock::new(data) })
+    }
+
+    /// Changes the data and saves it if `change` says it changed something
+    fn change<T>(
+        &self,
+        change: impl FnOnce(&mut PermissionData) -> Result<T, PermissionError>,
+        changed: impl Fn(&T) -> bool,
+    ) -> Result<T, PermissionError> {
+        let mut data = self.data.write().unwrap();
+        let result = change(&mut data)?;
+        if changed(&result) {
+            data.save(&self.path)?;
+        }
+        Ok(result)
+    }
+}
+
+impl PermissionProvider for FilePermissionProvider {
+    fn has_permission(&self, user: Uuid, world: Option<&str>, permission: &str) -> bool {
+        self.data.read().unwrap().has_permission(user, world, permission)
+    }
+
+    fn set_user_name(&self, user: Uuid, name: &str) {
+        if let Err(e) = self.change(|data| Ok(data.set_user_name(user, name)), |changed| *changed) {
+            log::error!("Couldn't save permissions: {e}");
+        }
+    }
+
+    fn get_groups(&self) -> Vec<String> {
+        self.data.read().unwrap().get_groups().cloned().collect()
+    }
+
+    fn get_holder(&self, id: &PermissionHolderId) -> Option<PermissionHolder> {
+        self.data.read().unwrap().get_holder(id).cloned()
+    }
+
+    fn find_user(&self, name: &str) -> Option<Uuid> {
+        self.data.read().unwrap().find_user(name)
+    }
+
+    fn add_permission(&self, id: &PermissionHolderId, node: &str, world: Option<&str>) -> Result<bool, PermissionError> {
+        self.change(|data| data.add_permission(id, node, world), |changed| *changed)
+    }
+
+    fn remove_permission(&self, id: &PermissionHolderId, node: &str, world: Option<&str>) -> Result<bool, PermissionError> {
+        self.change(|data| data.remove_permission(id, node, world), |changed| *changed)
+    }
+
+    fn add_parent(&self, id: &PermissionHolderId, group: &str) -> Result<bool, PermissionError> {
+        self.change(|data| data.add_parent(id, group), |changed| *changed)
+    }
+
+    fn remove_parent(&self, id: &PermissionHolderId, group: &str) -> Result<bool, PermissionError> {
+        self.change(|data| data.remove_parent(id, group), |changed| *changed)
+    }
+
+    fn create_group(&self, group: &str) -> Result<(), PermissionError> {
+        self.change(|data| data.create_group(group), |_| true)
+    }
+
+    fn delete_group(&self, group: &str) -> Result<(), PermissionError> {
+        self.change(|data| data.delete_group(group), |_| true)
+    }
+
+    fn reload(&self) -> Result<(), PermissionError> {
+        *self.data.write().unwrap() = PermissionData::load(&self.path)?;
+        Ok(())
+    }
+}
@@ -1,7 +1,6 @@
 extern crate dashmap;
 
 use dashmap::DashMap;
-use regex::Regex;
 use server_util::ConnectionState;
 use tokio::time::timeout;
 
@@ -16,55 +15,75 @@ use std::sync::OnceLock;
 //use std::error::Error;
 use std::sync::Arc;
 use std::sync::Weak;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use tokio::sync::{Mutex, RwLock};
 use uuid::Uuid;
 use crate::connection::ConnectionError;
 use crate::data_types::text_component::Nbt;
+use crate::data_types::ChatSessionData;
+use crate::data_types::PlayerInfoEntry;
+use crate::data_types::Property;
+use crate::data_types::PropertyArray;
 
 use crate::data_types::TextComponent;
+use crate::data_types::{VarInt, Vec3d};
+use crate::chat::secure_chat::ChatState;
+use crate::game::gamemode::Gamemode;
+use crate::inventory::PlayerInventory;
+use crate::keep_alive::{KeepAlive, KeepAliveStatus};
 use crate::entity::entities::player::EntityPlayer;
+use crate::event::{self, EventManager, EventResult, PlayerKickEvent, PlayerQuitEvent};
 use crate::packet::configuration::CDisconnect_Config;
+use crate::packet::configuration::CKeepAlive_Config;
 use crate::packet::play::CDisconnect_Play;
+use crate::packet::play::CKeepAlive_Play;
+use crate::packet::play::CPlayerInfoRemove;
+use crate::packet::play::CSynchronizePlayerPosition;
 use crate::packet::play::CSystemChatMessage;
 use crate::packet::Clientbound;
 use crate::packet::SPacket;
+use crate::world::packet_handler::{self, PacketHandleError};
 
 use crate::TIMEOUT;
-use crate::connection::Connection;
+use crate::connection::{Connection, ConnectionWriter};
 use crate::packet::login::CDisconnect_Login;
 
-
-#[derive(Debug)]
-pub struct PermissionError;
-
-impl Error for PermissionError {}
-
-impl std::fmt::Display for PermissionError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Permission does not exist.")
-    }
-}
+/// `CSynchronizePlayerPosition` flags which keep the yaw and pitch
+const RELATIVE_ROTATION: u8 = 0x08 | 0x10;
 
 pub struct Player {
     connected: Mutex<bool>,
+    //Set by the first close, so a kick and a lost connection at once don't both disconnect
+    closing: AtomicBool,
     id: OnceLock<i32>,
+    entity_id: OnceLock<i32>,
     name: String,
+    properties: OnceLock<PropertyArray>,
     uuid: Uuid,
     connection: Mutex<Connection>,
+    //Sends packets without waiting for the connection, which is locked while reading
+    writer: ConnectionWriter,
     data: RwLock<Option<EntityPlayer>>,
     recv_queue: Mutex<VecDeque<SPacket>>,
     send_queue: Mutex<VecDeque<Vec<u8>>>,
-    permissions: std::sync::RwLock<Permissions>,
+    /// The level name of the world the player is in
+    world: std::sync::Mutex<Option<String>>,
+    keep_alive: std::sync::Mutex<KeepAlive>,
+    chat_state: std::sync::Mutex<ChatState>,
+    game_mode: std::sync::Mutex<Gamemode>,
+    inventory: std::sync::Mutex<PlayerInventory>,
+    teleport_id: std::sync::Mutex<i32>,
 }
-pub type Permissions = Vec<Regex>;
 
 
 impl Debug for Player {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Player")
             .field("connected", &self.connected)
+            .field("closing", &self.closing)
             .field("id", &self.id)
+            .field("entity_id", &self.entity_id)
             .field("name", &self.name)
             .field("uuid", &self.uuid)
             .field_with("connection",
@@ -81,7 +100,10 @@ impl Debug for Player {
             .field("data", &self.data)
             .field("recv_queue", &self.recv_queue)
             .field("send_queue", &self.send_queue)
-            .field("permissions", &self.permissions)
+            .field("world", &self.world)
+            .field("keep_alive", &self.keep_alive)
+            .field("chat_state", &self.chat_state)
+            .field("game_mode", &self.game_mode)
             .finish()
     }
 }
@@ -90,19 +112,32 @@ impl Player {
     pub fn new(name: String, uuid: Uuid, connection: Connection) -> Self {
         Player { 
             connected : Mutex::new(true),
+            closing : AtomicBool::new(false),
             id : OnceLock::new(), //temp value is changed quickly
+            entity_id : OnceLock::new(),
             name : name, 
+            properties : OnceLock::new(),
             uuid : uuid, 
+            writer : connection.get_writer(),
             connection : Mutex::new(connection),
             data : RwLock::new(None),
             recv_queue : Mutex::new(VecDeque::new()),
             send_queue : Mutex::new(VecDeque::new()),
-            permissions : std::sync::RwLock::new(Vec::new()),
+            world : std::sync::Mutex::new(None),
+            keep_alive : std::sync::Mutex::new(KeepAlive::new()),
+            chat_state : std::sync::Mutex::new(ChatState::new()),
+            game_mode : std::sync::Mutex::new(crate::THE_SERVER.get_properties().get_gamemode()),
+            inventory : std::sync::Mutex::new(PlayerInventory::new()),
+            teleport_id : std::sync::Mutex::new(0),
         }
     }
 
 
 
+    pub async fn get_addr(&self) -> std::net::SocketAddr {
+        self.connection.lock().await.get_addr()
+    }
+
     pub fn get_connection(&self) -> &Mutex<Connection> {
         &self.connection
     }
@@ -124,10 +159,123 @@ impl Player {
 
 
 
+    pub(crate) fn set_entity_id(&self, entity_id: i32) -> Result<(), i32> {
+        self.entity_id.set(entity_id)
+    }
+
+    /// The entity id assigned when entering the play state. 
+    /// This is not the same as the id returned by `get_id`.
+    pub fn get_entity_id(&self) -> i32 {
+        match self.entity_id.get() {
+            Some(some) => *some,
+            None => -1
+        }
+    }
+
     pub fn get_name(&self) -> &str {
         &self.name
     }
 
+    pub(crate) fn set_properties(&self, properties: PropertyArray) -> Result<(), PropertyArray> {
+        self.properties.set(properties)
+    }
+
+    /// The profile properties (skin, cape) sent in `CLoginSuccess`
+    pub fn get_properties(&self) -> &[Property] {
+        match self.properties.get() {
+            Some(properties) => properties.as_slice(),
+            None => &[],
+        }
+    }
+
+    pub fn get_info_entry(&self) -> PlayerInfoEntry {
+        PlayerInfoEntry::new(
+            self.uuid, 
+            &self.name, 
+            self.get_properties().to_vec(), 
+            self.get_game_mode().get_id() as i32, 
+            true, 
+            self.get_ping(), 
+            None
+        ).with_chat_session(self.get_chat_session())
+    }
+
+    pub fn get_game_mode(&self) -> Gamemode {
+        *self.game_mode.lock().unwrap()
+    }
+
+    /// Only updates the server's state, clients are told with `CGameEvent`
+    pub fn set_game_mode(&self, game_mode: Gamemode) {
+        *self.game_mode.lock().unwrap() = game_mode;
+    }
+
+    pub fn get_inventory(&self) -> &std::sync::Mutex<PlayerInventory> {
+        &self.inventory
+    }
+
+    /// The id of the next `CSynchronizePlayerPosition`, which the client confirms
+    pub fn next_teleport_id(&self) -> i32 {
+        let mut teleport_id = self.teleport_id.lock().unwrap();
+        *teleport_id = teleport_id.wrapping_add(1);
+        *teleport_id
+    }
+
+    /// Moves the player's client to `position`, keeping where they look
+    pub async fn synchronize_position(&self, position: Vec3d) {
+        self.queue_send_packet(CSynchronizePlayerPosition::new(
+            position.x,
+            position.y,
+            position.z,
+            0.0,
+            0.0,
+            RELATIVE_ROTATION,
+            VarInt::new(self.next_teleport_id())
+        )).await;
+    }
+
+    /// The smoothed round trip time of keep alives in milliseconds
+    pub fn get_ping(&self) -> i32 {
+        self.keep_alive.lock().unwrap().get_ping()
+    }
+
+    /// Called every tick. Returns the id of the keep alive to send if one is due,
+    /// or whether the player hasn't answered in time.
+    pub fn poll_keep_alive(&self) -> KeepAliveStatus {
+        self.keep_alive.lock().unwrap().poll(std::time::Instant::now())
+    }
+
+    /// Sends a keep alive, only waiting for the reply once it was written
+    pub async fn send_keep_alive(&self, id: i64) {
+        let result = match self.get_connection_state().await {
+            ConnectionState::Configuration => self.send_packet(CKeepAlive_Config::new(id)).await,
+            ConnectionState::Play => self.send_packet(CKeepAlive_Play::new(id)).await,
+            _ => return,
+        };
+        match result {
+            Ok(()) => self.keep_alive.lock().unwrap().sent(id, std::time::Instant::now()),
+            Err(_) => self.connection_lost().await,
+        }
+    }
+
+    /// The secure chat state, which tracks the player's chat session and the messages they have seen
+    pub fn get_chat_state(&self) -> &std::sync::Mutex<ChatState> {
+        &self.chat_state
+    }
+
+    /// The verified chat session of the player, if they have sent one
+    pub fn get_chat_session(&self) -> Option<ChatSessionData> {
+        self.chat_state.lock().unwrap().get_session().map(|session| session.get_data().clone())
+    }
+
+    /// Handles `SKeepAlive_Config` and `SKeepAlive_Play`. 
+    /// Ids which were never sent are ignored.
+    pub fn handle_keep_alive(&self, keep_alive_id: i64) {
+        let now = std::time::Instant::now();
+        if self.keep_alive.lock().unwrap().receive(keep_alive_id, now).is_none() {
+            log::debug!("{} sent an unknown keep alive id: {}", self.name, keep_alive_id);
+        }
+    }
+
     pub fn get_uuid(&self) -> Uuid {
         self.uuid
     }
@@ -136,23 +284,47 @@ impl Player {
         self.connection.lock().await.read_next_packet().await
     }
 
-    pub async fn queue_packet(&self, packet: SPacket) {
-        self.recv_queue.lock().await.push_back(packet);
+    /// Queues a packet for the world to handle on its next tick.
+    /// Fails without queueing it if the player has sent too many packets.
+    pub async fn queue_packet(&self, packet: SPacket) -> Result<(), PacketHandleError> {
+        packet_handler::queue_packet(&mut *self.recv_queue.lock().await, packet)
+    }
 
+    /// Takes up to `max` packets from the front of the receive queue.
+    pub async fn take_packets(&self, max: usize) -> Vec<SPacket> {
+        let mut lock = self.recv_queue.lock().await;
+        let count = std::cmp::min(max, lock.len());
+        lock.drain(..count).collect()
     }
 
     pub async fn send_packet(&self, packet: impl Clientbound) -> 
         Result<(), ConnectionError> 
     {
-        self.connection.lock().await.send_packet(packet).await
+        self.writer.send_packet(packet).await
     }
 
     pub async fn queue_send_packet(&self, packet: impl Clientbound) {
         self.send_queue.lock().await.push_back(packet.to_be_bytes());
     }
 
+    /// Queues packets which have already been serialized with `to_be_bytes`
+    pub async fn queue_send_raw(&self, packets: Vec<Vec<u8>>) {
+        self.send_queue.lock().await.extend(packets);
+    }
+
+    /// Sends everything in the send queue, in the order it was queued
+    pub async fn flush_send_queue(&self) -> Result<(), ConnectionError> {
+        //The queue stays locked while writing so another flush can't overtake this one
+        let mut queue = self.send_queue.lock().await;
+        if queue.is_empty() {
+            return Ok(());
+        }
+        let bytes: Vec<u8> = queue.drain(..).flatten().collect();
+        self.writer.send_raw(bytes.as_slice()).await
+    }
+
     pub async fn get_connection_state(&self) -> ConnectionState {
-        self.connection.lock().await.get_connection_state()
+        self.writer.get_connection_state()
     }
 
     pub async fn disconnect(&self, reason: &str) {
@@ -162,22 +334,66 @@ impl Player {
         ).await
     }
 
+    /// Kicks the player. Players who joined stay online if a `PlayerKickEvent` handler cancels it.
     pub async fn disconnect_tc(&self, reason: TextComponent<Nbt>) {
-        *self.connected.lock().await = false;
+        self.close(reason, true).await
+    }
+
+    /// Disconnects the player after their connection was lost, which can't be cancelled
+    pub async fn connection_lost(&self) {
+        self.close(TextComponent::builder().text("Connection lost").build(), false).await
+    }
+
+    /// Disconnects the player after they didn't answer a keep alive in time, which can't be cancelled
+    pub async fn timed_out(&self) {
+        self.close(TextComponent::builder().text("Timed out").build(), false).await
+    }
+
+    async fn close(&self, mut reason: TextComponent<Nbt>, kicked: bool) {
+        if self.closing.swap(true, Ordering::AcqRel) || !*self.connected.lock().await {
+            return;
+        }
         let player_id : i32;
         match self.id.get() {
             Some(some) => player_id = *some,
-            None => return,
+            None => {
+                *self.connected.lock().await = false;
+                return;
+            },
+        }
+        let connection_state = timeout(TIMEOUT, self.get_connection_state()).await.ok();
+        //Only players in the play state have joined
+        let joined = matches!(connection_state, Some(ConnectionState::Play));
+        let weak = crate::THE_SERVER.get_player_by_id_async(player_id).await.unwrap_or_default();
+        let mut quit_message = Some(PlayerQuitEvent::default_quit_message(&self.name));
+        if joined {
+            let event_manager = crate::THE_SERVER.get_event_manager();
+            let Some(close) = confirm_close(event_manager, weak.clone(), reason, quit_message, kicked).await else {
+                //The player stays, so they can be closed again
+                self.closing.store(false, Ordering::Release);
+                return;
+            };
+            (reason, quit_message) = close;
+        }
+        *self.connected.lock().await = false;
+        if joined {
+            let mut e = PlayerQuitEvent::new(weak, quit_message);
+            event::listen(crate::THE_SERVER.get_event_manager(), &mut e).await;
+            quit_message = e.get_quit_message().cloned();
         }
+
         crate::THE_SERVER.drop_player_by_id_async(player_id).await;
-        if let Ok(connection_state) = 
-            timeout(TIMEOUT, self.get_connection_state()).await 
-        {
+        if let Some(world) = self.get_world_name().and_then(|name| crate::THE_SERVER.get_world(&name)) {
+            //Closing can happen while the world ticks, so the player leaves it once it's free
+            let (world, entity_id) = (world.clone(), self.get_entity_id());
+            crate::RUNTIME.spawn(async move { world.lock().await.remove_player_by_id(player_id, entity_id) });
+        }
+        if let Some(connection_state) = connection_state {
             match connection_state {
                 server_util::ConnectionState::Login => {
                     timeout(TIMEOUT, self.send_packet(
                         CDisconnect_Login::new(
-                            format!("'{}'", reason.get_text().unwrap())
+                            serde_json::to_string(&reason.to_plain_string()).unwrap()
                         )
                     )).await.unwrap_or(Ok(())).unwrap_or(())
                 },
@@ -189,56 +405,50 @@ impl Player {
                 server_util::ConnectionState::Play => {
                     timeout(TIMEOUT, self.send_packet(
                         CDisconnect_Play::new(reason)
-                    )).await.unwrap_or(Ok(())).unwrap_or(())
+                    )).await.unwrap_or(Ok(())).unwrap_or(());
+                    for other in crate::THE_SERVER.get_players_async().await {
+                        if let Some(other) = other.upgrade() {
+                            other.queue_send_packet(CPlayerInfoRemove::new(vec![self.uuid])).await;
+                        }
+                    }
+                    if let Some(quit_message) = quit_message {
+                        crate::THE_SERVER.broadcast_text(quit_message).await;
+                    }
                 }
                 _ => ()
             }
         }
     }
 
+    /// Asks the server's permission provider, in the world the player is in.
+    /// Ops with the `op-permission-level` have every permission.
     pub fn has_permission(&self, permission: &str) -> bool {
-        for regex in self.permissions.read().unwrap().iter() {
-            if regex.is_match(permission) {
-                return true
-            }
+        if crate::THE_SERVER.is_op(self.uuid) {
+            return true;
         }
-        false
+        let world = self.world.lock().unwrap().clone();
+        crate::THE_SERVER.get_permission_provider()
+            .has_permission(self.uuid, world.as_deref(), permission)
     }
 
-    pub fn add_permission(&self, permission: &str) -> 
-        Result<(), PermissionError> 
-    {
-        static IS_VALID_PERMISSION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-            Regex::new("^([a-zA-Z]+\\.)*(([a-zA-Z]+)|\\*)").unwrap()
-        });
-        if !IS_VALID_PERMISSION_REGEX.is_match(permission) {
-            return Err(PermissionError)
-        }
-
-        let copy = 
-            String::from("^") + &permission.replace(".", "\\.");
-        let re = match Regex::new(copy.as_str()) {
-            Ok(regex) => regex,
-            Err(_) => return Err(PermissionError),
-        };
-
-        for regex in self.permissions.read().unwrap().iter() {
-            if regex.as_str() == re.as_str() {
-                return Ok(());
-            }
-        }
+    pub fn get_world_name(&self) -> Option<String> {
+        self.world.lock().unwrap().clone()
+    }
 
-        self.permissions.write().unwrap().push(re);
-        Ok(())
+    pub fn set_world_name(&self, world: Option<String>) {
+        *self.world.lock().unwrap() = world;
     }
 
     pub async fn send_message(&self, message: String) -> bool {
+        self.send_text(TextComponent::builder()
+            .text(message.as_str())
+            .build()
+        ).await
+    }
+
+    pub async fn send_text(&self, message: TextComponent<Nbt>) -> bool {
         if matches!(self.get_connection_state().await, ConnectionState::Play) {
-            self.queue_send_packet(CSystemChatMessage::new(
-                TextComponent::builder()
-                    .text(message.as_str())
-                    .build(), 
-                false)).await;
+            self.queue_send_packet(CSystemChatMessage::new(message, false)).await;
             true
         } else { false }
     }
@@ -246,10 +456,25 @@ impl Player {
     pub fn get_data(&self) -> &RwLock<Option<EntityPlayer>> {
         &self.data
     }
-    
-    pub fn get_permissions(&self) -> &std::sync::RwLock<Permissions> {
-        &self.permissions
+}
+
+/// Lets `PlayerKickEvent` handlers change or cancel a kick, returning the reason and quit message,
+/// or None if it was cancelled. Lost connections and timeouts aren't kicks, so nothing can cancel them.
+async fn confirm_close(
+    event_manager: &EventManager,
+    player: Weak<Player>,
+    reason: TextComponent<Nbt>,
+    quit_message: Option<TextComponent<Nbt>>,
+    kicked: bool
+) -> Option<(TextComponent<Nbt>, Option<TextComponent<Nbt>>)> {
+    if !kicked {
+        return Some((reason, quit_message));
     }
+    let mut e = PlayerKickEvent::new(player, reason, quit_message);
+    if event::listen(event_manager, &mut e).await == EventResult::Deny {
+        return None;
+    }
+    Some((e.get_reason().clone(), e.get_leave_message().cloned()))
 }
 
 
@@ -288,24 +513,18 @@ impl Players {
     }
     /// The reference 
     pub async fn get_by_id(&self, id: i32) -> Option<Weak<Player>> {
-        match self.players.blocking_read().get(&id) {
+        match self.players.read().await.get(&id) {
             Some(player) => Some(Arc::downgrade(player)),
             None => None
         }
     }
 
     pub async fn drop_by_id(&self, id: i32) {
-        if self.players.blocking_read().contains_key(&id) {
-            self.players.blocking_write().remove(&id);
-        } 
+        self.players.write().await.remove(&id);
     }
 
     pub async fn drop_by_uuid(&self, uuid: Uuid) {
-        if let Some((id, _)) = self.players.read().await
-            .iter()
-            .find(|(_, x)| x.get_uuid() == uuid) {
-                self.players.write().await.remove(id);
-            }
+        self.players.write().await.retain(|_, x| x.get_uuid() != uuid);
     }
 
     pub async fn get_players(&self) -> Vec<Weak<Player>> {
@@ -340,3 +559,35 @@ impl Players {
 }
 
 
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{EventHandler, EventPriority};
+
+    use super::*;
+
+    #[test]
+    fn lost_connections_cant_be_cancelled() {
+        let manager = EventManager::new();
+        manager.register_event_handler(EventHandler::new("test", EventPriority::Normal, |_: &mut PlayerKickEvent| {
+            EventResult::Deny
+        }));
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let reason = || TextComponent::builder().text("Connection lost").build();
+
+        assert!(runtime.block_on(confirm_close(&manager, Weak::new(), reason(), None, true)).is_none());
+        let (closed, _) = runtime.block_on(confirm_close(&manager, Weak::new(), reason(), None, false)).unwrap();
+        assert_eq!(closed.to_plain_string(), "Connection lost");
+    }
+
+    #[test]
+    fn lookups_inside_the_runtime() {
+        let players = Players::new(1);
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            assert!(players.get_by_id(0).await.is_none());
+            players.drop_by_id(0).await;
+            players.drop_by_uuid(Uuid::nil()).await;
+        });
+    }
+}
@@ -1 +1,9 @@
-pub mod plugin;
\ No newline at end of file
+pub mod plugin;
+pub mod plugin_manager;
+pub mod plugin_yml;
+pub mod wasm_plugin;
+
+pub use plugin::{PluginEnableError, TraitPlugin};
+pub use plugin_manager::{PluginManager, PLUGINS_DIRECTORY};
+pub use plugin_yml::PluginYML;
+pub use wasm_plugin::{WasmLimits, WasmPlugin};
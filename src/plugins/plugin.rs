@@ -1,23 +1,59 @@
+use std::error::Error;
+use std::ffi::c_char;
+use std::fmt::Display;
 use std::path::Path;
 
+use crate::command::Command;
+
+/// The version of the plugin API. Plugins declare the version they were built against
+/// as `api_version` in their `plugin.yml`, and are only loaded if it matches.
+pub const API_VERSION: u16 = 1;
+
+/// Every plugin library exports a `CreatePlugin` function with this name
+pub const CREATE_PLUGIN_SYMBOL: &[u8] = b"create_plugin";
+
+/// Plugin libraries without a `.yml` manifest next to them export a `PluginManifest` function with this name
+pub const PLUGIN_MANIFEST_SYMBOL: &[u8] = b"plugin_manifest";
+
+/// Creates the plugin when its library is loaded.
+/// The library must be built with the same compiler as the server.
+pub type CreatePlugin = fn() -> Box<dyn TraitPlugin>;
+
+/// Returns the contents of the plugin's `plugin.yml` as a nul terminated UTF-8 string
+pub type PluginManifest = extern "C" fn() -> *const c_char;
+
+#[derive(Debug)]
 pub enum PluginEnableError {
-    ApiVersionError,
-    Panic,
+    /// The plugin panicked, with the panic message
+    Panic(String),
+    /// The plugin failed to enable, with its reason
+    Failed(String),
+    /// A plugin it depends on isn't enabled
+    DependencyNotEnabled(String),
 }
 
-pub trait TraitPlugin {
-    fn get_data_folder(&self) -> Path;
-    fn get_version(&self) -> String;
-    fn get_description(&self) -> String;
-    fn get_api_version(&self) -> u16;
-    fn on_enable(&mut self) -> Result<(), PluginEnableError>;
-    fn on_disable(&mut self);
+impl Error for PluginEnableError {}
+
+impl Display for PluginEnableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PluginEnableError::Panic(message) => write!(f, "Panicked: {message}"),
+            PluginEnableError::Failed(reason) => write!(f, "{reason}"),
+            PluginEnableError::DependencyNotEnabled(dependency) => write!(f, "Its dependency {dependency} isn't enabled"),
+        }
+    }
 }
 
-pub struct PluginYML {
-    plugin_name: String,
-    plugin_version: String,
-    plugin_description: String,
-    api_version: u16,
-    depends: Vec<(String, (u16, u16, u16))>,
-}
\ No newline at end of file
+pub trait TraitPlugin: Send {
+    /// Called once the plugins it depends on are enabled.
+    /// `data_folder` is where the plugin keeps its files, which may not exist yet.
+    fn on_enable(&mut self, data_folder: &Path) -> Result<(), PluginEnableError>;
+
+    /// Called before the plugins it depends on are disabled
+    fn on_disable(&mut self);
+
+    /// The commands of the plugin, registered after it is enabled
+    fn get_commands(&self) -> Vec<Command> {
+        Vec::new()
+    }
+}
@@ -0,0 +1,430 @@
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::ffi::CStr;
+use std::fmt::Display;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use libloading::Library;
+
+use crate::command::Command;
+use crate::THE_SERVER;
+
+use super::plugin::{CreatePlugin, PluginEnableError, PluginManifest, TraitPlugin, API_VERSION, CREATE_PLUGIN_SYMBOL, PLUGIN_MANIFEST_SYMBOL};
+use super::plugin_yml::{Dependency, ManifestError, PluginYML};
+use super::wasm_plugin::{self, WasmLimits, WasmPlugin, MANIFEST_SECTION};
+
+pub const PLUGINS_DIRECTORY: &str = "plugins";
+
+/// The manifest of a library like `example.so` may be `example.yml` next to it
+pub const MANIFEST_EXTENSION: &str = "yml";
+
+pub const WASM_EXTENSION: &str = "wasm";
+
+#[derive(Debug)]
+pub enum PluginLoadError {
+    Io(PathBuf, std::io::Error),
+    Library(PathBuf, libloading::Error),
+    Wasm(PathBuf, wasmi::Error),
+    MissingManifest(PathBuf),
+    Manifest(PathBuf, ManifestError),
+    ApiVersion { plugin: String, api_version: u16 },
+    DuplicateName { plugin: String, path: PathBuf },
+    MissingDependency { plugin: String, dependency: Dependency },
+    DependencyVersion { plugin: String, dependency: Dependency, found: String },
+    DependencyFailed { plugin: String, dependency: String },
+    CyclicDependency { plugin: String },
+    Panic { plugin: String, message: String },
+}
+
+impl Error for PluginLoadError {}
+
+impl Display for PluginLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PluginLoadError::Io(path, e) => write!(f, "Couldn't read {}: {e}", path.display()),
+            PluginLoadError::Library(path, e) => write!(f, "Couldn't load {}: {e}", path.display()),
+            PluginLoadError::Wasm(path, e) => write!(f, "Couldn't load {}: {e}", path.display()),
+            PluginLoadError::MissingManifest(path) => write!(
+                f,
+                "{} has no plugin.yml, put it next to the plugin as .{MANIFEST_EXTENSION}, export it from the library \
+                or add it to the WASM module as a '{MANIFEST_SECTION}' custom section",
+                path.display()
+            ),
+            PluginLoadError::Manifest(path, e) => write!(f, "Invalid plugin.yml of {}: {e}", path.display()),
+            PluginLoadError::ApiVersion { plugin, api_version } => write!(
+                f,
+                "{plugin} was made for API version {api_version}, but the server has API version {API_VERSION}"
+            ),
+            PluginLoadError::DuplicateName { plugin, path } => write!(
+                f,
+                "{} is also named {plugin}, only the first is loaded",
+                path.display()
+            ),
+            PluginLoadError::MissingDependency { plugin, dependency } => write!(
+                f,
+                "{plugin} depends on {dependency}, which isn't installed"
+            ),
+            PluginLoadError::DependencyVersion { plugin, dependency, found } => write!(
+                f,
+                "{plugin} depends on {dependency}, but version {found} is installed"
+            ),
+            PluginLoadError::DependencyFailed { plugin, dependency } => write!(
+                f,
+                "{plugin} depends on {dependency}, which couldn't be loaded"
+            ),
+            PluginLoadError::CyclicDependency { plugin } => write!(
+                f,
+                "{plugin} is part of or depends on plugins which depend on each other"
+            ),
+            PluginLoadError::Panic { plugin, message } => write!(f, "{plugin} panicked while loading: {message}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluginKind {
+    /// A shared library
+    Native,
+    /// A sandboxed WebAssembly module
+    Wasm,
+}
+
+pub struct LoadedPlugin {
+    manifest: PluginYML,
+    kind: PluginKind,
+    enabled: bool,
+    //Declared before the library, so it's dropped while the library's code is still loaded
+    plugin: Box<dyn TraitPlugin>,
+    //WASM plugins have no library
+    library: Option<Library>,
+}
+
+impl LoadedPlugin {
+    pub fn get_manifest(&self) -> &PluginYML {
+        &self.manifest
+    }
+
+    pub fn get_kind(&self) -> PluginKind {
+        self.kind
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+/// Loads the plugins in the plugins directory, shared libraries and WASM modules,
+/// and enables them after the plugins they depend on.
+pub struct PluginManager {
+    directory: PathBuf,
+    //In the order they are enabled, dependencies first
+    plugins: Vec<LoadedPlugin>,
+}
+
+impl PluginManager {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self { directory: directory.into(), plugins: vec![] }
+    }
+
+    /// The plugins in the order they were enabled
+    pub fn get_plugins(&self) -> &[LoadedPlugin] {
+        &self.plugins
+    }
+
+    pub fn get_plugin(&self, name: &str) -> Option<&LoadedPlugin> {
+        self.plugins.iter().find(|plugin| plugin.manifest.get_name() == name)
+    }
+
+    /// The commands of the enabled plugins
+    pub fn get_commands(&self) -> Vec<Command> {
+        self.plugins.iter()
+            .filter(|plugin| plugin.enabled)
+            .flat_map(|plugin| plugin.plugin.get_commands())
+            .collect()
+    }
+
+    /// Where the plugin keeps its files
+    pub fn get_data_folder(&self, name: &str) -> PathBuf {
+        self.directory.join(name)
+    }
+
+    /// Loads the plugin libraries and WASM modules in the plugins directory, creating it if it doesn't exist.
+    /// Plugins which can't be loaded are logged and skipped.
+    pub fn load_plugins(&mut self) {
+        let mut errors = vec![];
+        let candidates = self.read_manifests(&mut errors);
+        for (manifest, (path, library)) in resolve_load_order(candidates, &mut errors) {
+            match load_plugin(&path, manifest, library) {
+                Ok(plugin) => {
+                    log::info!("Loaded {} v{}", plugin.manifest.get_name(), plugin.manifest.get_version());
+                    self.plugins.push(plugin);
+                },
+                Err(e) => errors.push(e),
+            }
+        }
+        for e in errors {
+            log::error!("Couldn't load plugin: {e}");
+        }
+    }
+
+    /// The manifests of the plugins with a supported API version, and the libraries
+    /// which were opened to read their manifest
+    fn read_manifests(&self, errors: &mut Vec<PluginLoadError>) -> Vec<(PluginYML, (PathBuf, Option<Library>))> {
+        if let Err(e) = std::fs::create_dir_all(&self.directory) {
+            errors.push(PluginLoadError::Io(self.directory.clone(), e));
+            return vec![];
+        }
+        let mut paths: Vec<PathBuf> = match std::fs::read_dir(&self.directory) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.is_file() && path.extension()
+                    .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION || ext == WASM_EXTENSION))
+                .collect(),
+            Err(e) => {
+                errors.push(PluginLoadError::Io(self.directory.clone(), e));
+                return vec![];
+            },
+        };
+        //Sorted, so which of two plugins with the same name is loaded doesn't depend on the file system
+        paths.sort();
+
+        let mut candidates: Vec<(PluginYML, (PathBuf, Option<Library>))> = vec![];
+        for path in paths {
+            let (manifest, library) = match read_manifest(&path) {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                },
+            };
+            if manifest.get_api_version() != API_VERSION {
+                errors.push(PluginLoadError::ApiVersion {
+                    plugin: manifest.get_name().to_string(),
+                    api_version: manifest.get_api_version()
+                });
+            } else if candidates.iter().any(|(other, _)| other.get_name() == manifest.get_name()) {
+                errors.push(PluginLoadError::DuplicateName { plugin: manifest.get_name().to_string(), path });
+            } else {
+                candidates.push((manifest, (path, library)));
+            }
+        }
+        candidates
+    }
+
+    /// Enables the loaded plugins which aren't enabled, dependencies first.
+    /// Plugins which fail to enable, or panic, are logged and stay disabled.
+    pub fn enable_plugins(&mut self) {
+        let mut enabled: HashSet<String> = self.plugins.iter()
+            .filter(|plugin| plugin.enabled)
+            .map(|plugin| plugin.manifest.get_name().to_string())
+            .collect();
+        for index in 0..self.plugins.len() {
+            if self.plugins[index].enabled {
+                continue;
+            }
+            let data_folder = self.get_data_folder(self.plugins[index].manifest.get_name());
+            let plugin = &mut self.plugins[index];
+            let missing = plugin.manifest.get_depends().iter().find(|dependency| !enabled.contains(dependency.get_name()));
+            let result = match missing {
+                Some(dependency) => Err(PluginEnableError::DependencyNotEnabled(dependency.get_name().to_string())),
+                None => panic::catch_unwind(AssertUnwindSafe(|| plugin.plugin.on_enable(&data_folder)))
+                    .unwrap_or_else(|payload| Err(PluginEnableError::Panic(get_panic_message(payload)))),
+            };
+            let (name, version) = (plugin.manifest.get_name(), plugin.manifest.get_version());
+            match result {
+                Ok(()) => {
+                    log::info!("Enabled {name} v{version}");
+                    plugin.enabled = true;
+                    enabled.insert(name.to_string());
+                },
+                Err(e) => log::error!("Couldn't enable {name} v{version}: {e}"),
+            }
+        }
+    }
+
+    /// Disables the enabled plugins, dependents first, and unregisters their event handlers and tasks
+    pub fn disable_plugins(&mut self) {
+        for plugin in self.plugins.iter_mut().rev().filter(|plugin| plugin.enabled) {
+            let (name, version) = (plugin.manifest.get_name(), plugin.manifest.get_version());
+            if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| plugin.plugin.on_disable())) {
+                log::error!("{name} v{version} panicked while disabling: {}", get_panic_message(payload));
+            }
+            THE_SERVER.get_event_manager().unregister_owner(name);
+            THE_SERVER.get_scheduler().lock().unwrap().cancel_owner(name);
+            log::info!("Disabled {name} v{version}");
+            plugin.enabled = false;
+        }
+    }
+}
+
+fn is_wasm(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == WASM_EXTENSION)
+}
+
+/// Reads the manifest next to the plugin, the custom section of a WASM module,
+/// or opens the library to read the one it exports
+fn read_manifest(path: &Path) -> Result<(PluginYML, Option<Library>), PluginLoadError> {
+    let manifest_path = path.with_extension(MANIFEST_EXTENSION);
+    if manifest_path.is_file() {
+        let source = std::fs::read_to_string(&manifest_path)
+            .map_err(|e| PluginLoadError::Io(manifest_path.clone(), e))?;
+        let manifest = PluginYML::parse(&source).map_err(|e| PluginLoadError::Manifest(manifest_path, e))?;
+        return Ok((manifest, None));
+    }
+
+    if is_wasm(path) {
+        let wasm = std::fs::read(path).map_err(|e| PluginLoadError::Io(path.to_path_buf(), e))?;
+        let source = wasm_plugin::read_custom_section(&wasm, MANIFEST_SECTION)
+            .ok_or_else(|| PluginLoadError::MissingManifest(path.to_path_buf()))?;
+        let manifest = PluginYML::parse(&String::from_utf8_lossy(source))
+            .map_err(|e| PluginLoadError::Manifest(path.to_path_buf(), e))?;
+        return Ok((manifest, None));
+    }
+
+    //SAFETY: loading a library runs its initializers, plugins are trusted like the server itself
+    let library = unsafe { Library::new(path) }.map_err(|e| PluginLoadError::Library(path.to_path_buf(), e))?;
+    let source = {
+        let Ok(get_manifest) = (unsafe { library.get::<PluginManifest>(PLUGIN_MANIFEST_SYMBOL) }) else {
+            return Err(PluginLoadError::MissingManifest(path.to_path_buf()));
+        };
+        let manifest = get_manifest();
+        if manifest.is_null() {
+            return Err(PluginLoadError::MissingManifest(path.to_path_buf()));
+        }
+        //SAFETY: the library returns a nul terminated string which lives as long as the library
+        unsafe { CStr::from_ptr(manifest) }.to_string_lossy().into_owned()
+    };
+    let manifest = PluginYML::parse(&source).map_err(|e| PluginLoadError::Manifest(path.to_path_buf(), e))?;
+    Ok((manifest, Some(library)))
+}
+
+fn load_plugin(path: &Path, manifest: PluginYML, library: Option<Library>) -> Result<LoadedPlugin, PluginLoadError> {
+    if is_wasm(path) {
+        let wasm = std::fs::read(path).map_err(|e| PluginLoadError::Io(path.to_path_buf(), e))?;
+        let properties = THE_SERVER.get_properties();
+        let cap = WasmLimits::new(properties.get_max_plugin_fuel_per_call(), properties.get_max_plugin_memory());
+        let limits = WasmLimits::capped(manifest.get_fuel_per_call(), manifest.get_max_memory(), cap);
+        let plugin = WasmPlugin::load(manifest.get_name(), &wasm, limits)
+            .map_err(|e| PluginLoadError::Wasm(path.to_path_buf(), e))?;
+        return Ok(LoadedPlugin { manifest, kind: PluginKind::Wasm, enabled: false, plugin: Box::new(plugin), library: None });
+    }
+
+    let library = match library {
+        Some(library) => library,
+        //SAFETY: see `read_manifest`
+        None => unsafe { Library::new(path) }.map_err(|e| PluginLoadError::Library(path.to_path_buf(), e))?,
+    };
+    let plugin = {
+        let create = unsafe { library.get::<CreatePlugin>(CREATE_PLUGIN_SYMBOL) }
+            .map_err(|e| PluginLoadError::Library(path.to_path_buf(), e))?;
+        panic::catch_unwind(|| create()).map_err(|payload| PluginLoadError::Panic {
+            plugin: manifest.get_name().to_string(),
+            message: get_panic_message(payload)
+        })?
+    };
+    Ok(LoadedPlugin { manifest, kind: PluginKind::Native, enabled: false, plugin, library: Some(library) })
+}
+
+/// Orders the plugins so each comes after the plugins it depends on, and plugins which don't
+/// depend on each other are sorted by name. Plugins with missing dependencies, dependencies of
+/// the wrong version or in a dependency cycle are left out, and so are the plugins depending on them.
+fn resolve_load_order<T>(candidates: Vec<(PluginYML, T)>, errors: &mut Vec<PluginLoadError>) -> Vec<(PluginYML, T)> {
+    let versions: HashMap<String, String> = candidates.iter()
+        .map(|(manifest, _)| (manifest.get_name().to_string(), manifest.get_version().to_string()))
+        .collect();
+    let mut pending: BTreeMap<String, (PluginYML, T)> = BTreeMap::new();
+    for (manifest, value) in candidates {
+        let name = manifest.get_name().to_string();
+        let error = manifest.get_depends().iter().find_map(|dependency| match versions.get(dependency.get_name()) {
+            None => Some(PluginLoadError::MissingDependency { plugin: name.clone(), dependency: dependency.clone() }),
+            Some(version) if !dependency.matches(version) => Some(PluginLoadError::DependencyVersion {
+                plugin: name.clone(),
+                dependency: dependency.clone(),
+                found: version.clone()
+            }),
+            Some(_) => None,
+        });
+        match error {
+            Some(error) => errors.push(error),
+            None => {
+                pending.insert(name, (manifest, value));
+            },
+        }
+    }
+
+    let mut failed: HashSet<String> = versions.keys().filter(|name| !pending.contains_key(*name)).cloned().collect();
+    let mut loaded: HashSet<String> = HashSet::new();
+    let mut order = vec![];
+    loop {
+        let mut progress = false;
+        let names: Vec<String> = pending.keys().cloned().collect();
+        for name in names {
+            let (manifest, _) = &pending[&name];
+            if let Some(dependency) = manifest.get_depends().iter().find(|dependency| failed.contains(dependency.get_name())) {
+                errors.push(PluginLoadError::DependencyFailed { plugin: name.clone(), dependency: dependency.get_name().to_string() });
+                pending.remove(&name);
+                failed.insert(name);
+                progress = true;
+            } else if manifest.get_depends().iter().all(|dependency| loaded.contains(dependency.get_name())) {
+                order.push(pending.remove(&name).unwrap());
+                loaded.insert(name);
+                progress = true;
+            }
+        }
+        if !progress {
+            break;
+        }
+    }
+    for plugin in pending.into_keys() {
+        errors.push(PluginLoadError::CyclicDependency { plugin });
+    }
+    order
+}
+
+fn get_panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload.downcast_ref::<&str>().map_or("Unknown panic".to_string(), |message| message.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(name: &str, version: &str, depends: &[&str]) -> (PluginYML, ()) {
+        let depends: String = depends.iter().map(|dependency| format!("\n  - {dependency}")).collect();
+        let source = format!("name: {name}\nversion: {version}\napi_version: {API_VERSION}\ndepends:{depends}");
+        (PluginYML::parse(&source).unwrap(), ())
+    }
+
+    #[test]
+    fn load_order() {
+        let mut errors = vec![];
+        let order = resolve_load_order(vec![
+            manifest("worlds", "1.0", &["economy >= 2"]),
+            manifest("shop", "1.0", &["economy", "worlds"]),
+            manifest("economy", "2.1", &[]),
+            manifest("chat", "1.0", &[]),
+            manifest("old", "1.0", &["economy < 2"]),
+            manifest("missing", "1.0", &["permissions"]),
+            manifest("needs_old", "1.0", &["old"]),
+            manifest("a", "1.0", &["b"]),
+            manifest("b", "1.0", &["a"]),
+        ], &mut errors);
+        let order: Vec<&str> = order.iter().map(|(manifest, _)| manifest.get_name()).collect();
+        assert_eq!(order, ["chat", "economy", "worlds", "shop"]);
+
+        let mut errors: Vec<String> = errors.iter().map(PluginLoadError::to_string).collect();
+        errors.sort();
+        assert_eq!(errors, [
+            "a is part of or depends on plugins which depend on each other",
+            "b is part of or depends on plugins which depend on each other",
+            "missing depends on permissions, which isn't installed",
+            "needs_old depends on old, which couldn't be loaded",
+            "old depends on economy < 2.0.0, but version 2.1 is installed",
+        ]);
+    }
+}
@@ -0,0 +1,305 @@
+use std::error::Error;
+use std::fmt::Display;
+
+use yaml_rust::{ScanError, Yaml, YamlLoader};
+
+/// A version like `1.2.3`, where missing parts are 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version(pub u16, pub u16, pub u16);
+
+impl Version {
+    pub fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.trim().split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next().map_or(Some(0), |part| part.parse().ok())?;
+        let patch = parts.next().map_or(Some(0), |part| part.parse().ok())?;
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Version(major, minor, patch))
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionOp {
+    Equal,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    /// At least the version, with the same major version
+    Compatible,
+}
+
+impl VersionOp {
+    //Longer operators first, so `>=` isn't read as `>`
+    const ALL: [(&'static str, VersionOp); 6] = [
+        (">=", VersionOp::GreaterOrEqual),
+        ("<=", VersionOp::LessOrEqual),
+        (">", VersionOp::Greater),
+        ("<", VersionOp::Less),
+        ("=", VersionOp::Equal),
+        ("^", VersionOp::Compatible),
+    ];
+
+    pub fn matches(&self, version: Version, required: Version) -> bool {
+        match self {
+            VersionOp::Equal => version == required,
+            VersionOp::Greater => version > required,
+            VersionOp::GreaterOrEqual => version >= required,
+            VersionOp::Less => version < required,
+            VersionOp::LessOrEqual => version <= required,
+            VersionOp::Compatible => version >= required && version.0 == required.0,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        VersionOp::ALL.iter().find(|(_, op)| op == self).unwrap().0
+    }
+}
+
+/// A plugin another plugin needs, written as `name` or `name <op> <version>`
+/// like `economy >= 1.2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dependency {
+    name: String,
+    requirement: Option<(VersionOp, Version)>,
+}
+
+impl Dependency {
+    pub fn parse(dependency: &str) -> Option<Self> {
+        let dependency = dependency.trim();
+        let Some(split) = dependency.find(|c: char| c.is_whitespace() || "<>=^".contains(c)) else {
+            return is_valid_name(dependency).then(|| Self { name: dependency.to_string(), requirement: None });
+        };
+        let (name, requirement) = dependency.split_at(split);
+        let requirement = requirement.trim_start();
+        let (op, version) = VersionOp::ALL.iter()
+            .find_map(|(prefix, op)| requirement.strip_prefix(prefix).map(|version| (*op, version)))?;
+        if !is_valid_name(name) {
+            return None;
+        }
+        Some(Self { name: name.to_string(), requirement: Some((op, Version::parse(version)?)) })
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_requirement(&self) -> Option<(VersionOp, Version)> {
+        self.requirement
+    }
+
+    /// Whether the version of the plugin depended on is good enough.
+    /// Versions which don't parse only match dependencies without a requirement.
+    pub fn matches(&self, version: &str) -> bool {
+        match (self.requirement, Version::parse(version)) {
+            (None, _) => true,
+            (Some((op, required)), Some(version)) => op.matches(version, required),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+impl Display for Dependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.requirement {
+            Some((op, version)) => write!(f, "{} {} {version}", self.name, op.as_str()),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Plugin names are also the names of their data folders
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+    Yaml(ScanError),
+    Empty,
+    MissingField(&'static str),
+    InvalidField { field: &'static str, message: String },
+}
+
+impl Error for ManifestError {}
+
+impl Display for ManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestError::Yaml(e) => write!(f, "{e}"),
+            ManifestError::Empty => write!(f, "The manifest is empty"),
+            ManifestError::MissingField(field) => write!(f, "Missing field '{field}'"),
+            ManifestError::InvalidField { field, message } => write!(f, "Invalid field '{field}': {message}"),
+        }
+    }
+}
+
+impl From<ScanError> for ManifestError {
+    fn from(e: ScanError) -> Self {
+        ManifestError::Yaml(e)
+    }
+}
+
+/// The manifest of a plugin, read from its `plugin.yml`
+///
+/// ```yaml
+/// name: example
+/// version: 1.0.0
+/// description: An example plugin
+/// api_version: 1
+/// depends:
+///   - economy >= 1.2
+/// limits:
+///   fuel_per_call: 20000000
+///   max_memory: 134217728
+/// ```
+///
+/// The `limits` only apply to WASM plugins, and can't be more than the server allows. See `WasmLimits::capped`
+#[derive(Debug, Clone)]
+pub struct PluginYML {
+    plugin_name: String,
+    plugin_version: String,
+    plugin_description: String,
+    api_version: u16,
+    depends: Vec<Dependency>,
+    fuel_per_call: Option<u64>,
+    //In bytes
+    max_memory: Option<usize>,
+}
+
+impl PluginYML {
+    pub fn parse(source: &str) -> Result<Self, ManifestError> {
+        let docs = YamlLoader::load_from_str(source)?;
+        let yaml = docs.first().ok_or(ManifestError::Empty)?;
+
+        let plugin_name = get_scalar(yaml, "name")?.ok_or(ManifestError::MissingField("name"))?;
+        if !is_valid_name(&plugin_name) {
+            return Err(ManifestError::InvalidField {
+                field: "name",
+                message: "only letters, digits, '_' and '-' are allowed".to_string()
+            });
+        }
+        let plugin_version = get_scalar(yaml, "version")?.ok_or(ManifestError::MissingField("version"))?;
+        let plugin_description = get_scalar(yaml, "description")?.unwrap_or_default();
+        let api_version = get_scalar(yaml, "api_version")?
+            .ok_or(ManifestError::MissingField("api_version"))?
+            .parse()
+            .map_err(|e: std::num::ParseIntError| ManifestError::InvalidField { field: "api_version", message: e.to_string() })?;
+
+        let depends = match &yaml["depends"] {
+            Yaml::BadValue | Yaml::Null => vec![],
+            Yaml::Array(entries) => entries.iter()
+                .map(|entry| entry.as_str()
+                    .and_then(Dependency::parse)
+                    .ok_or_else(|| ManifestError::InvalidField { field: "depends", message: format!("{entry:?}") }))
+                .collect::<Result<_, _>>()?,
+            _ => return Err(ManifestError::InvalidField { field: "depends", message: "expected a list".to_string() }),
+        };
+
+        let limits = match &yaml["limits"] {
+            Yaml::BadValue | Yaml::Null => &Yaml::Null,
+            limits @ Yaml::Hash(_) => limits,
+            _ => return Err(ManifestError::InvalidField { field: "limits", message: "expected a map".to_string() }),
+        };
+        let fuel_per_call = get_limit(limits, "fuel_per_call")?;
+        let max_memory = get_limit(limits, "max_memory")?.map(|max_memory| max_memory as usize);
+
+        Ok(Self { plugin_name, plugin_version, plugin_description, api_version, depends, fuel_per_call, max_memory })
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.plugin_name
+    }
+
+    pub fn get_version(&self) -> &str {
+        &self.plugin_version
+    }
+
+    pub fn get_description(&self) -> &str {
+        &self.plugin_description
+    }
+
+    pub fn get_api_version(&self) -> u16 {
+        self.api_version
+    }
+
+    pub fn get_depends(&self) -> &[Dependency] {
+        &self.depends
+    }
+
+    pub fn get_fuel_per_call(&self) -> Option<u64> {
+        self.fuel_per_call
+    }
+
+    /// In bytes
+    pub fn get_max_memory(&self) -> Option<usize> {
+        self.max_memory
+    }
+}
+
+/// Versions like `1.0` are numbers in YAML, so those are read as strings too
+fn get_scalar(yaml: &Yaml, field: &'static str) -> Result<Option<String>, ManifestError> {
+    match &yaml[field] {
+        Yaml::BadValue | Yaml::Null => Ok(None),
+        Yaml::String(value) | Yaml::Real(value) => Ok(Some(value.clone())),
+        Yaml::Integer(value) => Ok(Some(value.to_string())),
+        _ => Err(ManifestError::InvalidField { field, message: "expected a string".to_string() }),
+    }
+}
+
+fn get_limit(limits: &Yaml, field: &'static str) -> Result<Option<u64>, ManifestError> {
+    match &limits[field] {
+        Yaml::BadValue | Yaml::Null => Ok(None),
+        Yaml::Integer(value) if *value > 0 => Ok(Some(*value as u64)),
+        _ => Err(ManifestError::InvalidField { field, message: "expected a positive number".to_string() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest() {
+        let manifest = PluginYML::parse("
+name: example
+version: 1.0
+api_version: 1
+depends:
+  - economy
+  - worlds >= 2.1
+  - chat^1.4.2
+").unwrap();
+        assert_eq!(manifest.get_name(), "example");
+        assert_eq!(manifest.get_version(), "1.0");
+        assert_eq!(manifest.get_description(), "");
+        assert_eq!(manifest.get_api_version(), 1);
+        let depends: Vec<String> = manifest.get_depends().iter().map(Dependency::to_string).collect();
+        assert_eq!(depends, ["economy", "worlds >= 2.1.0", "chat ^ 1.4.2"]);
+
+        let chat = &manifest.get_depends()[2];
+        assert!(chat.matches("1.5"));
+        assert!(!chat.matches("1.4.1"));
+        assert!(!chat.matches("2.0.0"));
+        assert!(!chat.matches("snapshot"));
+        assert!(manifest.get_depends()[0].matches("snapshot"));
+
+        assert!(matches!(PluginYML::parse("name: ../x\nversion: 1\napi_version: 1"), Err(ManifestError::InvalidField { field: "name", .. })));
+        assert!(matches!(PluginYML::parse("name: x\napi_version: 1"), Err(ManifestError::MissingField("version"))));
+        assert!(Dependency::parse("worlds >= x").is_none());
+
+        assert_eq!((manifest.get_fuel_per_call(), manifest.get_max_memory()), (None, None));
+        let limited = PluginYML::parse("name: x\nversion: 1\napi_version: 1\nlimits:\n  fuel_per_call: 5000").unwrap();
+        assert_eq!((limited.get_fuel_per_call(), limited.get_max_memory()), (Some(5000), None));
+        assert!(matches!(PluginYML::parse("name: x\nversion: 1\napi_version: 1\nlimits:\n  max_memory: -1"), Err(ManifestError::InvalidField { field: "max_memory", .. })));
+        assert!(matches!(PluginYML::parse("name: x\nversion: 1\napi_version: 1\nlimits:\n  fuel_per_call: 0"), Err(ManifestError::InvalidField { field: "fuel_per_call", .. })));
+    }
+}
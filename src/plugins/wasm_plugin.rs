@@ -0,0 +1,565 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::Path;
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard, OnceLock, PoisonError, RwLock, Weak};
+
+use serde_json::Value;
+use wasmi::{Caller, Config, Engine, Extern, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Val};
+
+use crate::command::argument::{ArgType, Argument, StringArgProperties, StringType};
+use crate::command::builtin;
+use crate::command::command_tree::CommandNode;
+use crate::command::{Command, CommandError, CommandFuture, CommandSender, CommandSuccess};
+use crate::data_types::{BlockPos, TextComponent};
+use crate::event::named::{self, NamedHandler};
+use crate::event::{CommandEvent, EventPriority, EventResult};
+use crate::permission::validate_node;
+use crate::server::scheduler::{Task, TaskId};
+use crate::{RUNTIME, THE_SERVER};
+
+use super::plugin::{PluginEnableError, TraitPlugin};
+
+/// The module WASM plugins import the host functions from. A new version of the
+/// host API gets a new module, so plugins built against an older one keep working.
+///
+/// Strings are passed as a pointer and a length into the plugin's memory, as UTF-8.
+/// - `log(level, ptr, len)`, where the level is 0 error, 1 warn, 2 info and 3 debug
+/// - `broadcast(ptr, len)` sends a message to every player
+/// - `send_message(player_ptr, player_len, ptr, len) -> i32` sends a message to a player, -1 if they aren't online
+/// - `reply(ptr, len) -> i32` sends a message to whoever ran the command being handled, -1 outside of `on_command`
+/// - `has_permission(ptr, len) -> i32` 1 if whoever ran the command being handled has the permission, 0 if
+///   they don't and -1 outside of `on_command`
+/// - `register_command(ptr, len) -> i32` adds a command the plugin handles which anyone may run, only in
+///   `on_enable`. -1 if the name is invalid or a builtin or another plugin has it
+/// - `register_command_with_permission(ptr, len, permission_ptr, permission_len) -> i32` adds a command
+///   which needs the permission, like `register_command`
+/// - `register_event(ptr, len, priority) -> i32` handles the event with the name, one of `named::EVENT_NAMES`,
+///   with the priority 0 lowest to 4 highest or 5 monitor. -1 if there is no such event. The handler is the
+///   plugin's `on_event(name_ptr, name_len, data_ptr, data_len) -> i32`, which gets the event's data as JSON
+///   and returns 0 to deny the event, 2 to allow it and anything else for the default.
+/// - `get_block(world_ptr, world_len, x, y, z) -> i32` the block state, -1 if it isn't known
+/// - `set_block(world_ptr, world_len, x, y, z, state) -> i32` -1 if there is no such world or position. `get_block`
+///   sees the block right away, players once the call into the plugin returns
+/// - `schedule(delay, period, task) -> i64` calls `on_task(task)` in `delay` ticks, then every `period` ticks if it is positive
+/// - `cancel_task(id) -> i32` -1 if the plugin has no such task
+pub const HOST_MODULE: &str = "server_v1";
+
+/// The custom section with the `plugin.yml` of WASM plugins without a `.yml` manifest next to them
+pub const MANIFEST_SECTION: &str = "plugin.yml";
+
+pub const DEFAULT_FUEL_PER_CALL: u64 = 10_000_000;
+pub const DEFAULT_MAX_MEMORY: usize = 64 * 1024 * 1024;
+
+static ENGINE: LazyLock<Engine> = LazyLock::new(|| {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    Engine::new(&config)
+});
+
+//The plugins handling each command, for the command handlers which are plain functions
+static COMMANDS: LazyLock<RwLock<HashMap<String, Weak<WasmInstance>>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// How much a WASM plugin may do. Each call into the plugin gets the fuel again.
+#[derive(Debug, Clone, Copy)]
+pub struct WasmLimits {
+    fuel_per_call: u64,
+    max_memory: usize,
+}
+
+impl WasmLimits {
+    pub fn new(fuel_per_call: u64, max_memory: usize) -> Self {
+        Self { fuel_per_call, max_memory }
+    }
+
+    /// The limits a plugin asks for in its manifest, the defaults for those it leaves out and
+    /// at most the server's `cap`
+    pub fn capped(fuel_per_call: Option<u64>, max_memory: Option<usize>, cap: WasmLimits) -> Self {
+        Self::new(
+            fuel_per_call.unwrap_or(DEFAULT_FUEL_PER_CALL).min(cap.fuel_per_call),
+            max_memory.unwrap_or(DEFAULT_MAX_MEMORY).min(cap.max_memory)
+        )
+    }
+
+    pub fn get_fuel_per_call(&self) -> u64 {
+        self.fuel_per_call
+    }
+
+    /// In bytes
+    pub fn get_max_memory(&self) -> usize {
+        self.max_memory
+    }
+}
+
+impl Default for WasmLimits {
+    fn default() -> Self {
+        Self::new(DEFAULT_FUEL_PER_CALL, DEFAULT_MAX_MEMORY)
+    }
+}
+
+struct HostState {
+    plugin: String,
+    instance: Weak<WasmInstance>,
+    limits: StoreLimits,
+    enabling: bool,
+    //The commands with their permissions
+    commands: Vec<(String, Option<String>)>,
+    //The blocks set during the current call, by world
+    block_updates: Vec<(String, BlockPos)>,
+    tasks: HashSet<u64>,
+    //Who ran the command being handled
+    sender: Option<CommandSender>,
+}
+
+struct WasmStore {
+    store: Store<HostState>,
+    instance: Instance,
+}
+
+struct WasmInstance {
+    name: String,
+    fuel_per_call: u64,
+    inner: Mutex<WasmStore>,
+}
+
+impl WasmInstance {
+    fn lock(&self) -> MutexGuard<'_, WasmStore> {
+        //A trap can't leave the store in a broken state, so a poisoned lock is still usable
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Calls the export with the strings written to the plugin's memory as a pointer and a length
+    /// each, followed by `params`. Returns None if the plugin doesn't export it, and 0 for exports
+    /// without a result.
+    fn call(&self, export: &str, strings: &[&str], params: &[Val]) -> Result<Option<i32>, wasmi::Error> {
+        self.call_locked(&mut self.lock(), export, strings, params)
+    }
+
+    /// Like `call`, with the store already locked so the host state can be set up around the call
+    fn call_locked(&self, guard: &mut WasmStore, export: &str, strings: &[&str], params: &[Val]) -> Result<Option<i32>, wasmi::Error> {
+        let WasmStore { store, instance } = guard;
+        let Some(func) = instance.get_func(&*store, export) else {
+            return Ok(None);
+        };
+        store.set_fuel(self.fuel_per_call).map_err(|e| wasmi::Error::new(e.to_string()))?;
+        let mut inputs = Vec::with_capacity(strings.len() * 2 + params.len());
+        for string in strings {
+            let ptr = write_str(store, instance, string)?;
+            inputs.push(Val::I32(ptr as i32));
+            inputs.push(Val::I32(string.len() as i32));
+        }
+        inputs.extend_from_slice(params);
+        let mut outputs: Vec<Val> = func.ty(&*store).results().iter().map(|ty| Val::default(*ty)).collect();
+        let result = func.call(&mut *store, &inputs, &mut outputs);
+        send_block_updates(store.data_mut());
+        result?;
+        Ok(Some(outputs.first().and_then(Val::i32).unwrap_or(0)))
+    }
+
+    fn on_event(&self, event: &str, data: Value) -> EventResult {
+        match self.call("on_event", &[event, &data.to_string()], &[]) {
+            Ok(Some(0)) => EventResult::Deny,
+            Ok(Some(2)) => EventResult::Allow,
+            Ok(_) => EventResult::Default,
+            Err(e) => {
+                log::error!("{} failed to handle {event}: {e}", self.name);
+                EventResult::Default
+            },
+        }
+    }
+
+    fn on_command(&self, sender: CommandSender, label: &str, input: &str) -> Result<i32, wasmi::Error> {
+        //Locked the whole time, so replies can't go to whoever runs a command at the same time
+        let mut guard = self.lock();
+        guard.store.data_mut().sender = Some(sender);
+        let result = self.call_locked(&mut guard, "on_command", &[label, input], &[]);
+        guard.store.data_mut().sender = None;
+        result.map(|count| count.unwrap_or(0))
+    }
+
+    /// `finished` is the id of a task which won't run again, which the plugin can't cancel anymore
+    fn on_task(&self, task: i32, finished: Option<u64>) {
+        let mut guard = self.lock();
+        if let Some(id) = finished {
+            guard.store.data_mut().tasks.remove(&id);
+        }
+        if let Err(e) = self.call_locked(&mut guard, "on_task", &[], &[Val::I32(task)]) {
+            log::error!("A task of {} failed: {e}", self.name);
+        }
+    }
+}
+
+/// A plugin compiled to WebAssembly. It can only use the host functions of `HOST_MODULE`,
+/// and each call into it is limited by fuel, so it can't crash or hang the server.
+pub struct WasmPlugin {
+    instance: Arc<WasmInstance>,
+}
+
+impl WasmPlugin {
+    pub fn load(name: &str, wasm: &[u8], limits: WasmLimits) -> Result<Self, wasmi::Error> {
+        let module = Module::new(&ENGINE, wasm)?;
+        let state = HostState {
+            plugin: name.to_string(),
+            instance: Weak::new(),
+            limits: StoreLimitsBuilder::new().memory_size(limits.max_memory).instances(1).build(),
+            enabling: false,
+            commands: vec![],
+            block_updates: vec![],
+            tasks: HashSet::new(),
+            sender: None,
+        };
+        let mut store = Store::new(&ENGINE, state);
+        store.limiter(|state| &mut state.limits);
+        //The start function gets fuel too
+        store.set_fuel(limits.fuel_per_call).map_err(|e| wasmi::Error::new(e.to_string()))?;
+        let instance = get_linker().instantiate(&mut store, &module)?.start(&mut store)?;
+
+        let instance = Arc::new_cyclic(|weak| {
+            store.data_mut().instance = weak.clone();
+            WasmInstance {
+                name: name.to_string(),
+                fuel_per_call: limits.fuel_per_call,
+                inner: Mutex::new(WasmStore { store, instance }),
+            }
+        });
+        Ok(Self { instance })
+    }
+}
+
+impl TraitPlugin for WasmPlugin {
+    /// Calls the plugin's `on_enable() -> i32` export if it has one, which fails if it returns something other than 0.
+    /// WASM plugins can't access files, so there is no data folder.
+    fn on_enable(&mut self, _data_folder: &Path) -> Result<(), PluginEnableError> {
+        let (result, commands) = {
+            let mut guard = self.instance.lock();
+            guard.store.data_mut().enabling = true;
+            let result = self.instance.call_locked(&mut guard, "on_enable", &[], &[]);
+            let state = guard.store.data_mut();
+            state.enabling = false;
+            (result, state.commands.clone())
+        };
+        match result {
+            Ok(None | Some(0)) => {
+                let mut registered = COMMANDS.write().unwrap();
+                for (command, _) in commands {
+                    registered.insert(command, Arc::downgrade(&self.instance));
+                }
+                Ok(())
+            },
+            Ok(Some(code)) => Err(PluginEnableError::Failed(format!("on_enable returned {code}"))),
+            Err(e) => Err(PluginEnableError::Failed(e.to_string())),
+        }
+    }
+
+    fn on_disable(&mut self) {
+        if let Err(e) = self.instance.call("on_disable", &[], &[]) {
+            log::error!("{} failed to disable: {e}", self.instance.name);
+        }
+        let instance = Arc::downgrade(&self.instance);
+        COMMANDS.write().unwrap().retain(|_, plugin| !plugin.ptr_eq(&instance));
+        let mut guard = self.instance.lock();
+        let state = guard.store.data_mut();
+        state.commands.clear();
+        state.tasks.clear();
+    }
+
+    /// The commands the plugin registered while enabling. Everything typed after the
+    /// label is passed to its `on_command(label_ptr, label_len, input_ptr, input_len) -> i32`,
+    /// which returns how many things the command affected, or a negative number if it failed.
+    fn get_commands(&self) -> Vec<Command> {
+        self.instance.lock().store.data().commands.iter()
+            .map(|(name, permission)| Command::new(
+                name,
+                None,
+                CommandNode::single_usage(name, &[Argument::new(
+                    "input".to_string(),
+                    ArgType::String { properties: Some(StringArgProperties::new(StringType::Greedy)) },
+                    false
+                )]),
+                permission.as_deref(),
+                &[],
+                run_command
+            ))
+            .collect()
+    }
+}
+
+fn run_command(e: CommandEvent) -> CommandFuture {
+    let plugin = COMMANDS.read().unwrap().get(e.get_command().get_name()).and_then(Weak::upgrade);
+    let result = match plugin {
+        None => Err(CommandError::Other { message: "The plugin of this command is disabled".to_string() }),
+        Some(plugin) => match plugin.on_command(e.get_sender().clone(), e.get_label(), &e.get_args().join(" ")) {
+            Ok(count) if count >= 0 => Ok(CommandSuccess::new(count).with_broadcast(false)),
+            Ok(_) => Err(CommandError::Other { message: format!("/{} failed", e.get_label()) }),
+            Err(e) => {
+                log::error!("{} failed to run a command: {e}", plugin.name);
+                Err(CommandError::Other { message: "An error occurred while running the command".to_string() })
+            },
+        },
+    };
+    Box::pin(std::future::ready(result))
+}
+
+fn get_linker() -> Linker<HostState> {
+    let mut linker = Linker::new(&ENGINE);
+    linker.func_wrap(HOST_MODULE, "log", |caller: Caller<'_, HostState>, level: u32, ptr: u32, len: u32| {
+        let message = read_str(&caller, ptr, len)?;
+        let plugin = &caller.data().plugin;
+        match level {
+            0 => log::error!("[{plugin}] {message}"),
+            1 => log::warn!("[{plugin}] {message}"),
+            2 => log::info!("[{plugin}] {message}"),
+            _ => log::debug!("[{plugin}] {message}"),
+        }
+        Ok(())
+    }).unwrap();
+    linker.func_wrap(HOST_MODULE, "broadcast", |caller: Caller<'_, HostState>, ptr: u32, len: u32| {
+        let message = read_str(&caller, ptr, len)?;
+        RUNTIME.spawn(THE_SERVER.broadcast_text(TextComponent::builder().text(&message).build()));
+        Ok(())
+    }).unwrap();
+    linker.func_wrap(
+        HOST_MODULE,
+        "send_message",
+        |caller: Caller<'_, HostState>, player_ptr: u32, player_len: u32, ptr: u32, len: u32| {
+            let player = read_str(&caller, player_ptr, player_len)?;
+            let message = read_str(&caller, ptr, len)?;
+            let Some(player) = wait(THE_SERVER.get_player_by_name_async(&player)).as_ref().and_then(Weak::upgrade) else {
+                return Ok(-1);
+            };
+            RUNTIME.spawn(async move { player.send_message(message).await });
+            Ok(0)
+        }
+    ).unwrap();
+    linker.func_wrap(HOST_MODULE, "reply", |caller: Caller<'_, HostState>, ptr: u32, len: u32| {
+        let message = read_str(&caller, ptr, len)?;
+        match &caller.data().sender {
+            Some(sender) => {
+                sender.send_message(message);
+                Ok(0)
+            },
+            None => Ok(-1),
+        }
+    }).unwrap();
+    linker.func_wrap(HOST_MODULE, "has_permission", |caller: Caller<'_, HostState>, ptr: u32, len: u32| {
+        let permission = read_str(&caller, ptr, len)?;
+        Ok(match &caller.data().sender {
+            Some(sender) => sender.has_permission(&permission) as i32,
+            None => -1,
+        })
+    }).unwrap();
+    linker.func_wrap(HOST_MODULE, "register_command", |mut caller: Caller<'_, HostState>, ptr: u32, len: u32| {
+        let name = read_str(&caller, ptr, len)?;
+        Ok(register_command(caller.data_mut(), name, None))
+    }).unwrap();
+    linker.func_wrap(
+        HOST_MODULE,
+        "register_command_with_permission",
+        |mut caller: Caller<'_, HostState>, ptr: u32, len: u32, permission_ptr: u32, permission_len: u32| {
+            let name = read_str(&caller, ptr, len)?;
+            let permission = read_str(&caller, permission_ptr, permission_len)?;
+            if validate_node(&permission).is_err() {
+                return Ok(-1);
+            }
+            Ok(register_command(caller.data_mut(), name, Some(permission)))
+        }
+    ).unwrap();
+    linker.func_wrap(HOST_MODULE, "register_event", |caller: Caller<'_, HostState>, ptr: u32, len: u32, priority: u32| {
+        let event = read_str(&caller, ptr, len)?;
+        let Some(priority) = EventPriority::ORDER.get(priority as usize) else {
+            return Ok(-1);
+        };
+        let state = caller.data();
+        let instance = state.instance.clone();
+        let name = event.clone();
+        let handler: NamedHandler = Arc::new(move |data| {
+            instance.upgrade().map_or(EventResult::Default, |instance| instance.on_event(&name, data))
+        });
+        Ok(match named::register_named_handler(&state.plugin, &event, *priority, handler) {
+            Some(_) => 0,
+            None => -1,
+        })
+    }).unwrap();
+    linker.func_wrap(
+        HOST_MODULE,
+        "get_block",
+        |caller: Caller<'_, HostState>, world_ptr: u32, world_len: u32, x: i32, y: i32, z: i32| {
+            let world = read_str(&caller, world_ptr, world_len)?;
+            let (Some(blocks), Ok(position)) = (THE_SERVER.get_block_changes(&world), BlockPos::new(x, y, z)) else {
+                return Ok(-1);
+            };
+            //Plugins run inside ticks, while the world is locked, so they don't go through it
+            Ok(blocks.get(&(position.x, position.y, position.z)).map_or(-1, |state| *state))
+        }
+    ).unwrap();
+    linker.func_wrap(
+        HOST_MODULE,
+        "set_block",
+        |mut caller: Caller<'_, HostState>, world_ptr: u32, world_len: u32, x: i32, y: i32, z: i32, state: i32| {
+            let world = read_str(&caller, world_ptr, world_len)?;
+            let (Some(blocks), Ok(position)) = (THE_SERVER.get_block_changes(&world), BlockPos::new(x, y, z)) else {
+                return Ok(-1);
+            };
+            blocks.insert((position.x, position.y, position.z), state);
+            caller.data_mut().block_updates.push((world, position));
+            Ok(0)
+        }
+    ).unwrap();
+    linker.func_wrap(HOST_MODULE, "schedule", |mut caller: Caller<'_, HostState>, delay: i64, period: i64, task: i32| {
+        let instance = caller.data().instance.clone();
+        //Set once the task is scheduled, which is before it can run
+        let scheduled = Arc::new(OnceLock::new());
+        let finished = (period <= 0).then(|| scheduled.clone());
+        let run: Task = Box::new(move || {
+            if let Some(instance) = instance.upgrade() {
+                instance.on_task(task, finished.as_ref().and_then(|id| id.get().copied()));
+            }
+        });
+        let state = caller.data_mut();
+        let mut scheduler = THE_SERVER.get_scheduler().lock().unwrap();
+        let delay = delay.max(0) as u64;
+        let id = match period {
+            ..=0 => scheduler.schedule(&state.plugin, delay, run),
+            period => scheduler.schedule_repeating(&state.plugin, delay, period as u64, run),
+        };
+        let _ = scheduled.set(id.0);
+        state.tasks.insert(id.0);
+        Ok(id.0 as i64)
+    }).unwrap();
+    linker.func_wrap(HOST_MODULE, "cancel_task", |mut caller: Caller<'_, HostState>, id: i64| {
+        //Plugins can only cancel their own tasks
+        if !caller.data_mut().tasks.remove(&(id as u64)) {
+            return Ok(-1);
+        }
+        Ok(if THE_SERVER.get_scheduler().lock().unwrap().cancel(TaskId(id as u64)) { 0 } else { -1 })
+    }).unwrap();
+    linker
+}
+
+/// Shows players the blocks set during a call. The worlds may be locked by whoever called
+/// into the plugin, so they are updated in a task once they are free. Each update sends
+/// the block as it is by then, so the order the tasks run in doesn't matter.
+fn send_block_updates(state: &mut HostState) {
+    if state.block_updates.is_empty() {
+        return;
+    }
+    let updates = std::mem::take(&mut state.block_updates);
+    RUNTIME.spawn(async move {
+        for (world, position) in updates {
+            if let Some(world) = THE_SERVER.get_world(&world) {
+                world.lock().await.update_block(position).await;
+            }
+        }
+    });
+}
+
+/// Host functions are sync and called from inside the runtime, where it can't block on a future.
+/// Another thread can, so they wait for the server's async accessors on one.
+fn wait<F: Future + Send>(future: F) -> F::Output where F::Output: Send {
+    std::thread::scope(|scope| scope.spawn(|| RUNTIME.block_on(future)).join().unwrap())
+}
+
+/// Plugins can't take the name of a builtin command or another plugin's command
+fn register_command(state: &mut HostState, name: String, permission: Option<String>) -> i32 {
+    let name = name.to_lowercase();
+    if !state.enabling || name.is_empty() || name.contains(char::is_whitespace) || builtin::is_builtin(&name) {
+        return -1;
+    }
+    let taken = COMMANDS.read().unwrap().get(&name)
+        .is_some_and(|plugin| plugin.strong_count() > 0 && !plugin.ptr_eq(&state.instance));
+    if taken || state.commands.iter().any(|(command, _)| *command == name) {
+        return -1;
+    }
+    state.commands.push((name, permission));
+    0
+}
+
+fn read_str(caller: &Caller<'_, HostState>, ptr: u32, len: u32) -> Result<String, wasmi::Error> {
+    let memory = caller.get_export("memory").and_then(Extern::into_memory)
+        .ok_or_else(|| wasmi::Error::new("The plugin exports no memory"))?;
+    let (start, end) = (ptr as usize, ptr as usize + len as usize);
+    let bytes = memory.data(caller).get(start..end)
+        .ok_or_else(|| wasmi::Error::new("A string is out of the plugin's memory"))?;
+    String::from_utf8(bytes.to_vec()).map_err(|e| wasmi::Error::new(e.to_string()))
+}
+
+/// Copies the string into memory the plugin allocates with its `alloc(len) -> ptr` export
+fn write_str(store: &mut Store<HostState>, instance: &Instance, string: &str) -> Result<u32, wasmi::Error> {
+    let alloc = instance.get_typed_func::<u32, u32>(&*store, "alloc")
+        .map_err(|_| wasmi::Error::new("The plugin exports no alloc(len) -> ptr"))?;
+    let memory = instance.get_memory(&*store, "memory")
+        .ok_or_else(|| wasmi::Error::new("The plugin exports no memory"))?;
+    let ptr = alloc.call(&mut *store, string.len() as u32)?;
+    memory.write(&mut *store, ptr as usize, string.as_bytes()).map_err(|e| wasmi::Error::new(e.to_string()))?;
+    Ok(ptr)
+}
+
+/// The contents of the first custom section with the name
+pub fn read_custom_section<'a>(wasm: &'a [u8], name: &str) -> Option<&'a [u8]> {
+    let mut rest = wasm.strip_prefix(b"\0asm")?.get(4..)?;
+    while let Some((&id, after_id)) = rest.split_first() {
+        let (size, after_size) = read_leb128(after_id)?;
+        let section = after_size.get(..size)?;
+        rest = &after_size[size..];
+        if id == 0 {
+            let (name_len, content) = read_leb128(section)?;
+            if content.get(..name_len)? == name.as_bytes() {
+                return Some(&content[name_len..]);
+            }
+        }
+    }
+    None
+}
+
+fn read_leb128(bytes: &[u8]) -> Option<(usize, &[u8])> {
+    let mut value = 0usize;
+    for (index, byte) in bytes.iter().enumerate().take(5) {
+        value |= ((byte & 0x7f) as usize) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Some((value, &bytes[index + 1..]));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //A module with a plugin.yml section whose on_enable loops forever
+    const LOOPING_PLUGIN: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00,
+        //Custom section "plugin.yml"
+        0x00, 0x11, 0x0a, b'p', b'l', b'u', b'g', b'i', b'n', b'.', b'y', b'm', b'l', b'n', b'a', b'm', b'e', b':', b' ',
+        //Type section: () -> i32
+        0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7f,
+        //Function section
+        0x03, 0x02, 0x01, 0x00,
+        //Export section: on_enable
+        0x07, 0x0d, 0x01, 0x09, b'o', b'n', b'_', b'e', b'n', b'a', b'b', b'l', b'e', 0x00, 0x00,
+        //Code section: loop br 0 end, i32.const 0
+        0x0a, 0x0b, 0x01, 0x09, 0x00, 0x03, 0x40, 0x0c, 0x00, 0x0b, 0x41, 0x00, 0x0b,
+    ];
+
+    #[test]
+    fn custom_section() {
+        assert_eq!(read_custom_section(LOOPING_PLUGIN, MANIFEST_SECTION), Some(&b"name: "[..]));
+        assert_eq!(read_custom_section(LOOPING_PLUGIN, "name"), None);
+        assert_eq!(read_custom_section(b"not wasm", MANIFEST_SECTION), None);
+    }
+
+    #[test]
+    fn out_of_fuel() {
+        let mut plugin = WasmPlugin::load("looping", LOOPING_PLUGIN, WasmLimits::new(10_000, DEFAULT_MAX_MEMORY)).unwrap();
+        assert!(matches!(plugin.on_enable(Path::new("")), Err(PluginEnableError::Failed(_))));
+        assert!(plugin.get_commands().is_empty());
+    }
+
+    #[test]
+    fn capped_limits() {
+        let cap = WasmLimits::new(100_000_000, 512 * 1024 * 1024);
+        let limits = WasmLimits::capped(Some(1_000), None, cap);
+        assert_eq!((limits.get_fuel_per_call(), limits.get_max_memory()), (1_000, DEFAULT_MAX_MEMORY));
+        let limits = WasmLimits::capped(Some(u64::MAX), Some(usize::MAX), cap);
+        assert_eq!((limits.get_fuel_per_call(), limits.get_max_memory()), (cap.get_fuel_per_call(), cap.get_max_memory()));
+    }
+}
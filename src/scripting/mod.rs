@@ -0,0 +1,5 @@
+pub mod script;
+pub mod script_manager;
+
+pub use script::Script;
+pub use script_manager::{ScriptManager, SCRIPTS_DIRECTORY};
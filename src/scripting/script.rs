@@ -0,0 +1,329 @@
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Arc, LazyLock, Mutex, OnceLock, RwLock, Weak};
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, FnPtr, AST};
+
+use crate::command::argument::{ArgType, Argument, StringArgProperties, StringType};
+use crate::command::builtin;
+use crate::command::command_tree::CommandNode;
+use crate::command::{Command, CommandError, CommandFuture, CommandSender, CommandSuccess};
+use crate::data_types::TextComponent;
+use crate::event::named::{self, NamedHandler};
+use crate::event::{CommandEvent, EventPriority, EventResult};
+use crate::permission::validate_node;
+use crate::player::Player;
+use crate::server::scheduler::{Task, TaskId};
+use crate::{COMMAND_MAP, RUNTIME, THE_SERVER};
+
+/// How many operations a script may run each time it is called, so a script
+/// stuck in a loop fails instead of hanging the server
+pub const MAX_OPERATIONS: u64 = 1_000_000;
+
+//The scripts handling each command, for the command handlers which are plain functions
+static COMMANDS: LazyLock<RwLock<HashMap<String, ScriptCommand>>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+struct ScriptCommand {
+    script: Weak<Script>,
+    handler: FnPtr,
+    permission: Option<String>,
+}
+
+/// A Rhai script. Running it registers its event handlers, commands and tasks:
+///
+/// ```rhai
+/// on("player_join", |e| get_player(e.player).send_message("Welcome!"));
+/// on("block_break", "high", |e| e.position.y > 0);
+/// command("spawn", |sender, args| { sender.send_message("Teleporting"); 1 });
+/// command("heal", "myserver.heal", |sender, args| 1);
+/// schedule_repeating(0, 20 * 60, || broadcast("A minute has passed"));
+/// ```
+///
+/// Event handlers get the event's data, and return false to deny the event and true to allow it.
+pub struct Script {
+    name: String,
+    owner: String,
+    engine: Engine,
+    ast: AST,
+    //The tasks which are still scheduled, the only ones the script may cancel
+    tasks: Mutex<HashSet<u64>>,
+}
+
+impl Script {
+    /// Compiles the script without running it
+    pub fn compile(name: &str, source: &str) -> Result<Arc<Self>, String> {
+        let ast = new_engine().compile(source).map_err(|e| e.to_string())?;
+        Ok(Arc::new_cyclic(|script| {
+            let owner = get_owner(name);
+            let mut engine = new_engine();
+            register_api(&mut engine, script, name, &owner);
+            Self { name: name.to_string(), owner, engine, ast, tasks: Mutex::new(HashSet::new()) }
+        }))
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    /// What the script's event handlers and tasks are registered as
+    pub fn get_owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// Runs the top level of the script
+    pub fn run(&self) -> Result<(), String> {
+        self.engine.run_ast(&self.ast).map_err(|e| e.to_string())
+    }
+
+    /// Calls a function of the script, logging the error if it fails
+    fn call(&self, func: &FnPtr, args: impl rhai::FuncArgs) -> Option<Dynamic> {
+        match func.call::<Dynamic>(&self.engine, &self.ast, args) {
+            Ok(result) => Some(result),
+            Err(e) => {
+                log::error!("Error in script {}: {e}", self.name);
+                None
+            },
+        }
+    }
+
+    /// Removes the commands of the script
+    pub fn unregister_commands(self: &Arc<Self>) {
+        let script = Arc::downgrade(self);
+        COMMANDS.write().unwrap().retain(|_, command| !command.script.ptr_eq(&script));
+    }
+}
+
+/// Scripts own their handlers and tasks as `script:<name>`, which plugin names can't be
+pub fn get_owner(name: &str) -> String {
+    format!("script:{name}")
+}
+
+/// The commands scripts registered
+pub fn get_commands() -> Vec<Command> {
+    COMMANDS.read().unwrap().iter()
+        .map(|(name, command)| Command::new(
+            name,
+            None,
+            CommandNode::single_usage(name, &[Argument::new(
+                "args".to_string(),
+                ArgType::String { properties: Some(StringArgProperties::new(StringType::Greedy)) },
+                false
+            )]),
+            command.permission.as_deref(),
+            &[],
+            run_command
+        ))
+        .collect()
+}
+
+fn run_command(e: CommandEvent) -> CommandFuture {
+    let command = COMMANDS.read().unwrap().get(e.get_command().get_name())
+        .and_then(|command| Some((command.script.upgrade()?, command.handler.clone())));
+    let result = match command {
+        None => Err(CommandError::Other { message: "The script of this command isn't loaded".to_string() }),
+        Some((script, handler)) => {
+            let args: Array = e.get_args().iter().cloned().map(Dynamic::from).collect();
+            match script.call(&handler, (e.get_sender().clone(), args)) {
+                None => Err(CommandError::Other { message: "An error occurred while running the command".to_string() }),
+                Some(result) => Ok(CommandSuccess::new(result.as_int().unwrap_or(1) as i32).with_broadcast(false)),
+            }
+        },
+    };
+    Box::pin(std::future::ready(result))
+}
+
+fn new_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine
+}
+
+fn register_api(engine: &mut Engine, script: &Weak<Script>, name: &str, owner: &str) {
+    let prefix = format!("[{name}]");
+    engine.on_print(move |text| log::info!("{prefix} {text}"));
+    let prefix = format!("[{name}]");
+    engine.on_debug(move |text, _, position| log::debug!("{prefix} {position:?}: {text}"));
+
+    engine.register_type_with_name::<Arc<Player>>("Player")
+        .register_get("name", |player: &mut Arc<Player>| player.get_name().to_string())
+        .register_get("uuid", |player: &mut Arc<Player>| player.get_uuid().to_string())
+        .register_fn("send_message", |player: &mut Arc<Player>, message: &str| {
+            let (player, message) = (player.clone(), message.to_string());
+            RUNTIME.spawn(async move { player.send_message(message).await });
+        })
+        .register_fn("has_permission", |player: &mut Arc<Player>, permission: &str| player.has_permission(permission));
+
+    engine.register_type_with_name::<CommandSender>("Sender")
+        .register_get("name", |sender: &mut CommandSender| sender.get_name().map_or(Dynamic::UNIT, Dynamic::from))
+        .register_get("player", |sender: &mut CommandSender| sender.get_player().map_or(Dynamic::UNIT, Dynamic::from))
+        .register_fn("send_message", |sender: &mut CommandSender, message: &str| sender.send_message(message.to_string()))
+        .register_fn("has_permission", |sender: &mut CommandSender, permission: &str| sender.has_permission(permission));
+
+    engine.register_fn("broadcast", |message: &str| {
+        RUNTIME.spawn(THE_SERVER.broadcast_text(TextComponent::builder().text(message).build()));
+    });
+    engine.register_fn("get_player", |name: &str| {
+        wait(THE_SERVER.get_player_by_name_async(name)).as_ref().and_then(Weak::upgrade).map_or(Dynamic::UNIT, Dynamic::from)
+    });
+    engine.register_fn("get_players", || -> Array {
+        wait(THE_SERVER.get_players_async()).iter().filter_map(Weak::upgrade).map(Dynamic::from).collect()
+    });
+
+    let (handler_script, handler_owner) = (script.clone(), owner.to_string());
+    let on = move |event: &str, priority: &str, handler: FnPtr| -> Result<(), Box<EvalAltResult>> {
+        let priority = match priority {
+            "lowest" => EventPriority::Lowest,
+            "low" => EventPriority::Low,
+            "normal" => EventPriority::Normal,
+            "high" => EventPriority::High,
+            "highest" => EventPriority::Highest,
+            "monitor" => EventPriority::Monitor,
+            _ => return Err(format!("Unknown priority '{priority}'").into()),
+        };
+        let script = handler_script.clone();
+        let handler: NamedHandler = Arc::new(move |data| {
+            let Some(script) = script.upgrade() else {
+                return EventResult::Default;
+            };
+            let data = match rhai::serde::to_dynamic(data) {
+                Ok(data) => data,
+                Err(e) => {
+                    log::error!("Error in script {}: {e}", script.name);
+                    return EventResult::Default;
+                },
+            };
+            match script.call(&handler, (data,)).and_then(|result| result.as_bool().ok()) {
+                Some(false) => EventResult::Deny,
+                Some(true) => EventResult::Allow,
+                None => EventResult::Default,
+            }
+        });
+        match named::register_named_handler(&handler_owner, event, priority, handler) {
+            Some(_) => Ok(()),
+            None => Err(format!("Unknown event '{event}', the events are {}", named::EVENT_NAMES.join(", ")).into()),
+        }
+    };
+    let on_normal = on.clone();
+    engine.register_fn("on", move |event: &str, handler: FnPtr| on_normal(event, "normal", handler));
+    engine.register_fn("on", on);
+
+    let command_script = script.clone();
+    let command = move |name: &str, permission: Option<String>, handler: FnPtr| -> Result<(), Box<EvalAltResult>> {
+        let name = name.to_lowercase();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(format!("Invalid command name '{name}'").into());
+        }
+        if let Some(permission) = &permission {
+            validate_node(permission).map_err(|e| e.to_string())?;
+        }
+        //Scripts can't take the name of a builtin command or another script's command
+        let taken = builtin::is_builtin(&name) || COMMANDS.read().unwrap().get(&name)
+            .is_some_and(|command| command.script.strong_count() > 0 && !command.script.ptr_eq(&command_script));
+        if taken {
+            return Err(format!("There already is a command /{name}").into());
+        }
+        //Commands are only added to the command map while the server enables
+        let registered = COMMAND_MAP.try_lock()
+            .map_or(true, |map| map.as_ref().map_or(true, |map| map.get_command(&name).is_some()));
+        if !registered {
+            log::info!("Reload the server to use the new command /{name}");
+        }
+        COMMANDS.write().unwrap().insert(name, ScriptCommand { script: command_script.clone(), handler, permission });
+        Ok(())
+    };
+    let command_anyone = command.clone();
+    engine.register_fn("command", move |name: &str, handler: FnPtr| command_anyone(name, None, handler));
+    engine.register_fn("command", move |name: &str, permission: &str, handler: FnPtr| {
+        command(name, Some(permission.to_string()), handler)
+    });
+
+    let (task_script, task_owner) = (script.clone(), owner.to_string());
+    let schedule = move |delay: i64, period: Option<i64>, handler: FnPtr| -> i64 {
+        let script = task_script.clone();
+        //Set once the task is scheduled, which is before it can run
+        let id = Arc::new(OnceLock::new());
+        let task_id = id.clone();
+        let task: Task = Box::new(move || {
+            let Some(script) = script.upgrade() else {
+                return;
+            };
+            if period.is_none() {
+                if let Some(id) = task_id.get() {
+                    script.tasks.lock().unwrap().remove(id);
+                }
+            }
+            script.call(&handler, ());
+        });
+        let mut scheduler = THE_SERVER.get_scheduler().lock().unwrap();
+        let delay = delay.max(0) as u64;
+        let scheduled = match period {
+            Some(period) => scheduler.schedule_repeating(&task_owner, delay, period.max(1) as u64, task),
+            None => scheduler.schedule(&task_owner, delay, task),
+        };
+        let _ = id.set(scheduled.0);
+        if let Some(script) = task_script.upgrade() {
+            script.tasks.lock().unwrap().insert(scheduled.0);
+        }
+        scheduled.0 as i64
+    };
+    let schedule_once = schedule.clone();
+    engine.register_fn("schedule", move |delay: i64, handler: FnPtr| schedule_once(delay, None, handler));
+    engine.register_fn("schedule_repeating", move |delay: i64, period: i64, handler: FnPtr| schedule(delay, Some(period), handler));
+    let cancel_script = script.clone();
+    engine.register_fn("cancel_task", move |id: i64| -> bool {
+        //Scripts can only cancel their own tasks
+        let Some(script) = cancel_script.upgrade() else {
+            return false;
+        };
+        if !script.tasks.lock().unwrap().remove(&(id as u64)) {
+            return false;
+        }
+        THE_SERVER.get_scheduler().lock().unwrap().cancel(TaskId(id as u64))
+    });
+}
+
+/// Scripts run in sync code inside the runtime, which can't block on a future,
+/// so they wait for the server's async accessors on another thread
+fn wait<F: Future + Send>(future: F) -> F::Output where F::Output: Send {
+    std::thread::scope(|scope| scope.spawn(|| RUNTIME.block_on(future)).join().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors() {
+        assert!(Script::compile("broken", "let x = ;").is_err());
+        let script = Script::compile("looping", "let x = 0; loop { x += 1; }").unwrap();
+        assert_eq!(script.get_owner(), "script:looping");
+        assert!(script.run().is_err());
+        assert!(Script::compile("fine", "let x = 1 + 1;").unwrap().run().is_ok());
+    }
+    #[test]
+    fn commands() {
+        assert!(Script::compile("op", r#"command("op", |sender, args| 1);"#).unwrap().run().is_err());
+        let script = Script::compile("heal", r#"command("heal", "test.heal", |sender, args| 1);"#).unwrap();
+        assert!(script.run().is_ok());
+        let other = Script::compile("other", r#"command("heal", |sender, args| 1);"#).unwrap();
+        assert!(other.run().is_err());
+
+        let commands = get_commands();
+        let heal = commands.iter().find(|command| command.get_name() == "heal").unwrap();
+        assert_eq!(heal.get_permission().map(String::as_str), Some("test.heal"));
+        script.unregister_commands();
+        assert!(get_commands().iter().all(|command| command.get_name() != "heal"));
+    }
+
+    #[test]
+    fn own_tasks() {
+        let eval = |name: &str, source: &str| {
+            let script = Script::compile(name, source).unwrap();
+            script.engine.eval_ast::<Dynamic>(&script.ast).unwrap()
+        };
+        let id = eval("timer", "schedule(100, || 1)").as_int().unwrap();
+        assert!(!eval("thief", &format!("cancel_task({id})")).as_bool().unwrap());
+        let own = format!("let id = schedule(100, || 1); cancel_task(id) && !cancel_task({id})");
+        assert!(eval("owner", &own).as_bool().unwrap());
+    }
+}
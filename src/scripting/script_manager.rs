@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::{SCRIPT_MANAGER, THE_SERVER};
+
+use super::script::Script;
+
+pub const SCRIPTS_DIRECTORY: &str = "scripts";
+
+pub const SCRIPT_EXTENSION: &str = "rhai";
+
+/// How many ticks pass between checking the scripts directory for changes
+pub const RELOAD_INTERVAL: u64 = 20;
+
+struct LoadedScript {
+    modified: Option<SystemTime>,
+    //None if the script didn't compile or failed to run
+    script: Option<Arc<Script>>,
+}
+
+/// Loads the scripts in the scripts directory, and loads them again when they change.
+/// Scripts which fail are logged and don't stop the others.
+pub struct ScriptManager {
+    directory: PathBuf,
+    scripts: BTreeMap<String, LoadedScript>,
+    ticks: u64,
+}
+
+impl ScriptManager {
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self { directory: directory.into(), scripts: BTreeMap::new(), ticks: 0 }
+    }
+
+    /// The scripts which are running
+    pub fn get_scripts(&self) -> impl Iterator<Item = &Arc<Script>> {
+        self.scripts.values().filter_map(|loaded| loaded.script.as_ref())
+    }
+
+    pub fn get_script(&self, name: &str) -> Option<&Arc<Script>> {
+        self.scripts.get(name).and_then(|loaded| loaded.script.as_ref())
+    }
+
+    /// Loads the scripts, creating the directory if it doesn't exist
+    pub fn load_scripts(&mut self) {
+        if let Err(e) = std::fs::create_dir_all(&self.directory) {
+            log::error!("Couldn't create {}: {e}", self.directory.display());
+            return;
+        }
+        self.reload_changed();
+    }
+
+    /// Checks for changed scripts every `RELOAD_INTERVAL` ticks
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+        if self.ticks % RELOAD_INTERVAL == 0 {
+            self.reload_changed();
+        }
+    }
+
+    /// Loads new and changed scripts, and unloads the deleted ones
+    pub fn reload_changed(&mut self) {
+        let files = match read_scripts(&self.directory) {
+            Ok(files) => files,
+            Err(e) => {
+                log::error!("Couldn't read {}: {e}", self.directory.display());
+                return;
+            },
+        };
+        let deleted: Vec<String> = self.scripts.keys().filter(|name| !files.contains_key(*name)).cloned().collect();
+        for name in deleted {
+            if let Some(script) = self.scripts.remove(&name).and_then(|loaded| loaded.script) {
+                unload(&script);
+                log::info!("Unloaded script {name}");
+            }
+        }
+        for (name, (path, modified)) in files {
+            let changed = self.scripts.get(&name).map_or(true, |loaded| loaded.modified != modified);
+            if changed {
+                self.load(&name, &path, modified);
+            }
+        }
+    }
+
+    fn load(&mut self, name: &str, path: &Path, modified: Option<SystemTime>) {
+        let compiled = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|source| Script::compile(name, &source));
+        let loaded = self.scripts.entry(name.to_string()).or_insert(LoadedScript { modified, script: None });
+        loaded.modified = modified;
+        let script = match compiled {
+            Ok(script) => script,
+            Err(e) => {
+                //The old version keeps running until the script is fixed
+                log::error!("Couldn't load script {name}: {e}");
+                return;
+            },
+        };
+        let reloaded = loaded.script.take().map(|old| unload(&old)).is_some();
+        if let Err(e) = script.run() {
+            log::error!("Error in script {name}: {e}");
+            unload(&script);
+            return;
+        }
+        log::info!("{} script {name}", if reloaded { "Reloaded" } else { "Loaded" });
+        loaded.script = Some(script);
+    }
+
+    /// Unloads every script
+    pub fn unload_scripts(&mut self) {
+        for script in std::mem::take(&mut self.scripts).into_values().filter_map(|loaded| loaded.script) {
+            unload(&script);
+        }
+    }
+}
+
+/// Unregisters the script's event handlers, tasks and commands
+fn unload(script: &Arc<Script>) {
+    THE_SERVER.get_event_manager().unregister_owner(script.get_owner());
+    THE_SERVER.get_scheduler().lock().unwrap().cancel_owner(script.get_owner());
+    script.unregister_commands();
+}
+
+/// The scripts in the directory by name, with when they were last modified
+fn read_scripts(directory: &Path) -> std::io::Result<BTreeMap<String, (PathBuf, Option<SystemTime>)>> {
+    let mut scripts = BTreeMap::new();
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if !path.is_file() || !path.extension().is_some_and(|ext| ext == SCRIPT_EXTENSION) {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|name| name.to_str()).map(str::to_string) else {
+            continue;
+        };
+        let modified = path.metadata().and_then(|metadata| metadata.modified()).ok();
+        scripts.insert(name, (path, modified));
+    }
+    Ok(scripts)
+}
+
+/// Reloads the scripts which changed, called every tick
+pub async fn tick() {
+    if let Some(script_manager) = SCRIPT_MANAGER.lock().await.as_mut() {
+        script_manager.tick();
+    }
+}
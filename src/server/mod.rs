@@ -1,4 +1,6 @@
+pub mod scheduler;
 pub mod server;
 pub mod server_properties;
+pub mod user_list;
 
 pub use server::*;
\ No newline at end of file
@@ -0,0 +1,147 @@
+use std::collections::HashSet;
+
+/// Runs on the server thread between ticks. Tasks which need to await spawn themselves on the runtime.
+pub type Task = Box<dyn FnMut() + Send>;
+
+/// Identifies a scheduled task, to cancel it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskId(pub u64);
+
+struct ScheduledTask {
+    id: TaskId,
+    owner: String,
+    tick: u64,
+    period: Option<u64>,
+    task: Task,
+}
+
+/// Tasks plugins and scripts run after a delay, or every few ticks
+pub struct Scheduler {
+    ticks: u64,
+    next_id: u64,
+    tasks: Vec<ScheduledTask>,
+    //Tasks running right now which were cancelled, so repeating ones aren't scheduled again
+    running: HashSet<TaskId>,
+    cancelled: HashSet<TaskId>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self { ticks: 0, next_id: 0, tasks: vec![], running: HashSet::new(), cancelled: HashSet::new() }
+    }
+
+    /// Ticks since the server started
+    pub fn get_ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Runs the task in `delay` ticks, at least 1. `owner` is the plugin or script scheduling it.
+    pub fn schedule(&mut self, owner: &str, delay: u64, task: Task) -> TaskId {
+        self.add(owner, delay, None, task)
+    }
+
+    /// Runs the task in `delay` ticks, then every `period` ticks until it is cancelled
+    pub fn schedule_repeating(&mut self, owner: &str, delay: u64, period: u64, task: Task) -> TaskId {
+        self.add(owner, delay, Some(period.max(1)), task)
+    }
+
+    fn add(&mut self, owner: &str, delay: u64, period: Option<u64>, task: Task) -> TaskId {
+        let id = TaskId(self.next_id);
+        self.next_id += 1;
+        self.tasks.push(ScheduledTask { id, owner: owner.to_string(), tick: self.ticks + delay.max(1), period, task });
+        id
+    }
+
+    /// Returns false if there is no such task
+    pub fn cancel(&mut self, id: TaskId) -> bool {
+        if self.running.contains(&id) {
+            return self.cancelled.insert(id);
+        }
+        let before = self.tasks.len();
+        self.tasks.retain(|task| task.id != id);
+        self.tasks.len() != before
+    }
+
+    /// Cancels every task of the owner, like a plugin being disabled.
+    /// Returns how many tasks were cancelled.
+    pub fn cancel_owner(&mut self, owner: &str) -> usize {
+        let before = self.tasks.len();
+        self.tasks.retain(|task| task.owner != owner);
+        before - self.tasks.len()
+    }
+
+    /// Advances a tick and takes out the tasks which are due, in the order they were scheduled
+    fn take_due(&mut self) -> Vec<ScheduledTask> {
+        self.ticks += 1;
+        let ticks = self.ticks;
+        let (due, pending) = std::mem::take(&mut self.tasks).into_iter().partition(|task| task.tick <= ticks);
+        self.tasks = pending;
+        self.running = due.iter().map(|task: &ScheduledTask| task.id).collect();
+        due
+    }
+
+    /// Puts the repeating tasks which ran back, unless they were cancelled while running
+    fn reschedule(&mut self, ran: Vec<ScheduledTask>) {
+        for mut task in ran {
+            if let Some(period) = task.period.filter(|_| !self.cancelled.contains(&task.id)) {
+                task.tick = self.ticks + period;
+                self.tasks.push(task);
+            }
+        }
+        self.running.clear();
+        self.cancelled.clear();
+    }
+}
+
+/// Runs the tasks which are due. The scheduler isn't locked while they run,
+/// so tasks may schedule and cancel tasks.
+pub fn tick(scheduler: &std::sync::Mutex<Scheduler>) {
+    let mut due = scheduler.lock().unwrap().take_due();
+    for task in due.iter_mut() {
+        if let Err(payload) = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| (task.task)())) {
+            let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            log::error!("A task of {} panicked: {message}", task.owner);
+            //A task which panicked won't run again
+            task.period = None;
+        }
+    }
+    scheduler.lock().unwrap().reschedule(due);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    #[test]
+    fn tasks() {
+        let scheduler = Arc::new(Mutex::new(Scheduler::new()));
+        let runs = Arc::new(Mutex::new(Vec::new()));
+
+        let log = runs.clone();
+        scheduler.lock().unwrap().schedule("a", 2, Box::new(move || log.lock().unwrap().push("once")));
+        let log = runs.clone();
+        let inner = scheduler.clone();
+        let repeating = Arc::new(Mutex::new(None));
+        let id = repeating.clone();
+        *repeating.lock().unwrap() = Some(scheduler.lock().unwrap().schedule_repeating("b", 1, 2, Box::new(move || {
+            log.lock().unwrap().push("repeat");
+            if log.lock().unwrap().len() == 3 {
+                //Cancelling itself while running
+                inner.lock().unwrap().cancel(id.lock().unwrap().unwrap());
+            }
+        })));
+        let log = runs.clone();
+        scheduler.lock().unwrap().schedule("c", 3, Box::new(move || log.lock().unwrap().push("cancelled")));
+        assert_eq!(scheduler.lock().unwrap().cancel_owner("c"), 1);
+
+        for _ in 0..8 {
+            tick(&scheduler);
+        }
+        assert_eq!(*runs.lock().unwrap(), ["repeat", "once", "repeat"]);
+        assert!(scheduler.lock().unwrap().tasks.is_empty());
+    }
+}
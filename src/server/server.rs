@@ -1,26 +1,55 @@
 use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::error::Error;
+use std::net::IpAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::RwLock;
 use std::sync::Weak;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 
 use tokio::sync::RwLockReadGuard;
 use tokio::sync::RwLockWriteGuard;
 use uuid::Uuid;
 
+use crate::event::events::player_login::PlayerLoginResult;
 use crate::event::EventHandler;
+use crate::event::HandlerId;
+use crate::event::PlayerChangedWorldEvent;
+use crate::game::difficulty::Difficulty;
 use crate::event::EventManager;
-use crate::event::HandlerList;
 use crate::event::TraitEvent;
+use crate::data_types::text_component::{Nbt, TextComponent};
+use crate::data_types::PlayerInfoActions;
+use crate::data_types::Vec3d;
+use crate::data_types::identifier::Identifier;
+use crate::data_types::PlayerInfoEntry;
+use crate::packet::play::CPlayerInfoUpdate;
+use crate::packet::Clientbound;
+use crate::permission::{FilePermissionProvider, PermissionProvider, PERMISSIONS_FILE};
+use crate::keep_alive::KeepAliveStatus;
 use crate::player::Player;
 use crate::player::Players;
 
-use crate::world::chunk_loader::Loader;
-use crate::world::World;
+use super::scheduler::{self, Scheduler};
+use super::user_list::*;
+use crate::world::chunk_loader::{Loader, VanillaLoader};
+use crate::world::{BlockChanges, World};
 use crate::ServerProperties;
+use crate::server::server_properties::{WritePropertiesError, SERVER_PROPERTIES_FILE};
+
+/// Loads one of the vanilla lists, or exits if it is broken so it isn't overwritten
+fn load_user_list<E: UserListEntry>(path: PathBuf) -> UserList<E> {
+    UserList::load(&path).unwrap_or_else(|e| {
+        log::error!("Couldn't load {}: {e}", path.display());
+        std::process::exit(1);
+    })
+}
+
+/// Vanilla sends the tab list latency every 600 ticks
+const LATENCY_UPDATE_INTERVAL: u64 = 600;
 
 #[derive(Debug)]
 pub struct ServerFullError;
@@ -36,32 +65,78 @@ impl std::fmt::Display for ServerFullError {
 pub struct Server {
     //const
     properties: ServerProperties,
+    //Where server.properties, the user lists and the permissions are
+    directory: PathBuf,
     worlds: HashMap<String, Arc<tokio::sync::Mutex<World>>>,
+    //The blocks of each world, which don't need the world to be locked
+    block_changes: HashMap<String, Arc<BlockChanges>>,
     players: Players,
     entity_id_cap: Mutex<i32>,
+    player_ticks: Mutex<u64>,
     event_manager: EventManager,
-    is_running: bool,
+    is_running: AtomicBool,
+    difficulty: Mutex<Difficulty>,
+    permission_provider: RwLock<Arc<dyn PermissionProvider>>,
+    ops: UserList<OpEntry>,
+    whitelist: UserList<WhitelistEntry>,
+    banned_players: UserList<PlayerBanEntry>,
+    banned_ips: UserList<IpBanEntry>,
+    whitelist_enabled: AtomicBool,
+    scheduler: Mutex<Scheduler>,
 }
 
 impl Server {
-    pub fn new(properties: ServerProperties) -> Self {
+    pub fn new(properties: ServerProperties, directory: PathBuf) -> Self {
         let max_players = properties.get_max_players();
+        let difficulty = properties.get_difficulty();
+        let whitelist_enabled = properties.is_white_list();
+        let mut worlds = HashMap::with_capacity(3);
+        //TODO: load the world from its directory once worlds are saved
+        let level_name = properties.get_level_name().to_string();
+        let overworld = World::create_new_world(
+            level_name.clone(),
+            Identifier::new("minecraft:overworld").unwrap(),
+            Box::new(VanillaLoader::new(level_name.clone()))
+        );
+        let mut block_changes = HashMap::with_capacity(3);
+        if let Some(overworld) = overworld {
+            block_changes.insert(level_name.clone(), overworld.get_block_changes().clone());
+            worlds.insert(level_name, Arc::new(tokio::sync::Mutex::new(overworld)));
+        }
+        let permissions_file = directory.join(PERMISSIONS_FILE);
         Server { 
             properties: properties,
-            worlds: HashMap::with_capacity(3),
+            worlds: worlds,
+            block_changes: block_changes,
             players: Players::new(max_players),
             entity_id_cap: Mutex::new(0),
+            player_ticks: Mutex::new(0),
             event_manager: EventManager::new(),
-            is_running: false,
+            is_running: AtomicBool::new(false),
+            difficulty: Mutex::new(difficulty),
+            permission_provider: RwLock::new(Arc::new(
+                FilePermissionProvider::load(&permissions_file).unwrap_or_else(|e| {
+                    log::error!("Couldn't load {}: {e}", permissions_file.display());
+                    std::process::exit(1);
+                })
+            )),
+            ops: load_user_list(directory.join(OPS_FILE)),
+            whitelist: load_user_list(directory.join(WHITELIST_FILE)),
+            banned_players: load_user_list(directory.join(BANNED_PLAYERS_FILE)),
+            banned_ips: load_user_list(directory.join(BANNED_IPS_FILE)),
+            directory: directory,
+            whitelist_enabled: AtomicBool::new(whitelist_enabled),
+            scheduler: Mutex::new(Scheduler::new()),
         }
     }
 
-    pub fn set_running(&mut self, value: bool) {
-        self.is_running = value;
+    pub fn set_running(&self, value: bool) {
+        self.is_running.store(value, Ordering::Release);
     }
 
+    /// Whether the server is enabled and accepting commands
     pub fn is_running(&self) -> bool {
-        self.is_running
+        self.is_running.load(Ordering::Acquire)
     }
 
     pub async fn get_next_eid(&self) -> i32 {
@@ -72,12 +147,107 @@ impl Server {
         idx_value
     }
 
+    pub fn get_difficulty(&self) -> Difficulty {
+        *self.difficulty.lock().unwrap()
+    }
+
+    pub fn set_difficulty(&self, difficulty: Difficulty) {
+        *self.difficulty.lock().unwrap() = difficulty;
+    }
+
+    pub fn get_permission_provider(&self) -> Arc<dyn PermissionProvider> {
+        self.permission_provider.read().unwrap().clone()
+    }
+
+    /// Replaces where permissions come from, and resends everyone the commands they may use now
+    pub async fn set_permission_provider(&self, provider: Arc<dyn PermissionProvider>) {
+        *self.permission_provider.write().unwrap() = provider;
+        for player in self.get_players_async().await.iter().filter_map(Weak::upgrade) {
+            crate::command::brigadier::send_commands(&player).await;
+        }
+    }
+
+    pub fn get_ops(&self) -> &UserList<OpEntry> {
+        &self.ops
+    }
+
+    pub fn get_whitelist(&self) -> &UserList<WhitelistEntry> {
+        &self.whitelist
+    }
+
+    pub fn get_banned_players(&self) -> &UserList<PlayerBanEntry> {
+        &self.banned_players
+    }
+
+    pub fn get_banned_ips(&self) -> &UserList<IpBanEntry> {
+        &self.banned_ips
+    }
+
+    pub fn is_whitelist_enabled(&self) -> bool {
+        self.whitelist_enabled.load(Ordering::Acquire)
+    }
+
+    /// Turns the whitelist on or off, and saves it as `white-list` in server.properties
+    pub fn set_whitelist_enabled(&self, value: bool) -> Result<(), WritePropertiesError> {
+        self.whitelist_enabled.store(value, Ordering::Release);
+        let mut properties = self.properties.clone();
+        properties.set_white_list(value);
+        properties.save(&self.directory.join(SERVER_PROPERTIES_FILE))
+    }
+
+    /// Whether the player may join while the whitelist is on. Ops always may.
+    pub fn is_whitelisted(&self, uuid: Uuid) -> bool {
+        self.whitelist.contains(&uuid) || self.ops.contains(&uuid)
+    }
+
+    /// Whether the player is an op with at least the `op-permission-level`, which gives every permission
+    pub fn is_op(&self, uuid: Uuid) -> bool {
+        self.ops.get(&uuid).is_some_and(|op| op.get_level() >= self.properties.get_op_permission_level())
+    }
+
+    /// Checks the ban lists, then the whitelist
+    pub fn check_login(&self, uuid: Uuid, ip: IpAddr) -> PlayerLoginResult {
+        if let Some(ban) = self.banned_players.get(&uuid) {
+            let message = ban.get_details().get_kick_message("You are banned from this server.");
+            return PlayerLoginResult::KickBanned { message: Some(message) };
+        }
+        if let Some(ban) = self.banned_ips.get(&ip.to_string()) {
+            let message = ban.get_details().get_kick_message("Your IP address is banned from this server.");
+            return PlayerLoginResult::KickBanned { message: Some(message) };
+        }
+        if self.is_whitelist_enabled() && !self.is_whitelisted(uuid) {
+            return PlayerLoginResult::KickWhitelist { message: None };
+        }
+        PlayerLoginResult::Allowed
+    }
+
+    /// Kicks the players who aren't whitelisted, if the whitelist is on and `enforce-whitelist` is set
+    pub async fn enforce_whitelist(&self) {
+        if !self.is_whitelist_enabled() || !self.properties.is_enforce_whitelist() {
+            return;
+        }
+        for player in self.get_players_async().await.iter().filter_map(Weak::upgrade) {
+            if !self.is_whitelisted(player.get_uuid()) {
+                player.disconnect_tc(PlayerLoginResult::default_kick_whitelist_message()).await;
+            }
+        }
+    }
+
+    /// Sends a system message to every player who joined, and logs it
+    pub async fn broadcast_text(&self, message: TextComponent<Nbt>) {
+        log::info!("{}", message.to_plain_string());
+        for player in self.get_players_async().await.iter().filter_map(Weak::upgrade) {
+            player.send_text(message.clone()).await;
+        }
+    }
+
     pub fn get_properties(&self) -> &ServerProperties {
         &self.properties
     }
 
     pub async fn register_player(&self, player: Player) -> Result<Arc<Player>, ServerFullError> {
-        if self.players.get_num_players().await == self.get_max_players() {
+        let bypasses_limit = self.ops.get(&player.get_uuid()).is_some_and(|op| op.bypasses_player_limit());
+        if self.players.get_num_players().await >= self.get_max_players() && !bypasses_limit {
             player.disconnect("Server is full!").await;
             return Err(ServerFullError);
         }
@@ -149,6 +319,44 @@ impl Server {
         crate::RUNTIME.block_on(self.drop_player_by_uuid_async(uuid))
     }
 
+    pub fn get_worlds(&self) -> impl Iterator<Item = &Arc<tokio::sync::Mutex<World>>> {
+        self.worlds.values()
+    }
+
+    pub fn get_world(&self, level_name: &str) -> Option<&Arc<tokio::sync::Mutex<World>>> {
+        self.worlds.get(level_name)
+    }
+
+    /// The blocks of a world, which can be read and set while it is locked, like during its tick.
+    /// Players only see the changes once the world's `update_block` is called.
+    pub fn get_block_changes(&self, level_name: &str) -> Option<&Arc<BlockChanges>> {
+        self.block_changes.get(level_name)
+    }
+
+    /// Moves the player into another world at `position`.
+    /// Neither world may be locked by the caller.
+    pub async fn change_world(&self, player: &Arc<Player>, level_name: &str, position: Vec3d) -> Result<(), String> {
+        let Some(to) = self.get_world(level_name) else {
+            return Err(format!("Unknown world '{level_name}'"));
+        };
+        let from = player.get_world_name();
+        if from.as_deref() == Some(level_name) {
+            return Err(format!("{} is already in {level_name}", player.get_name()));
+        }
+        if let Some(from) = from.as_deref().and_then(|from| self.get_world(from)) {
+            from.lock().await.remove_player_by_id(player.get_id(), player.get_entity_id());
+        }
+        let to = to.lock().await;
+        player.queue_send_packet(to.create_respawn_packet(player, 0x03)).await;
+        to.add_player(player.get_id(), Arc::downgrade(player), position);
+        drop(to);
+        player.synchronize_position(position).await;
+
+        let mut e = PlayerChangedWorldEvent::new(Arc::downgrade(player), from);
+        crate::event::listen(self.get_event_manager(), &mut e).await;
+        Ok(())
+    }
+
     pub async fn tick_worlds(&'static self) {
         let mut handles = Vec::new();
         for (_, world) in &self.worlds {
@@ -160,6 +368,54 @@ impl Server {
         }
     }
 
+    /// Sends keep alives to every connected player and 
+    /// periodically updates everyone's latency in the tab list.
+    pub async fn tick_players(&'static self) {
+        let tick = {
+            let mut tick_lock = self.player_ticks.lock().unwrap();
+            *tick_lock += 1;
+            *tick_lock - 1
+        };
+
+        let players: Vec<Arc<Player>> = self.get_players_async().await
+            .iter()
+            .filter_map(|weak| weak.upgrade())
+            .collect();
+
+        //Players get an entity id once they are in the play state
+        let latency_update = if tick % LATENCY_UPDATE_INTERVAL == 0 {
+            let entries: Vec<PlayerInfoEntry> = players.iter()
+                .filter(|player| player.get_entity_id() != -1)
+                .map(|player| player.get_info_entry())
+                .collect();
+            Some(Arc::new(CPlayerInfoUpdate::new(PlayerInfoActions::UPDATE_LATENCY, entries).to_be_bytes()))
+        } else {
+            None
+        };
+
+        for player in players {
+            let keep_alive = player.poll_keep_alive();
+            let latency_update = latency_update.clone().filter(|_| player.get_entity_id() != -1);
+            //Most ticks nobody needs anything
+            if keep_alive == KeepAliveStatus::Waiting && latency_update.is_none() {
+                continue;
+            }
+            crate::RUNTIME.spawn(async move {
+                match keep_alive {
+                    KeepAliveStatus::Due(id) => player.send_keep_alive(id).await,
+                    KeepAliveStatus::TimedOut => player.timed_out().await,
+                    KeepAliveStatus::Waiting => (),
+                }
+                if let Some(latency_update) = latency_update {
+                    player.queue_send_raw(vec![latency_update.to_vec()]).await;
+                    if player.flush_send_queue().await.is_err() {
+                        player.connection_lost().await;
+                    }
+                }
+            });
+        }
+    }
+
     pub async fn save_worlds(&'static self) {
 
     }
@@ -168,8 +424,17 @@ impl Server {
         &self.event_manager
     }
 
-    pub fn register_event_handler<E: TraitEvent + PartialEq + Clone + 'static>(&self, handler: EventHandler<E>) {
-        self.get_event_manager().register_event_handler::<E>(handler);
+    pub fn get_scheduler(&self) -> &Mutex<Scheduler> {
+        &self.scheduler
+    }
+
+    /// Runs the scheduled tasks which are due
+    pub fn tick_scheduler(&self) {
+        scheduler::tick(&self.scheduler);
+    }
+
+    pub fn register_event_handler<E: TraitEvent + ?Sized>(&self, handler: EventHandler<E>) -> HandlerId {
+        self.get_event_manager().register_event_handler::<E>(handler)
     }
 
 }
\ No newline at end of file
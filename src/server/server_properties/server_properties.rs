@@ -6,30 +6,51 @@ use std::io::{BufRead, BufReader, BufWriter, Write};
 
 
 use serde::{Serialize, Deserialize};
+use sha2::{Digest, Sha256};
 
 use server_macros::ServerPropertiesDerive;
 
 
 
-//use crate::game::gamemode::Gamemode;
+use crate::game::difficulty::Difficulty;
+use crate::game::gamemode::Gamemode;
 
 
 
 
 
-#[derive(Serialize, Deserialize, Debug, ServerPropertiesDerive)]
+/// Where the properties are loaded from and saved to
+pub const SERVER_PROPERTIES_FILE: &str = "server.properties";
+
+#[derive(Serialize, Deserialize, Debug, Clone, ServerPropertiesDerive)]
 pub struct ServerProperties {
     #[serde(rename = "server-port")]
     server_port: u16,
 
     motd: String,
 
+    gamemode: Gamemode,
+
+    difficulty: Difficulty,
+
+    #[serde(rename = "level-name")]
+    level_name: String,
+
+    #[serde(rename = "level-seed")]
+    level_seed: i64,
+
     #[serde(rename = "max-players")]
     max_players: i32,
 
     #[serde(rename = "online-mode")]
     online_mode: bool,
 
+    #[serde(rename = "enforce-secure-profile")]
+    enforce_secure_profile: bool,
+
+    #[serde(rename = "profile-keys-url")]
+    profile_keys_url: String,
+
     #[serde(rename = "view-distance")]
     view_distance: i32,
 
@@ -38,6 +59,36 @@ pub struct ServerProperties {
 
     #[serde(rename = "spawn-chunk-radius")]
     spawn_chunk_radius: i32,
+
+    #[serde(rename = "entity-tracking-range-players")]
+    entity_tracking_range_players: i32,
+
+    #[serde(rename = "entity-tracking-range-animals")]
+    entity_tracking_range_animals: i32,
+
+    #[serde(rename = "entity-tracking-range-monsters")]
+    entity_tracking_range_monsters: i32,
+
+    #[serde(rename = "entity-tracking-range-misc")]
+    entity_tracking_range_misc: i32,
+
+    #[serde(rename = "entity-tracking-range-other")]
+    entity_tracking_range_other: i32,
+
+    #[serde(rename = "white-list")]
+    white_list: bool,
+
+    #[serde(rename = "enforce-whitelist")]
+    enforce_whitelist: bool,
+
+    #[serde(rename = "op-permission-level")]
+    op_permission_level: i32,
+
+    #[serde(rename = "max-plugin-fuel-per-call")]
+    max_plugin_fuel_per_call: u64,
+
+    #[serde(rename = "max-plugin-memory")]
+    max_plugin_memory: u64,
 }
 
 impl ServerProperties {
@@ -49,6 +100,30 @@ impl ServerProperties {
         &self.motd
     }
 
+    /// The game mode of players who join for the first time
+    pub fn get_gamemode(&self) -> Gamemode {
+        self.gamemode
+    }
+
+    pub fn get_difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// The world players join
+    pub fn get_level_name(&self) -> &str {
+        &self.level_name
+    }
+
+    pub fn get_level_seed(&self) -> i64 {
+        self.level_seed
+    }
+
+    /// The first 8 bytes of the SHA-256 of the seed, which clients use for biome noise
+    pub fn get_hashed_seed(&self) -> i64 {
+        let hash = Sha256::digest(self.level_seed.to_le_bytes());
+        i64::from_le_bytes(hash[..8].try_into().unwrap())
+    }
+
     pub fn get_max_players(&self) -> i32 {
         self.max_players
     }
@@ -57,6 +132,17 @@ impl ServerProperties {
         self.online_mode
     }
 
+    /// Whether players need a Mojang signed chat session to chat.
+    /// Only offline players can join without one, so this does nothing in offline mode.
+    pub fn is_enforce_secure_profile(&self) -> bool {
+        self.enforce_secure_profile && self.online_mode
+    }
+
+    /// Where the public keys used to verify chat sessions are fetched from
+    pub fn get_profile_keys_url(&self) -> &str {
+        &self.profile_keys_url
+    }
+
     pub fn get_view_distance(&self) -> i32 {
         self.view_distance
     }
@@ -69,16 +155,83 @@ impl ServerProperties {
         self.spawn_chunk_radius
     }
 
+    /// Tracking ranges are in blocks and are capped by the view distance.
+    pub fn get_entity_tracking_range_players(&self) -> i32 {
+        self.entity_tracking_range_players
+    }
+
+    pub fn get_entity_tracking_range_animals(&self) -> i32 {
+        self.entity_tracking_range_animals
+    }
+
+    pub fn get_entity_tracking_range_monsters(&self) -> i32 {
+        self.entity_tracking_range_monsters
+    }
+
+    pub fn get_entity_tracking_range_misc(&self) -> i32 {
+        self.entity_tracking_range_misc
+    }
+
+    pub fn get_entity_tracking_range_other(&self) -> i32 {
+        self.entity_tracking_range_other
+    }
+
+    /// Whether the whitelist is on when the server starts
+    pub fn is_white_list(&self) -> bool {
+        self.white_list
+    }
+
+    pub fn set_white_list(&mut self, white_list: bool) {
+        self.white_list = white_list;
+    }
+
+    /// Whether players who aren't whitelisted are kicked when the whitelist changes
+    pub fn is_enforce_whitelist(&self) -> bool {
+        self.enforce_whitelist
+    }
+
+    /// The level `/op` gives, and which ops need to have every permission
+    pub fn get_op_permission_level(&self) -> i32 {
+        self.op_permission_level
+    }
+
+    /// The most fuel a WASM plugin's manifest can ask for each call
+    pub fn get_max_plugin_fuel_per_call(&self) -> u64 {
+        self.max_plugin_fuel_per_call
+    }
+
+    /// The most memory in bytes a WASM plugin's manifest can ask for
+    pub fn get_max_plugin_memory(&self) -> usize {
+        self.max_plugin_memory as usize
+    }
+
     /// Generates the default server_properties.json
     pub fn default() -> Self {
         ServerProperties { 
             server_port: 25565, 
             motd: "A Minecraft Server (§cMade with Rust!§r)".to_string(), 
+            gamemode: Gamemode::Survival,
+            difficulty: Difficulty::Easy,
+            level_name: "world".to_string(),
+            //A random seed, like an empty level-seed in vanilla
+            level_seed: uuid::Uuid::new_v4().as_u64_pair().0 as i64,
             max_players: 20, 
             online_mode: false,
+            enforce_secure_profile: true,
+            profile_keys_url: "https://api.minecraftservices.com/publickeys".to_string(),
             view_distance: 10,
             simulation_distance: 10,
             spawn_chunk_radius: 11,
+            entity_tracking_range_players: 48,
+            entity_tracking_range_animals: 48,
+            entity_tracking_range_monsters: 48,
+            entity_tracking_range_misc: 32,
+            entity_tracking_range_other: 64,
+            white_list: false,
+            enforce_whitelist: false,
+            op_permission_level: 4,
+            max_plugin_fuel_per_call: 100_000_000,
+            max_plugin_memory: 512 * 1024 * 1024,
         }
     }
 
@@ -88,6 +241,17 @@ impl ServerProperties {
         Ok(())
     }
 
+    /// Writes to a file next to `path` first, so the properties are never half written
+    pub fn save(&self, path: &Path) -> Result<(), WritePropertiesError> {
+        let temporary = path.with_extension("properties.tmp");
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        self.write_to_file(&mut writer)?;
+        writer.flush()?;
+        drop(writer);
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+
     
     
 }
@@ -0,0 +1,433 @@
+use std::error::Error;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use chrono::{DateTime, FixedOffset, Local};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::data_types::text_component::{Nbt, TextComponent};
+
+pub const OPS_FILE: &str = "ops.json";
+pub const WHITELIST_FILE: &str = "whitelist.json";
+pub const BANNED_PLAYERS_FILE: &str = "banned-players.json";
+pub const BANNED_IPS_FILE: &str = "banned-ips.json";
+
+/// How vanilla writes dates in the ban lists
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S %z";
+const DEFAULT_BAN_REASON: &str = "Banned by an operator.";
+
+#[derive(Debug)]
+pub enum UserListError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+}
+
+impl Error for UserListError {}
+
+impl Display for UserListError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UserListError::Io(e) => write!(f, "{e}"),
+            UserListError::Json(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for UserListError {
+    fn from(e: std::io::Error) -> Self {
+        UserListError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for UserListError {
+    fn from(e: serde_json::Error) -> Self {
+        UserListError::Json(e)
+    }
+}
+
+/// An entry of a `UserList`, which has at most one entry per key
+pub trait UserListEntry: Clone + Serialize + DeserializeOwned {
+    type Key: PartialEq + ?Sized;
+
+    fn get_key(&self) -> &Self::Key;
+
+    /// Expired entries are ignored, and dropped the next time the list is saved
+    fn has_expired(&self) -> bool {
+        false
+    }
+}
+
+/// A list kept in a JSON file like the vanilla ones, which is saved after every change
+pub struct UserList<E> {
+    path: PathBuf,
+    entries: RwLock<Vec<E>>,
+}
+
+impl<E: UserListEntry> UserList<E> {
+    /// Loads the list, creating an empty one if the file doesn't exist
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, UserListError> {
+        let list = Self { path: path.into(), entries: RwLock::new(vec![]) };
+        list.reload()?;
+        Ok(list)
+    }
+
+    /// Reads the file again
+    pub fn reload(&self) -> Result<(), UserListError> {
+        let mut entries = self.entries.write().unwrap();
+        match std::fs::read_to_string(&self.path) {
+            Ok(json) => *entries = serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                entries.clear();
+                self.save(&mut entries)?;
+            },
+            Err(e) => return Err(e.into()),
+        }
+        Ok(())
+    }
+
+    /// Writes a temporary file first, so a crash can't leave half a list behind
+    fn save(&self, entries: &mut Vec<E>) -> Result<(), UserListError> {
+        entries.retain(|entry| !entry.has_expired());
+        let temporary = self.path.with_extension("json.tmp");
+        std::fs::write(&temporary, serde_json::to_string_pretty(entries)?)?;
+        std::fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &E::Key) -> Option<E> {
+        self.entries.read().unwrap().iter()
+            .find(|entry| entry.get_key() == key && !entry.has_expired())
+            .cloned()
+    }
+
+    pub fn contains(&self, key: &E::Key) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get_entries(&self) -> Vec<E> {
+        self.entries.read().unwrap().iter()
+            .filter(|entry| !entry.has_expired())
+            .cloned()
+            .collect()
+    }
+
+    /// Adds the entry, replacing the one with the same key. Returns whether there was none.
+    pub fn add(&self, entry: E) -> Result<bool, UserListError> {
+        let mut entries = self.entries.write().unwrap();
+        let existing = entries.iter().position(|existing| existing.get_key() == entry.get_key());
+        let added = match existing {
+            Some(index) => {
+                let added = entries[index].has_expired();
+                entries[index] = entry;
+                added
+            },
+            None => {
+                entries.push(entry);
+                true
+            },
+        };
+        self.save(&mut entries)?;
+        Ok(added)
+    }
+
+    /// Returns whether there was an entry to remove
+    pub fn remove(&self, key: &E::Key) -> Result<bool, UserListError> {
+        let mut entries = self.entries.write().unwrap();
+        let before = entries.iter().filter(|entry| !entry.has_expired()).count();
+        entries.retain(|entry| entry.get_key() != key);
+        let removed = entries.iter().filter(|entry| !entry.has_expired()).count() != before;
+        if removed {
+            self.save(&mut entries)?;
+        }
+        Ok(removed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpEntry {
+    uuid: Uuid,
+    name: String,
+    level: i32,
+    bypasses_player_limit: bool,
+}
+
+impl OpEntry {
+    pub fn new(uuid: Uuid, name: &str, level: i32) -> Self {
+        Self { uuid, name: name.to_string(), level, bypasses_player_limit: false }
+    }
+
+    pub fn get_uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_level(&self) -> i32 {
+        self.level
+    }
+
+    pub fn bypasses_player_limit(&self) -> bool {
+        self.bypasses_player_limit
+    }
+}
+
+impl UserListEntry for OpEntry {
+    type Key = Uuid;
+
+    fn get_key(&self) -> &Uuid {
+        &self.uuid
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    uuid: Uuid,
+    name: String,
+}
+
+impl WhitelistEntry {
+    pub fn new(uuid: Uuid, name: &str) -> Self {
+        Self { uuid, name: name.to_string() }
+    }
+
+    pub fn get_uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl UserListEntry for WhitelistEntry {
+    type Key = Uuid;
+
+    fn get_key(&self) -> &Uuid {
+        &self.uuid
+    }
+}
+
+/// Who banned someone, when and why, and until when
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BanDetails {
+    #[serde(with = "ban_date")]
+    created: DateTime<FixedOffset>,
+    source: String,
+    #[serde(with = "ban_expiry")]
+    expires: Option<DateTime<FixedOffset>>,
+    reason: String,
+}
+
+impl BanDetails {
+    pub fn new(source: &str, reason: Option<&str>, expires: Option<DateTime<FixedOffset>>) -> Self {
+        Self {
+            created: Local::now().fixed_offset(),
+            source: source.to_string(),
+            expires,
+            reason: reason.unwrap_or(DEFAULT_BAN_REASON).to_string(),
+        }
+    }
+
+    pub fn get_created(&self) -> DateTime<FixedOffset> {
+        self.created
+    }
+
+    pub fn get_source(&self) -> &str {
+        &self.source
+    }
+
+    /// None if the ban is forever
+    pub fn get_expires(&self) -> Option<DateTime<FixedOffset>> {
+        self.expires
+    }
+
+    pub fn get_reason(&self) -> &str {
+        &self.reason
+    }
+
+    pub fn has_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires <= Local::now())
+    }
+
+    /// What a banned player is told when they try to join
+    pub fn get_kick_message(&self, banned: &str) -> TextComponent<Nbt> {
+        let mut message = format!("{banned}\nReason: {}", self.reason);
+        if let Some(expires) = self.expires {
+            message.push_str(&format!("\nYour ban will be removed on {}", expires.format(DATE_FORMAT)));
+        }
+        TextComponent::builder().text(&message).build()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerBanEntry {
+    uuid: Uuid,
+    name: String,
+    #[serde(flatten)]
+    details: BanDetails,
+}
+
+impl PlayerBanEntry {
+    pub fn new(uuid: Uuid, name: &str, details: BanDetails) -> Self {
+        Self { uuid, name: name.to_string(), details }
+    }
+
+    pub fn get_uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_details(&self) -> &BanDetails {
+        &self.details
+    }
+}
+
+impl UserListEntry for PlayerBanEntry {
+    type Key = Uuid;
+
+    fn get_key(&self) -> &Uuid {
+        &self.uuid
+    }
+
+    fn has_expired(&self) -> bool {
+        self.details.has_expired()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IpBanEntry {
+    ip: String,
+    #[serde(flatten)]
+    details: BanDetails,
+}
+
+impl IpBanEntry {
+    pub fn new(ip: &str, details: BanDetails) -> Self {
+        Self { ip: ip.to_string(), details }
+    }
+
+    pub fn get_ip(&self) -> &str {
+        &self.ip
+    }
+
+    pub fn get_details(&self) -> &BanDetails {
+        &self.details
+    }
+}
+
+impl UserListEntry for IpBanEntry {
+    type Key = str;
+
+    fn get_key(&self) -> &str {
+        &self.ip
+    }
+
+    fn has_expired(&self) -> bool {
+        self.details.has_expired()
+    }
+}
+
+mod ban_date {
+    use chrono::{DateTime, FixedOffset};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::DATE_FORMAT;
+
+    pub fn serialize<S: Serializer>(date: &DateTime<FixedOffset>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&date.format(DATE_FORMAT).to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<FixedOffset>, D::Error> {
+        let date = String::deserialize(deserializer)?;
+        DateTime::parse_from_str(&date, DATE_FORMAT).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Like `ban_date`, where `forever` is no date
+mod ban_expiry {
+    use chrono::{DateTime, FixedOffset};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::DATE_FORMAT;
+
+    const FOREVER: &str = "forever";
+
+    pub fn serialize<S: Serializer>(date: &Option<DateTime<FixedOffset>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => super::ban_date::serialize(date, serializer),
+            None => serializer.serialize_str(FOREVER),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<FixedOffset>>, D::Error> {
+        match String::deserialize(deserializer)? {
+            date if date == FOREVER => Ok(None),
+            date => DateTime::parse_from_str(&date, DATE_FORMAT).map(Some).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vanilla_format() {
+        let json = r#"[
+            {
+                "uuid": "069a79f4-44e9-4726-a5be-fca90e38aaf5",
+                "name": "Notch",
+                "created": "2024-05-01 12:30:00 +0200",
+                "source": "Server",
+                "expires": "forever",
+                "reason": "Banned by an operator."
+            },
+            {
+                "uuid": "853c80ef-3c37-49fd-aa49-938b674adae6",
+                "name": "jeb_",
+                "created": "2024-05-01 12:30:00 +0200",
+                "source": "Notch",
+                "expires": "2024-05-02 12:30:00 +0200",
+                "reason": "Flying"
+            }
+        ]"#;
+        let bans: Vec<PlayerBanEntry> = serde_json::from_str(json).unwrap();
+        assert_eq!(bans[0].get_details().get_expires(), None);
+        assert!(!bans[0].has_expired());
+        assert_eq!(bans[1].get_details().get_reason(), "Flying");
+        assert!(bans[1].has_expired());
+
+        let json = serde_json::to_string(&bans[1]).unwrap();
+        assert!(json.contains(r#""expires":"2024-05-02 12:30:00 +0200""#), "{json}");
+        assert_eq!(serde_json::from_str::<PlayerBanEntry>(&json).unwrap(), bans[1]);
+    }
+
+    #[test]
+    fn persistence() {
+        let path = std::env::temp_dir().join(format!("banned-ips-{}.json", Uuid::new_v4()));
+        let list = UserList::<IpBanEntry>::load(&path).unwrap();
+        assert!(list.get_entries().is_empty());
+
+        assert!(list.add(IpBanEntry::new("127.0.0.1", BanDetails::new("Server", None, None))).unwrap());
+        assert!(!list.add(IpBanEntry::new("127.0.0.1", BanDetails::new("Server", Some("Again"), None))).unwrap());
+        let expired = Local::now().fixed_offset() - chrono::Duration::seconds(1);
+        list.add(IpBanEntry::new("10.0.0.1", BanDetails::new("Server", None, Some(expired)))).unwrap();
+        assert!(!list.contains("10.0.0.1"));
+
+        let reloaded = UserList::<IpBanEntry>::load(&path).unwrap();
+        assert_eq!(reloaded.get_entries().len(), 1);
+        assert_eq!(reloaded.get("127.0.0.1").unwrap().get_details().get_reason(), "Again");
+        assert!(reloaded.remove("127.0.0.1").unwrap());
+        assert!(!reloaded.remove("127.0.0.1").unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,12 +1,9 @@
 use std::error::Error;
 use std::sync::Arc;
-// use std::sync::Weak;
 use std::time::Duration;
-// use std::time::SystemTime;
 
 use log::debug;
 use server_util::ConnectionState;
-// use tokio::time;
 
 use crate::data_types::datapack::DataPackID;
 use crate::data_types::{Identifier, IdentifierArray};
@@ -22,7 +19,6 @@ pub(in crate::state) async fn configuration_state(player_ref: Arc<Player>) {
     drop(lock);
 
     debug!("Made it to the configuration state!");
-    //TODO: keep alive
     //TODO: Handle plugin message.
     //TODO: Handle Client Information.
     //TODO: Clientbound plugin message
@@ -130,6 +126,10 @@ async fn filter_packets_until_s_acknowledge_finish_config(player_ref: Arc<Player
                     SPacket::SPluginMessage_Config(_) => continue,
                     SPacket::SClientInformation_Config(_) => continue,
                     SPacket::SKnownPacks(_) => continue,
+                    SPacket::SKeepAlive_Config(packet) => {
+                        player_ref.handle_keep_alive(packet.get_keep_alive_id());
+                        continue
+                    },
                     SPacket::SAcknowledgeFinishConfig(_) => return Ok(()),
                     _ => return Err(format!("Wrong packet: {:?}!", packet))?
                 }
@@ -148,6 +148,10 @@ async fn filter_packets_until_s_known_packs(player_ref: Arc<Player>) -> Result<(
                 match packet {
                     SPacket::SPluginMessage_Config(_) => continue,
                     SPacket::SClientInformation_Config(_) => continue,
+                    SPacket::SKeepAlive_Config(packet) => {
+                        player_ref.handle_keep_alive(packet.get_keep_alive_id());
+                        continue
+                    },
                     SPacket::SKnownPacks(_) => return Ok(()),
                     _ => return Err(format!("Wrong packet: {:?}!", packet))?
                 }
@@ -157,47 +161,3 @@ async fn filter_packets_until_s_known_packs(player_ref: Arc<Player>) -> Result<(
     }
     //Err("Did not find SAcknowledgeFinishConfig!")?
 }
-
-//TODO: Fix this so it actually works, although it doesn't seem necessary in the configuration state
-// fn keep_alive(weak: Weak<Player>) {
-//     #[allow(non_snake_case)]
-//     let keep_alive__config = async move {
-//         let mut timer = time::interval(Duration::from_secs(5));
-//         loop {
-//             timer.tick().await;
-//             match weak.upgrade() {
-//                 Some(player) => {
-//                     match player.get_connection_state().await {
-//                         ConnectionState::Configuration => {
-//                             //potential BUG: Client might not immediately send the keep alive packet
-//                             let lock = player.get_connection().write().await;
-//                             let time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs() as i64;
-//                             match time::timeout(crate::TIMEOUT, lock.send_packet(CKeepAlive_Config::new(time))).await {
-//                                 Ok(_) => {
-//                                     match lock.read_next_packet().await {
-//                                         Ok(s_packet) => match s_packet {
-//                                             SPacket::SKeepAlive_Config(packet) => {
-//                                                 if packet.get_keep_alive_id() == time {
-//                                                     drop(lock);
-//                                                     continue;
-//                                                 }
-//                                             },
-//                                             _ => ()
-//                                         },
-//                                         Err(_) => ()
-//                                     }
-//                                 },
-//                                 Err(_) => (),
-//                             }
-//                             player.disconnect("Timed out.").await;
-//                             break;
-//                         },
-//                         _ => break
-//                     }
-//                 },
-//                 None => break
-//             };
-//         }
-//     };
-//     tokio::spawn(keep_alive__config);
-// }
\ No newline at end of file
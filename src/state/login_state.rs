@@ -10,6 +10,8 @@ use uuid::Uuid;
 use serde::Deserialize;
 
 use crate::data_types::Property;
+use crate::event::{self, EventPlayerLogin, EventResult};
+use crate::event::events::player_login::PlayerLoginResult;
 use crate::data_types::PropertyArray;
 use crate::player::Player;
 use crate::state::configuration_state::configuration_state;
@@ -30,6 +32,8 @@ use crate::TIMEOUT;
 /// 
 /// __Server auth step__ //TODO:
 /// 
+/// __Ban and whitelist checks, EventPlayerLogin__
+/// 
 /// __S -> C__ CSetCompression //Optional
 /// 
 /// __S -> C__ CLoginSuccess
@@ -64,12 +68,20 @@ pub(in crate::state) async fn login_state(mut connection: Connection) {
                 return;
             }
 
+            if let Some(message) = THE_SERVER.check_login(player_uuid, addr.ip()).get_message() {
+                info!("Refused {player_name} ({player_uuid}) from {addr}: {}", message.to_plain_string());
+                let reason = serde_json::to_string(&message.to_plain_string()).unwrap();
+                let _ = connection.send_packet(CDisconnect_Login::new(reason)).await;
+                return;
+            }
+
             if let Some(p) = THE_SERVER.get_player_by_name_async(&player_name).await {
                 p.upgrade().unwrap().disconnect("Logged in from another location.").await;
             }
 
             info!("Player {player_name} ({player_uuid}) logged in from {addr}.");
 
+            THE_SERVER.get_permission_provider().set_user_name(player_uuid, &player_name);
             let player = Player::new(player_name, player_uuid, connection);
             debug!("Registering player...");    
             
@@ -79,6 +91,23 @@ pub(in crate::state) async fn login_state(mut connection: Connection) {
             };
 
             debug!("Registered player!");
+
+            let (hostname, port) = {
+                let connection = player_ref.get_connection().lock().await;
+                (connection.get_hostname().cloned().unwrap_or_default(), connection.get_port().unwrap_or_default())
+            };
+            let mut e = EventPlayerLogin::new(Arc::downgrade(&player_ref), &hostname, port, addr, addr);
+            //Handlers which deny without a result of their own kick with the default message
+            if matches!(event::listen(THE_SERVER.get_event_manager(), &mut e).await, EventResult::Deny)
+                && matches!(e.get_result(), PlayerLoginResult::Allowed)
+            {
+                e.disallow(PlayerLoginResult::KickOther { message: None });
+            }
+            if let Some(message) = e.get_result().get_message() {
+                info!("{} was kicked while logging in: {}", player_ref.get_name(), message.to_plain_string());
+                player_ref.disconnect_tc(message).await;
+                return;
+            }
         } else {
             error!("Incorrect packet.");
             connection.drop().await;
@@ -91,11 +120,15 @@ pub(in crate::state) async fn login_state(mut connection: Connection) {
     //TODO: Everything in between
 
 
+    let _ = player_ref.set_properties(
+        get_player_property_array(player_ref.get_uuid()).await
+    );
+
     debug!("Sending CLoginSuccess...");
     if player_ref.send_packet(CLoginSuccess::new(
         player_ref.get_uuid(), 
         player_ref.get_name().to_string(), 
-        get_player_property_array(player_ref.get_uuid()).await,
+        player_ref.get_properties().to_vec(),
         false
     )).await.is_err() {
         player_ref.disconnect("Connection closed.").await;
@@ -151,7 +184,8 @@ async fn get_player_property_array(player_uuid: Uuid) -> PropertyArray {
     vec![]
 }
 
-async fn get_player_uuid(player_name: &String) -> Result<Uuid, Box<dyn Error + Send + Sync>> {
+/// The UUID of the player with the name, from Mojang in online mode
+pub(crate) async fn get_player_uuid(player_name: &String) -> Result<Uuid, Box<dyn Error + Send + Sync>> {
     if THE_SERVER.get_properties().is_online_mode() {
         match reqwest::get(format!("https://api.mojang.com/users/profiles/minecraft/{}", player_name)).await {
             Ok(response) => {
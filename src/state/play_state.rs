@@ -1,13 +1,11 @@
-use std::sync::{Arc, Weak};
-use std::time::{Duration, SystemTime};
+use std::sync::Arc;
 
 use log::debug;
 use server_util::ConnectionState;
-use tokio::time;
-use tokio::sync::mpsc;
 
-use crate::data_types::text_component::Formatting;
-use crate::data_types::{Identifier, TextComponent, VarInt};
+use crate::command::brigadier;
+use crate::event::{self, PlayerJoinEvent};
+use crate::data_types::{Identifier, PlayerInfoActions, VarInt};
 use crate::player::Player;
 use crate::packet::{SPacket, play::*};
 use crate::THE_SERVER;
@@ -17,38 +15,13 @@ pub(in crate::state) async fn play_state(player_ref: Arc<Player>) {
     lock.set_connection_state(ConnectionState::Play).await;
     drop(lock);
     debug!("Made it to the play state!");
-    let tx = keep_alive(Arc::downgrade(&player_ref));
 
-    let reason = 
-        TextComponent::builder()
-            .text("")
-            .formatting(Formatting::builder().color_rgb(0xff00ff).build())
-            .add_extra(
-                TextComponent::builder()
-                    .text("# ")
-                    .formatting(Formatting::builder().color(0x4).obfuscated(true).build())
-                    .build()
-            ).add_extra(
-                TextComponent::builder()
-                    .text("Test ")
-                    .reset_fmt()
-                    .build()
-            ).add_extra(
-                TextComponent::builder()
-                    .text(":3")
-                    .formatting(Formatting::builder().bold(true).build())
-                    .build()
-            ).add_extra(
-                TextComponent::builder()
-                    .text(" #")
-                    .reset_fmt()
-                    .formatting(Formatting::builder().color(0x4).obfuscated(true).build())
-                    .build()
-            ).build();
+    let entity_id = THE_SERVER.get_next_eid().await;
+    let _ = player_ref.set_entity_id(entity_id);
 
     debug!("sending login play packet");
     match player_ref.send_packet(CLogin_Play::new(
-        THE_SERVER.get_next_eid().await, 
+        entity_id, 
         false,
         VarInt::new(3), 
         vec![
@@ -64,14 +37,14 @@ pub(in crate::state) async fn play_state(player_ref: Arc<Player>) {
         false,
         VarInt::new(0),
         Identifier::new("minecraft:overworld").unwrap(),
-        4297447447117712613,
-        1,
-        1,
+        THE_SERVER.get_properties().get_hashed_seed(),
+        player_ref.get_game_mode().get_id(),
+        -1,
         false,
         true,
         None,
         VarInt::new(0),
-        false
+        THE_SERVER.get_properties().is_enforce_secure_profile()
     )).await {
         Ok(_) => (),
         Err(e) => {
@@ -81,54 +54,61 @@ pub(in crate::state) async fn play_state(player_ref: Arc<Player>) {
     }
     debug!("send login play complete");
 
-    player_ref.disconnect_tc(reason).await;
+    let _ = player_ref.send_packet(CChangeDifficulty::new(THE_SERVER.get_difficulty().get_id(), false)).await;
+
+    send_player_info(&player_ref).await;
+    brigadier::send_commands(&player_ref).await;
+
+    let mut e = PlayerJoinEvent::new(Arc::downgrade(&player_ref), player_ref.get_name());
+    event::listen(THE_SERVER.get_event_manager(), &mut e).await;
+    if let Some(join_message) = e.get_join_message() {
+        THE_SERVER.broadcast_text(join_message.clone()).await;
+    }
+
+    if let Some(world) = THE_SERVER.get_world(THE_SERVER.get_properties().get_level_name()) {
+        let world = world.lock().await;
+        let spawn_position = world.get_spawn_position();
+        world.add_player(player_ref.get_id(), Arc::downgrade(&player_ref), spawn_position);
+        drop(world);
+        player_ref.synchronize_position(spawn_position).await;
+    }
 
     while *player_ref.is_connected().lock().await {
         match player_ref.read_next_packet().await {
             Ok(SPacket::SKeepAlive_Play(packet)) => {
-                let _ = tx.send(packet.get_keep_alive_id()).await;
+                player_ref.handle_keep_alive(packet.get_keep_alive_id());
+            }
+            Ok(packet) => {
+                if let Err(e) = player_ref.queue_packet(packet).await {
+                    player_ref.disconnect_tc(e.to_text_component()).await;
+                }
             }
-            Ok(packet) => player_ref.queue_packet(packet).await,
             Err(_) => {
-                player_ref.disconnect("Connection lost").await;
+                player_ref.connection_lost().await;
                 return;
             }
         }   
     }
 }
 
-//TODO: this really needs reworking
-fn keep_alive(weak: Weak<Player>) -> mpsc::Sender<i64>{
-    let (tx, mut rx) = mpsc::channel::<i64>(1);
-    let keep_alive = async move {
-        let mut timer = time::interval(Duration::from_secs(5));
-        loop {
-            timer.tick().await;
-            match weak.upgrade() {
-                Some(player) => {
-                    //potential BUG: Client might not immediately send the keep alive packet
-                    let mut lock = player.get_connection().lock().await;
-                    let time = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs() as i64;
-                    match time::timeout(crate::TIMEOUT, lock.send_packet(CKeepAlive_Play::new(time))).await {
-                        Ok(_) => {
-                            match rx.recv().await {
-                                Some(long) => {
-                                    if long == time {
-                                        continue;
-                                    }
-                                }
-                                None => ()
-                            }
-                        },
-                        Err(_) => (),
-                    }
-                    player.disconnect("Timed out.").await;
-                    break;
-                },
-                None => break
-            };
-        }
-    };
-    tokio::spawn(keep_alive);
-    tx
-}
\ No newline at end of file
+/// Adds the player to everyone's tab list and sends the current tab list to the player.
+async fn send_player_info(player_ref: &Arc<Player>) {
+    let actions = PlayerInfoActions::ADD_PLAYER
+        | PlayerInfoActions::INITIALIZE_CHAT
+        | PlayerInfoActions::UPDATE_GAME_MODE
+        | PlayerInfoActions::UPDATE_LISTED;
+    let others: Vec<Arc<Player>> = THE_SERVER.get_players_async().await
+        .iter()
+        .filter_map(|weak| weak.upgrade())
+        .filter(|other| other.get_uuid() != player_ref.get_uuid())
+        .collect();
+
+    let entry = player_ref.get_info_entry();
+    for other in others.iter() {
+        other.queue_send_packet(CPlayerInfoUpdate::new(actions, vec![entry.clone()])).await;
+    }
+
+    let mut entries = vec![entry];
+    entries.extend(others.iter().map(|other| other.get_info_entry()));
+    let _ = player_ref.send_packet(CPlayerInfoUpdate::new(actions, entries)).await;
+}
@@ -86,6 +86,7 @@ async fn generate_status_response() -> CStatusResponse {
     let player_count = THE_SERVER.get_num_players_async().await;
     let max_players = THE_SERVER.get_max_players();
     let motd = THE_SERVER.get_motd().to_string();
+    let enforce_secure_chat = THE_SERVER.get_properties().is_enforce_secure_profile();
 
     let mut result = String::new();
     let data = std::fs::read(Path::new("server-icon.png"));
@@ -113,7 +114,7 @@ async fn generate_status_response() -> CStatusResponse {
             "text": motd
         },
         "favicon": favicon_str,
-        "enforceSecureChat": false,
+        "enforceSecureChat": enforce_secure_chat,
         //"previewsChat": false
     }).to_string())
 }
\ No newline at end of file
@@ -0,0 +1,540 @@
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::data_types::{Angle, ToProtocol, VarInt, Vec3d};
+use crate::entity::EnumEntityType;
+use crate::packet::play::{
+    CRemoveEntities,
+    CSetEntityVelocity,
+    CSetHeadRotation,
+    CSpawnEntity,
+    CTeleportEntity,
+    CUpdateEntityPosition,
+    CUpdateEntityPositionAndRotation,
+    CUpdateEntityRotation
+};
+use crate::packet::Clientbound;
+use crate::THE_SERVER;
+
+/// Vanilla resends the absolute position every 400 ticks
+/// to correct any drift from relative moves.
+const FORCED_TELEPORT_INTERVAL: u32 = 400;
+
+/// Relative moves are sent as fixed point shorts with 12 fractional bits.
+const DELTA_SCALE: f64 = 4096.0;
+
+/// Velocity is sent in units of 1/8000 of a block per tick, clamped to this value.
+const MAX_VELOCITY: f64 = 3.9;
+const VELOCITY_SCALE: f64 = 8000.0;
+
+/// The squared change in velocity before it is resent to viewers.
+const VELOCITY_EPSILON: f64 = 1.0e-7;
+
+/// Entity categories for the purpose of tracking range.
+///
+/// Ranges are configured in server.properties.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TrackingCategory {
+    Player,
+    Animal,
+    Monster,
+    Misc,
+    Other,
+}
+
+impl TrackingCategory {
+    /// The tracking range in blocks, capped by the view distance.
+    pub fn get_range(&self) -> i32 {
+        let properties = THE_SERVER.get_properties();
+        let range = match self {
+            TrackingCategory::Player => properties.get_entity_tracking_range_players(),
+            TrackingCategory::Animal => properties.get_entity_tracking_range_animals(),
+            TrackingCategory::Monster => properties.get_entity_tracking_range_monsters(),
+            TrackingCategory::Misc => properties.get_entity_tracking_range_misc(),
+            TrackingCategory::Other => properties.get_entity_tracking_range_other(),
+        };
+        std::cmp::min(range, properties.get_view_distance() * 16)
+    }
+}
+
+/// An entity as seen by the players tracking it.
+#[derive(Debug)]
+pub struct TrackedEntity {
+    entity_id: i32,
+    uuid: Uuid,
+    entity_type: EnumEntityType,
+    category: TrackingCategory,
+    data: i32,
+
+    position: Vec3d,
+    yaw: Angle,
+    pitch: Angle,
+    head_yaw: Angle,
+    velocity: Vec3d,
+    on_ground: bool,
+    tags: HashSet<String>,
+
+    //The last state which was sent to viewers
+    sent_position: Vec3d,
+    sent_yaw: u8,
+    sent_pitch: u8,
+    sent_head_yaw: u8,
+    sent_velocity: Vec3d,
+    ticks_since_teleport: u32,
+
+    viewers: HashSet<i32>,
+}
+
+impl TrackedEntity {
+    /// `data` is the object data field of `CSpawnEntity`, which depends on the entity type.
+    pub fn new(
+        entity_id: i32,
+        uuid: Uuid,
+        entity_type: EnumEntityType,
+        category: TrackingCategory,
+        data: i32,
+        position: Vec3d,
+    ) -> Self {
+        Self {
+            entity_id,
+            uuid,
+            entity_type,
+            category,
+            data,
+            position,
+            yaw: Angle::new(0.0),
+            pitch: Angle::new(0.0),
+            head_yaw: Angle::new(0.0),
+            velocity: Vec3d::new(0.0, 0.0, 0.0),
+            on_ground: false,
+            tags: HashSet::new(),
+            sent_position: position,
+            sent_yaw: 0,
+            sent_pitch: 0,
+            sent_head_yaw: 0,
+            sent_velocity: Vec3d::new(0.0, 0.0, 0.0),
+            ticks_since_teleport: 0,
+            viewers: HashSet::new(),
+        }
+    }
+
+    pub fn player(entity_id: i32, uuid: Uuid, position: Vec3d) -> Self {
+        Self::new(
+            entity_id,
+            uuid,
+            EnumEntityType::Player,
+            TrackingCategory::Player,
+            0,
+            position
+        )
+    }
+
+    pub fn get_entity_id(&self) -> i32 {
+        self.entity_id
+    }
+
+    pub fn get_uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn get_entity_type(&self) -> EnumEntityType {
+        self.entity_type
+    }
+
+    pub fn get_category(&self) -> TrackingCategory {
+        self.category
+    }
+
+    /// The scoreboard tags of the entity, used by the `tag` selector filter
+    pub fn get_tags(&self) -> &HashSet<String> {
+        &self.tags
+    }
+
+    /// Returns false if the entity already had the tag
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        self.tags.insert(tag.to_string())
+    }
+
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        self.tags.remove(tag)
+    }
+
+    pub fn get_position(&self) -> Vec3d {
+        self.position
+    }
+
+    /// The yaw in degrees
+    pub fn get_yaw(&self) -> f64 {
+        self.yaw.get_degrees()
+    }
+
+    /// The pitch in degrees
+    pub fn get_pitch(&self) -> f64 {
+        self.pitch.get_degrees()
+    }
+
+    pub fn get_viewers(&self) -> &HashSet<i32> {
+        &self.viewers
+    }
+
+    fn is_in_range_of(&self, viewer_position: Vec3d) -> bool {
+        is_in_range(self.position, viewer_position, self.category.get_range() as f64)
+    }
+
+    fn spawn_packets(&self) -> Vec<Vec<u8>> {
+        let velocity = encode_velocity(self.velocity);
+        let mut packets = vec![
+            CSpawnEntity::new(
+                VarInt::new(self.entity_id),
+                self.uuid,
+                VarInt::new(self.entity_type as i32),
+                self.position.x,
+                self.position.y,
+                self.position.z,
+                self.pitch,
+                self.yaw,
+                self.head_yaw,
+                VarInt::new(self.data),
+                velocity.0,
+                velocity.1,
+                velocity.2,
+            ).to_be_bytes(),
+            CSetHeadRotation::new(VarInt::new(self.entity_id), self.head_yaw).to_be_bytes(),
+        ];
+        if self.velocity.x != 0.0 || self.velocity.y != 0.0 || self.velocity.z != 0.0 {
+            packets.push(
+                CSetEntityVelocity::new(
+                    VarInt::new(self.entity_id),
+                    velocity.0,
+                    velocity.1,
+                    velocity.2
+                ).to_be_bytes()
+            );
+        }
+        packets
+    }
+
+    /// Builds the movement packets for this tick and marks the current state as sent.
+    fn update_packets(&mut self) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        let entity_id = VarInt::new(self.entity_id);
+
+        let yaw = angle_byte(&self.yaw);
+        let pitch = angle_byte(&self.pitch);
+        let rotated = yaw != self.sent_yaw || pitch != self.sent_pitch;
+
+        let delta = (
+            encode_delta(self.position.x, self.sent_position.x),
+            encode_delta(self.position.y, self.sent_position.y),
+            encode_delta(self.position.z, self.sent_position.z),
+        );
+        let moved = delta != (0, 0, 0);
+        let in_delta_range = [delta.0, delta.1, delta.2].iter()
+            .all(|d| (i16::MIN as i64..=i16::MAX as i64).contains(d));
+
+        self.ticks_since_teleport += 1;
+        if !in_delta_range || self.ticks_since_teleport > FORCED_TELEPORT_INTERVAL {
+            self.ticks_since_teleport = 0;
+            packets.push(CTeleportEntity::new(
+                entity_id,
+                self.position.x,
+                self.position.y,
+                self.position.z,
+                self.yaw,
+                self.pitch,
+                self.on_ground,
+            ).to_be_bytes());
+        } else if moved && rotated {
+            packets.push(CUpdateEntityPositionAndRotation::new(
+                entity_id,
+                delta.0 as i16,
+                delta.1 as i16,
+                delta.2 as i16,
+                self.yaw,
+                self.pitch,
+                self.on_ground,
+            ).to_be_bytes());
+        } else if moved {
+            packets.push(CUpdateEntityPosition::new(
+                entity_id,
+                delta.0 as i16,
+                delta.1 as i16,
+                delta.2 as i16,
+                self.on_ground,
+            ).to_be_bytes());
+        } else if rotated {
+            packets.push(CUpdateEntityRotation::new(
+                entity_id,
+                self.yaw,
+                self.pitch,
+                self.on_ground,
+            ).to_be_bytes());
+        }
+
+        let head_yaw = angle_byte(&self.head_yaw);
+        if head_yaw != self.sent_head_yaw {
+            packets.push(CSetHeadRotation::new(entity_id, self.head_yaw).to_be_bytes());
+        }
+
+        let velocity_change = self.velocity - self.sent_velocity;
+        let velocity_change_sq = velocity_change.x * velocity_change.x
+            + velocity_change.y * velocity_change.y
+            + velocity_change.z * velocity_change.z;
+        let stopped = self.velocity.x == 0.0 && self.velocity.y == 0.0 && self.velocity.z == 0.0;
+        if velocity_change_sq > VELOCITY_EPSILON || (stopped && velocity_change_sq > 0.0) {
+            let velocity = encode_velocity(self.velocity);
+            packets.push(CSetEntityVelocity::new(
+                entity_id,
+                velocity.0,
+                velocity.1,
+                velocity.2
+            ).to_be_bytes());
+            self.sent_velocity = self.velocity;
+        }
+
+        self.mark_sent();
+        packets
+    }
+
+    fn mark_sent(&mut self) {
+        self.sent_position = self.position;
+        self.sent_yaw = angle_byte(&self.yaw);
+        self.sent_pitch = angle_byte(&self.pitch);
+        self.sent_head_yaw = angle_byte(&self.head_yaw);
+    }
+}
+
+/// Keeps track of which players can see which entities in a world
+/// and sends them the packets to spawn, move and remove those entities.
+///
+/// Players are tracked entities as well, which is where the viewer positions come from.
+pub struct EntityTracker {
+    entities: HashMap<i32, TrackedEntity>,
+    pending_removals: HashMap<i32, Vec<i32>>,
+}
+
+impl EntityTracker {
+    pub fn new() -> Self {
+        Self {
+            entities: HashMap::new(),
+            pending_removals: HashMap::new(),
+        }
+    }
+
+    pub fn track(&mut self, entity: TrackedEntity) {
+        self.entities.insert(entity.entity_id, entity);
+    }
+
+    /// Stops tracking the entity. Viewers are sent `CRemoveEntities` on the next tick.
+    pub fn untrack(&mut self, entity_id: i32) -> Option<TrackedEntity> {
+        let entity = self.entities.remove(&entity_id)?;
+        for viewer in entity.viewers.iter() {
+            self.pending_removals.entry(*viewer).or_default().push(entity_id);
+        }
+        self.remove_viewer(entity_id);
+        Some(entity)
+    }
+
+    /// Forgets everything the viewer was tracking without sending anything to it,
+    /// for when the viewer leaves the world.
+    pub fn remove_viewer(&mut self, viewer_id: i32) {
+        self.entities.values_mut().for_each(|entity| {
+            entity.viewers.remove(&viewer_id);
+        });
+        self.pending_removals.remove(&viewer_id);
+    }
+
+    pub fn get_entity(&self, entity_id: i32) -> Option<&TrackedEntity> {
+        self.entities.get(&entity_id)
+    }
+
+    pub fn get_entity_mut(&mut self, entity_id: i32) -> Option<&mut TrackedEntity> {
+        self.entities.get_mut(&entity_id)
+    }
+
+    pub fn get_entities(&self) -> impl Iterator<Item = &TrackedEntity> {
+        self.entities.values()
+    }
+
+    pub fn is_tracked(&self, entity_id: i32) -> bool {
+        self.entities.contains_key(&entity_id)
+    }
+
+    pub fn update_position(
+        &mut self,
+        entity_id: i32,
+        position: Vec3d,
+        on_ground: bool
+    ) {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            entity.position = position;
+            entity.on_ground = on_ground;
+        }
+    }
+
+    pub fn update_on_ground(&mut self, entity_id: i32, on_ground: bool) {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            entity.on_ground = on_ground;
+        }
+    }
+
+    pub fn update_rotation(&mut self, entity_id: i32, yaw: Angle, pitch: Angle) {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            entity.yaw = yaw;
+            entity.pitch = pitch;
+        }
+    }
+
+    pub fn update_head_yaw(&mut self, entity_id: i32, head_yaw: Angle) {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            entity.head_yaw = head_yaw;
+        }
+    }
+
+    /// Velocity is in blocks per tick.
+    pub fn update_velocity(&mut self, entity_id: i32, velocity: Vec3d) {
+        if let Some(entity) = self.entities.get_mut(&entity_id) {
+            entity.velocity = velocity;
+        }
+    }
+
+    /// Updates which entities each viewer can see and returns the
+    /// serialized packets to queue for each viewer, keyed by entity id.
+    ///
+    /// `viewer_ids` are the entity ids of the players in the world.
+    pub fn tick(&mut self, viewer_ids: &[i32]) -> HashMap<i32, Vec<Vec<u8>>> {
+        let viewers: Vec<(i32, Vec3d)> = viewer_ids.iter()
+            .filter_map(|id| self.entities.get(id).map(|entity| (*id, entity.position)))
+            .collect();
+
+        let mut removals: HashMap<i32, Vec<i32>> = std::mem::take(&mut self.pending_removals);
+        let mut outgoing: HashMap<i32, Vec<Vec<u8>>> = HashMap::new();
+
+        let mut entity_ids: Vec<i32> = self.entities.keys().copied().collect();
+        entity_ids.sort_unstable();
+
+        for entity_id in entity_ids {
+            let entity = self.entities.get_mut(&entity_id).unwrap();
+
+            //Viewers which have left the world
+            let gone: Vec<i32> = entity.viewers.iter()
+                .filter(|viewer| !viewers.iter().any(|(id, _)| id == *viewer))
+                .copied()
+                .collect();
+            gone.iter().for_each(|viewer| { entity.viewers.remove(viewer); });
+
+            let mut spawned_for: Vec<i32> = Vec::new();
+            for (viewer_id, viewer_position) in viewers.iter() {
+                if *viewer_id == entity_id {
+                    continue;
+                }
+                let in_range = entity.is_in_range_of(*viewer_position);
+                let is_viewer = entity.viewers.contains(viewer_id);
+                if in_range && !is_viewer {
+                    entity.viewers.insert(*viewer_id);
+                    spawned_for.push(*viewer_id);
+                } else if !in_range && is_viewer {
+                    entity.viewers.remove(viewer_id);
+                    removals.entry(*viewer_id).or_default().push(entity_id);
+                }
+            }
+
+            //Newly spawned viewers receive the current state in the spawn packets
+            let update = entity.update_packets();
+            for viewer_id in entity.viewers.iter() {
+                if spawned_for.contains(viewer_id) || update.is_empty() {
+                    continue;
+                }
+                outgoing.entry(*viewer_id).or_default().extend(update.iter().cloned());
+            }
+            if !spawned_for.is_empty() {
+                let spawn = entity.spawn_packets();
+                for viewer_id in spawned_for {
+                    outgoing.entry(viewer_id).or_default().extend(spawn.iter().cloned());
+                }
+            }
+        }
+
+        //Removals go first so an entity leaving and re-entering range is respawned cleanly
+        for (viewer_id, entity_ids) in removals.into_iter() {
+            if !viewers.iter().any(|(id, _)| *id == viewer_id) {
+                continue;
+            }
+            let packet = CRemoveEntities::new(
+                entity_ids.into_iter().map(VarInt::new).collect()
+            ).to_be_bytes();
+            outgoing.entry(viewer_id).or_default().insert(0, packet);
+        }
+
+        outgoing
+    }
+}
+
+/// Whether the entity is within `range` blocks of the viewer horizontally, like vanilla the height doesn't matter
+fn is_in_range(position: Vec3d, viewer_position: Vec3d, range: f64) -> bool {
+    let (dx, dz) = (position.x - viewer_position.x, position.z - viewer_position.z);
+    dx * dx + dz * dz <= range * range
+}
+
+fn encode_delta(new: f64, old: f64) -> i64 {
+    (new * DELTA_SCALE).round() as i64 - (old * DELTA_SCALE).round() as i64
+}
+
+fn encode_velocity(velocity: Vec3d) -> (i16, i16, i16) {
+    let encode = |v: f64| (v.clamp(-MAX_VELOCITY, MAX_VELOCITY) * VELOCITY_SCALE) as i16;
+    (encode(velocity.x), encode(velocity.y), encode(velocity.z))
+}
+
+fn angle_byte(angle: &Angle) -> u8 {
+    angle.to_protocol_bytes()[0]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moved_by(x: f64, y: f64, z: f64) -> (TrackedEntity, Vec<Vec<u8>>) {
+        let mut entity = TrackedEntity::player(1, Uuid::nil(), Vec3d::new(0.0, 64.0, 0.0));
+        entity.position = Vec3d::new(x, 64.0 + y, z);
+        let packets = entity.update_packets();
+        (entity, packets)
+    }
+
+    #[test]
+    fn delta_or_teleport() {
+        let (_, packets) = moved_by(0.0, 0.0, 0.0);
+        assert!(packets.is_empty());
+
+        //4096 units per block, so up to 8 blocks fit in a short
+        let (mut entity, packets) = moved_by(1.5, -0.25, 0.0);
+        assert_eq!(packets, vec![
+            CUpdateEntityPosition::new(VarInt::new(1), 6144, -1024, 0, false).to_be_bytes()
+        ]);
+        assert!(entity.update_packets().is_empty());
+
+        let (_, packets) = moved_by(0.0, 0.0, 8.5);
+        assert_eq!(packets, vec![
+            CTeleportEntity::new(VarInt::new(1), 0.0, 64.0, 8.5, Angle::new(0.0), Angle::new(0.0), false).to_be_bytes()
+        ]);
+
+        let (mut entity, _) = moved_by(0.0, 0.0, 0.0);
+        for _ in 1..FORCED_TELEPORT_INTERVAL {
+            entity.position.x += 0.1;
+            assert_eq!(entity.update_packets().len(), 1);
+            assert_ne!(entity.ticks_since_teleport, 0);
+        }
+        entity.update_packets();
+        assert_eq!(entity.ticks_since_teleport, 0);
+    }
+
+    #[test]
+    fn range() {
+        let viewer = Vec3d::new(0.0, 64.0, 0.0);
+        assert!(is_in_range(Vec3d::new(48.0, 64.0, 0.0), viewer, 48.0));
+        assert!(is_in_range(Vec3d::new(33.9, 64.0, -33.9), viewer, 48.0));
+        assert!(!is_in_range(Vec3d::new(34.0, 64.0, -34.0), viewer, 48.0));
+        assert!(!is_in_range(Vec3d::new(0.0, 64.0, -48.5), viewer, 48.0));
+        assert!(is_in_range(Vec3d::new(0.0, 200.0, 0.0), viewer, 48.0));
+    }
+}
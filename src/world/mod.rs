@@ -4,6 +4,8 @@ pub mod chunk;
 pub mod anvil;
 pub mod chunk_loader;
 pub mod border;
+pub mod entity_tracker;
+pub mod packet_handler;
 pub mod generator_settings;
 
 pub use world::*;
\ No newline at end of file
@@ -0,0 +1,424 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::sync::{Arc, Weak};
+
+use log::{debug, warn};
+
+use crate::chat::chat_message::ChatMessage;
+use crate::chat::secure_chat::{get_profile_keys, ChatValidationError, RemoteChatSession};
+use crate::command::{self, brigadier, CommandSender};
+use crate::data_types::text_component::Nbt;
+use crate::block::block_state::{Facing, AIR};
+use crate::data_types::{Angle, BlockPos, ChatSessionData, PlayerInfoActions, TextComponent, VarInt, Vec3d};
+use crate::game::gamemode::Gamemode;
+use crate::game::hand::Hand;
+use crate::packet::play::{CAcknowledgeBlockChange, CBlockUpdate, CPlayerInfoUpdate};
+use crate::event::{
+    self, BlockBreakEvent, BlockDamageEvent, EventResult, InteractAction, PlayerInteractEvent, PlayerRespawnEvent
+};
+use crate::packet::SPacket;
+use crate::player::Player;
+use crate::THE_SERVER;
+
+use super::entity_tracker::TrackedEntity;
+use super::World;
+
+/// The most packets handled for a single player in one tick.
+/// Anything beyond this stays queued until the next tick.
+pub const MAX_PACKETS_PER_TICK: usize = 64;
+
+/// Players who send more packets than this before they are handled are kicked.
+pub const MAX_QUEUED_PACKETS: usize = 1024;
+
+/// Vanilla rejects positions further out than this.
+const MAX_COORDINATE: f64 = 3.0e7;
+
+/// The longest chat message or command a client may send.
+const MAX_CHAT_LENGTH: usize = 256;
+
+/// The action of `SClientStatus` sent when the player clicks respawn
+const RESPAWN_ACTION: i32 = 0;
+
+/// The statuses of `SPlayerAction` for digging blocks
+const STARTED_DIGGING: i32 = 0;
+const CANCELLED_DIGGING: i32 = 1;
+const FINISHED_DIGGING: i32 = 2;
+
+#[derive(Debug)]
+pub enum PacketHandleError {
+    TooManyPackets,
+    InvalidMove,
+    IllegalChatCharacters,
+    ChatMessageTooLong,
+    ChatValidation(ChatValidationError),
+    InvalidBlockInteraction,
+}
+
+impl PacketHandleError {
+    /// The reason shown to the kicked player
+    pub fn to_text_component(&self) -> TextComponent<Nbt> {
+        match self {
+            PacketHandleError::ChatValidation(e) => e.to_text_component(),
+            _ => TextComponent::builder().text(self.to_string().as_str()).build(),
+        }
+    }
+}
+
+impl From<ChatValidationError> for PacketHandleError {
+    fn from(value: ChatValidationError) -> Self {
+        PacketHandleError::ChatValidation(value)
+    }
+}
+
+impl Error for PacketHandleError {}
+
+impl std::fmt::Display for PacketHandleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            PacketHandleError::ChatValidation(e) => return write!(f, "{e}"),
+            PacketHandleError::TooManyPackets => "Too many packets",
+            PacketHandleError::InvalidMove => "Invalid move player packet received",
+            PacketHandleError::IllegalChatCharacters => "Illegal characters in chat",
+            PacketHandleError::ChatMessageTooLong => "Chat message too long",
+            PacketHandleError::InvalidBlockInteraction => "Invalid block interaction packet received",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+/// Drains each player's receive queue and hands the packets to their handlers.
+///
+/// Players are handled in the order given and their packets in the order they arrived,
+/// so the outcome of a tick does not depend on task scheduling.
+pub(super) async fn dispatch_packets(world: &World, players: &[Arc<Player>]) {
+    for player in players {
+        let packets = player.take_packets(MAX_PACKETS_PER_TICK).await;
+        if let Err(e) = handle_packets(world, player, packets).await {
+            let player = player.clone();
+            crate::RUNTIME.spawn(async move {
+                player.disconnect_tc(e.to_text_component()).await;
+            });
+        }
+    }
+}
+
+/// Adds a packet the client sent to its receive queue, failing once `MAX_QUEUED_PACKETS` are waiting
+pub fn queue_packet<T>(queue: &mut VecDeque<T>, packet: T) -> Result<(), PacketHandleError> {
+    if queue.len() >= MAX_QUEUED_PACKETS {
+        return Err(PacketHandleError::TooManyPackets);
+    }
+    queue.push_back(packet);
+    Ok(())
+}
+
+async fn handle_packets(
+    world: &World,
+    player: &Arc<Player>,
+    packets: Vec<SPacket>
+) -> Result<(), PacketHandleError> {
+    for packet in packets {
+        match packet {
+            SPacket::SConfirmTeleportation(_) => (),
+            SPacket::SSetPlayerPosition(packet) => {
+                let position = Vec3d::new(packet.get_x(), packet.get_feet_y(), packet.get_z());
+                handle_move(world, player, Some(position), None, packet.get_on_ground())?;
+            },
+            SPacket::SSetPlayerPositionAndRotation(packet) => {
+                let position = Vec3d::new(packet.get_x(), packet.get_feet_y(), packet.get_z());
+                let rotation = (packet.get_yaw(), packet.get_pitch());
+                handle_move(world, player, Some(position), Some(rotation), packet.get_on_ground())?;
+            },
+            SPacket::SSetPlayerRotation(packet) => {
+                let rotation = (packet.get_yaw(), packet.get_pitch());
+                handle_move(world, player, None, Some(rotation), packet.get_on_ground())?;
+            },
+            SPacket::SSetPlayerOnGround(packet) => {
+                handle_move(world, player, None, None, packet.get_on_ground())?;
+            },
+            SPacket::SChatMessage(packet) => {
+                validate_chat(packet.get_message())?;
+                let signed = player.get_chat_state().lock().unwrap().validate(
+                    player.get_uuid(),
+                    packet.get_message(),
+                    packet.get_timestamp(),
+                    packet.get_salt(),
+                    packet.get_signature(),
+                    packet.get_message_count().get(),
+                    packet.get_acknowledged(),
+                    THE_SERVER.get_properties().is_enforce_secure_profile(),
+                    current_millis()
+                )?;
+                crate::chat::queue_message(ChatMessage::new(
+                    Arc::downgrade(player),
+                    packet.get_message(),
+                    packet.get_timestamp(),
+                    packet.get_salt(),
+                    signed
+                ));
+            },
+            SPacket::SChatCommand(packet) => {
+                validate_chat(packet.get_command())?;
+                handle_command(player, packet.get_command());
+            },
+            SPacket::SSignedChatCommand(packet) => {
+                validate_chat(packet.get_command())?;
+                player.get_chat_state().lock().unwrap().apply_update(
+                    packet.get_timestamp(),
+                    packet.get_message_count().get(),
+                    packet.get_acknowledged()
+                )?;
+                handle_command(player, packet.get_command());
+            },
+            SPacket::SCommandSuggestionsRequest(packet) => {
+                let response = brigadier::get_suggestions_response(
+                    player,
+                    packet.get_transaction_id().get(),
+                    packet.get_text()
+                ).await;
+                player.queue_send_packet(response).await;
+            },
+            SPacket::SAcknowledgeMessage(packet) => {
+                player.get_chat_state().lock().unwrap().apply_offset(packet.get_message_count().get())?;
+            },
+            SPacket::SPlayerSession(packet) => {
+                let data = ChatSessionData::new(
+                    packet.get_session_id(),
+                    packet.get_expires_at(),
+                    packet.get_public_key().get_bytes().clone(),
+                    packet.get_key_signature().get_bytes().clone()
+                );
+                handle_session(player, data).await?;
+            },
+            SPacket::SClientStatus(packet) => {
+                if packet.get_action_id().get() == RESPAWN_ACTION {
+                    respawn(world, player).await;
+                }
+            },
+            SPacket::SSetHeldItem(packet) => {
+                //Like vanilla, an invalid slot is only logged
+                let selected = usize::try_from(packet.get_slot())
+                    .is_ok_and(|slot| player.get_inventory().lock().unwrap().set_selected_slot(slot));
+                if !selected {
+                    log::warn!("{} tried to set an invalid carried item", player.get_name());
+                }
+            },
+            SPacket::SPlayerAction(packet) => {
+                let face = Facing::from_id(packet.get_face() as i32).ok_or(PacketHandleError::InvalidBlockInteraction)?;
+                let sequence = packet.get_sequence().get();
+                match packet.get_status().get() {
+                    STARTED_DIGGING => start_digging(world, player, packet.get_location(), face, sequence).await,
+                    CANCELLED_DIGGING => acknowledge(player, sequence).await,
+                    FINISHED_DIGGING => break_block(world, player, packet.get_location(), sequence).await,
+                    //TODO: dropping items, eating, shooting and swapping hands
+                    _ => debug!("Unhandled player action from {}: {:?}", player.get_name(), packet),
+                }
+            },
+            SPacket::SUseItemOn(packet) => {
+                let hand = Hand::from_id(packet.get_hand().get()).ok_or(PacketHandleError::InvalidBlockInteraction)?;
+                let face = Facing::from_id(packet.get_face().get()).ok_or(PacketHandleError::InvalidBlockInteraction)?;
+                let cursor = Vec3d::new(packet.get_cursor_x() as f64, packet.get_cursor_y() as f64, packet.get_cursor_z() as f64);
+                use_item_on(world, player, hand, packet.get_location(), face, cursor, packet.get_sequence().get()).await;
+            },
+            SPacket::SUseItem(packet) => {
+                let hand = Hand::from_id(packet.get_hand().get()).ok_or(PacketHandleError::InvalidBlockInteraction)?;
+                let item = player.get_inventory().lock().unwrap().get_held_item(hand);
+                let mut e = PlayerInteractEvent::new(Arc::downgrade(player), InteractAction::RightClickAir, hand, item);
+                event::listen(THE_SERVER.get_event_manager(), &mut e).await;
+                //TODO: use the item unless the event was cancelled
+                acknowledge(player, packet.get_sequence().get()).await;
+            },
+            packet => debug!("Unhandled packet from {}: {:?}", player.get_name(), packet),
+        }
+    }
+    Ok(())
+}
+
+fn validate_chat(message: &str) -> Result<(), PacketHandleError> {
+    if message.chars().count() > MAX_CHAT_LENGTH {
+        return Err(PacketHandleError::ChatMessageTooLong);
+    }
+    if message.chars().any(|c| c == '\u{a7}' || c < ' ' || c == '\u{7f}') {
+        return Err(PacketHandleError::IllegalChatCharacters);
+    }
+    Ok(())
+}
+
+fn current_millis() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_millis() as i64)
+}
+
+/// Verifies a new chat session and sends it to everyone's tab list,
+/// so they can verify the player's messages.
+async fn handle_session(player: &Arc<Player>, data: ChatSessionData) -> Result<(), PacketHandleError> {
+    let Some(keys) = get_profile_keys() else {
+        warn!("Ignoring chat session of {}, profile public keys are not loaded.", player.get_name());
+        return Ok(());
+    };
+    let session = RemoteChatSession::validate(data, player.get_uuid(), keys, current_millis())?;
+    player.get_chat_state().lock().unwrap().set_session(session);
+
+    let entry = player.get_info_entry();
+    for other in THE_SERVER.get_players_async().await.iter().filter_map(Weak::upgrade) {
+        if other.get_entity_id() != -1 {
+            other.queue_send_packet(CPlayerInfoUpdate::new(
+                PlayerInfoActions::INITIALIZE_CHAT,
+                vec![entry.clone()]
+            )).await;
+        }
+    }
+    Ok(())
+}
+
+/// Commands run after the tick, since they may need to lock the worlds, like selectors do.
+fn handle_command(player: &Arc<Player>, input: &str) {
+    log::info!("{} issued server command: /{}", player.get_name(), input);
+    let sender = CommandSender::Player { player: Arc::downgrade(player) };
+    let input = input.to_string();
+    crate::RUNTIME.spawn(async move {
+        let _ = command::dispatch(sender, input.as_str()).await;
+    });
+}
+
+/// Respawns the player in their world after they clicked respawn on the death screen
+async fn respawn(world: &World, player: &Arc<Player>) {
+    //TODO: the world spawn or the player's bed, once worlds keep them. Until then players respawn where they died.
+    let position = world.get_entity_tracker().lock().unwrap()
+        .get_entity(player.get_entity_id())
+        .map_or(Vec3d::new(0.0, 0.0, 0.0), TrackedEntity::get_position);
+    let mut e = PlayerRespawnEvent::new(Arc::downgrade(player), position);
+    event::listen(THE_SERVER.get_event_manager(), &mut e).await;
+    let position = e.get_respawn_position();
+
+    player.queue_send_packet(world.create_respawn_packet(player, 0)).await;
+    world.get_entity_tracker().lock().unwrap().update_position(player.get_entity_id(), position, false);
+    player.synchronize_position(position).await;
+}
+
+/// Tells the client the server handled its block changes up to `sequence`,
+/// so it drops the blocks it predicted and shows the ones the server sent instead
+async fn acknowledge(player: &Player, sequence: i32) {
+    player.queue_send_packet(CAcknowledgeBlockChange::new(VarInt::new(sequence))).await;
+}
+
+/// Undoes the blocks the client changed on its own, after an event was cancelled
+async fn revert(world: &World, player: &Player, positions: &[BlockPos], sequence: i32) {
+    for position in positions {
+        //Blocks the server doesn't know are restored by the client itself once it gets the acknowledgement
+        if let Some(block_state) = world.get_block_state(*position) {
+            player.queue_send_packet(CBlockUpdate::new(*position, VarInt::new(block_state))).await;
+        }
+    }
+    acknowledge(player, sequence).await;
+}
+
+async fn start_digging(world: &World, player: &Arc<Player>, position: BlockPos, face: Facing, sequence: i32) {
+    let item = player.get_inventory().lock().unwrap().get_held_item(Hand::MainHand);
+    let mut e = PlayerInteractEvent::new(Arc::downgrade(player), InteractAction::LeftClickBlock, Hand::MainHand, item)
+        .with_block(position, face);
+    if event::listen(THE_SERVER.get_event_manager(), &mut e).await == EventResult::Deny {
+        return revert(world, player, &[position], sequence).await;
+    }
+
+    let insta_break = player.get_game_mode() == Gamemode::Creative;
+    let mut e = BlockDamageEvent::new(Arc::downgrade(player), position, item, insta_break);
+    if event::listen(THE_SERVER.get_event_manager(), &mut e).await == EventResult::Deny {
+        revert(world, player, &[position], sequence).await;
+    } else if e.is_insta_break() {
+        break_block(world, player, position, sequence).await;
+    } else {
+        acknowledge(player, sequence).await;
+    }
+}
+
+//TODO: check the player could reach the block and dug it long enough
+async fn break_block(world: &World, player: &Arc<Player>, position: BlockPos, sequence: i32) {
+    let mut e = BlockBreakEvent::new(Arc::downgrade(player), position, world.get_block_state(position));
+    if event::listen(THE_SERVER.get_event_manager(), &mut e).await == EventResult::Deny {
+        return revert(world, player, &[position], sequence).await;
+    }
+    //TODO: spawn the drops and experience orbs once there are item and orb entities
+    world.set_block_state(position, AIR).await;
+    acknowledge(player, sequence).await;
+}
+
+async fn use_item_on(
+    world: &World,
+    player: &Arc<Player>,
+    hand: Hand,
+    position: BlockPos,
+    face: Facing,
+    cursor: Vec3d,
+    sequence: i32
+) {
+    let placed = face.offset(position);
+    let item = player.get_inventory().lock().unwrap().get_held_item(hand);
+    let mut e = PlayerInteractEvent::new(Arc::downgrade(player), InteractAction::RightClickBlock, hand, item)
+        .with_block(position, face)
+        .with_cursor_position(cursor);
+    if event::listen(THE_SERVER.get_event_manager(), &mut e).await == EventResult::Deny {
+        return revert(world, player, &[position, placed], sequence).await;
+    }
+
+    //TODO: place the block and fire a BlockPlaceEvent once block states of items are known,
+    //the client shows its prediction until then
+    acknowledge(player, sequence).await;
+}
+
+fn handle_move(
+    world: &World,
+    player: &Arc<Player>,
+    position: Option<Vec3d>,
+    rotation: Option<(f32, f32)>,
+    on_ground: bool,
+) -> Result<(), PacketHandleError> {
+    let entity_id = player.get_entity_id();
+    let mut tracker = world.get_entity_tracker().lock().unwrap();
+    if let Some(position) = position {
+        let valid = [position.x, position.y, position.z].iter()
+            .all(|v| v.is_finite() && v.abs() <= MAX_COORDINATE);
+        if !valid {
+            return Err(PacketHandleError::InvalidMove);
+        }
+        tracker.update_position(entity_id, position, on_ground);
+    } else {
+        tracker.update_on_ground(entity_id, on_ground);
+    }
+    if let Some((yaw, pitch)) = rotation {
+        if !yaw.is_finite() || !pitch.is_finite() {
+            return Err(PacketHandleError::InvalidMove);
+        }
+        let yaw = Angle::new(yaw as f64);
+        tracker.update_rotation(entity_id, yaw, Angle::new(pitch as f64));
+        tracker.update_head_yaw(entity_id, yaw);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use server_util::ConnectionState;
+
+    use super::*;
+
+    fn confirm_teleportation() -> SPacket {
+        crate::packet::create_packet(0x00, ConnectionState::Play, &mut vec![0u8].into_iter()).unwrap()
+    }
+
+    #[test]
+    fn queue_cap() {
+        let mut queue = VecDeque::new();
+        for _ in 0..MAX_QUEUED_PACKETS {
+            assert!(queue_packet(&mut queue, confirm_teleportation()).is_ok());
+        }
+        //The packet which overflows the queue is dropped and the player kicked for it
+        let e = queue_packet(&mut queue, confirm_teleportation()).unwrap_err();
+        assert!(matches!(e, PacketHandleError::TooManyPackets));
+        assert_eq!(e.to_text_component().to_plain_string(), "Too many packets");
+        assert_eq!(queue.len(), MAX_QUEUED_PACKETS);
+
+        queue.drain(..MAX_PACKETS_PER_TICK);
+        assert!(queue_packet(&mut queue, confirm_teleportation()).is_ok());
+    }
+}
@@ -9,12 +9,18 @@ use dashmap::DashMap;
 
 use crate::data_types::identifier::Identifier;
 use crate::data_types::registry::DimensionProperties;
+use crate::block::block_state::Facing;
+use crate::data_types::{BlockPos, Vec3d};
+use crate::event::{self, BlockPhysicsEvent};
 //use crate::entity::entity_base::EntityBase;
-use crate::packet::play::CUpdateTime;
+use crate::data_types::VarInt;
+use crate::packet::play::{CBlockUpdate, CGameEvent, CRespawn, CUpdateTime};
 use crate::player::Player;
 use crate::{SERVER_REGISTRY, THE_SERVER};
 
 use super::chunk_loader::{self, Loader};
+use super::entity_tracker::{EntityTracker, TrackedEntity};
+use super::packet_handler;
 
 
 
@@ -24,6 +30,39 @@ pub const TIME_NOON: i64 = 6000;
 pub const TIME_NIGHT: i64 = 12000;
 pub const TIME_MIDNIGHT: i64 = 18000;
 
+pub const TICKS_PER_DAY: i64 = 24000;
+
+/// The blocks changed since the server started by position, until chunks store their blocks
+pub type BlockChanges = DashMap<(i32, i32, i32), i32>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Thunder,
+}
+
+impl Weather {
+    /// The game events which show this weather to a client
+    pub fn get_game_events(&self) -> [CGameEvent; 3] {
+        let (raining, rain_level, thunder_level) = match self {
+            Weather::Clear => (false, 0.0, 0.0),
+            Weather::Rain => (true, 1.0, 0.0),
+            Weather::Thunder => (true, 1.0, 1.0),
+        };
+        [
+            CGameEvent::new(if raining { GAME_EVENT_BEGIN_RAINING } else { GAME_EVENT_END_RAINING }, 0.0),
+            CGameEvent::new(GAME_EVENT_RAIN_LEVEL, rain_level),
+            CGameEvent::new(GAME_EVENT_THUNDER_LEVEL, thunder_level),
+        ]
+    }
+}
+
+const GAME_EVENT_END_RAINING: u8 = 1;
+const GAME_EVENT_BEGIN_RAINING: u8 = 2;
+const GAME_EVENT_RAIN_LEVEL: u8 = 7;
+const GAME_EVENT_THUNDER_LEVEL: u8 = 8;
+
 
 
 pub struct World {
@@ -32,9 +71,15 @@ pub struct World {
     dimension_type: Identifier,
     level_name: String,
     chunk_sections: u8,
+    spawn_position: Vec3d,
     world_age: Mutex<i64>,
     world_time: Mutex<i64>,
+    //The weather and the ticks until it clears up
+    weather: Mutex<(Weather, i64)>,
     chunk_loader: Box<dyn Loader>,
+    entity_tracker: Mutex<EntityTracker>,
+    //Shared with the server, so blocks can be read and set while the world is locked
+    block_changes: Arc<BlockChanges>,
     //beds_explode: bool,
     //ticket_regions: RwLock<HashMap<(i32, i32), TicketRegion>>,
     //load_regions: HashMap<(i32, i32), LoadRegion>,
@@ -51,14 +96,20 @@ impl World{
     /// Use this function when loading the world from a directory
     pub fn new(level_name: String, dimension_type: Identifier, world_age: i64, world_time: i64, loader: Box<dyn Loader>) -> Self {
         let the_world = World {
-            players : DashMap::with_capacity(THE_SERVER.get_max_players() as usize),
+            //Worlds are created while the server is, so they can't ask it for the max players
+            players : DashMap::new(),
             //loaded_entities: DashMap::new(),
             dimension_type : dimension_type.clone(),
             level_name: level_name,
             chunk_sections: (Self::get_dimension_info_by_id(dimension_type.to_string().as_str()).get_logical_height()/16).try_into().unwrap(),
+            //TODO: read the spawn from level.dat
+            spawn_position: Vec3d::new(0.5, 64.0, 0.5),
             world_age: Mutex::new(world_age.into()),
             world_time: Mutex::new(world_time.into()),
+            weather: Mutex::new((Weather::Clear, 0)),
             chunk_loader: loader,
+            entity_tracker: Mutex::new(EntityTracker::new()),
+            block_changes: Arc::new(DashMap::new()),
             
             //TODO: Move this logic into the chunk loader module
             /*
@@ -96,55 +147,191 @@ impl World{
         Some(new_world)
     }
 
-    pub fn add_player(&self, player_id: i32, weak: Weak<Player>) {
+    pub fn add_player(&self, player_id: i32, weak: Weak<Player>, position: Vec3d) {
+        if let Some(player) = weak.upgrade() {
+            player.set_world_name(Some(self.level_name.clone()));
+            self.entity_tracker.lock().unwrap().track(
+                TrackedEntity::player(player.get_entity_id(), player.get_uuid(), position)
+            );
+        }
         self.players.insert(player_id, weak);
     }
 
-    pub fn remove_player_by_id(&self, player_id: i32) {
-        self.players.remove(&player_id);
+    /// Takes the entity id too, so players who were already dropped stop being tracked
+    pub fn remove_player_by_id(&self, player_id: i32, entity_id: i32) {
+        if let Some((_, weak)) = self.players.remove(&player_id) {
+            if let Some(player) = weak.upgrade() {
+                player.set_world_name(None);
+            }
+            let mut tracker = self.entity_tracker.lock().unwrap();
+            tracker.untrack(entity_id);
+            tracker.remove_viewer(entity_id);
+        }
+    }
+
+    pub fn get_dimension_type(&self) -> &Identifier {
+        &self.dimension_type
+    }
+
+    /// The index of the dimension type in the registry sent during configuration
+    pub fn get_dimension_type_id(&self) -> i32 {
+        let name = self.dimension_type.to_string();
+        crate::REGISTRY_NBT.get("dimension_type")
+            .and_then(|entries| entries.iter().position(|entry| entry.entry_identifier == name))
+            .unwrap_or(0) as i32
+    }
+
+    /// The packet which moves the player into this world.
+    /// `data_kept` is `0` after dying, and `0x03` to keep everything otherwise.
+    pub fn create_respawn_packet(&self, player: &Player, data_kept: u8) -> CRespawn {
+        CRespawn::new(
+            VarInt::new(self.get_dimension_type_id()),
+            self.dimension_type.clone(),
+            THE_SERVER.get_properties().get_hashed_seed(),
+            player.get_game_mode().get_id(),
+            -1,
+            false,
+            false,
+            None,
+            VarInt::new(0),
+            data_kept
+        )
+    }
+
+    pub fn get_level_name(&self) -> &str {
+        &self.level_name
+    }
+
+    /// None if the block wasn't changed since the server started
+    //TODO: read the block from its chunk once chunks store blocks
+    pub fn get_block_state(&self, position: BlockPos) -> Option<i32> {
+        self.block_changes.get(&(position.x, position.y, position.z)).map(|state| *state)
+    }
+
+    pub fn get_block_changes(&self) -> &Arc<BlockChanges> {
+        &self.block_changes
+    }
+
+    /// Sets the block, then updates it like `update_block`
+    pub async fn set_block_state(&self, position: BlockPos, block_state: i32) {
+        self.block_changes.insert((position.x, position.y, position.z), block_state);
+        self.update_block(position).await;
+    }
+
+    /// Sends the block to the players in the world and fires a `BlockPhysicsEvent` for each
+    /// of its neighbors, after it was changed through `get_block_changes`
+    pub async fn update_block(&self, position: BlockPos) {
+        let Some(block_state) = self.get_block_state(position) else {
+            return;
+        };
+        for player in self.get_players() {
+            player.queue_send_packet(CBlockUpdate::new(position, VarInt::new(block_state))).await;
+        }
+        for face in Facing::ALL {
+            let neighbor = face.offset(position);
+            let mut e = BlockPhysicsEvent::new(&self.level_name, neighbor, self.get_block_state(neighbor), position);
+            event::listen(THE_SERVER.get_event_manager(), &mut e).await;
+            //TODO: update the neighbor unless the event was cancelled, once blocks react to their neighbors
+        }
+    }
+
+    pub fn get_players(&self) -> Vec<Arc<Player>> {
+        self.players.iter().filter_map(|weak| weak.upgrade()).collect()
+    }
+
+    /// Ticks since the world was created
+    pub fn get_world_age(&self) -> i64 {
+        *self.world_age.lock().unwrap()
+    }
+
+    /// The time of day, from `0` to `TICKS_PER_DAY`
+    pub fn get_world_time(&self) -> i64 {
+        *self.world_time.lock().unwrap()
+    }
+
+    pub async fn set_world_time(&self, world_time: i64) {
+        let world_time = world_time.rem_euclid(TICKS_PER_DAY);
+        *self.world_time.lock().unwrap() = world_time;
+        let world_age = self.get_world_age();
+        for player in self.get_players() {
+            player.queue_send_packet(CUpdateTime::new(world_age, world_time)).await;
+        }
+    }
+
+    pub fn get_weather(&self) -> Weather {
+        self.weather.lock().unwrap().0
+    }
+
+    /// Changes the weather for `duration` ticks, after which it clears up
+    pub async fn set_weather(&self, weather: Weather, duration: i64) {
+        *self.weather.lock().unwrap() = (weather, duration);
+        for player in self.get_players() {
+            for event in weather.get_game_events() {
+                player.queue_send_packet(event).await;
+            }
+        }
+    }
+
+    pub fn get_entity_tracker(&self) -> &Mutex<EntityTracker> {
+        &self.entity_tracker
     }
 
     pub fn get_chunk_sections(&self) -> u8 {
         self.chunk_sections
     }
 
+    /// Where players joining for the first time appear
+    pub fn get_spawn_position(&self) -> Vec3d {
+        self.spawn_position
+    }
+
     pub async fn tick(&mut self) {
-        let mut world_age_lock = self.world_age.lock().unwrap();
-        let mut world_time_lock = self.world_time.lock().unwrap();
-        if *world_age_lock % 20 == 0 {
-            for weak in self.players.iter() {
-                match weak.upgrade() {
-                    Some(arc) => {
-                        let world_age = *world_age_lock;
-                        let world_time = *world_time_lock;
-                        crate::RUNTIME.spawn(async move {
-                            if arc.send_packet(CUpdateTime::new(world_age, world_time)).await.is_err() {
-                                arc.disconnect("Connection lost").await;
-                            }
-                        });
-                    },
-                    None => {
-                        self.players.remove(weak.key());
+        {
+            let mut world_age_lock = self.world_age.lock().unwrap();
+            let mut world_time_lock = self.world_time.lock().unwrap();
+            if *world_age_lock % 20 == 0 {
+                for weak in self.players.iter() {
+                    match weak.upgrade() {
+                        Some(arc) => {
+                            let world_age = *world_age_lock;
+                            let world_time = *world_time_lock;
+                            crate::RUNTIME.spawn(async move {
+                                if arc.send_packet(CUpdateTime::new(world_age, world_time)).await.is_err() {
+                                    arc.connection_lost().await;
+                                }
+                            });
+                        },
+                        None => {
+                            self.players.remove(weak.key());
+                        }
                     }
                 }
             }
-        }
 
-        //TODO: Plugin Scheduler stuff
+            //TODO: Plugin Scheduler stuff
 
-        //TODO: World Border Logic
+            //TODO: World Border Logic
 
-        //TODO: Weather
+            *world_age_lock += 1;
+            *world_time_lock = (*world_time_lock + 1) % TICKS_PER_DAY;
 
-        *world_age_lock += 1;
-        *world_time_lock = (*world_time_lock + 1) % 24000;
+            if self.get_dimension_info().bed_works() {
+                //TODO: Sleeping logic
+            }
+        
+            drop(world_age_lock);
+            drop(world_time_lock);
+        }
 
-        if self.get_dimension_info().bed_works() {
-            //TODO: Sleeping logic
+        //TODO: Random weather cycles
+        let clear_up = {
+            let mut weather = self.weather.lock().unwrap();
+            weather.1 -= 1;
+            weather.0 != Weather::Clear && weather.1 <= 0
+        };
+        if clear_up {
+            self.set_weather(Weather::Clear, 0).await;
         }
-        
-        drop(world_age_lock);
-        drop(world_time_lock);
 
         //TODO: Scheduled commands
 
@@ -184,8 +371,36 @@ impl World{
 
         //TODO: handle game events (whatever this implies)
 
-        //TODO: handle incoming packets from players
-        //TODO: send queued packets to players
+        let mut players: Vec<Arc<Player>> = Vec::with_capacity(self.players.len());
+        self.players.retain(|_, weak| {
+            match weak.upgrade() {
+                Some(arc) => {
+                    players.push(arc);
+                    true
+                },
+                None => false,
+            }
+        });
+        players.sort_by_key(|player| player.get_id());
+
+        packet_handler::dispatch_packets(self, &players).await;
+
+        let mut outgoing = {
+            let viewer_ids: Vec<i32> = players.iter().map(|player| player.get_entity_id()).collect();
+            self.entity_tracker.lock().unwrap().tick(&viewer_ids)
+        };
+
+        for player in players {
+            if let Some(packets) = outgoing.remove(&player.get_entity_id()) {
+                player.queue_send_raw(packets).await;
+            }
+            crate::RUNTIME.spawn(async move {
+                if player.flush_send_queue().await.is_err() {
+                    player.connection_lost().await;
+                }
+            });
+        }
+
         //TODO: send player info to players
 
 